@@ -2,18 +2,56 @@
 // RFC: Multi-backend database adapter architecture
 //
 // This module defines a common trait for all database backends (PostgreSQL,
-// MySQL, SQLite, Redis) and provides concrete implementations for each.
+// MySQL, SQLite, Redis, Elasticsearch) and provides concrete implementations
+// for each.
 // The trait is async-ready and designed for use behind Tauri's managed state.
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Condvar, Mutex, RwLock};
 use std::time::Duration;
 
 const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
 const QUERY_TIMEOUT: Duration = Duration::from_secs(30);
+/// Bound on getting ready to run a query (checking a connection out of a
+/// pool, preparing a statement), kept well under `QUERY_TIMEOUT` so a
+/// saturated pool is reported as such rather than as a slow query.
+const ACQUIRE_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long `ConnectionManager::get` waits for a connection that is still
+/// being opened before reporting it inactive.
+pub const CONNECTION_READY_WAIT: Duration = Duration::from_secs(2);
+/// Queries allowed in flight at once on a connection unless configured.
+pub const DEFAULT_MAX_CONCURRENT_QUERIES: u32 = 4;
+/// How long a query queues for a free slot before giving up.
+const QUERY_SLOT_WAIT: Duration = Duration::from_secs(30);
+
+use crate::commands::{AppError, ColumnInfo, ConnectionTestStatus, QueryResult, TableSchema};
+
+/// Error for a query stopped by `cancel_query`.
+fn query_cancelled() -> AppError {
+    AppError::QueryError("cancelled".to_string())
+}
+
+/// Run a step that gets a query ready (`what`, e.g. "waiting for a pooled
+/// connection"), giving up after `limit` with an error that names the step
+/// rather than blaming the query.
+async fn acquire_within<T>(
+    limit: Duration,
+    what: &str,
+    step: impl std::future::Future<Output = T>,
+) -> Result<T, AppError> {
+    tokio::time::timeout(limit, step).await.map_err(|_| {
+        AppError::ConnectionFailed(format!("Timed out after {}s {what}", limit.as_secs()))
+    })
+}
 
-use crate::commands::{AppError, ColumnInfo, QueryResult, TableSchema};
+/// Error for a query that was running when `limit` elapsed.
+fn execution_timeout(limit: Duration) -> AppError {
+    AppError::QueryError(format!(
+        "Query exceeded the {}s execution timeout",
+        limit.as_secs()
+    ))
+}
 
 // ---------------------------------------------------------------------------
 // Core types
@@ -23,23 +61,91 @@ use crate::commands::{AppError, ColumnInfo, QueryResult, TableSchema};
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum DatabaseKind {
     PostgreSQL,
+    /// CockroachDB, served by `PostgresAdapter` in its Cockroach flavor.
+    CockroachDB,
     MySQL,
     SQLite,
     Redis,
+    Elasticsearch,
 }
 
 impl DatabaseKind {
     pub fn from_str_loose(s: &str) -> Option<Self> {
         match s.to_lowercase().as_str() {
             "postgresql" | "postgres" | "pg" => Some(Self::PostgreSQL),
+            "cockroachdb" | "cockroach" | "crdb" => Some(Self::CockroachDB),
             "mysql" | "mariadb" => Some(Self::MySQL),
             "sqlite" | "sqlite3" => Some(Self::SQLite),
             "redis" => Some(Self::Redis),
+            "elasticsearch" | "elastic" | "opensearch" | "es" => Some(Self::Elasticsearch),
+            _ => None,
+        }
+    }
+}
+
+/// How a PostgreSQL connection uses TLS, following libpq's `sslmode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SslMode {
+    Disable,
+    /// Encrypt, but accept any server certificate.
+    Require,
+    /// Check the certificate chain against the trusted roots, not the host
+    /// name.
+    VerifyCa,
+    /// Check the chain and that the certificate was issued for the host.
+    VerifyFull,
+}
+
+impl SslMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Disable => "disable",
+            Self::Require => "require",
+            Self::VerifyCa => "verify-ca",
+            Self::VerifyFull => "verify-full",
+        }
+    }
+
+    pub fn from_str_loose(s: &str) -> Option<Self> {
+        match s.to_lowercase().replace('_', "-").as_str() {
+            "disable" => Some(Self::Disable),
+            "require" => Some(Self::Require),
+            "verify-ca" => Some(Self::VerifyCa),
+            "verify-full" => Some(Self::VerifyFull),
             _ => None,
         }
     }
 }
 
+/// How binary column values appear in query results.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BinaryEncoding {
+    /// A short marker such as `<blob 12 bytes>` (hex for PostgreSQL BYTEA).
+    #[default]
+    Placeholder,
+    /// The full contents, base64-encoded.
+    Base64,
+}
+
+impl BinaryEncoding {
+    /// Render `bytes`, falling back to `placeholder` in placeholder mode.
+    fn encode(self, bytes: &[u8], placeholder: impl FnOnce(&[u8]) -> String) -> serde_json::Value {
+        use base64::Engine;
+        match self {
+            Self::Placeholder => serde_json::Value::String(placeholder(bytes)),
+            Self::Base64 => {
+                serde_json::Value::String(base64::engine::general_purpose::STANDARD.encode(bytes))
+            }
+        }
+    }
+}
+
+fn blob_placeholder(bytes: &[u8]) -> String {
+    format!("<blob {} bytes>", bytes.len())
+}
+
 /// Parameters needed to open a connection to any backend.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConnectParams {
@@ -50,6 +156,57 @@ pub struct ConnectParams {
     pub username: String,
     pub password: String,
     pub use_ssl: bool,
+    /// Quote every identifier in generated SQL, not only those that need it.
+    pub quote_all_identifiers: bool,
+    /// Queries allowed in flight at once; further ones queue.
+    pub max_concurrent_queries: u32,
+    /// Connections kept in the pool, for drivers that pool (PostgreSQL).
+    pub pool_size: usize,
+    /// Overrides `CONNECT_TIMEOUT` when set.
+    pub connect_timeout_secs: Option<u64>,
+    /// Overrides `QUERY_TIMEOUT` when set. For SQLite this is the busy
+    /// timeout.
+    pub query_timeout_secs: Option<u64>,
+    /// The connection's environment label (e.g. "production"), echoed on
+    /// its query results.
+    pub environment: Option<String>,
+    /// PostgreSQL TLS mode; unset derives it from `use_ssl`.
+    pub ssl_mode: Option<SslMode>,
+    /// PEM file of root certificates to trust instead of the bundled
+    /// webpki roots.
+    pub ca_cert_path: Option<String>,
+    /// PEM client certificate chain and private key for mutual TLS
+    /// (PostgreSQL and MySQL); both or neither.
+    pub client_cert_path: Option<String>,
+    pub client_key_path: Option<String>,
+    /// Open SQLite files read-only, without WAL sidecar files.
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+impl ConnectParams {
+    /// A zero timeout, which would fail every call, reads as unset.
+    pub fn connect_timeout(&self) -> Duration {
+        self.connect_timeout_secs
+            .filter(|secs| *secs > 0)
+            .map_or(CONNECT_TIMEOUT, Duration::from_secs)
+    }
+
+    pub fn query_timeout(&self) -> Duration {
+        self.query_timeout_secs
+            .filter(|secs| *secs > 0)
+            .map_or(QUERY_TIMEOUT, Duration::from_secs)
+    }
+
+    /// The explicit `ssl_mode`, else what the `use_ssl` toggle has always
+    /// meant: verify the server against the webpki roots.
+    pub fn ssl_mode(&self) -> SslMode {
+        match self.ssl_mode {
+            Some(mode) => mode,
+            None if self.use_ssl => SslMode::VerifyFull,
+            None => SslMode::Disable,
+        }
+    }
 }
 
 /// Index metadata returned from introspection.
@@ -69,6 +226,50 @@ pub struct ForeignKeyInfo {
     pub to_column: String,
 }
 
+/// Narrows what `get_schema_with` introspects.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SchemaOptions {
+    /// Only introspect these tables; `None` means every table.
+    pub tables: Option<Vec<String>>,
+    /// Count each table with `SELECT COUNT(*)` instead of reading the
+    /// planner's estimate. Slow on large tables.
+    #[serde(default)]
+    pub exact_counts: bool,
+}
+
+impl SchemaOptions {
+    pub fn only(table: &str) -> Self {
+        Self {
+            tables: Some(vec![table.to_string()]),
+            ..Self::default()
+        }
+    }
+
+    pub fn includes(&self, table: &str) -> bool {
+        self.tables
+            .as_ref()
+            .is_none_or(|tables| tables.iter().any(|t| t == table))
+    }
+}
+
+/// Start an empty `TableMetadata` for each table in `schema`, keyed by name,
+/// for batched lookups to fill in.
+fn empty_metadata(schema: Vec<TableSchema>) -> HashMap<String, TableMetadata> {
+    schema
+        .into_iter()
+        .map(|t| {
+            (
+                t.name.clone(),
+                TableMetadata {
+                    schema: t,
+                    indexes: Vec::new(),
+                    foreign_keys: Vec::new(),
+                },
+            )
+        })
+        .collect()
+}
+
 /// Extended table metadata combining schema + indexes + FKs.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TableMetadata {
@@ -92,24 +293,341 @@ pub trait DatabaseAdapter: Send + Sync {
     /// Retrieve the full schema: tables, columns, types, row counts.
     fn get_schema(&self) -> Result<Vec<TableSchema>, AppError>;
 
+    /// Retrieve the schema for the tables selected by `options`. SQL
+    /// adapters override this to skip introspecting unselected tables.
+    fn get_schema_with(&self, options: &SchemaOptions) -> Result<Vec<TableSchema>, AppError> {
+        Ok(self
+            .get_schema()?
+            .into_iter()
+            .filter(|t| options.includes(&t.name))
+            .collect())
+    }
+
+    /// List table names only, without columns or row counts.
+    fn list_table_names(&self) -> Result<Vec<String>, AppError> {
+        Ok(self.get_schema()?.into_iter().map(|t| t.name).collect())
+    }
+
+    /// Find tables and columns whose names contain `term`, ignoring case.
+    /// SQL backends override this to ask `information_schema` directly
+    /// rather than introspecting the whole schema.
+    fn search_objects(&self, term: &str) -> Result<Vec<ObjectMatch>, AppError> {
+        Ok(search_schema(&self.get_schema()?, term))
+    }
+
     /// Retrieve extended metadata (indexes, FKs) for a specific table.
     fn get_table_metadata(&self, table: &str) -> Result<TableMetadata, AppError>;
 
+    /// Metadata for several tables, keyed by table name; tables that don't
+    /// exist are left out. The default asks one table at a time; SQL
+    /// backends override it to fetch each kind of metadata in one query.
+    fn get_tables_metadata(
+        &self,
+        tables: &[String],
+    ) -> Result<HashMap<String, TableMetadata>, AppError> {
+        let mut metadata = HashMap::new();
+        for table in tables {
+            match self.get_table_metadata(table) {
+                Ok(m) => {
+                    metadata.insert(table.clone(), m);
+                }
+                Err(AppError::NotFound(_)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(metadata)
+    }
+
+    /// The `CREATE` statements for `table` and its indexes as the backend
+    /// stored them, when it keeps them verbatim (SQLite). `None` means the
+    /// caller should reconstruct them from `get_table_metadata`.
+    fn stored_table_ddl(&self, _table: &str) -> Result<Option<String>, AppError> {
+        Ok(None)
+    }
+
+    /// The full declared type of each column of `table` (with length,
+    /// precision and element type), by column name, for backends whose
+    /// `ColumnInfo::data_type` is only the type's family. Columns left out
+    /// keep their `data_type` when DDL is reconstructed.
+    fn declared_column_types(&self, _table: &str) -> Result<Vec<(String, String)>, AppError> {
+        Ok(Vec::new())
+    }
+
+    /// Columns of `table` the database fills in itself (identity,
+    /// auto-increment, serial and generated columns), which inserts should
+    /// leave out.
+    fn generated_columns(&self, _table: &str) -> Result<Vec<String>, AppError> {
+        Ok(Vec::new())
+    }
+
     /// Execute an arbitrary SQL query and return the result set.
     fn execute_query(&self, sql: &str) -> Result<QueryResult, AppError>;
 
+    /// Execute a query, materializing at most `max_rows` rows and setting
+    /// `truncated` when more were available. Adapters that stream rows
+    /// override this so the cap bounds memory; the default trims afterwards.
+    fn execute_query_limited(&self, sql: &str, max_rows: Option<usize>) -> Result<QueryResult, AppError> {
+        let mut result = self.execute_query(sql)?;
+        if let Some(max) = max_rows {
+            if result.rows.len() > max {
+                result.rows.truncate(max);
+                result.row_count = max;
+                result.truncated = true;
+            }
+        }
+        Ok(result)
+    }
+
+    /// `execute_query_limited`, rendering binary values with `encoding`.
+    /// Backends without binary column types ignore the encoding.
+    fn execute_query_encoded(
+        &self,
+        sql: &str,
+        max_rows: Option<usize>,
+        _encoding: BinaryEncoding,
+    ) -> Result<QueryResult, AppError> {
+        self.execute_query_limited(sql, max_rows)
+    }
+
+    /// At most `limit` of the query's rows after skipping `offset`. The
+    /// default wraps a single read-only SQL query as a subselect and
+    /// refuses anything else.
+    fn execute_query_page(
+        &self,
+        sql: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<QueryResult, AppError> {
+        let paged = crate::dialect::paginate(self.kind(), sql, offset, limit).ok_or_else(|| {
+            AppError::QueryError("Only a single read-only query can be paginated".to_string())
+        })?;
+        self.execute_query(&paged)
+    }
+
+    /// Stream a query's rows into `sink` without materializing the result,
+    /// returning how many rows were delivered. Binary values follow
+    /// `encoding`. Adapters with a row-at-a-time driver API override this;
+    /// the default replays `execute_query_encoded`.
+    fn stream_query(
+        &self,
+        sql: &str,
+        encoding: BinaryEncoding,
+        sink: &mut dyn RowSink,
+    ) -> Result<u64, AppError> {
+        let result = self.execute_query_encoded(sql, None, encoding)?;
+        sink.columns(&result.columns)?;
+        let mut delivered = 0u64;
+        for row in result.rows {
+            sink.row(row)?;
+            delivered += 1;
+        }
+        Ok(delivered)
+    }
+
     /// Execute a statement that modifies data (INSERT/UPDATE/DELETE).
     /// Returns the number of affected rows.
     fn execute_statement(&self, sql: &str) -> Result<u64, AppError>;
 
+    /// Execute a query with `params` bound to its placeholders (`$1` for
+    /// Postgres, `?` for MySQL and SQLite, see `dialect::placeholder`)
+    /// instead of spliced into the SQL text.
+    fn execute_query_params(
+        &self,
+        sql: &str,
+        params: &[serde_json::Value],
+    ) -> Result<QueryResult, AppError> {
+        let _ = (sql, params);
+        Err(AppError::QueryError(format!(
+            "Parameterized queries are not supported for {:?}",
+            self.kind()
+        )))
+    }
+
+    /// `execute_statement` with bound `params`; see `execute_query_params`.
+    fn execute_statement_params(
+        &self,
+        sql: &str,
+        params: &[serde_json::Value],
+    ) -> Result<u64, AppError> {
+        let _ = (sql, params);
+        Err(AppError::QueryError(format!(
+            "Parameterized queries are not supported for {:?}",
+            self.kind()
+        )))
+    }
+
+    /// Run `statements` in order inside a single transaction. Execution stops
+    /// at the first failure and the transaction is rolled back; the returned
+    /// outcomes end with the failed statement. Errors are reserved for
+    /// failures outside any one statement (connecting, BEGIN, COMMIT).
+    fn execute_transaction(&self, statements: &[String]) -> Result<Vec<StatementOutcome>, AppError> {
+        let _ = statements;
+        Err(AppError::QueryError(format!(
+            "Transactions are not supported for {:?}",
+            self.kind()
+        )))
+    }
+
+    /// Open a transaction that every later statement on this connection
+    /// runs in, until `commit_transaction` or `rollback_transaction`.
+    fn begin_transaction(&self) -> Result<(), AppError> {
+        Err(AppError::QueryError(format!(
+            "Transactions are not supported for {:?}",
+            self.kind()
+        )))
+    }
+
+    /// Commit the transaction opened with `begin_transaction`.
+    fn commit_transaction(&self) -> Result<(), AppError> {
+        Err(AppError::QueryError(format!(
+            "Transactions are not supported for {:?}",
+            self.kind()
+        )))
+    }
+
+    /// Roll back the transaction opened with `begin_transaction`.
+    fn rollback_transaction(&self) -> Result<(), AppError> {
+        Err(AppError::QueryError(format!(
+            "Transactions are not supported for {:?}",
+            self.kind()
+        )))
+    }
+
+    /// Whether a transaction opened with `begin_transaction` is still open.
+    fn in_transaction(&self) -> bool {
+        false
+    }
+
+    /// Attach another database file under `alias` so queries can join
+    /// across both. Returns the aliases attached afterwards.
+    fn attach_database(&self, _file_path: &str, _alias: &str) -> Result<Vec<String>, AppError> {
+        Err(AppError::QueryError(format!(
+            "Attaching databases is not supported for {:?}",
+            self.kind()
+        )))
+    }
+
+    /// Detach the database attached as `alias`. Returns the aliases still
+    /// attached.
+    fn detach_database(&self, _alias: &str) -> Result<Vec<String>, AppError> {
+        Err(AppError::QueryError(format!(
+            "Attaching databases is not supported for {:?}",
+            self.kind()
+        )))
+    }
+
     /// Return summary statistics: table count, total rows, disk usage.
     fn get_stats(&self) -> Result<AdapterStats, AppError>;
 
+    /// Return storage usage for a single table: data, index and (where the
+    /// backend tracks it) bloat estimates. Backends without per-table storage
+    /// accounting keep the default.
+    fn get_table_size(&self, table: &str) -> Result<TableSizeInfo, AppError> {
+        let _ = table;
+        Err(AppError::QueryError(format!(
+            "Table size is not supported for {:?}",
+            self.kind()
+        )))
+    }
+
+    /// List the SQL functions available on this connection. The default is
+    /// the static built-in list for the backend's dialect.
+    fn list_functions(&self) -> Result<Vec<SqlFunction>, AppError> {
+        Ok(crate::dialect::builtin_functions(self.kind())
+            .iter()
+            .map(|name| SqlFunction {
+                name: name.to_string(),
+                signature: None,
+                builtin: true,
+            })
+            .collect())
+    }
+
+    /// List the extensions the server can install, with the installed
+    /// version where one is. Backends without extensions return nothing.
+    fn list_extensions(&self) -> Result<Vec<DbExtension>, AppError> {
+        Ok(Vec::new())
+    }
+
+    /// Install an extension, doing nothing if it is already installed.
+    fn create_extension(&self, name: &str) -> Result<(), AppError> {
+        let _ = name;
+        Err(AppError::QueryError(format!(
+            "Extensions are not supported for {:?}",
+            self.kind()
+        )))
+    }
+
+    /// Remove an installed extension. Objects depending on it are not
+    /// dropped, so this fails while anything still uses it.
+    fn drop_extension(&self, name: &str) -> Result<(), AppError> {
+        let _ = name;
+        Err(AppError::QueryError(format!(
+            "Extensions are not supported for {:?}",
+            self.kind()
+        )))
+    }
+
+    /// Report the server version and time zone. Backends without a notion of
+    /// a server time zone leave those fields empty.
+    fn get_server_info(&self) -> Result<ServerInfo, AppError> {
+        Ok(ServerInfo {
+            kind: self.kind(),
+            version: None,
+            timezone: None,
+            utc_offset_seconds: None,
+            current_role: None,
+        })
+    }
+
+    /// Stop whatever queries are currently running on this connection. They
+    /// fail with a "cancelled" error; queries started afterwards are not
+    /// affected.
+    fn cancel_query(&self) -> Result<(), AppError> {
+        Err(AppError::QueryError(format!(
+            "Cancelling queries is not supported for {:?}",
+            self.kind()
+        )))
+    }
+
+    /// Run later queries as `role` (`SET ROLE`), or as the login role again
+    /// when `role` is `None`.
+    fn set_session_role(&self, role: Option<&str>) -> Result<(), AppError> {
+        let _ = role;
+        Err(AppError::QueryError(format!(
+            "Session roles are not supported for {:?}",
+            self.kind()
+        )))
+    }
+
     /// Close the connection / return it to the pool.
     fn disconnect(&self) -> Result<(), AppError>;
 
     /// Return the backend kind.
     fn kind(&self) -> DatabaseKind;
+
+    /// Whether generated SQL should quote every identifier on this
+    /// connection. Set per connection; off by default.
+    fn quote_all_identifiers(&self) -> bool {
+        false
+    }
+
+    /// Spell an identifier for SQL generated against this connection.
+    fn quote_ident(&self, name: &str) -> String {
+        crate::dialect::quote_ident(self.kind(), name, self.quote_all_identifiers())
+    }
+
+    /// Quote a table name as returned by `list_table_names`, which for
+    /// PostgreSQL may be schema-qualified.
+    fn quote_table(&self, name: &str) -> String {
+        self.quote_ident(name)
+    }
+
+    /// Quote a table from `get_schema`, using its `schema` rather than
+    /// resolving the name again.
+    fn quote_listed_table(&self, table: &TableSchema) -> String {
+        self.quote_table(&table.name)
+    }
 }
 
 /// Summary statistics returned by `get_stats`.
@@ -120,13 +638,308 @@ pub struct AdapterStats {
     pub disk_usage_bytes: u64,
 }
 
+/// Server identification returned by `get_server_info`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerInfo {
+    pub kind: DatabaseKind,
+    pub version: Option<String>,
+    /// Time zone name the server (or session) uses, as the server reports it.
+    pub timezone: Option<String>,
+    /// Current offset of that time zone from UTC, in seconds.
+    pub utc_offset_seconds: Option<i32>,
+    /// Role the session's queries run as, where the backend has one.
+    #[serde(default)]
+    pub current_role: Option<String>,
+}
+
+/// Receives a query's rows one at a time as they are decoded, so results
+/// larger than memory can be processed. Returning an error from either
+/// method stops the query; the error is passed back to the caller.
+pub trait RowSink {
+    fn columns(&mut self, columns: &[String]) -> Result<(), AppError>;
+    fn row(&mut self, row: Vec<serde_json::Value>) -> Result<(), AppError>;
+}
+
+/// Exports and other streaming reads get far longer than interactive queries.
+const STREAM_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+/// Outcome of one statement in a transactional batch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatementOutcome {
+    pub index: usize,
+    pub sql: String,
+    pub success: bool,
+    /// Affected rows for data-modifying statements; `None` for statements
+    /// that returned rows or where the backend doesn't report a count.
+    pub rows_affected: Option<u64>,
+    pub error: Option<String>,
+}
+
+impl StatementOutcome {
+    fn ok(index: usize, sql: &str, rows_affected: Option<u64>) -> Self {
+        Self {
+            index,
+            sql: sql.to_string(),
+            success: true,
+            rows_affected,
+            error: None,
+        }
+    }
+
+    fn failed(index: usize, sql: &str, error: String) -> Self {
+        Self {
+            index,
+            sql: sql.to_string(),
+            success: false,
+            rows_affected: None,
+            error: Some(error),
+        }
+    }
+}
+
+/// An SQL function known to a connection, for autocomplete and validation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SqlFunction {
+    pub name: String,
+    /// Argument list and return type, when the catalog provides them cheaply.
+    pub signature: Option<String>,
+    /// False for user-defined functions and stored routines.
+    pub builtin: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ObjectType {
+    Table,
+    Column,
+    Routine,
+}
+
+/// A schema object whose name matched a `search_objects` term.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObjectMatch {
+    pub object_type: ObjectType,
+    /// The table itself, or the table a column belongs to.
+    pub table: Option<String>,
+    pub column: Option<String>,
+    pub routine: Option<String>,
+}
+
+impl ObjectMatch {
+    pub fn table(table: &str) -> Self {
+        Self {
+            object_type: ObjectType::Table,
+            table: Some(table.to_string()),
+            column: None,
+            routine: None,
+        }
+    }
+
+    pub fn column(table: &str, column: &str) -> Self {
+        Self {
+            object_type: ObjectType::Column,
+            table: Some(table.to_string()),
+            column: Some(column.to_string()),
+            routine: None,
+        }
+    }
+
+    pub fn routine(name: &str) -> Self {
+        Self {
+            object_type: ObjectType::Routine,
+            table: None,
+            column: None,
+            routine: Some(name.to_string()),
+        }
+    }
+}
+
+/// Case-insensitive substring search over an already loaded schema.
+pub fn search_schema(schema: &[TableSchema], term: &str) -> Vec<ObjectMatch> {
+    let needle = term.to_lowercase();
+    let mut matches = Vec::new();
+    for table in schema {
+        if table.name.to_lowercase().contains(&needle) {
+            matches.push(ObjectMatch::table(&table.name));
+        }
+        for column in &table.columns {
+            if column.name.to_lowercase().contains(&needle) {
+                matches.push(ObjectMatch::column(&table.name, &column.name));
+            }
+        }
+    }
+    matches
+}
+
+/// `%term%` with LIKE wildcards in the term escaped, so `order_id` does
+/// not also match `orderXid`.
+fn like_contains_pattern(term: &str) -> String {
+    let mut pattern = String::with_capacity(term.len() + 2);
+    pattern.push('%');
+    for c in term.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    pattern.push('%');
+    pattern
+}
+
+/// Turn `(table, column)` rows from a search query into matches; a NULL
+/// column marks a table hit.
+fn object_matches(rows: Vec<(String, Option<String>)>) -> Vec<ObjectMatch> {
+    rows.into_iter()
+        .map(|(table, column)| match column {
+            Some(column) => ObjectMatch::column(&table, &column),
+            None => ObjectMatch::table(&table),
+        })
+        .collect()
+}
+
+/// A server extension returned by `list_extensions`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DbExtension {
+    pub name: String,
+    pub default_version: Option<String>,
+    /// `None` when the extension is available but not installed.
+    pub installed_version: Option<String>,
+    /// Schema the extension's objects live in, once installed.
+    pub schema: Option<String>,
+    pub comment: Option<String>,
+}
+
+/// Per-table storage figures returned by `get_table_size`. Fields a backend
+/// cannot report are left as `None`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TableSizeInfo {
+    pub table: String,
+    /// Everything the table occupies on disk (heap + indexes + TOAST).
+    pub total_bytes: Option<u64>,
+    /// The main table data only.
+    pub table_bytes: Option<u64>,
+    pub index_bytes: Option<u64>,
+    pub toast_bytes: Option<u64>,
+    /// Allocated but unused space (MySQL `DATA_FREE`, SQLite unused page bytes).
+    pub free_bytes: Option<u64>,
+    pub live_rows: Option<u64>,
+    /// Estimated dead tuples awaiting vacuum (PostgreSQL only).
+    pub dead_rows: Option<u64>,
+    pub page_count: Option<u64>,
+}
+
 // ---------------------------------------------------------------------------
 // PostgreSQL adapter
 // ---------------------------------------------------------------------------
 
+/// How many times a statement that failed with a CockroachDB serialization
+/// error (SQLSTATE 40001) is retried before the error is surfaced.
+const CRDB_MAX_RETRIES: u32 = 3;
+
 pub struct PostgresAdapter {
-    client: tokio_postgres::Client,
+    pool: deadpool_postgres::Pool,
     runtime: tokio::runtime::Handle,
+    /// Also bounds cancel requests, which open a connection of their own.
+    connect_timeout: Duration,
+    query_timeout: Duration,
+    /// Set when the server identifies as CockroachDB, which needs different
+    /// row-count introspection and client-side retries.
+    cockroach: bool,
+    /// Cancel requests go over a new connection and need the same TLS
+    /// setup; `None` when TLS is disabled.
+    tls: Option<tokio_postgres_rustls::MakeRustlsConnect>,
+    quote_all_identifiers: bool,
+    /// Role from `set_session_role`, applied to each connection as it is
+    /// checked out of the pool.
+    session_role: RwLock<Option<String>>,
+    /// Set once any role has been applied, after which pooled connections
+    /// may carry a stale one and get `RESET ROLE` on checkout.
+    role_used: std::sync::atomic::AtomicBool,
+    /// Cancel tokens of pooled connections running a query, for
+    /// `cancel_query`.
+    running: Mutex<HashMap<u64, tokio_postgres::CancelToken>>,
+    /// The connection an open `begin_transaction` runs on, kept out of the
+    /// pool until the transaction ends.
+    transaction: tokio::sync::Mutex<Option<deadpool_postgres::Object>>,
+}
+
+/// The connection an operation runs on: the one holding the open
+/// transaction, if any, otherwise one checked out of the pool.
+enum PgClient<'a> {
+    Pooled(Box<deadpool_postgres::Object>),
+    Transaction(tokio::sync::MappedMutexGuard<'a, deadpool_postgres::Object>),
+}
+
+impl PgClient<'_> {
+    fn in_transaction(&self) -> bool {
+        matches!(self, Self::Transaction(_))
+    }
+}
+
+impl std::ops::Deref for PgClient<'_> {
+    type Target = deadpool_postgres::Object;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Self::Pooled(client) => client,
+            Self::Transaction(client) => client,
+        }
+    }
+}
+
+fn no_open_transaction() -> AppError {
+    AppError::QueryError("No transaction is open on this connection".to_string())
+}
+
+fn transaction_already_open() -> AppError {
+    AppError::QueryError("A transaction is already open on this connection".to_string())
+}
+
+/// How `execute_transaction` opens, commits and rolls back its batch: in a
+/// transaction of its own, or under a savepoint when `begin_transaction`
+/// already holds one, which then stays open whatever the batch does.
+fn batch_boundary(in_transaction: bool) -> (&'static str, &'static str, &'static str) {
+    if in_transaction {
+        ("SAVEPOINT arc_batch", "RELEASE SAVEPOINT arc_batch", "ROLLBACK TO SAVEPOINT arc_batch")
+    } else {
+        ("BEGIN", "COMMIT", "ROLLBACK")
+    }
+}
+
+/// Registers a connection's cancel token in `PostgresAdapter::running` for
+/// as long as it lives.
+struct RunningPgQuery<'a> {
+    running: &'a Mutex<HashMap<u64, tokio_postgres::CancelToken>>,
+    id: u64,
+}
+
+impl<'a> RunningPgQuery<'a> {
+    fn start(
+        running: &'a Mutex<HashMap<u64, tokio_postgres::CancelToken>>,
+        client: &tokio_postgres::Client,
+    ) -> Self {
+        static NEXT_ID: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+        let id = NEXT_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        running.lock().unwrap().insert(id, client.cancel_token());
+        Self { running, id }
+    }
+}
+
+impl Drop for RunningPgQuery<'_> {
+    fn drop(&mut self) {
+        self.running.lock().unwrap().remove(&self.id);
+    }
+}
+
+/// CockroachDB aborts contended transactions with SQLSTATE 40001 and expects
+/// the client to retry them.
+fn pg_is_retryable(e: &tokio_postgres::Error) -> bool {
+    e.code() == Some(&tokio_postgres::error::SqlState::T_R_SERIALIZATION_FAILURE)
+}
+
+/// Backoff before retry `attempt` (1-based): 50ms, 100ms, 200ms, ...
+fn crdb_retry_delay(attempt: u32) -> Duration {
+    Duration::from_millis(50 << (attempt - 1).min(5))
 }
 
 /// Extract the full error message from a tokio_postgres::Error,
@@ -152,106 +965,895 @@ fn pg_error_message(e: &tokio_postgres::Error) -> String {
     msg
 }
 
-impl PostgresAdapter {
-    pub fn connect(params: &ConnectParams, rt: tokio::runtime::Handle) -> Result<Self, AppError> {
-        // Single-quote and escape values for libpq connection string format.
-        // Inside single quotes, backslashes escape the next character.
-        fn escape(val: &str) -> String {
-            let escaped = val.replace('\\', "\\\\").replace('\'', "\\'");
-            format!("'{}'", escaped)
-        }
-
-        let sslmode = if params.use_ssl { "require" } else { "disable" };
-        let connect_str = format!(
-            "host={} port={} dbname={} user={} password={} sslmode={}",
-            escape(&params.host),
-            params.port,
-            escape(&params.database),
-            escape(&params.username),
-            escape(&params.password),
-            sslmode,
-        );
+/// Map a query error, reporting server-side cancellation (SQLSTATE 57014)
+/// the same way on every backend.
+fn pg_query_error(e: &tokio_postgres::Error) -> AppError {
+    if e.code() == Some(&tokio_postgres::error::SqlState::QUERY_CANCELED) {
+        return query_cancelled();
+    }
+    AppError::QueryError(pg_error_message(e))
+}
 
-        let client = if params.use_ssl {
-            let mut root_store = rustls::RootCertStore::empty();
-            root_store.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
-            let tls_config = rustls::ClientConfig::builder()
-                .with_root_certificates(root_store)
-                .with_no_client_auth();
-            let tls = tokio_postgres_rustls::MakeRustlsConnect::new(tls_config);
-            rt.block_on(async {
-                let connect_fut = tokio_postgres::connect(&connect_str, tls);
-                let (client, connection) = tokio::time::timeout(CONNECT_TIMEOUT, connect_fut)
-                    .await
-                    .map_err(|_| AppError::ConnectionFailed("Connection timed out".to_string()))?
-                    .map_err(|e| AppError::ConnectionFailed(pg_error_message(&e)))?;
-                tokio::spawn(async move {
-                    if let Err(e) = connection.await {
-                        eprintln!("PostgreSQL connection error: {e}");
-                    }
-                });
-                Ok::<_, AppError>(client)
-            })?
-        } else {
-            rt.block_on(async {
-                let connect_fut = tokio_postgres::connect(&connect_str, tokio_postgres::NoTls);
-                let (client, connection) = tokio::time::timeout(CONNECT_TIMEOUT, connect_fut)
-                    .await
-                    .map_err(|_| AppError::ConnectionFailed("Connection timed out".to_string()))?
-                    .map_err(|e| AppError::ConnectionFailed(pg_error_message(&e)))?;
-                tokio::spawn(async move {
-                    if let Err(e) = connection.await {
-                        eprintln!("PostgreSQL connection error: {e}");
-                    }
-                });
-                Ok::<_, AppError>(client)
-            })?
-        };
+/// Map a failure to check a connection out of the pool.
+fn pg_pool_error(e: deadpool_postgres::PoolError) -> AppError {
+    match e {
+        deadpool_postgres::PoolError::Backend(e) => pg_connect_error(&e),
+        e => AppError::ConnectionFailed(e.to_string()),
+    }
+}
 
-        Ok(Self { client, runtime: rt })
+/// A connect failure whose cause the driver identified.
+fn connect_rejected(status: ConnectionTestStatus, message: impl Into<String>) -> AppError {
+    AppError::ConnectRejected {
+        status,
+        message: message.into(),
     }
 }
 
-impl DatabaseAdapter for PostgresAdapter {
-    fn kind(&self) -> DatabaseKind {
-        DatabaseKind::PostgreSQL
+fn connect_timed_out() -> AppError {
+    connect_rejected(ConnectionTestStatus::Timeout, "Connection timed out")
+}
+
+/// Classify a failed Postgres connect by SQLSTATE, or by an I/O cause for
+/// servers that could not be reached at all.
+fn pg_connect_error(e: &tokio_postgres::Error) -> AppError {
+    use tokio_postgres::error::SqlState;
+    let message = pg_error_message(e);
+    let auth = [SqlState::INVALID_PASSWORD, SqlState::INVALID_AUTHORIZATION_SPECIFICATION];
+    if e.code().is_some_and(|code| auth.contains(code)) {
+        connect_rejected(ConnectionTestStatus::AuthFailed, message)
+    } else if std::error::Error::source(e).is_some_and(|s| s.is::<std::io::Error>()) {
+        connect_rejected(ConnectionTestStatus::Unreachable, message)
+    } else {
+        AppError::ConnectionFailed(message)
     }
+}
 
-    fn test_connection(&self) -> Result<bool, AppError> {
-        self.runtime
-            .block_on(async {
-                tokio::time::timeout(QUERY_TIMEOUT, async {
-                    self.client
-                        .simple_query("SELECT 1")
-                        .await
-                        .map(|_| true)
-                        .map_err(|e| AppError::ConnectionFailed(e.to_string()))
-                })
-                .await
-                .map_err(|_| AppError::ConnectionFailed("Operation timed out".to_string()))?
-            })
+/// SQL condition excluding the system schemas (Postgres' catalogs, TOAST
+/// and temp schemas, CockroachDB's virtual ones) on `column`.
+fn pg_user_schema(column: &str) -> String {
+    format!(
+        "{column} NOT IN ('information_schema', 'crdb_internal', 'pg_extension') \
+         AND {column} NOT LIKE 'pg\\_%'"
+    )
+}
+
+/// How a Postgres table is named across Arc: bare for `public`, otherwise
+/// `schema.table`.
+fn pg_qualified_name(schema: &str, table: &str) -> String {
+    if schema == "public" {
+        table.to_string()
+    } else {
+        format!("{schema}.{table}")
     }
+}
 
-    fn get_schema(&self) -> Result<Vec<TableSchema>, AppError> {
-        self.runtime.block_on(async {
-            tokio::time::timeout(QUERY_TIMEOUT, async {
-                let rows = self
-                    .client
-                    .query(
-                        "SELECT table_name FROM information_schema.tables
-                         WHERE table_schema = 'public' AND table_type = 'BASE TABLE'
-                         ORDER BY table_name",
-                        &[],
-                    )
+/// The table's own name within `schema`, from its `pg_qualified_name`.
+/// The schema is known, so a dot in a `public` table's name is kept.
+fn pg_unqualified_name<'n>(schema: &str, name: &'n str) -> &'n str {
+    if schema == "public" {
+        return name;
+    }
+    name.strip_prefix(schema)
+        .and_then(|rest| rest.strip_prefix('.'))
+        .unwrap_or(name)
+}
+
+/// Every user table as its schema and name, `public` first and then the
+/// other schemas alphabetically.
+async fn pg_table_refs(client: &tokio_postgres::Client) -> Result<Vec<(String, String)>, AppError> {
+    let sql = format!(
+        "SELECT table_schema, table_name FROM information_schema.tables
+         WHERE {} AND table_type = 'BASE TABLE'
+         ORDER BY table_schema <> 'public', table_schema, table_name",
+        pg_user_schema("table_schema")
+    );
+    let rows = client
+        .query(&sql, &[])
+        .await
+        .map_err(|e| AppError::QueryError(e.to_string()))?;
+    Ok(rows
+        .iter()
+        .filter_map(|r| Some((r.try_get(0).ok()?, r.try_get(1).ok()?)))
+        .collect())
+}
+
+/// libpq-style connection string for `params`.
+fn pg_connect_string(params: &ConnectParams) -> String {
+    // Single-quote and escape values for libpq connection string format.
+    // Inside single quotes, backslashes escape the next character.
+    fn escape(val: &str) -> String {
+        let escaped = val.replace('\\', "\\\\").replace('\'', "\\'");
+        format!("'{}'", escaped)
+    }
+
+    // tokio-postgres only knows disable/prefer/require; certificate checks
+    // for the verify modes are done by the rustls config from `pg_tls`.
+    let sslmode = match params.ssl_mode() {
+        SslMode::Disable => "disable",
+        SslMode::Require | SslMode::VerifyCa | SslMode::VerifyFull => "require",
+    };
+    format!(
+        "host={} port={} dbname={} user={} password={} sslmode={}",
+        escape(&params.host),
+        params.port,
+        escape(&params.database),
+        escape(&params.username),
+        escape(&params.password),
+        sslmode,
+    )
+}
+
+/// Accepts any server certificate, for `sslmode=require`, which encrypts
+/// without authenticating the server. Handshake signatures are still
+/// checked.
+#[derive(Debug)]
+struct UnverifiedServerCert(Arc<rustls::crypto::CryptoProvider>);
+
+impl rustls::client::danger::ServerCertVerifier for UnverifiedServerCert {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &rustls::pki_types::CertificateDer<'_>,
+        _intermediates: &[rustls::pki_types::CertificateDer<'_>],
+        _server_name: &rustls::pki_types::ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: rustls::pki_types::UnixTime,
+    ) -> Result<rustls::client::danger::ServerCertVerified, rustls::Error> {
+        Ok(rustls::client::danger::ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &rustls::pki_types::CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &rustls::pki_types::CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+/// Checks the certificate chain but tolerates a host name mismatch, for
+/// `sslmode=verify-ca`.
+#[derive(Debug)]
+struct CaOnlyVerifier(Arc<rustls::client::WebPkiServerVerifier>);
+
+impl rustls::client::danger::ServerCertVerifier for CaOnlyVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &rustls::pki_types::CertificateDer<'_>,
+        intermediates: &[rustls::pki_types::CertificateDer<'_>],
+        server_name: &rustls::pki_types::ServerName<'_>,
+        ocsp_response: &[u8],
+        now: rustls::pki_types::UnixTime,
+    ) -> Result<rustls::client::danger::ServerCertVerified, rustls::Error> {
+        use rustls::CertificateError;
+        // The name is checked only once the chain has verified, so a name
+        // error means the chain itself is good.
+        match self
+            .0
+            .verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now)
+        {
+            Err(rustls::Error::InvalidCertificate(
+                CertificateError::NotValidForName | CertificateError::NotValidForNameContext { .. },
+            )) => Ok(rustls::client::danger::ServerCertVerified::assertion()),
+            result => result,
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &rustls::pki_types::CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        self.0.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &rustls::pki_types::CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        self.0.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+        self.0.supported_verify_schemes()
+    }
+}
+
+/// Trusted roots: the certificates in `ca_cert_path` if given, else the
+/// bundled webpki roots.
+fn tls_root_store(ca_cert_path: Option<&str>) -> Result<rustls::RootCertStore, AppError> {
+    use rustls::pki_types::pem::PemObject;
+
+    let mut roots = rustls::RootCertStore::empty();
+    let Some(path) = ca_cert_path else {
+        roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+        return Ok(roots);
+    };
+    let unreadable = |e: rustls::pki_types::pem::Error| {
+        AppError::ConnectionFailed(format!("Cannot read CA certificate {path}: {e}"))
+    };
+    for cert in rustls::pki_types::CertificateDer::pem_file_iter(path).map_err(unreadable)? {
+        roots.add(cert.map_err(unreadable)?).map_err(|e| {
+            AppError::ConnectionFailed(format!("Invalid CA certificate in {path}: {e}"))
+        })?;
+    }
+    if roots.is_empty() {
+        return Err(AppError::ConnectionFailed(format!(
+            "No certificates found in {path}"
+        )));
+    }
+    Ok(roots)
+}
+
+/// Client certificate chain and key from `params`, if mutual TLS is set up.
+fn tls_client_identity(
+    params: &ConnectParams,
+) -> Result<
+    Option<(
+        Vec<rustls::pki_types::CertificateDer<'static>>,
+        rustls::pki_types::PrivateKeyDer<'static>,
+    )>,
+    AppError,
+> {
+    use rustls::pki_types::pem::PemObject;
+
+    let (cert_path, key_path) = match (&params.client_cert_path, &params.client_key_path) {
+        (Some(cert), Some(key)) => (cert, key),
+        (None, None) => return Ok(None),
+        _ => {
+            return Err(AppError::ConnectionFailed(
+                "A client certificate needs both a certificate and a key file".to_string(),
+            ))
+        }
+    };
+    let certs = rustls::pki_types::CertificateDer::pem_file_iter(cert_path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| {
+            AppError::ConnectionFailed(format!("Cannot read client certificate {cert_path}: {e}"))
+        })?;
+    if certs.is_empty() {
+        return Err(AppError::ConnectionFailed(format!(
+            "No certificates found in {cert_path}"
+        )));
+    }
+    let key = rustls::pki_types::PrivateKeyDer::from_pem_file(key_path).map_err(|e| {
+        AppError::ConnectionFailed(format!("Cannot read client key {key_path}: {e}"))
+    })?;
+    Ok(Some((certs, key)))
+}
+
+/// TLS connector for `params.ssl_mode()`, or `None` when TLS is disabled.
+fn pg_tls(
+    params: &ConnectParams,
+) -> Result<Option<tokio_postgres_rustls::MakeRustlsConnect>, AppError> {
+    let mode = params.ssl_mode();
+    if mode == SslMode::Disable {
+        return Ok(None);
+    }
+    let roots = tls_root_store(params.ca_cert_path.as_deref())?;
+    let builder = rustls::ClientConfig::builder().with_root_certificates(roots.clone());
+    let mut tls_config = match tls_client_identity(params)? {
+        Some((certs, key)) => builder
+            .with_client_auth_cert(certs, key)
+            .map_err(|e| AppError::ConnectionFailed(format!("Invalid client certificate: {e}")))?,
+        None => builder.with_no_client_auth(),
+    };
+    let provider = tls_config.crypto_provider().clone();
+    match mode {
+        SslMode::Require => {
+            tls_config
+                .dangerous()
+                .set_certificate_verifier(Arc::new(UnverifiedServerCert(provider)));
+        }
+        SslMode::VerifyCa => {
+            let verifier = rustls::client::WebPkiServerVerifier::builder_with_provider(
+                Arc::new(roots),
+                provider,
+            )
+            .build()
+            .map_err(|e| AppError::ConnectionFailed(e.to_string()))?;
+            tls_config
+                .dangerous()
+                .set_certificate_verifier(Arc::new(CaOnlyVerifier(verifier)));
+        }
+        SslMode::VerifyFull | SslMode::Disable => {}
+    }
+    Ok(Some(tokio_postgres_rustls::MakeRustlsConnect::new(
+        tls_config,
+    )))
+}
+
+impl PostgresAdapter {
+    pub fn connect(params: &ConnectParams, rt: tokio::runtime::Handle) -> Result<Self, AppError> {
+        let config: tokio_postgres::Config = pg_connect_string(params)
+            .parse()
+            .map_err(|e: tokio_postgres::Error| AppError::ConnectionFailed(pg_error_message(&e)))?;
+        let manager_config = deadpool_postgres::ManagerConfig {
+            recycling_method: deadpool_postgres::RecyclingMethod::Fast,
+        };
+        let tls = pg_tls(params)?;
+        let manager = match tls.clone() {
+            Some(tls) => deadpool_postgres::Manager::from_config(config, tls, manager_config),
+            None => deadpool_postgres::Manager::from_config(config, tokio_postgres::NoTls, manager_config),
+        };
+        let pool = deadpool_postgres::Pool::builder(manager)
+            .max_size(params.pool_size.max(1))
+            .runtime(deadpool_postgres::Runtime::Tokio1)
+            .build()
+            .map_err(|e| AppError::ConnectionFailed(e.to_string()))?;
+
+        // Open the first connection now so bad credentials fail the connect
+        // rather than the first query. CockroachDB speaks the Postgres
+        // protocol; tell it apart by version().
+        let version: String = rt.block_on(async {
+            let client = tokio::time::timeout(params.connect_timeout(), pool.get())
+                .await
+                .map_err(|_| connect_timed_out())?
+                .map_err(pg_pool_error)?;
+            Ok::<_, AppError>(
+                tokio::time::timeout(params.query_timeout(), client.query_one("SELECT version()", &[]))
                     .await
-                    .map_err(|e| AppError::QueryError(e.to_string()))?;
+                    .ok()
+                    .and_then(|row| row.ok())
+                    .and_then(|row| row.try_get(0).ok())
+                    .unwrap_or_default(),
+            )
+        })?;
+        let cockroach = params.kind == DatabaseKind::CockroachDB || version.contains("CockroachDB");
+
+        Ok(Self {
+            pool,
+            runtime: rt,
+            cockroach,
+            connect_timeout: params.connect_timeout(),
+            query_timeout: params.query_timeout(),
+            tls,
+            quote_all_identifiers: params.quote_all_identifiers,
+            session_role: RwLock::new(None),
+            role_used: std::sync::atomic::AtomicBool::new(false),
+            running: Mutex::new(HashMap::new()),
+            transaction: tokio::sync::Mutex::new(None),
+        })
+    }
+
+    /// The connection for the next operation: the open transaction's while
+    /// there is one, so its statements all see each other.
+    async fn client(&self) -> Result<PgClient<'_>, AppError> {
+        let held = self.transaction.lock().await;
+        if held.is_some() {
+            return Ok(PgClient::Transaction(tokio::sync::MutexGuard::map(held, |c| {
+                c.as_mut().unwrap()
+            })));
+        }
+        drop(held);
+        Ok(PgClient::Pooled(Box::new(self.pooled_client().await?)))
+    }
+
+    /// Ask the server to cancel whatever `token`'s connection is running.
+    async fn send_cancel(&self, token: tokio_postgres::CancelToken) -> Result<(), AppError> {
+        let cancel = async {
+            match &self.tls {
+                Some(tls) => token.cancel_query(tls.clone()).await,
+                None => token.cancel_query(tokio_postgres::NoTls).await,
+            }
+        };
+        tokio::time::timeout(self.connect_timeout, cancel)
+            .await
+            .map_err(|_| AppError::ConnectionFailed("Cancel request timed out".to_string()))?
+            .map_err(|e| AppError::ConnectionFailed(pg_error_message(&e)))
+    }
+
+    /// Whether a statement that failed on `client` should be retried.
+    /// Only CockroachDB asks for retries, and never inside a transaction
+    /// opened with `begin_transaction`: by then the whole transaction has
+    /// been aborted, not just the one statement.
+    fn should_retry(&self, client: &PgClient<'_>, e: &tokio_postgres::Error, attempt: u32) -> bool {
+        self.cockroach
+            && !client.in_transaction()
+            && pg_is_retryable(e)
+            && attempt < CRDB_MAX_RETRIES
+    }
+
+    /// The schema and table that `name` (as listed by `list_table_names`)
+    /// refers to. Looked up in the catalog rather than split on the first
+    /// `.`, since a `public` table's name may itself contain a dot.
+    fn table_ref(&self, name: &str) -> Result<(String, String), AppError> {
+        self.runtime.block_on(async {
+            tokio::time::timeout(self.query_timeout, async {
+                let client = self.pooled_client().await?;
+                pg_table_refs(&client)
+                    .await?
+                    .into_iter()
+                    .find(|(schema, table)| pg_qualified_name(schema, table) == name)
+                    .ok_or_else(|| AppError::NotFound(format!("Table {name} not found")))
+            })
+            .await
+            .map_err(|_| execution_timeout(self.query_timeout))?
+        })
+    }
+
+    /// `table` in `schema`, left unqualified in `public` as it is listed.
+    fn quote_schema_table(&self, schema: &str, table: &str) -> String {
+        if schema == "public" {
+            self.quote_ident(table)
+        } else {
+            format!("{}.{}", self.quote_ident(schema), self.quote_ident(table))
+        }
+    }
+
+    /// Check a connection out of the pool, giving up after `ACQUIRE_TIMEOUT`,
+    /// and put it in the session role chosen with `set_session_role` (or
+    /// back in the login role, if one was chosen before).
+    async fn pooled_client(&self) -> Result<deadpool_postgres::Object, AppError> {
+        let client =
+            acquire_within(ACQUIRE_TIMEOUT, "waiting for a pooled connection", self.pool.get())
+                .await?
+                .map_err(pg_pool_error)?;
+
+        let role = self.session_role.read().unwrap().clone();
+        let sql = match role {
+            Some(role) => format!("SET ROLE {}", self.quote_ident(&role)),
+            None if self.role_used.load(std::sync::atomic::Ordering::Relaxed) => {
+                "RESET ROLE".to_string()
+            }
+            None => return Ok(client),
+        };
+        client
+            .batch_execute(&sql)
+            .await
+            .map_err(|e| AppError::QueryError(pg_error_message(&e)))?;
+        Ok(client)
+    }
+
+    /// Run `sql` (COMMIT or ROLLBACK) on the transaction's connection and
+    /// hand the connection back to the pool, whether or not it succeeds.
+    fn end_transaction(&self, sql: &str) -> Result<(), AppError> {
+        self.runtime.block_on(async {
+            let client = self
+                .transaction
+                .lock()
+                .await
+                .take()
+                .ok_or_else(no_open_transaction)?;
+            tokio::time::timeout(self.query_timeout, client.batch_execute(sql))
+                .await
+                .map_err(|_| execution_timeout(self.query_timeout))?
+                .map_err(|e| AppError::QueryError(pg_error_message(&e)))
+        })
+    }
+
+    /// Estimated row counts per table (keyed by qualified name) from
+    /// CockroachDB's `SHOW TABLES`, which (unlike `pg_class.reltuples`) is
+    /// kept up to date by its stats jobs.
+    async fn crdb_row_counts(
+        &self,
+        client: &tokio_postgres::Client,
+    ) -> Result<HashMap<String, i64>, AppError> {
+        let rows = client
+            .query(
+                "SELECT schema_name, table_name, COALESCE(estimated_row_count, 0)::INT8
+                 FROM [SHOW TABLES] WHERE type = 'table'",
+                &[],
+            )
+            .await
+            .map_err(|e| AppError::QueryError(pg_error_message(&e)))?;
+        Ok(rows
+            .iter()
+            .filter_map(|r| {
+                let name = pg_qualified_name(r.try_get(0).ok()?, r.try_get(1).ok()?);
+                Some((name, r.try_get(2).ok()?))
+            })
+            .collect())
+    }
+
+    /// Run extension DDL, refusing up front on a read-only server (a hot
+    /// standby, or `default_transaction_read_only`) so the user gets a clear
+    /// reason instead of a generic permission error.
+    fn extension_ddl(&self, sql: &str) -> Result<(), AppError> {
+        self.runtime.block_on(async {
+            tokio::time::timeout(self.query_timeout, async {
+                let client = self.client().await?;
+                let read_only: String = client
+                    .query_one("SELECT current_setting('transaction_read_only')", &[])
+                    .await
+                    .and_then(|row| row.try_get(0))
+                    .map_err(|e| AppError::QueryError(pg_error_message(&e)))?;
+                if read_only == "on" {
+                    return Err(AppError::QueryError(
+                        "The server is read-only; extensions cannot be changed".to_string(),
+                    ));
+                }
+                client
+                    .batch_execute(sql)
+                    .await
+                    .map_err(|e| AppError::QueryError(pg_error_message(&e)))
+            })
+            .await
+            .map_err(|_| execution_timeout(self.query_timeout))?
+        })
+    }
+
+    async fn prepare(
+        &self,
+        client: &deadpool_postgres::Object,
+        sql: &str,
+    ) -> Result<tokio_postgres::Statement, AppError> {
+        acquire_within(ACQUIRE_TIMEOUT, "preparing the statement", client.prepare_cached(sql))
+            .await?
+            .map_err(|e| AppError::QueryError(pg_error_message(&e)))
+    }
+
+    async fn query_once(
+        &self,
+        client: &tokio_postgres::Client,
+        stmt: &tokio_postgres::Statement,
+        params: &[&(dyn tokio_postgres::types::ToSql + Sync)],
+        max_rows: Option<usize>,
+        encoding: BinaryEncoding,
+    ) -> Result<QueryResult, tokio_postgres::Error> {
+        use futures_util::TryStreamExt;
+
+        let start = std::time::Instant::now();
+        let stream = client.query_raw(stmt, params.iter().copied()).await?;
+        futures_util::pin_mut!(stream);
+
+        // Rows are decoded as they arrive so the cap bounds memory;
+        // dropping the stream discards whatever the server still sends.
+        let mut result_rows: Vec<Vec<serde_json::Value>> = Vec::new();
+        let mut truncated = false;
+        while let Some(row) = stream.try_next().await? {
+            if max_rows.is_some_and(|max| result_rows.len() >= max) {
+                truncated = true;
+                break;
+            }
+            result_rows.push(pg_row_to_json(stmt, &row, encoding));
+        }
+        Ok(pg_query_result(stmt, result_rows, truncated, start))
+    }
+
+    /// Run `stmt` through a portal the server executes only as far as
+    /// `max_rows` + 1 rows, so a capped query over a huge table stops there
+    /// instead of sending every row to be discarded. Portals live inside a
+    /// transaction, so this opens one of its own.
+    async fn query_portal(
+        &self,
+        client: &mut deadpool_postgres::Object,
+        stmt: &tokio_postgres::Statement,
+        max_rows: usize,
+        encoding: BinaryEncoding,
+    ) -> Result<QueryResult, tokio_postgres::Error> {
+        let start = std::time::Instant::now();
+        let transaction = client.transaction().await?;
+        let portal = transaction.bind(stmt, &[]).await?;
+        let fetch = i32::try_from(max_rows.saturating_add(1)).unwrap_or(i32::MAX);
+        let mut rows = transaction.query_portal(&portal, fetch).await?;
+        drop(portal);
+        transaction.commit().await?;
+
+        let truncated = rows.len() > max_rows;
+        rows.truncate(max_rows);
+        let rows = rows.iter().map(|row| pg_row_to_json(stmt, row, encoding)).collect();
+        Ok(pg_query_result(stmt, rows, truncated, start))
+    }
+
+    /// Run a query on `client`, capped at `max_rows`. Queries that return
+    /// rows on a pooled connection go through a portal; inside a
+    /// `begin_transaction` (which a portal's own transaction would end) and
+    /// on CockroachDB they are streamed and cut off client-side.
+    async fn query_capped(
+        &self,
+        client: &mut PgClient<'_>,
+        stmt: &tokio_postgres::Statement,
+        max_rows: Option<usize>,
+        encoding: BinaryEncoding,
+    ) -> Result<QueryResult, tokio_postgres::Error> {
+        match (max_rows, client) {
+            (Some(max_rows), PgClient::Pooled(pooled))
+                if !self.cockroach && !stmt.columns().is_empty() =>
+            {
+                self.query_portal(pooled, stmt, max_rows, encoding).await
+            }
+            (max_rows, client) => self.query_once(client, stmt, &[], max_rows, encoding).await,
+        }
+    }
+}
+
+/// One row of `stmt`'s result as JSON values.
+fn pg_row_to_json(
+    stmt: &tokio_postgres::Statement,
+    row: &tokio_postgres::Row,
+    encoding: BinaryEncoding,
+) -> Vec<serde_json::Value> {
+    stmt.columns()
+        .iter()
+        .enumerate()
+        .map(|(i, col)| pg_value_to_json(row, i, col.type_(), encoding))
+        .collect()
+}
+
+/// A `QueryResult` for `rows` of `stmt`, timed from `start`.
+fn pg_query_result(
+    stmt: &tokio_postgres::Statement,
+    rows: Vec<Vec<serde_json::Value>>,
+    truncated: bool,
+    start: std::time::Instant,
+) -> QueryResult {
+    let columns = stmt.columns();
+    QueryResult {
+        columns: columns.iter().map(|c| c.name().to_string()).collect(),
+        row_count: rows.len(),
+        rows,
+        execution_time_ms: start.elapsed().as_millis() as u64,
+        truncated,
+        applied_limit: None,
+        environment: None,
+        columns_binary: columns
+            .iter()
+            .map(|c| *c.type_() == tokio_postgres::types::Type::BYTEA)
+            .collect(),
+        column_types: columns.iter().map(|c| c.type_().name().to_string()).collect(),
+        warning: None,
+    }
+}
+
+/// A notification delivered to a `PgListener`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PgNotification {
+    pub channel: String,
+    pub payload: String,
+    pub process_id: i32,
+}
+
+/// A dedicated Postgres connection that `LISTEN`s on one channel. It is
+/// separate from the adapter's query connection so notifications keep
+/// arriving while queries run. Dropping the listener closes it.
+pub struct PgListener {
+    client: tokio_postgres::Client,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl PgListener {
+    /// Connect with `params`, `LISTEN` on `channel` and call `on_notify` for
+    /// every notification until the listener is dropped or the connection
+    /// drops.
+    pub fn start<F>(
+        params: &ConnectParams,
+        channel: &str,
+        rt: tokio::runtime::Handle,
+        on_notify: F,
+    ) -> Result<Self, AppError>
+    where
+        F: Fn(PgNotification) + Send + 'static,
+    {
+        let connect_str = pg_connect_string(params);
+        let (client, task) = if let Some(tls) = pg_tls(params)? {
+            rt.block_on(async {
+                let connect_fut = tokio_postgres::connect(&connect_str, tls);
+                let (client, connection) = tokio::time::timeout(params.connect_timeout(), connect_fut)
+                    .await
+                    .map_err(|_| AppError::ConnectionFailed("Connection timed out".to_string()))?
+                    .map_err(|e| AppError::ConnectionFailed(pg_error_message(&e)))?;
+                Ok::<_, AppError>((client, tokio::spawn(pg_forward_notifications(connection, on_notify))))
+            })?
+        } else {
+            rt.block_on(async {
+                let connect_fut = tokio_postgres::connect(&connect_str, tokio_postgres::NoTls);
+                let (client, connection) = tokio::time::timeout(params.connect_timeout(), connect_fut)
+                    .await
+                    .map_err(|_| AppError::ConnectionFailed("Connection timed out".to_string()))?
+                    .map_err(|e| AppError::ConnectionFailed(pg_error_message(&e)))?;
+                Ok::<_, AppError>((client, tokio::spawn(pg_forward_notifications(connection, on_notify))))
+            })?
+        };
+
+        let listen_sql = format!(
+            "LISTEN {}",
+            crate::dialect::quote_ident(DatabaseKind::PostgreSQL, channel, false)
+        );
+        let listened = rt.block_on(async {
+            tokio::time::timeout(params.query_timeout(), client.batch_execute(&listen_sql))
+                .await
+                .map_err(|_| AppError::ConnectionFailed("Operation timed out".to_string()))?
+                .map_err(|e| AppError::QueryError(pg_error_message(&e)))
+        });
+        if let Err(e) = listened {
+            task.abort();
+            return Err(e);
+        }
+
+        Ok(Self { client, task })
+    }
+
+    /// Whether the listening connection is still open.
+    pub fn is_alive(&self) -> bool {
+        !self.task.is_finished() && !self.client.is_closed()
+    }
+}
+
+impl Drop for PgListener {
+    fn drop(&mut self) {
+        // Aborting the task drops the connection half; the server ends the
+        // session, which also releases the LISTEN.
+        self.task.abort();
+    }
+}
+
+/// Drive a listener connection, forwarding notifications until it closes.
+async fn pg_forward_notifications<S, T, F>(mut connection: tokio_postgres::Connection<S, T>, on_notify: F)
+where
+    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+    T: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+    F: Fn(PgNotification),
+{
+    use futures_util::StreamExt;
+    let messages = futures_util::stream::poll_fn(move |cx| connection.poll_message(cx));
+    futures_util::pin_mut!(messages);
+    while let Some(message) = messages.next().await {
+        match message {
+            Ok(tokio_postgres::AsyncMessage::Notification(n)) => on_notify(PgNotification {
+                channel: n.channel().to_string(),
+                payload: n.payload().to_string(),
+                process_id: n.process_id(),
+            }),
+            Ok(_) => {}
+            Err(e) => {
+                eprintln!("PostgreSQL listener connection error: {e}");
+                break;
+            }
+        }
+    }
+}
+
+impl DatabaseAdapter for PostgresAdapter {
+    fn kind(&self) -> DatabaseKind {
+        if self.cockroach {
+            DatabaseKind::CockroachDB
+        } else {
+            DatabaseKind::PostgreSQL
+        }
+    }
+
+    fn quote_all_identifiers(&self) -> bool {
+        self.quote_all_identifiers
+    }
+
+    /// Qualified names are looked up rather than split on the first `.`,
+    /// since a `public` table's name may itself contain a dot.
+    fn quote_table(&self, name: &str) -> String {
+        match self.table_ref(name) {
+            Ok((schema, table)) => self.quote_schema_table(&schema, &table),
+            Err(_) => self.quote_ident(name),
+        }
+    }
+
+    fn quote_listed_table(&self, table: &TableSchema) -> String {
+        match &table.schema {
+            Some(schema) => {
+                self.quote_schema_table(schema, pg_unqualified_name(schema, &table.name))
+            }
+            None => self.quote_table(&table.name),
+        }
+    }
+
+    fn test_connection(&self) -> Result<bool, AppError> {
+        self.runtime
+            .block_on(async {
+                tokio::time::timeout(self.query_timeout, async {
+                    let client = self.pooled_client().await?;
+                    client
+                        .simple_query("SELECT 1")
+                        .await
+                        .map(|_| true)
+                        .map_err(|e| AppError::ConnectionFailed(e.to_string()))
+                })
+                .await
+                .map_err(|_| AppError::ConnectionFailed("Operation timed out".to_string()))?
+            })
+    }
+
+    fn get_schema(&self) -> Result<Vec<TableSchema>, AppError> {
+        self.get_schema_with(&SchemaOptions::default())
+    }
+
+    fn list_table_names(&self) -> Result<Vec<String>, AppError> {
+        self.runtime.block_on(async {
+            tokio::time::timeout(self.query_timeout, async {
+                let client = self.pooled_client().await?;
+                Ok(pg_table_refs(&client)
+                    .await?
+                    .iter()
+                    .map(|(schema, table)| pg_qualified_name(schema, table))
+                    .collect())
+            })
+            .await
+            .map_err(|_| AppError::ConnectionFailed("Operation timed out".to_string()))?
+        })
+    }
+
+    fn search_objects(&self, term: &str) -> Result<Vec<ObjectMatch>, AppError> {
+        let pattern = like_contains_pattern(term);
+        self.runtime.block_on(async {
+            tokio::time::timeout(self.query_timeout, async {
+                let client = self.pooled_client().await?;
+                let qualified = "CASE WHEN t.table_schema = 'public' THEN t.table_name
+                                  ELSE t.table_schema || '.' || t.table_name END::text";
+                let sql = format!(
+                    "SELECT {qualified}, NULL::text
+                     FROM information_schema.tables t
+                     WHERE {user_schema} AND t.table_type = 'BASE TABLE'
+                       AND t.table_name ILIKE $1
+                     UNION ALL
+                     SELECT {qualified}, c.column_name::text
+                     FROM information_schema.columns c
+                     JOIN information_schema.tables t
+                       ON t.table_schema = c.table_schema AND t.table_name = c.table_name
+                     WHERE {user_schema} AND t.table_type = 'BASE TABLE'
+                       AND c.column_name ILIKE $1
+                     ORDER BY 1, 2 NULLS FIRST",
+                    user_schema = pg_user_schema("t.table_schema"),
+                );
+                let rows = client
+                    .query(&sql, &[&pattern])
+                    .await
+                    .map_err(|e| AppError::QueryError(pg_error_message(&e)))?;
+                Ok(object_matches(
+                    rows.iter()
+                        .filter_map(|r| Some((r.try_get(0).ok()?, r.try_get(1).ok()?)))
+                        .collect(),
+                ))
+            })
+            .await
+            .map_err(|_| AppError::ConnectionFailed("Operation timed out".to_string()))?
+        })
+    }
+
+    fn get_schema_with(&self, options: &SchemaOptions) -> Result<Vec<TableSchema>, AppError> {
+        self.runtime.block_on(async {
+            tokio::time::timeout(self.query_timeout, async {
+                let client = self.pooled_client().await?;
+                let table_refs: Vec<(String, String)> = pg_table_refs(&client)
+                    .await?
+                    .into_iter()
+                    .filter(|(schema, table)| options.includes(&pg_qualified_name(schema, table)))
+                    .collect();
+                let crdb_counts = if self.cockroach && !options.exact_counts {
+                    Some(self.crdb_row_counts(&client).await?)
+                } else {
+                    None
+                };
 
                 let mut tables = Vec::new();
-                for row in &rows {
-                    let table_name: String = row.try_get(0).unwrap_or_default();
+                for (schema, table) in &table_refs {
+                    let table_name = pg_qualified_name(schema, table);
 
-                    let col_rows = self
-                        .client
+                    let col_rows = client
                         .query(
                             "SELECT c.column_name, c.data_type, c.is_nullable,
                                     CASE WHEN pk.column_name IS NOT NULL THEN true ELSE false END as is_pk
@@ -261,11 +1863,13 @@ impl DatabaseAdapter for PostgresAdapter {
                                  FROM information_schema.table_constraints tc
                                  JOIN information_schema.key_column_usage kcu
                                      ON tc.constraint_name = kcu.constraint_name
-                                 WHERE tc.table_name = $1 AND tc.constraint_type = 'PRIMARY KEY'
+                                     AND tc.constraint_schema = kcu.constraint_schema
+                                 WHERE tc.table_name = $1 AND tc.table_schema = $2
+                                   AND tc.constraint_type = 'PRIMARY KEY'
                              ) pk ON pk.column_name = c.column_name
-                             WHERE c.table_name = $1 AND c.table_schema = 'public'
+                             WHERE c.table_name = $1 AND c.table_schema = $2
                              ORDER BY c.ordinal_position",
-                            &[&table_name],
+                            &[&table, &schema],
                         )
                         .await
                         .map_err(|e| AppError::QueryError(e.to_string()))?;
@@ -280,21 +1884,46 @@ impl DatabaseAdapter for PostgresAdapter {
                         })
                         .collect();
 
-                    let row_count: i64 = self
-                        .client
-                        .query_one(
-                            "SELECT COALESCE(reltuples, 0)::bigint FROM pg_class WHERE relname = $1",
-                            &[&table_name],
-                        )
-                        .await
-                        .ok()
-                        .and_then(|r| r.try_get(0).ok())
-                        .unwrap_or(0);
+                    // A table whose exact count fails (e.g. for lack of
+                    // SELECT privilege) falls back to the estimate, flagged
+                    // as such.
+                    let exact_count: Option<i64> = if options.exact_counts {
+                        client
+                            .query_one(
+                                &format!(
+                                    "SELECT COUNT(*) FROM {}",
+                                    self.quote_schema_table(schema, table)
+                                ),
+                                &[],
+                            )
+                            .await
+                            .ok()
+                            .and_then(|r| r.try_get(0).ok())
+                    } else {
+                        None
+                    };
+                    let row_count: i64 = match (exact_count, &crdb_counts) {
+                        (Some(count), _) => count,
+                        (None, Some(counts)) => counts.get(&table_name).copied().unwrap_or(0),
+                        (None, None) => client
+                            .query_one(
+                                "SELECT COALESCE(c.reltuples, 0)::bigint FROM pg_class c
+                                 JOIN pg_namespace n ON n.oid = c.relnamespace
+                                 WHERE c.relname = $1 AND n.nspname = $2",
+                                &[&table, &schema],
+                            )
+                            .await
+                            .ok()
+                            .and_then(|r| r.try_get(0).ok())
+                            .unwrap_or(0),
+                    };
 
                     tables.push(TableSchema {
+                        schema: Some(schema.clone()),
                         name: table_name,
                         columns,
                         row_count: row_count.max(0) as u64,
+                        is_estimate: exact_count.is_none(),
                     });
                 }
 
@@ -305,28 +1934,78 @@ impl DatabaseAdapter for PostgresAdapter {
         })
     }
 
+    fn generated_columns(&self, table: &str) -> Result<Vec<String>, AppError> {
+        let (schema, table_name) = self.table_ref(table)?;
+        self.runtime.block_on(async {
+            tokio::time::timeout(self.query_timeout, async {
+                let client = self.pooled_client().await?;
+                let rows = client
+                    .query(
+                        "SELECT column_name::text FROM information_schema.columns
+                         WHERE table_schema = $1 AND table_name = $2
+                           AND (is_identity = 'YES' OR is_generated = 'ALWAYS'
+                                OR column_default LIKE 'nextval(%')",
+                        &[&schema, &table_name],
+                    )
+                    .await
+                    .map_err(|e| AppError::QueryError(pg_error_message(&e)))?;
+                Ok(rows.iter().map(|r| r.get(0)).collect())
+            })
+            .await
+            .map_err(|_| execution_timeout(self.query_timeout))?
+        })
+    }
+
+    /// `information_schema` reports `ARRAY`, `USER-DEFINED` and lengthless
+    /// `character varying`; `format_type` spells the type as declared.
+    fn declared_column_types(&self, table: &str) -> Result<Vec<(String, String)>, AppError> {
+        let (schema, table_name) = self.table_ref(table)?;
+        self.runtime.block_on(async {
+            tokio::time::timeout(self.query_timeout, async {
+                let client = self.pooled_client().await?;
+                let rows = client
+                    .query(
+                        "SELECT a.attname::text, format_type(a.atttypid, a.atttypmod)
+                         FROM pg_attribute a
+                         JOIN pg_class c ON c.oid = a.attrelid
+                         JOIN pg_namespace n ON n.oid = c.relnamespace
+                         WHERE n.nspname = $1 AND c.relname = $2
+                           AND a.attnum > 0 AND NOT a.attisdropped",
+                        &[&schema, &table_name],
+                    )
+                    .await
+                    .map_err(|e| AppError::QueryError(pg_error_message(&e)))?;
+                Ok(rows.iter().map(|r| (r.get(0), r.get(1))).collect())
+            })
+            .await
+            .map_err(|_| execution_timeout(self.query_timeout))?
+        })
+    }
+
     fn get_table_metadata(&self, table: &str) -> Result<TableMetadata, AppError> {
-        let schema_tables = self.get_schema()?;
+        let schema_tables = self.get_schema_with(&SchemaOptions::only(table))?;
         let table_schema = schema_tables
             .into_iter()
             .find(|t| t.name == table)
             .ok_or_else(|| AppError::NotFound(format!("Table {table} not found")))?;
 
-        let table_name = table.to_string();
+        let schema = table_schema.schema.clone().unwrap_or_else(|| "public".to_string());
+        let table_name = pg_unqualified_name(&schema, table).to_string();
         self.runtime.block_on(async {
-            tokio::time::timeout(QUERY_TIMEOUT, async {
-                let idx_rows = self
-                    .client
+            tokio::time::timeout(self.query_timeout, async {
+                let client = self.pooled_client().await?;
+                let idx_rows = client
                     .query(
                         "SELECT i.relname, array_agg(a.attname ORDER BY x.n), ix.indisunique
                          FROM pg_index ix
                          JOIN pg_class t ON t.oid = ix.indrelid
+                         JOIN pg_namespace n ON n.oid = t.relnamespace
                          JOIN pg_class i ON i.oid = ix.indexrelid
                          JOIN LATERAL unnest(ix.indkey) WITH ORDINALITY AS x(attnum, n) ON true
                          JOIN pg_attribute a ON a.attrelid = t.oid AND a.attnum = x.attnum
-                         WHERE t.relname = $1
+                         WHERE t.relname = $1 AND n.nspname = $2
                          GROUP BY i.relname, ix.indisunique",
-                        &[&table_name],
+                        &[&table_name, &schema],
                     )
                     .await
                     .map_err(|e| AppError::QueryError(e.to_string()))?;
@@ -340,35 +2019,412 @@ impl DatabaseAdapter for PostgresAdapter {
                     })
                     .collect();
 
-                let fk_rows = self
-                    .client
+                let fk_rows = client
                     .query(
-                        "SELECT tc.constraint_name, kcu.column_name, ccu.table_name, ccu.column_name
+                        "SELECT tc.constraint_name, kcu.column_name, ccu.table_schema, ccu.table_name,
+                                ccu.column_name
                          FROM information_schema.table_constraints tc
                          JOIN information_schema.key_column_usage kcu
                              ON tc.constraint_name = kcu.constraint_name
+                             AND tc.constraint_schema = kcu.constraint_schema
                          JOIN information_schema.constraint_column_usage ccu
                              ON tc.constraint_name = ccu.constraint_name
-                         WHERE tc.table_name = $1 AND tc.constraint_type = 'FOREIGN KEY'",
-                        &[&table_name],
+                             AND tc.constraint_schema = ccu.constraint_schema
+                         WHERE tc.table_name = $1 AND tc.table_schema = $2
+                           AND tc.constraint_type = 'FOREIGN KEY'",
+                        &[&table_name, &schema],
                     )
                     .await
                     .map_err(|e| AppError::QueryError(e.to_string()))?;
 
-                let foreign_keys: Vec<ForeignKeyInfo> = fk_rows
-                    .iter()
-                    .map(|r| ForeignKeyInfo {
-                        name: r.get(0),
-                        from_column: r.get(1),
-                        to_table: r.get(2),
-                        to_column: r.get(3),
-                    })
-                    .collect();
-
-                Ok(TableMetadata {
-                    schema: table_schema,
-                    indexes,
-                    foreign_keys,
+                let foreign_keys: Vec<ForeignKeyInfo> = fk_rows
+                    .iter()
+                    .map(|r| ForeignKeyInfo {
+                        name: r.get(0),
+                        from_column: r.get(1),
+                        to_table: pg_qualified_name(r.get(2), r.get(3)),
+                        to_column: r.get(4),
+                    })
+                    .collect();
+
+                Ok(TableMetadata {
+                    schema: table_schema,
+                    indexes,
+                    foreign_keys,
+                })
+            })
+            .await
+            .map_err(|_| AppError::ConnectionFailed("Operation timed out".to_string()))?
+        })
+    }
+
+
+    fn get_tables_metadata(
+        &self,
+        tables: &[String],
+    ) -> Result<HashMap<String, TableMetadata>, AppError> {
+        let mut metadata = empty_metadata(self.get_schema_with(&SchemaOptions {
+            tables: Some(tables.to_vec()),
+            ..SchemaOptions::default()
+        })?);
+        if metadata.is_empty() {
+            return Ok(metadata);
+        }
+        let (schemas, names): (Vec<String>, Vec<String>) = metadata
+            .values()
+            .filter_map(|m| {
+                let schema = m.schema.schema.as_deref()?;
+                Some((schema.to_string(), pg_unqualified_name(schema, &m.schema.name).to_string()))
+            })
+            .unzip();
+
+        self.runtime.block_on(async {
+            tokio::time::timeout(self.query_timeout, async {
+                let client = self.pooled_client().await?;
+                let idx_rows = client
+                    .query(
+                        "SELECT n.nspname, t.relname, i.relname, array_agg(a.attname ORDER BY x.n),
+                                ix.indisunique
+                         FROM pg_index ix
+                         JOIN pg_class t ON t.oid = ix.indrelid
+                         JOIN pg_namespace n ON n.oid = t.relnamespace
+                         JOIN pg_class i ON i.oid = ix.indexrelid
+                         JOIN LATERAL unnest(ix.indkey) WITH ORDINALITY AS x(attnum, n) ON true
+                         JOIN pg_attribute a ON a.attrelid = t.oid AND a.attnum = x.attnum
+                         WHERE (n.nspname, t.relname) IN (SELECT * FROM unnest($1::text[], $2::text[]))
+                         GROUP BY n.nspname, t.relname, i.relname, ix.indisunique",
+                        &[&schemas, &names],
+                    )
+                    .await
+                    .map_err(|e| AppError::QueryError(e.to_string()))?;
+                for r in &idx_rows {
+                    if let Some(m) = metadata.get_mut(&pg_qualified_name(r.get(0), r.get(1))) {
+                        m.indexes.push(IndexInfo {
+                            name: r.get(2),
+                            columns: r.get(3),
+                            unique: r.get(4),
+                        });
+                    }
+                }
+
+                let fk_rows = client
+                    .query(
+                        "SELECT tc.table_schema, tc.table_name, tc.constraint_name, kcu.column_name,
+                                ccu.table_schema, ccu.table_name, ccu.column_name
+                         FROM information_schema.table_constraints tc
+                         JOIN information_schema.key_column_usage kcu
+                             ON tc.constraint_name = kcu.constraint_name
+                             AND tc.constraint_schema = kcu.constraint_schema
+                         JOIN information_schema.constraint_column_usage ccu
+                             ON tc.constraint_name = ccu.constraint_name
+                             AND tc.constraint_schema = ccu.constraint_schema
+                         WHERE (tc.table_schema, tc.table_name) IN (SELECT * FROM unnest($1::text[], $2::text[]))
+                           AND tc.constraint_type = 'FOREIGN KEY'",
+                        &[&schemas, &names],
+                    )
+                    .await
+                    .map_err(|e| AppError::QueryError(e.to_string()))?;
+                for r in &fk_rows {
+                    if let Some(m) = metadata.get_mut(&pg_qualified_name(r.get(0), r.get(1))) {
+                        m.foreign_keys.push(ForeignKeyInfo {
+                            name: r.get(2),
+                            from_column: r.get(3),
+                            to_table: pg_qualified_name(r.get(4), r.get(5)),
+                            to_column: r.get(6),
+                        });
+                    }
+                }
+
+                Ok(metadata)
+            })
+            .await
+            .map_err(|_| AppError::ConnectionFailed("Operation timed out".to_string()))?
+        })
+    }
+    fn execute_query(&self, sql: &str) -> Result<QueryResult, AppError> {
+        self.execute_query_limited(sql, None)
+    }
+
+    fn execute_query_limited(&self, sql: &str, max_rows: Option<usize>) -> Result<QueryResult, AppError> {
+        self.execute_query_encoded(sql, max_rows, BinaryEncoding::Placeholder)
+    }
+
+    fn execute_query_encoded(
+        &self,
+        sql: &str,
+        max_rows: Option<usize>,
+        encoding: BinaryEncoding,
+    ) -> Result<QueryResult, AppError> {
+        self.runtime.block_on(async {
+            let mut client = self.client().await?;
+            let _running = RunningPgQuery::start(&self.running, &client);
+            let stmt = self.prepare(&client, sql).await?;
+            tokio::time::timeout(self.query_timeout, async {
+                let mut attempt = 0;
+                loop {
+                    match self.query_capped(&mut client, &stmt, max_rows, encoding).await {
+                        Err(e) if self.should_retry(&client, &e, attempt) => {
+                            attempt += 1;
+                            tokio::time::sleep(crdb_retry_delay(attempt)).await;
+                        }
+                        result => return result.map_err(|e| pg_query_error(&e)),
+                    }
+                }
+            })
+            .await
+            .map_err(|_| execution_timeout(self.query_timeout))?
+        })
+    }
+
+    fn stream_query(
+        &self,
+        sql: &str,
+        encoding: BinaryEncoding,
+        sink: &mut dyn RowSink,
+    ) -> Result<u64, AppError> {
+        self.runtime.block_on(async {
+            let client = self.client().await?;
+            let stmt = self.prepare(&client, sql).await?;
+            tokio::time::timeout(STREAM_TIMEOUT, async {
+                use futures_util::TryStreamExt;
+
+                let stream = client
+                    .query_raw(&stmt, std::iter::empty::<&(dyn tokio_postgres::types::ToSql + Sync)>())
+                    .await
+                    .map_err(|e| AppError::QueryError(pg_error_message(&e)))?;
+                futures_util::pin_mut!(stream);
+
+                let columns: Vec<String> = stmt.columns().iter().map(|c| c.name().to_string()).collect();
+                sink.columns(&columns)?;
+
+                let mut delivered = 0u64;
+                while let Some(row) = stream
+                    .try_next()
+                    .await
+                    .map_err(|e| AppError::QueryError(pg_error_message(&e)))?
+                {
+                    let values = stmt
+                        .columns()
+                        .iter()
+                        .enumerate()
+                        .map(|(i, col)| {
+                            pg_value_to_json(&row, i, col.type_(), encoding)
+                        })
+                        .collect();
+                    sink.row(values)?;
+                    delivered += 1;
+                }
+                Ok(delivered)
+            })
+            .await
+            .map_err(|_| execution_timeout(STREAM_TIMEOUT))?
+        })
+    }
+
+    fn execute_statement(&self, sql: &str) -> Result<u64, AppError> {
+        let sql = sql.to_string();
+        self.runtime.block_on(async {
+            tokio::time::timeout(self.query_timeout, async {
+                let client = self.client().await?;
+                let mut attempt = 0;
+                loop {
+                    match client.execute(&sql, &[]).await {
+                        Err(e) if self.should_retry(&client, &e, attempt) => {
+                            attempt += 1;
+                            tokio::time::sleep(crdb_retry_delay(attempt)).await;
+                        }
+                        result => return result.map_err(|e| pg_query_error(&e)),
+                    }
+                }
+            })
+            .await
+            .map_err(|_| execution_timeout(self.query_timeout))?
+        })
+    }
+
+    fn execute_query_params(
+        &self,
+        sql: &str,
+        params: &[serde_json::Value],
+    ) -> Result<QueryResult, AppError> {
+        self.runtime.block_on(async {
+            let client = self.client().await?;
+            let _running = RunningPgQuery::start(&self.running, &client);
+            let stmt = self.prepare(&client, sql).await?;
+            let bound = pg_params(&stmt, params)?;
+            let refs: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> =
+                bound.iter().map(|p| p.as_ref()).collect();
+            tokio::time::timeout(self.query_timeout, async {
+                let mut attempt = 0;
+                loop {
+                    let placeholder = BinaryEncoding::Placeholder;
+                    match self.query_once(&client, &stmt, &refs, None, placeholder).await {
+                        Err(e) if self.should_retry(&client, &e, attempt) => {
+                            attempt += 1;
+                            tokio::time::sleep(crdb_retry_delay(attempt)).await;
+                        }
+                        result => return result.map_err(|e| pg_query_error(&e)),
+                    }
+                }
+            })
+            .await
+            .map_err(|_| execution_timeout(self.query_timeout))?
+        })
+    }
+
+    fn execute_statement_params(
+        &self,
+        sql: &str,
+        params: &[serde_json::Value],
+    ) -> Result<u64, AppError> {
+        self.runtime.block_on(async {
+            let client = self.client().await?;
+            let stmt = self.prepare(&client, sql).await?;
+            let bound = pg_params(&stmt, params)?;
+            let refs: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> =
+                bound.iter().map(|p| p.as_ref()).collect();
+            tokio::time::timeout(self.query_timeout, async {
+                let mut attempt = 0;
+                loop {
+                    match client.execute(&stmt, &refs).await {
+                        Err(e) if self.should_retry(&client, &e, attempt) => {
+                            attempt += 1;
+                            tokio::time::sleep(crdb_retry_delay(attempt)).await;
+                        }
+                        result => return result.map_err(|e| pg_query_error(&e)),
+                    }
+                }
+            })
+            .await
+            .map_err(|_| execution_timeout(self.query_timeout))?
+        })
+    }
+
+    /// The query timeout applies to each statement rather than the whole
+    /// batch, so a long seed script isn't cut off partway. A statement that
+    /// runs over is cancelled on the server and the batch rolled back, so
+    /// no transaction is left open on the connection.
+    fn execute_transaction(&self, statements: &[String]) -> Result<Vec<StatementOutcome>, AppError> {
+        self.runtime.block_on(async {
+            let client = self.client().await?;
+            let _running = RunningPgQuery::start(&self.running, &client);
+            let (begin, commit, rollback) = batch_boundary(client.in_transaction());
+            let control = |sql: &'static str| async {
+                tokio::time::timeout(self.query_timeout, client.batch_execute(sql))
+                    .await
+                    .map_err(|_| execution_timeout(self.query_timeout))?
+                    .map_err(|e| AppError::QueryError(pg_error_message(&e)))
+            };
+            control(begin).await?;
+
+            let mut outcomes = Vec::with_capacity(statements.len());
+            for (index, sql) in statements.iter().enumerate() {
+                let failure = match tokio::time::timeout(
+                    self.query_timeout,
+                    client.execute(sql.as_str(), &[]),
+                )
+                .await
+                {
+                    Ok(Ok(affected)) => {
+                        outcomes.push(StatementOutcome::ok(index, sql, Some(affected)));
+                        continue;
+                    }
+                    Ok(Err(e)) => pg_error_message(&e),
+                    Err(_) => {
+                        // Still running on the server; stop it so the
+                        // rollback queued behind it can go through.
+                        let _ = self.send_cancel(client.cancel_token()).await;
+                        execution_timeout(self.query_timeout).to_string()
+                    }
+                };
+                outcomes.push(StatementOutcome::failed(index, sql, failure));
+                if control(rollback).await.is_err() {
+                    if let PgClient::Pooled(client) = client {
+                        // Don't hand a connection stuck mid-transaction back
+                        // to the pool.
+                        drop(deadpool_postgres::Object::take(*client));
+                    }
+                }
+                return Ok(outcomes);
+            }
+
+            control(commit).await?;
+            Ok(outcomes)
+        })
+    }
+
+    fn begin_transaction(&self) -> Result<(), AppError> {
+        self.runtime.block_on(async {
+            let mut held = self.transaction.lock().await;
+            if held.is_some() {
+                return Err(transaction_already_open());
+            }
+            let client = self.pooled_client().await?;
+            tokio::time::timeout(self.query_timeout, client.batch_execute("BEGIN"))
+                .await
+                .map_err(|_| execution_timeout(self.query_timeout))?
+                .map_err(|e| AppError::QueryError(pg_error_message(&e)))?;
+            *held = Some(client);
+            Ok(())
+        })
+    }
+
+    fn commit_transaction(&self) -> Result<(), AppError> {
+        self.end_transaction("COMMIT")
+    }
+
+    fn rollback_transaction(&self) -> Result<(), AppError> {
+        self.end_transaction("ROLLBACK")
+    }
+
+    fn in_transaction(&self) -> bool {
+        self.runtime.block_on(self.transaction.lock()).is_some()
+    }
+
+    fn get_stats(&self) -> Result<AdapterStats, AppError> {
+        self.runtime.block_on(async {
+            tokio::time::timeout(self.query_timeout, async {
+                let client = self.pooled_client().await?;
+                if self.cockroach {
+                    // pg_database_size and reltuples aren't meaningful on CockroachDB.
+                    let counts = self.crdb_row_counts(&client).await?;
+                    let disk_usage: i64 = client
+                        .query_one(
+                            "SELECT COALESCE(sum(range_size), 0)::INT8 FROM crdb_internal.ranges
+                             WHERE database_name = current_database()",
+                            &[],
+                        )
+                        .await
+                        .ok()
+                        .and_then(|r| r.try_get(0).ok())
+                        .unwrap_or(0);
+                    return Ok(AdapterStats {
+                        table_count: counts.len() as u32,
+                        total_row_count: counts.values().map(|c| (*c).max(0) as u64).sum(),
+                        disk_usage_bytes: disk_usage.max(0) as u64,
+                    });
+                }
+
+                let sql = format!(
+                    "SELECT
+                        (SELECT count(*)::bigint FROM information_schema.tables
+                         WHERE {} AND table_type = 'BASE TABLE') as table_count,
+                        (SELECT COALESCE(sum(reltuples), 0)::bigint FROM pg_class
+                         JOIN pg_namespace ON pg_namespace.oid = relnamespace
+                         WHERE {} AND relkind = 'r') as total_rows,
+                        (SELECT pg_database_size(current_database())::bigint) as disk_usage",
+                    pg_user_schema("table_schema"),
+                    pg_user_schema("nspname"),
+                );
+                let row = client
+                    .query_one(&sql, &[])
+                    .await
+                    .map_err(|e| AppError::QueryError(e.to_string()))?;
+
+                Ok(AdapterStats {
+                    table_count: row.try_get::<_, i64>(0).unwrap_or(0) as u32,
+                    total_row_count: row.try_get::<_, i64>(1).unwrap_or(0) as u64,
+                    disk_usage_bytes: row.try_get::<_, i64>(2).unwrap_or(0) as u64,
                 })
             })
             .await
@@ -376,86 +2432,179 @@ impl DatabaseAdapter for PostgresAdapter {
         })
     }
 
-    fn execute_query(&self, sql: &str) -> Result<QueryResult, AppError> {
-        let sql = sql.to_string();
+    fn get_table_size(&self, table: &str) -> Result<TableSizeInfo, AppError> {
+        let table_name = table.to_string();
+        let (schema, relname) = self.table_ref(table)?;
         self.runtime.block_on(async {
-            tokio::time::timeout(QUERY_TIMEOUT, async {
-                let start = std::time::Instant::now();
-                let stmt = self
-                    .client
-                    .prepare(&sql)
-                    .await
-                    .map_err(|e| AppError::QueryError(e.to_string()))?;
-
-                let rows = self
-                    .client
-                    .query(&stmt, &[])
+            tokio::time::timeout(self.query_timeout, async {
+                let client = self.pooled_client().await?;
+                let row = client
+                    .query_opt(
+                        "SELECT pg_total_relation_size(c.oid)::bigint,
+                                pg_relation_size(c.oid)::bigint,
+                                pg_indexes_size(c.oid)::bigint,
+                                CASE WHEN c.reltoastrelid = 0 THEN 0
+                                     ELSE pg_total_relation_size(c.reltoastrelid) END::bigint,
+                                s.n_live_tup::bigint,
+                                s.n_dead_tup::bigint,
+                                c.relpages::bigint
+                         FROM pg_class c
+                         JOIN pg_namespace n ON n.oid = c.relnamespace
+                         LEFT JOIN pg_stat_user_tables s ON s.relid = c.oid
+                         WHERE c.relname = $1 AND n.nspname = $2 AND c.relkind IN ('r', 'p', 'm')",
+                        &[&relname, &schema],
+                    )
                     .await
-                    .map_err(|e| AppError::QueryError(e.to_string()))?;
-
-                let elapsed = start.elapsed().as_millis() as u64;
+                    .map_err(|e| AppError::QueryError(pg_error_message(&e)))?
+                    .ok_or_else(|| AppError::NotFound(format!("Table {table_name} not found")))?;
 
-                let columns: Vec<String> = stmt.columns().iter().map(|c| c.name().to_string()).collect();
+                let get = |i: usize| {
+                    row.try_get::<_, Option<i64>>(i)
+                        .ok()
+                        .flatten()
+                        .map(|v| v.max(0) as u64)
+                };
+
+                Ok(TableSizeInfo {
+                    table: table_name.clone(),
+                    total_bytes: get(0),
+                    table_bytes: get(1),
+                    index_bytes: get(2),
+                    toast_bytes: get(3),
+                    free_bytes: None,
+                    live_rows: get(4),
+                    dead_rows: get(5),
+                    page_count: get(6),
+                })
+            })
+            .await
+            .map_err(|_| AppError::ConnectionFailed("Operation timed out".to_string()))?
+        })
+    }
 
-                let mut result_rows: Vec<Vec<serde_json::Value>> = Vec::new();
-                for row in &rows {
-                    let mut values = Vec::new();
-                    for (i, col) in stmt.columns().iter().enumerate() {
-                        let value = pg_value_to_json(row, i, col.type_());
-                        values.push(value);
-                    }
-                    result_rows.push(values);
-                }
+    fn list_functions(&self) -> Result<Vec<SqlFunction>, AppError> {
+        self.runtime.block_on(async {
+            tokio::time::timeout(self.query_timeout, async {
+                let client = self.pooled_client().await?;
+                let rows = client
+                    .query(
+                        "SELECT DISTINCT ON (p.proname, args)
+                                p.proname,
+                                pg_get_function_identity_arguments(p.oid) AS args,
+                                pg_get_function_result(p.oid),
+                                n.nspname = 'pg_catalog'
+                         FROM pg_proc p
+                         JOIN pg_namespace n ON n.oid = p.pronamespace
+                         WHERE n.nspname NOT IN ('information_schema', 'pg_toast')
+                           AND n.nspname NOT LIKE 'pg_temp%'
+                           AND p.proname NOT LIKE '\\_%'
+                         ORDER BY p.proname, args",
+                        &[],
+                    )
+                    .await
+                    .map_err(|e| AppError::QueryError(pg_error_message(&e)))?;
 
-                let row_count = result_rows.len();
-                Ok(QueryResult {
-                    columns,
-                    rows: result_rows,
-                    row_count,
-                    execution_time_ms: elapsed,
-                })
+                Ok(rows
+                    .iter()
+                    .map(|r| {
+                        let name: String = r.try_get(0).unwrap_or_default();
+                        let args: Option<String> = r.try_get(1).ok();
+                        let result: Option<String> = r.try_get(2).ok().flatten();
+                        SqlFunction {
+                            signature: args.map(|a| match result {
+                                Some(ret) => format!("{}({}) → {}", name, a, ret),
+                                None => format!("{}({})", name, a),
+                            }),
+                            name,
+                            builtin: r.try_get(3).unwrap_or(true),
+                        }
+                    })
+                    .collect())
             })
             .await
             .map_err(|_| AppError::ConnectionFailed("Operation timed out".to_string()))?
         })
     }
 
-    fn execute_statement(&self, sql: &str) -> Result<u64, AppError> {
-        let sql = sql.to_string();
+    fn list_extensions(&self) -> Result<Vec<DbExtension>, AppError> {
+        // CockroachDB has no extension mechanism.
+        if self.cockroach {
+            return Ok(Vec::new());
+        }
         self.runtime.block_on(async {
-            tokio::time::timeout(QUERY_TIMEOUT, async {
-                self.client
-                    .execute(&sql, &[])
+            tokio::time::timeout(self.query_timeout, async {
+                let client = self.pooled_client().await?;
+                let rows = client
+                    .query(
+                        "SELECT a.name, a.default_version, e.extversion, n.nspname, a.comment
+                         FROM pg_available_extensions a
+                         LEFT JOIN pg_extension e ON e.extname = a.name
+                         LEFT JOIN pg_namespace n ON n.oid = e.extnamespace
+                         ORDER BY e.extname IS NULL, a.name",
+                        &[],
+                    )
                     .await
-                    .map_err(|e| AppError::QueryError(e.to_string()))
+                    .map_err(|e| AppError::QueryError(pg_error_message(&e)))?;
+
+                Ok(rows
+                    .iter()
+                    .map(|r| DbExtension {
+                        name: r.try_get(0).unwrap_or_default(),
+                        default_version: r.try_get(1).ok().flatten(),
+                        installed_version: r.try_get(2).ok().flatten(),
+                        schema: r.try_get(3).ok().flatten(),
+                        comment: r.try_get(4).ok().flatten(),
+                    })
+                    .collect())
             })
             .await
             .map_err(|_| AppError::ConnectionFailed("Operation timed out".to_string()))?
         })
     }
 
-    fn get_stats(&self) -> Result<AdapterStats, AppError> {
+    fn create_extension(&self, name: &str) -> Result<(), AppError> {
+        if self.cockroach {
+            return Err(AppError::QueryError(
+                "CockroachDB does not support extensions".to_string(),
+            ));
+        }
+        self.extension_ddl(&format!(
+            "CREATE EXTENSION IF NOT EXISTS {}",
+            self.quote_ident(name)
+        ))
+    }
+
+    fn drop_extension(&self, name: &str) -> Result<(), AppError> {
+        if self.cockroach {
+            return Err(AppError::QueryError(
+                "CockroachDB does not support extensions".to_string(),
+            ));
+        }
+        self.extension_ddl(&format!(
+            "DROP EXTENSION IF EXISTS {}",
+            self.quote_ident(name)
+        ))
+    }
+
+    fn get_server_info(&self) -> Result<ServerInfo, AppError> {
         self.runtime.block_on(async {
-            tokio::time::timeout(QUERY_TIMEOUT, async {
-                let row = self
-                    .client
+            tokio::time::timeout(self.query_timeout, async {
+                let client = self.pooled_client().await?;
+                let row = client
                     .query_one(
-                        "SELECT
-                            (SELECT count(*)::bigint FROM information_schema.tables
-                             WHERE table_schema = 'public' AND table_type = 'BASE TABLE') as table_count,
-                            (SELECT COALESCE(sum(reltuples), 0)::bigint FROM pg_class
-                             JOIN pg_namespace ON pg_namespace.oid = relnamespace
-                             WHERE nspname = 'public' AND relkind = 'r') as total_rows,
-                            (SELECT pg_database_size(current_database())::bigint) as disk_usage",
+                        "SELECT version(), current_setting('TimeZone'),
+                                EXTRACT(TIMEZONE FROM now())::int4, current_user::text",
                         &[],
                     )
                     .await
-                    .map_err(|e| AppError::QueryError(e.to_string()))?;
-
-                Ok(AdapterStats {
-                    table_count: row.try_get::<_, i64>(0).unwrap_or(0) as u32,
-                    total_row_count: row.try_get::<_, i64>(1).unwrap_or(0) as u64,
-                    disk_usage_bytes: row.try_get::<_, i64>(2).unwrap_or(0) as u64,
+                    .map_err(|e| AppError::QueryError(pg_error_message(&e)))?;
+
+                Ok(ServerInfo {
+                    kind: self.kind(),
+                    version: row.try_get(0).ok(),
+                    timezone: row.try_get(1).ok(),
+                    utc_offset_seconds: row.try_get(2).ok(),
+                    current_role: row.try_get(3).ok(),
                 })
             })
             .await
@@ -463,8 +2612,58 @@ impl DatabaseAdapter for PostgresAdapter {
         })
     }
 
+    /// Sends a protocol-level cancel request to every pooled connection that
+    /// is currently running a query.
+    fn cancel_query(&self) -> Result<(), AppError> {
+        let tokens: Vec<tokio_postgres::CancelToken> =
+            self.running.lock().unwrap().values().cloned().collect();
+        self.runtime.block_on(async {
+            for token in tokens {
+                self.send_cancel(token).await?;
+            }
+            Ok(())
+        })
+    }
+
+    /// The role is checked on one pooled connection, then remembered and
+    /// applied to each connection as it is checked out, so it sticks to
+    /// every later query until it is reset; a reconnect starts over as the
+    /// login role.
+    fn set_session_role(&self, role: Option<&str>) -> Result<(), AppError> {
+        let Some(role) = role else {
+            *self.session_role.write().unwrap() = None;
+            return Ok(());
+        };
+        let sql = format!("SET ROLE {}", self.quote_ident(role));
+        self.runtime.block_on(async {
+            let client = self.client().await?;
+            tokio::time::timeout(self.query_timeout, client.batch_execute(&sql))
+                .await
+                .map_err(|_| execution_timeout(self.query_timeout))?
+                .map_err(|e| {
+                    use tokio_postgres::error::SqlState;
+                    match e.code() {
+                        Some(&SqlState::INSUFFICIENT_PRIVILEGE) => AppError::QueryError(format!(
+                            "Cannot switch to role {role}: the login role is not a member of it"
+                        )),
+                        Some(&SqlState::UNDEFINED_OBJECT) => {
+                            AppError::QueryError(format!("Role {role} does not exist"))
+                        }
+                        _ => AppError::QueryError(pg_error_message(&e)),
+                    }
+                })
+        })?;
+        *self.session_role.write().unwrap() = Some(role.to_string());
+        self.role_used.store(true, std::sync::atomic::Ordering::Relaxed);
+        Ok(())
+    }
+
     fn disconnect(&self) -> Result<(), AppError> {
-        // tokio-postgres Client doesn't have an explicit close; dropping does it.
+        // Closing the pool drops idle connections now and any checked-out
+        // ones as soon as they are returned. An open transaction is dropped
+        // with its connection, which the server rolls back.
+        self.runtime.block_on(self.transaction.lock()).take();
+        self.pool.close();
         Ok(())
     }
 }
@@ -503,7 +2702,7 @@ impl<'a> tokio_postgres::types::FromSql<'a> for PgRawValue {
 }
 
 fn pg_numeric_from_raw(raw: &[u8]) -> Option<String> {
-    if raw.len() < 8 || (raw.len() - 8) % 2 != 0 {
+    if raw.len() < 8 || !(raw.len() - 8).is_multiple_of(2) {
         return None;
     }
 
@@ -604,6 +2803,150 @@ fn pg_numeric_from_raw(raw: &[u8]) -> Option<String> {
     Some(out)
 }
 
+/// Inverse of `pg_numeric_from_raw`: the binary wire format of a decimal
+/// such as `-12.50` or `1e-3`, or `None` if `text` is not a number.
+fn pg_numeric_to_raw(text: &str) -> Option<Vec<u8>> {
+    let header = |ndigits: usize, weight: i32, sign: u16, dscale: usize| -> Option<Vec<u8>> {
+        let mut raw = Vec::with_capacity(8 + ndigits * 2);
+        raw.extend_from_slice(&i16::try_from(ndigits).ok()?.to_be_bytes());
+        raw.extend_from_slice(&i16::try_from(weight).ok()?.to_be_bytes());
+        raw.extend_from_slice(&sign.to_be_bytes());
+        raw.extend_from_slice(&u16::try_from(dscale).ok()?.to_be_bytes());
+        Some(raw)
+    };
+    let text = text.trim();
+    match text.to_ascii_lowercase().as_str() {
+        "nan" => return header(0, 0, 0xC000, 0),
+        "infinity" | "+infinity" => return header(0, 0, 0xD000, 0),
+        "-infinity" => return header(0, 0, 0xF000, 0),
+        _ => {}
+    }
+
+    let (negative, unsigned) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let (mantissa, exponent) = match unsigned.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse::<i32>().ok()?),
+        None => (unsigned, 0),
+    };
+    let (int_digits, frac_digits) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    if int_digits.is_empty() && frac_digits.is_empty()
+        || !int_digits.chars().chain(frac_digits.chars()).all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+
+    // The value is `digits` * 10^-scale.
+    let mut digits = format!("{int_digits}{frac_digits}");
+    let mut scale = frac_digits.len() as i32 - exponent;
+    if scale < 0 {
+        digits.extend(std::iter::repeat_n('0', (-scale) as usize));
+        scale = 0;
+    }
+    let scale = scale as usize;
+    if digits.len() < scale {
+        digits.insert_str(0, &"0".repeat(scale - digits.len()));
+    }
+
+    // Group into base-10000 digits aligned on the decimal point.
+    let (int_part, frac_part) = digits.split_at(digits.len() - scale);
+    let int_pad = (4 - int_part.len() % 4) % 4;
+    let frac_pad = (4 - frac_part.len() % 4) % 4;
+    let padded = format!("{}{int_part}{frac_part}{}", "0".repeat(int_pad), "0".repeat(frac_pad));
+    let mut groups: Vec<u16> = padded
+        .as_bytes()
+        .chunks(4)
+        .map(|chunk| std::str::from_utf8(chunk).ok()?.parse().ok())
+        .collect::<Option<_>>()?;
+    let mut weight = ((int_part.len() + int_pad) / 4) as i32 - 1;
+    let leading = groups.iter().take_while(|g| **g == 0).count();
+    groups.drain(..leading);
+    weight -= leading as i32;
+    while groups.last() == Some(&0) {
+        groups.pop();
+    }
+    if groups.is_empty() {
+        return header(0, 0, 0, scale);
+    }
+
+    let sign = if negative { 0x4000 } else { 0 };
+    let mut raw = header(groups.len(), weight, sign, scale)?;
+    for group in groups {
+        raw.extend_from_slice(&group.to_be_bytes());
+    }
+    Some(raw)
+}
+
+/// A NUMERIC parameter, sent in the binary format so no digits are lost to
+/// a float conversion.
+#[derive(Debug)]
+struct PgNumericParam(Option<Vec<u8>>);
+
+impl tokio_postgres::types::ToSql for PgNumericParam {
+    fn to_sql(
+        &self,
+        _ty: &tokio_postgres::types::Type,
+        out: &mut bytes::BytesMut,
+    ) -> Result<tokio_postgres::types::IsNull, Box<dyn std::error::Error + Sync + Send>> {
+        match &self.0 {
+            Some(raw) => {
+                out.extend_from_slice(raw);
+                Ok(tokio_postgres::types::IsNull::No)
+            }
+            None => Ok(tokio_postgres::types::IsNull::Yes),
+        }
+    }
+
+    fn accepts(ty: &tokio_postgres::types::Type) -> bool {
+        *ty == tokio_postgres::types::Type::NUMERIC
+    }
+
+    tokio_postgres::types::to_sql_checked!();
+}
+
+/// Decode a composite (row type) value from its binary wire format into a JSON
+/// object keyed by attribute name. The layout is a field count followed by
+/// `(oid, length, bytes)` for each attribute, with a length of -1 for NULL.
+fn pg_composite_from_raw(
+    raw: &[u8],
+    fields: &[tokio_postgres::types::Field],
+    depth: usize,
+) -> Option<serde_json::Value> {
+    fn read_i32(buf: &[u8], pos: &mut usize) -> Option<i32> {
+        let bytes = buf.get(*pos..*pos + 4)?;
+        *pos += 4;
+        Some(i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    let mut pos = 0;
+    let count = read_i32(raw, &mut pos)?;
+    if count < 0 || count as usize != fields.len() {
+        return None;
+    }
+
+    let mut obj = serde_json::Map::new();
+    for field in fields {
+        let _oid = read_i32(raw, &mut pos)?;
+        let len = read_i32(raw, &mut pos)?;
+        let value = if len < 0 {
+            serde_json::Value::Null
+        } else {
+            let bytes = raw.get(pos..pos + len as usize)?;
+            pos += len as usize;
+            pg_decode_raw_by_type(Some(bytes), field.type_(), depth + 1)
+        };
+        obj.insert(field.name().to_string(), value);
+    }
+    Some(serde_json::Value::Object(obj))
+}
+
+/// A value whose type couldn't be decoded, as text rather than Null or hex:
+/// extension types without binary metadata mostly send readable text.
+fn pg_raw_text(raw: &[u8]) -> serde_json::Value {
+    serde_json::Value::String(String::from_utf8_lossy(raw).into_owned())
+}
+
 fn pg_decode_raw_by_type(
     raw: Option<&[u8]>,
     col_type: &tokio_postgres::types::Type,
@@ -698,26 +3041,178 @@ fn pg_decode_raw_by_type(
             .unwrap_or(serde_json::Value::Null),
         _ => match col_type.kind() {
             Kind::Domain(inner) => pg_decode_raw_by_type(Some(raw), inner, depth + 1),
-            Kind::Enum(_) => std::str::from_utf8(raw)
-                .map(|s| serde_json::Value::String(s.to_string()))
-                .unwrap_or_else(|_| serde_json::Value::String(format!("0x{}", pg_bytes_to_hex(raw)))),
-            Kind::Array(_) | Kind::Range(_) | Kind::Multirange(_) | Kind::Composite(_) => {
-                std::str::from_utf8(raw)
-                    .map(|s| serde_json::Value::String(s.to_string()))
-                    .unwrap_or_else(|_| serde_json::Value::String(format!("0x{}", pg_bytes_to_hex(raw))))
+            Kind::Enum(_) => pg_raw_text(raw),
+            Kind::Composite(fields) => {
+                pg_composite_from_raw(raw, fields, depth).unwrap_or_else(|| pg_raw_text(raw))
+            }
+            Kind::Simple if col_type.name() == "hstore" => {
+                std::collections::HashMap::<String, Option<String>>::from_sql(col_type, raw)
+                    .ok()
+                    .map(|map| {
+                        serde_json::Value::Object(
+                            map.into_iter()
+                                .map(|(k, v)| (k, v.map(serde_json::Value::String).unwrap_or_default()))
+                                .collect(),
+                        )
+                    })
+                    .unwrap_or_else(|| pg_raw_text(raw))
             }
-            Kind::Simple | Kind::Pseudo => std::str::from_utf8(raw)
-                .map(|s| serde_json::Value::String(s.to_string()))
-                .unwrap_or_else(|_| serde_json::Value::String(format!("0x{}", pg_bytes_to_hex(raw)))),
-            _ => serde_json::Value::String(format!("0x{}", pg_bytes_to_hex(raw))),
+            _ => pg_raw_text(raw),
         },
     }
 }
 
+type PgParam = Box<dyn tokio_postgres::types::ToSql + Sync>;
+
+/// `data_length, index_length, data_free, table_rows` from `information_schema.tables`.
+type MySqlTableSizeRow = (Option<u64>, Option<u64>, Option<u64>, Option<u64>);
+
+/// Convert one JSON parameter to a value of the Postgres type the server
+/// inferred for its placeholder. Numbers may arrive as strings (and vice
+/// versa for text), since that is how the UI tends to hold them.
+fn pg_param(
+    value: &serde_json::Value,
+    ty: &tokio_postgres::types::Type,
+) -> Result<PgParam, String> {
+    use serde_json::Value;
+    use tokio_postgres::types::Type;
+
+    let mismatch = || format!("cannot convert {value} to {ty}");
+    let text = match value {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
+    };
+    let int = || -> Result<Option<i64>, String> {
+        match value {
+            Value::Null => Ok(None),
+            Value::Number(n) => n.as_i64().map(Some).ok_or_else(mismatch),
+            Value::String(s) => s.trim().parse().map(Some).map_err(|_| mismatch()),
+            _ => Err(mismatch()),
+        }
+    };
+    let float = || -> Result<Option<f64>, String> {
+        match value {
+            Value::Null => Ok(None),
+            Value::Number(n) => n.as_f64().map(Some).ok_or_else(mismatch),
+            Value::String(s) => s.trim().parse().map(Some).map_err(|_| mismatch()),
+            _ => Err(mismatch()),
+        }
+    };
+
+    let param: PgParam = match *ty {
+        Type::BOOL => Box::new(match value {
+            Value::Null => None,
+            Value::Bool(b) => Some(*b),
+            Value::String(s) => Some(s.parse::<bool>().map_err(|_| mismatch())?),
+            _ => return Err(mismatch()),
+        }),
+        Type::INT2 => Box::new(
+            int()?
+                .map(i16::try_from)
+                .transpose()
+                .map_err(|_| mismatch())?,
+        ),
+        Type::INT4 => Box::new(
+            int()?
+                .map(i32::try_from)
+                .transpose()
+                .map_err(|_| mismatch())?,
+        ),
+        Type::INT8 => Box::new(int()?),
+        Type::FLOAT4 => Box::new(float()?.map(|f| f as f32)),
+        Type::FLOAT8 => Box::new(float()?),
+        Type::NUMERIC => Box::new(PgNumericParam(
+            text.map(|s| pg_numeric_to_raw(&s).ok_or_else(mismatch))
+                .transpose()?,
+        )),
+        Type::JSON | Type::JSONB => Box::new((!value.is_null()).then(|| value.clone())),
+        Type::UUID => Box::new(
+            text.map(|s| uuid::Uuid::parse_str(&s))
+                .transpose()
+                .map_err(|_| mismatch())?,
+        ),
+        Type::TIMESTAMPTZ => Box::new(
+            text.map(|s| {
+                chrono::DateTime::parse_from_rfc3339(&s).map(|t| t.with_timezone(&chrono::Utc))
+            })
+            .transpose()
+            .map_err(|_| mismatch())?,
+        ),
+        Type::TIMESTAMP => Box::new(
+            text.map(|s| {
+                chrono::NaiveDateTime::parse_from_str(&s, "%Y-%m-%dT%H:%M:%S%.f")
+                    .or_else(|_| chrono::NaiveDateTime::parse_from_str(&s, "%Y-%m-%d %H:%M:%S%.f"))
+            })
+            .transpose()
+            .map_err(|_| mismatch())?,
+        ),
+        Type::DATE => Box::new(
+            text.map(|s| chrono::NaiveDate::parse_from_str(&s, "%Y-%m-%d"))
+                .transpose()
+                .map_err(|_| mismatch())?,
+        ),
+        Type::TEXT | Type::VARCHAR | Type::BPCHAR | Type::NAME | Type::UNKNOWN => Box::new(text),
+        _ => return Err(format!(
+            "parameters of type {ty} are not supported; cast the placeholder, e.g. $1::text::{ty}"
+        )),
+    };
+    Ok(param)
+}
+
+/// Bind `params` to the placeholders of a prepared statement.
+fn pg_params(
+    stmt: &tokio_postgres::Statement,
+    params: &[serde_json::Value],
+) -> Result<Vec<PgParam>, AppError> {
+    if stmt.params().len() != params.len() {
+        return Err(AppError::QueryError(format!(
+            "Query expects {} parameters but {} were given",
+            stmt.params().len(),
+            params.len()
+        )));
+    }
+    params
+        .iter()
+        .zip(stmt.params())
+        .enumerate()
+        .map(|(i, (value, ty))| {
+            pg_param(value, ty)
+                .map_err(|e| AppError::QueryError(format!("Parameter ${}: {e}", i + 1)))
+        })
+        .collect()
+}
+
+/// Log, once per OID, a column type `pg_value_to_json` has no decoder for,
+/// so gaps in type coverage can be reported.
+fn pg_note_unknown_type(col_type: &tokio_postgres::types::Type) {
+    static SEEN: std::sync::OnceLock<Mutex<HashSet<u32>>> = std::sync::OnceLock::new();
+    let first = SEEN
+        .get_or_init(Default::default)
+        .lock()
+        .map(|mut seen| seen.insert(col_type.oid()))
+        .unwrap_or(false);
+    if first {
+        eprintln!(
+            "No decoder for PostgreSQL type {} (oid {}); using the generic fallback",
+            col_type.name(),
+            col_type.oid()
+        );
+    }
+}
+
+/// Convert a Postgres column value to JSON.
+///
+/// Date/time convention: `timestamptz` is normalized to UTC and rendered as
+/// RFC 3339 with a `Z` suffix, so it is unambiguous regardless of the session
+/// time zone. `timestamp` (without time zone) is rendered as an ISO 8601
+/// date-time with no offset, i.e. wall-clock time exactly as stored; `date`
+/// and `time` likewise carry no zone.
 fn pg_value_to_json(
     row: &tokio_postgres::Row,
     idx: usize,
     col_type: &tokio_postgres::types::Type,
+    encoding: BinaryEncoding,
 ) -> serde_json::Value {
     use serde_json::Value;
     use tokio_postgres::types::Type;
@@ -836,7 +3331,7 @@ fn pg_value_to_json(
             .try_get::<_, Option<Vec<u8>>>(idx)
             .ok()
             .flatten()
-            .map(|v| Value::String(format!("0x{}", pg_bytes_to_hex(&v))))
+            .map(|v| encoding.encode(&v, |b| format!("0x{}", pg_bytes_to_hex(b))))
             .unwrap_or(Value::Null),
         Type::BOOL_ARRAY => row
             .try_get::<_, Option<Vec<bool>>>(idx)
@@ -961,6 +3456,18 @@ fn pg_value_to_json(
             })
             .unwrap_or(Value::Null),
         _ => {
+            // Extension and row types have no fixed OID; decode them from the
+            // raw wire bytes using the type metadata fetched with the statement.
+            if col_type.name() == "hstore"
+                || matches!(col_type.kind(), tokio_postgres::types::Kind::Composite(_))
+            {
+                let raw = row
+                    .try_get::<_, PgRawValue>(idx)
+                    .ok()
+                    .and_then(|v| v.0);
+                return pg_decode_raw_by_type(raw.as_deref(), col_type, 0);
+            }
+            pg_note_unknown_type(col_type);
             if let Ok(v) = row.try_get::<_, Option<String>>(idx) {
                 return v.map(Value::String).unwrap_or(Value::Null);
             }
@@ -996,6 +3503,98 @@ pub struct MySqlAdapter {
     pool: mysql_async::Pool,
     runtime: tokio::runtime::Handle,
     database: String,
+    query_timeout: Duration,
+    quote_all_identifiers: bool,
+    /// Server thread IDs of pooled connections currently running a query,
+    /// for `KILL QUERY`.
+    running: Mutex<HashSet<u32>>,
+    /// The connection an open `begin_transaction` runs on, kept out of the
+    /// pool until the transaction ends.
+    transaction: tokio::sync::Mutex<Option<mysql_async::Conn>>,
+}
+
+/// The connection an operation runs on: the one holding the open
+/// transaction, if any, otherwise one checked out of the pool.
+enum MySqlConn<'a> {
+    Pooled(mysql_async::Conn),
+    Transaction(tokio::sync::MappedMutexGuard<'a, mysql_async::Conn>),
+}
+
+impl MySqlConn<'_> {
+    fn in_transaction(&self) -> bool {
+        matches!(self, Self::Transaction(_))
+    }
+}
+
+impl std::ops::Deref for MySqlConn<'_> {
+    type Target = mysql_async::Conn;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Self::Pooled(conn) => conn,
+            Self::Transaction(conn) => conn,
+        }
+    }
+}
+
+impl std::ops::DerefMut for MySqlConn<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match self {
+            Self::Pooled(conn) => conn,
+            Self::Transaction(conn) => conn,
+        }
+    }
+}
+
+/// Registers a connection in `MySqlAdapter::running` for as long as it lives.
+struct RunningQuery<'a> {
+    running: &'a Mutex<HashSet<u32>>,
+    id: u32,
+}
+
+impl<'a> RunningQuery<'a> {
+    fn start(running: &'a Mutex<HashSet<u32>>, id: u32) -> Self {
+        running.lock().unwrap().insert(id);
+        Self { running, id }
+    }
+}
+
+impl Drop for RunningQuery<'_> {
+    fn drop(&mut self) {
+        self.running.lock().unwrap().remove(&self.id);
+    }
+}
+
+/// Classify a failed MySQL connect: access-denied server errors are bad
+/// logins, socket errors an unreachable server.
+fn mysql_connect_error(e: mysql_async::Error) -> AppError {
+    let status = match &e {
+        // ER_DBACCESS_DENIED_ERROR, ER_ACCESS_DENIED_ERROR,
+        // ER_ACCESS_DENIED_NO_PASSWORD_ERROR
+        mysql_async::Error::Server(server) if matches!(server.code, 1044 | 1045 | 1698) => {
+            ConnectionTestStatus::AuthFailed
+        }
+        mysql_async::Error::Io(mysql_async::IoError::Io(_)) => ConnectionTestStatus::Unreachable,
+        _ => return AppError::ConnectionFailed(e.to_string()),
+    };
+    connect_rejected(status, e.to_string())
+}
+
+async fn kill_queries_on(conn: &mut mysql_async::Conn, ids: &[u32]) {
+    use mysql_async::prelude::Queryable;
+    for id in ids {
+        // The query may have finished in the meantime.
+        let _ = conn.query_drop(format!("KILL QUERY {id}")).await;
+    }
+}
+
+/// Map a query error, reporting `KILL QUERY` (ER_QUERY_INTERRUPTED) the
+/// same way on every backend.
+fn mysql_query_error(e: mysql_async::Error) -> AppError {
+    match e {
+        mysql_async::Error::Server(ref server) if server.code == 1317 => query_cancelled(),
+        e => AppError::QueryError(e.to_string()),
+    }
 }
 
 impl MySqlAdapter {
@@ -1004,22 +3603,180 @@ impl MySqlAdapter {
             "mysql://{}:{}@{}:{}/{}",
             params.username, params.password, params.host, params.port, params.database
         );
-        let pool = mysql_async::Pool::new(url.as_str());
+        let mut opts = mysql_async::OptsBuilder::from_opts(
+            mysql_async::Opts::from_url(&url)
+                .map_err(|e| AppError::ConnectionFailed(e.to_string()))?,
+        );
+        // TLS is only switched on for mutual TLS, so other connections stay
+        // exactly as they were.
+        if let (Some(cert), Some(key)) = (&params.client_cert_path, &params.client_key_path) {
+            let identity = mysql_async::ClientIdentity::new(
+                std::path::PathBuf::from(cert).into(),
+                std::path::PathBuf::from(key).into(),
+            );
+            opts =
+                opts.ssl_opts(mysql_async::SslOpts::default().with_client_identity(Some(identity)));
+        } else if params.client_cert_path.is_some() || params.client_key_path.is_some() {
+            return Err(AppError::ConnectionFailed(
+                "A client certificate needs both a certificate and a key file".to_string(),
+            ));
+        }
+        // Every session runs in UTC, so TIMESTAMP columns (which MySQL
+        // converts to the session zone) come back as UTC. `setup` is re-run
+        // whenever the pool resets a connection.
+        opts = opts.setup(vec!["SET time_zone = '+00:00'"]);
+        let pool = mysql_async::Pool::new(opts);
 
         // Test the connection with timeout
         rt.block_on(async {
-            let conn = tokio::time::timeout(CONNECT_TIMEOUT, pool.get_conn())
+            let conn = tokio::time::timeout(params.connect_timeout(), pool.get_conn())
                 .await
-                .map_err(|_| AppError::ConnectionFailed("Connection timed out".to_string()))?
-                .map_err(|e| AppError::ConnectionFailed(e.to_string()))?;
+                .map_err(|_| connect_timed_out())?
+                .map_err(mysql_connect_error)?;
             drop(conn);
-            Ok::<(), AppError>(())
+            Ok::<_, AppError>(())
         })?;
 
         Ok(Self {
             pool,
             runtime: rt,
             database: params.database.clone(),
+            query_timeout: params.query_timeout(),
+            quote_all_identifiers: params.quote_all_identifiers,
+            running: Mutex::new(HashSet::new()),
+            transaction: tokio::sync::Mutex::new(None),
+        })
+    }
+
+    /// The connection for the next operation: the open transaction's while
+    /// there is one, so its statements all see each other.
+    async fn conn(&self) -> Result<MySqlConn<'_>, AppError> {
+        let held = self.transaction.lock().await;
+        if held.is_some() {
+            return Ok(MySqlConn::Transaction(tokio::sync::MutexGuard::map(held, |c| {
+                c.as_mut().unwrap()
+            })));
+        }
+        drop(held);
+        Ok(MySqlConn::Pooled(self.pooled_conn().await?))
+    }
+
+    /// Run `sql` (COMMIT or ROLLBACK) on the transaction's connection and
+    /// hand the connection back to the pool, whether or not it succeeds.
+    fn end_transaction(&self, sql: &str) -> Result<(), AppError> {
+        self.runtime.block_on(async {
+            use mysql_async::prelude::Queryable;
+            let mut conn = self
+                .transaction
+                .lock()
+                .await
+                .take()
+                .ok_or_else(no_open_transaction)?;
+            tokio::time::timeout(self.query_timeout, conn.query_drop(sql))
+                .await
+                .map_err(|_| execution_timeout(self.query_timeout))?
+                .map_err(|e| AppError::QueryError(e.to_string()))
+        })
+    }
+
+    /// `KILL QUERY` the statements running on connections `ids`, from a
+    /// separate pooled connection. Best effort: failures are ignored.
+    async fn kill_queries(&self, ids: &[u32]) {
+        let kill = async {
+            if let Ok(mut conn) = self.pooled_conn().await {
+                kill_queries_on(&mut conn, ids).await;
+            }
+        };
+        let _ = tokio::time::timeout(self.query_timeout, kill).await;
+    }
+
+    /// Check a connection out of the pool, giving up after `ACQUIRE_TIMEOUT`
+    /// so that waiting on a busy pool is not mistaken for a slow query.
+    async fn pooled_conn(&self) -> Result<mysql_async::Conn, AppError> {
+        acquire_within(ACQUIRE_TIMEOUT, "waiting for a pooled connection", self.pool.get_conn())
+            .await?
+            .map_err(|e| AppError::ConnectionFailed(e.to_string()))
+    }
+
+    /// Decode a result set (text or binary protocol) into a `QueryResult`,
+    /// keeping at most `max_rows` rows. With `stop`, the id of the
+    /// connection running a read-only query, hitting the cap kills the
+    /// query so the server stops producing rows nobody will read.
+    async fn collect_rows<P: mysql_async::prelude::Protocol>(
+        &self,
+        mut result: mysql_async::QueryResult<'_, '_, P>,
+        start: std::time::Instant,
+        max_rows: Option<usize>,
+        stop: Option<u32>,
+        encoding: BinaryEncoding,
+    ) -> Result<QueryResult, AppError> {
+        let columns: Vec<String> = result
+            .columns_ref()
+            .iter()
+            .map(|c| c.name_str().to_string())
+            .collect();
+        let mut kinds: Vec<MySqlColumnKind> =
+            result.columns_ref().iter().map(mysql_column_kind).collect();
+        let column_types: Vec<String> = result.columns_ref().iter().map(mysql_type_name).collect();
+
+        let mut rows: Vec<Vec<serde_json::Value>> = Vec::new();
+        let mut truncated = false;
+        while let Some(row) = result.next().await.map_err(mysql_query_error)? {
+            if max_rows.is_some_and(|max| rows.len() >= max) {
+                truncated = true;
+                break;
+            }
+            let mut values = Vec::new();
+            for (i, kind) in kinds.iter_mut().enumerate() {
+                let val: mysql_async::Value = row.get(i).unwrap_or(mysql_async::Value::NULL);
+                // A text column holding bytes that aren't UTF-8 (e.g. a
+                // computed expression over binary data) is reported as
+                // binary, re-encoding the text values already collected so
+                // the whole column reads the same way.
+                let not_text = matches!(
+                    &val,
+                    mysql_async::Value::Bytes(b) if std::str::from_utf8(b).is_err()
+                );
+                if *kind == MySqlColumnKind::Other && not_text {
+                    *kind = MySqlColumnKind::Binary;
+                    for earlier in &mut rows {
+                        if let serde_json::Value::String(text) = &earlier[i] {
+                            earlier[i] = encoding.encode(text.as_bytes(), blob_placeholder);
+                        }
+                    }
+                }
+                values.push(mysql_value_to_json(val, *kind, encoding));
+            }
+            rows.push(values);
+        }
+        // Discard any rows past the cap so the connection goes back to the
+        // pool clean. A killed query ends with an "interrupted" error,
+        // which is expected.
+        match stop.filter(|_| truncated) {
+            Some(id) => {
+                self.kill_queries(&[id]).await;
+                let _ = result.drop_result().await;
+            }
+            None => result
+                .drop_result()
+                .await
+                .map_err(|e| AppError::QueryError(e.to_string()))?,
+        }
+
+        let elapsed = start.elapsed().as_millis() as u64;
+
+        let row_count = rows.len();
+        Ok(QueryResult {
+            columns,
+            rows,
+            row_count,
+            execution_time_ms: elapsed,
+            truncated,
+            applied_limit: None,
+            environment: None,
+            columns_binary: kinds.iter().map(|k| *k == MySqlColumnKind::Binary).collect(),
+            column_types,
+            warning: None,
         })
     }
 }
@@ -1029,15 +3786,15 @@ impl DatabaseAdapter for MySqlAdapter {
         DatabaseKind::MySQL
     }
 
+    fn quote_all_identifiers(&self) -> bool {
+        self.quote_all_identifiers
+    }
+
     fn test_connection(&self) -> Result<bool, AppError> {
         self.runtime.block_on(async {
-            tokio::time::timeout(QUERY_TIMEOUT, async {
+            tokio::time::timeout(self.query_timeout, async {
                 use mysql_async::prelude::Queryable;
-                let mut conn = self
-                    .pool
-                    .get_conn()
-                    .await
-                    .map_err(|e| AppError::ConnectionFailed(e.to_string()))?;
+                let mut conn = self.pooled_conn().await?;
                 conn.query_drop("SELECT 1")
                     .await
                     .map_err(|e| AppError::ConnectionFailed(e.to_string()))?;
@@ -1049,25 +3806,75 @@ impl DatabaseAdapter for MySqlAdapter {
     }
 
     fn get_schema(&self) -> Result<Vec<TableSchema>, AppError> {
+        self.get_schema_with(&SchemaOptions::default())
+    }
+
+    fn list_table_names(&self) -> Result<Vec<String>, AppError> {
         let db = self.database.clone();
         self.runtime.block_on(async {
-            tokio::time::timeout(QUERY_TIMEOUT, async {
+            tokio::time::timeout(self.query_timeout, async {
                 use mysql_async::prelude::Queryable;
-                let mut conn = self
-                    .pool
-                    .get_conn()
-                    .await
-                    .map_err(|e| AppError::QueryError(e.to_string()))?;
+                let mut conn = self.pooled_conn().await?;
 
-                let table_names: Vec<String> = conn
-                    .query(format!(
-                        "SELECT table_name FROM information_schema.tables
-                         WHERE table_schema = '{}' AND table_type = 'BASE TABLE'
-                         ORDER BY table_name",
-                        db
-                    ))
+                conn.query(format!(
+                    "SELECT table_name FROM information_schema.tables
+                     WHERE table_schema = '{}' AND table_type = 'BASE TABLE'
+                     ORDER BY table_name",
+                    db
+                ))
+                .await
+                .map_err(|e| AppError::QueryError(e.to_string()))
+            })
+            .await
+            .map_err(|_| AppError::ConnectionFailed("Operation timed out".to_string()))?
+        })
+    }
+
+    fn search_objects(&self, term: &str) -> Result<Vec<ObjectMatch>, AppError> {
+        let pattern = like_contains_pattern(&term.to_lowercase());
+        let db = self.database.clone();
+        self.runtime.block_on(async {
+            tokio::time::timeout(self.query_timeout, async {
+                use mysql_async::prelude::Queryable;
+                let mut conn = self.pooled_conn().await?;
+
+                let rows: Vec<(String, Option<String>)> = conn
+                    .exec(
+                        "SELECT t.table_name, NULL
+                         FROM information_schema.tables t
+                         WHERE t.table_schema = ? AND t.table_type = 'BASE TABLE'
+                           AND LOWER(t.table_name) LIKE ?
+                         UNION ALL
+                         SELECT c.table_name, c.column_name
+                         FROM information_schema.columns c
+                         JOIN information_schema.tables t
+                           ON t.table_schema = c.table_schema AND t.table_name = c.table_name
+                         WHERE c.table_schema = ? AND t.table_type = 'BASE TABLE'
+                           AND LOWER(c.column_name) LIKE ?
+                         ORDER BY 1, 2",
+                        (&db, &pattern, &db, &pattern),
+                    )
                     .await
                     .map_err(|e| AppError::QueryError(e.to_string()))?;
+                Ok(object_matches(rows))
+            })
+            .await
+            .map_err(|_| AppError::ConnectionFailed("Operation timed out".to_string()))?
+        })
+    }
+
+    fn get_schema_with(&self, options: &SchemaOptions) -> Result<Vec<TableSchema>, AppError> {
+        let table_names: Vec<String> = self
+            .list_table_names()?
+            .into_iter()
+            .filter(|t| options.includes(t))
+            .collect();
+
+        let db = self.database.clone();
+        self.runtime.block_on(async {
+            tokio::time::timeout(self.query_timeout, async {
+                use mysql_async::prelude::Queryable;
+                let mut conn = self.pooled_conn().await?;
 
                 let mut tables = Vec::new();
                 for table_name in table_names {
@@ -1092,12 +3899,17 @@ impl DatabaseAdapter for MySqlAdapter {
                         })
                         .collect();
 
-                    let count: Vec<u64> = conn
-                        .query(format!(
+                    let count_sql = if options.exact_counts {
+                        format!("SELECT COUNT(*) FROM {}", self.quote_ident(&table_name))
+                    } else {
+                        format!(
                             "SELECT table_rows FROM information_schema.tables
                              WHERE table_schema = '{}' AND table_name = '{}'",
                             db, table_name
-                        ))
+                        )
+                    };
+                    let count: Vec<u64> = conn
+                        .query(count_sql)
                         .await
                         .map_err(|e| AppError::QueryError(e.to_string()))?;
 
@@ -1105,8 +3917,10 @@ impl DatabaseAdapter for MySqlAdapter {
 
                     tables.push(TableSchema {
                         name: table_name,
+                        schema: None,
                         columns,
                         row_count,
+                        is_estimate: !options.exact_counts,
                     });
                 }
                 Ok(tables)
@@ -1116,8 +3930,29 @@ impl DatabaseAdapter for MySqlAdapter {
         })
     }
 
+    fn generated_columns(&self, table: &str) -> Result<Vec<String>, AppError> {
+        let db = self.database.clone();
+        let table_name = table.to_string();
+        self.runtime.block_on(async {
+            tokio::time::timeout(self.query_timeout, async {
+                use mysql_async::prelude::Queryable;
+                let mut conn = self.pooled_conn().await?;
+                conn.exec(
+                    "SELECT column_name FROM information_schema.columns
+                     WHERE table_schema = ? AND table_name = ?
+                       AND (extra LIKE '%auto_increment%' OR extra LIKE '%GENERATED%')",
+                    (db, table_name),
+                )
+                .await
+                .map_err(|e| AppError::QueryError(e.to_string()))
+            })
+            .await
+            .map_err(|_| AppError::ConnectionFailed("Operation timed out".to_string()))?
+        })
+    }
+
     fn get_table_metadata(&self, table: &str) -> Result<TableMetadata, AppError> {
-        let schema_tables = self.get_schema()?;
+        let schema_tables = self.get_schema_with(&SchemaOptions::only(table))?;
         let table_schema = schema_tables
             .into_iter()
             .find(|t| t.name == table)
@@ -1126,13 +3961,9 @@ impl DatabaseAdapter for MySqlAdapter {
         let db = self.database.clone();
         let table_name = table.to_string();
         self.runtime.block_on(async {
-            tokio::time::timeout(QUERY_TIMEOUT, async {
+            tokio::time::timeout(self.query_timeout, async {
                 use mysql_async::prelude::Queryable;
-                let mut conn = self
-                    .pool
-                    .get_conn()
-                    .await
-                    .map_err(|e| AppError::QueryError(e.to_string()))?;
+                let mut conn = self.pooled_conn().await?;
 
                 let idx_rows: Vec<(String, String, i32)> = conn
                     .query(format!(
@@ -1186,75 +4017,216 @@ impl DatabaseAdapter for MySqlAdapter {
         })
     }
 
-    fn execute_query(&self, sql: &str) -> Result<QueryResult, AppError> {
-        let sql = sql.to_string();
+
+    fn get_tables_metadata(
+        &self,
+        tables: &[String],
+    ) -> Result<HashMap<String, TableMetadata>, AppError> {
+        let mut metadata = empty_metadata(self.get_schema_with(&SchemaOptions {
+            tables: Some(tables.to_vec()),
+            ..SchemaOptions::default()
+        })?);
+        let names: Vec<String> = metadata.keys().cloned().collect();
+        if names.is_empty() {
+            return Ok(metadata);
+        }
+        let placeholders = vec!["?"; names.len()].join(", ");
+        let mut params = vec![mysql_async::Value::from(self.database.as_str())];
+        params.extend(names.iter().map(|n| mysql_async::Value::from(n.as_str())));
+
         self.runtime.block_on(async {
-            tokio::time::timeout(QUERY_TIMEOUT, async {
+            tokio::time::timeout(self.query_timeout, async {
                 use mysql_async::prelude::Queryable;
-                let mut conn = self
-                    .pool
-                    .get_conn()
+                let mut conn = self.pooled_conn().await?;
+
+                let idx_rows: Vec<(String, String, String, i32)> = conn
+                    .exec(
+                        format!(
+                            "SELECT table_name, index_name, column_name, non_unique
+                             FROM information_schema.statistics
+                             WHERE table_schema = ? AND table_name IN ({placeholders})
+                             ORDER BY table_name, index_name, seq_in_index"
+                        ),
+                        params.clone(),
+                    )
                     .await
                     .map_err(|e| AppError::QueryError(e.to_string()))?;
+                for (table, name, col, non_unique) in idx_rows {
+                    let Some(m) = metadata.get_mut(&table) else {
+                        continue;
+                    };
+                    match m.indexes.iter_mut().find(|i| i.name == name) {
+                        Some(index) => index.columns.push(col),
+                        None => m.indexes.push(IndexInfo {
+                            name,
+                            columns: vec![col],
+                            unique: non_unique == 0,
+                        }),
+                    }
+                }
 
-                let start = std::time::Instant::now();
-                let result: Vec<mysql_async::Row> = conn
-                    .query(&sql)
+                let fk_rows: Vec<(String, String, String, String, String)> = conn
+                    .exec(
+                        format!(
+                            "SELECT table_name, constraint_name, column_name, referenced_table_name, referenced_column_name
+                             FROM information_schema.key_column_usage
+                             WHERE table_schema = ? AND table_name IN ({placeholders})
+                               AND referenced_table_name IS NOT NULL"
+                        ),
+                        params,
+                    )
                     .await
                     .map_err(|e| AppError::QueryError(e.to_string()))?;
+                for (table, name, col, ref_table, ref_col) in fk_rows {
+                    if let Some(m) = metadata.get_mut(&table) {
+                        m.foreign_keys.push(ForeignKeyInfo {
+                            name,
+                            from_column: col,
+                            to_table: ref_table,
+                            to_column: ref_col,
+                        });
+                    }
+                }
 
-                let elapsed = start.elapsed().as_millis() as u64;
+                Ok(metadata)
+            })
+            .await
+            .map_err(|_| AppError::ConnectionFailed("Operation timed out".to_string()))?
+        })
+    }
+    fn execute_query(&self, sql: &str) -> Result<QueryResult, AppError> {
+        self.execute_query_limited(sql, None)
+    }
 
-                if result.is_empty() {
-                    return Ok(QueryResult {
-                        columns: vec![],
-                        rows: vec![],
-                        row_count: 0,
-                        execution_time_ms: elapsed,
-                    });
-                }
+    fn execute_query_limited(&self, sql: &str, max_rows: Option<usize>) -> Result<QueryResult, AppError> {
+        self.execute_query_encoded(sql, max_rows, BinaryEncoding::Placeholder)
+    }
+
+    fn execute_query_encoded(
+        &self,
+        sql: &str,
+        max_rows: Option<usize>,
+        encoding: BinaryEncoding,
+    ) -> Result<QueryResult, AppError> {
+        let sql = sql.to_string();
+        self.runtime.block_on(async {
+            tokio::time::timeout(self.query_timeout, async {
+                use mysql_async::prelude::Queryable;
+                let mut conn = self.conn().await?;
+                let _running = RunningQuery::start(&self.running, conn.id());
+                // Only a plain read is safe to cut short; killing e.g. a
+                // CALL could abandon work it does after its result set.
+                let stop = crate::dialect::is_read_only_query(DatabaseKind::MySQL, &sql)
+                    .then_some(conn.id());
+
+                let start = std::time::Instant::now();
+                let result = conn.query_iter(&sql).await.map_err(mysql_query_error)?;
+                self.collect_rows(result, start, max_rows, stop, encoding).await
+            })
+            .await
+            .map_err(|_| execution_timeout(self.query_timeout))?
+        })
+    }
+
+    fn stream_query(
+        &self,
+        sql: &str,
+        encoding: BinaryEncoding,
+        sink: &mut dyn RowSink,
+    ) -> Result<u64, AppError> {
+        self.runtime.block_on(async {
+            tokio::time::timeout(STREAM_TIMEOUT, async {
+                use mysql_async::prelude::Queryable;
+                let mut conn = self.conn().await?;
+
+                let mut result = conn
+                    .query_iter(sql)
+                    .await
+                    .map_err(|e| AppError::QueryError(e.to_string()))?;
 
-                let columns: Vec<String> = result[0]
+                let columns: Vec<String> = result
                     .columns_ref()
                     .iter()
                     .map(|c| c.name_str().to_string())
                     .collect();
+                let kinds: Vec<MySqlColumnKind> = result.columns_ref().iter().map(mysql_column_kind).collect();
+                sink.columns(&columns)?;
 
-                let mut rows = Vec::new();
-                for row in &result {
-                    let mut values = Vec::new();
-                    for i in 0..columns.len() {
+                let mut delivered = 0u64;
+                while let Some(row) = result
+                    .next()
+                    .await
+                    .map_err(|e| AppError::QueryError(e.to_string()))?
+                {
+                    let mut values = Vec::with_capacity(columns.len());
+                    for (i, &kind) in kinds.iter().enumerate() {
                         let val: mysql_async::Value = row.get(i).unwrap_or(mysql_async::Value::NULL);
-                        values.push(mysql_value_to_json(val));
+                        values.push(mysql_value_to_json(val, kind, encoding));
                     }
-                    rows.push(values);
+                    // On a sink error the connection is dropped rather than
+                    // drained; the pool discards it.
+                    sink.row(values)?;
+                    delivered += 1;
                 }
-
-                let row_count = rows.len();
-                Ok(QueryResult {
-                    columns,
-                    rows,
-                    row_count,
-                    execution_time_ms: elapsed,
-                })
+                Ok(delivered)
             })
             .await
-            .map_err(|_| AppError::ConnectionFailed("Operation timed out".to_string()))?
+            .map_err(|_| execution_timeout(STREAM_TIMEOUT))?
         })
     }
 
     fn execute_statement(&self, sql: &str) -> Result<u64, AppError> {
         let sql = sql.to_string();
         self.runtime.block_on(async {
-            tokio::time::timeout(QUERY_TIMEOUT, async {
+            tokio::time::timeout(self.query_timeout, async {
                 use mysql_async::prelude::Queryable;
-                let mut conn = self
-                    .pool
-                    .get_conn()
+                let mut conn = self.conn().await?;
+                let result = conn
+                    .query_iter(&sql)
                     .await
                     .map_err(|e| AppError::QueryError(e.to_string()))?;
+                let affected = result.affected_rows();
+                drop(result);
+                Ok(affected)
+            })
+            .await
+            .map_err(|_| execution_timeout(self.query_timeout))?
+        })
+    }
+
+    fn execute_query_params(
+        &self,
+        sql: &str,
+        params: &[serde_json::Value],
+    ) -> Result<QueryResult, AppError> {
+        self.runtime.block_on(async {
+            tokio::time::timeout(self.query_timeout, async {
+                use mysql_async::prelude::Queryable;
+                let mut conn = self.conn().await?;
+
+                let start = std::time::Instant::now();
                 let result = conn
-                    .query_iter(&sql)
+                    .exec_iter(sql, mysql_params(params))
+                    .await
+                    .map_err(|e| AppError::QueryError(e.to_string()))?;
+                self.collect_rows(result, start, None, None, BinaryEncoding::Placeholder).await
+            })
+            .await
+            .map_err(|_| execution_timeout(self.query_timeout))?
+        })
+    }
+
+    fn execute_statement_params(
+        &self,
+        sql: &str,
+        params: &[serde_json::Value],
+    ) -> Result<u64, AppError> {
+        self.runtime.block_on(async {
+            tokio::time::timeout(self.query_timeout, async {
+                use mysql_async::prelude::Queryable;
+                let mut conn = self.conn().await?;
+                let result = conn
+                    .exec_iter(sql, mysql_params(params))
                     .await
                     .map_err(|e| AppError::QueryError(e.to_string()))?;
                 let affected = result.affected_rows();
@@ -1262,20 +4234,118 @@ impl DatabaseAdapter for MySqlAdapter {
                 Ok(affected)
             })
             .await
-            .map_err(|_| AppError::ConnectionFailed("Operation timed out".to_string()))?
+            .map_err(|_| execution_timeout(self.query_timeout))?
+        })
+    }
+
+    /// Kills the running statement on each pooled connection busy with a
+    /// query, from a separate connection.
+    fn cancel_query(&self) -> Result<(), AppError> {
+        let ids: Vec<u32> = self.running.lock().unwrap().iter().copied().collect();
+        if ids.is_empty() {
+            return Ok(());
+        }
+        self.runtime.block_on(async {
+            tokio::time::timeout(self.query_timeout, async {
+                // Not `conn()`: the transaction's connection may be the one
+                // busy with the query.
+                let mut conn = self.pooled_conn().await?;
+                kill_queries_on(&mut conn, &ids).await;
+                Ok(())
+            })
+            .await
+            .map_err(|_| execution_timeout(self.query_timeout))?
+        })
+    }
+
+    /// MySQL commits implicitly around DDL, so only the DML in a script is
+    /// actually undone on rollback. As on Postgres, the query timeout
+    /// applies per statement, and one that runs over is killed before the
+    /// batch is rolled back.
+    fn execute_transaction(&self, statements: &[String]) -> Result<Vec<StatementOutcome>, AppError> {
+        self.runtime.block_on(async {
+            use mysql_async::prelude::Queryable;
+            let mut conn = self.conn().await?;
+            let id = conn.id();
+            let _running = RunningQuery::start(&self.running, id);
+            let (begin, commit, rollback) = batch_boundary(conn.in_transaction());
+            tokio::time::timeout(self.query_timeout, conn.query_drop(begin))
+                .await
+                .map_err(|_| execution_timeout(self.query_timeout))?
+                .map_err(|e| AppError::QueryError(e.to_string()))?;
+
+            let mut outcomes = Vec::with_capacity(statements.len());
+            for (index, sql) in statements.iter().enumerate() {
+                let run = async {
+                    let result = conn.query_iter(sql.as_str()).await?;
+                    let affected = result.affected_rows();
+                    let returned_rows = !result.columns_ref().is_empty();
+                    result.drop_result().await.map(|_| (affected, returned_rows))
+                };
+                let failure = match tokio::time::timeout(self.query_timeout, run).await {
+                    Ok(Ok((affected, returned_rows))) => {
+                        outcomes.push(StatementOutcome::ok(
+                            index,
+                            sql,
+                            (!returned_rows).then_some(affected),
+                        ));
+                        continue;
+                    }
+                    Ok(Err(e)) => e.to_string(),
+                    Err(_) => {
+                        self.kill_queries(&[id]).await;
+                        execution_timeout(self.query_timeout).to_string()
+                    }
+                };
+                outcomes.push(StatementOutcome::failed(index, sql, failure));
+                let _ = tokio::time::timeout(self.query_timeout, conn.query_drop(rollback)).await;
+                return Ok(outcomes);
+            }
+
+            tokio::time::timeout(self.query_timeout, conn.query_drop(commit))
+                .await
+                .map_err(|_| execution_timeout(self.query_timeout))?
+                .map_err(|e| AppError::QueryError(e.to_string()))?;
+            Ok(outcomes)
         })
     }
 
+    /// DDL commits implicitly in MySQL and ends the transaction.
+    fn begin_transaction(&self) -> Result<(), AppError> {
+        self.runtime.block_on(async {
+            use mysql_async::prelude::Queryable;
+            let mut held = self.transaction.lock().await;
+            if held.is_some() {
+                return Err(transaction_already_open());
+            }
+            let mut conn = self.pooled_conn().await?;
+            tokio::time::timeout(self.query_timeout, conn.query_drop("START TRANSACTION"))
+                .await
+                .map_err(|_| execution_timeout(self.query_timeout))?
+                .map_err(|e| AppError::QueryError(e.to_string()))?;
+            *held = Some(conn);
+            Ok(())
+        })
+    }
+
+    fn commit_transaction(&self) -> Result<(), AppError> {
+        self.end_transaction("COMMIT")
+    }
+
+    fn rollback_transaction(&self) -> Result<(), AppError> {
+        self.end_transaction("ROLLBACK")
+    }
+
+    fn in_transaction(&self) -> bool {
+        self.runtime.block_on(self.transaction.lock()).is_some()
+    }
+
     fn get_stats(&self) -> Result<AdapterStats, AppError> {
         let db = self.database.clone();
         self.runtime.block_on(async {
-            tokio::time::timeout(QUERY_TIMEOUT, async {
+            tokio::time::timeout(self.query_timeout, async {
                 use mysql_async::prelude::Queryable;
-                let mut conn = self
-                    .pool
-                    .get_conn()
-                    .await
-                    .map_err(|e| AppError::QueryError(e.to_string()))?;
+                let mut conn = self.pooled_conn().await?;
 
                 let rows: Vec<(u64, u64, u64)> = conn
                     .query(format!(
@@ -1301,10 +4371,131 @@ impl DatabaseAdapter for MySqlAdapter {
         })
     }
 
+    fn get_table_size(&self, table: &str) -> Result<TableSizeInfo, AppError> {
+        let db = self.database.clone();
+        let table_name = table.to_string();
+        self.runtime.block_on(async {
+            tokio::time::timeout(self.query_timeout, async {
+                use mysql_async::prelude::Queryable;
+                let mut conn = self.pooled_conn().await?;
+
+                let row: Option<MySqlTableSizeRow> = conn
+                    .exec_first(
+                        "SELECT data_length, index_length, data_free, table_rows
+                         FROM information_schema.tables
+                         WHERE table_schema = ? AND table_name = ?",
+                        (db, table_name.clone()),
+                    )
+                    .await
+                    .map_err(|e| AppError::QueryError(e.to_string()))?;
+
+                let (data, index, free, rows) = row
+                    .ok_or_else(|| AppError::NotFound(format!("Table {table_name} not found")))?;
+
+                Ok(TableSizeInfo {
+                    table: table_name,
+                    total_bytes: data.zip(index).map(|(d, i)| d + i),
+                    table_bytes: data,
+                    index_bytes: index,
+                    toast_bytes: None,
+                    free_bytes: free,
+                    live_rows: rows,
+                    dead_rows: None,
+                    page_count: None,
+                })
+            })
+            .await
+            .map_err(|_| AppError::ConnectionFailed("Operation timed out".to_string()))?
+        })
+    }
+
+    fn list_functions(&self) -> Result<Vec<SqlFunction>, AppError> {
+        let db = self.database.clone();
+        let routines: Vec<(String, String, Option<String>)> = self.runtime.block_on(async {
+            tokio::time::timeout(self.query_timeout, async {
+                use mysql_async::prelude::Queryable;
+                let mut conn = self.pooled_conn().await?;
+
+                conn.exec(
+                    "SELECT routine_name, routine_type, dtd_identifier
+                     FROM information_schema.routines
+                     WHERE routine_schema = ?
+                     ORDER BY routine_name",
+                    (db,),
+                )
+                .await
+                .map_err(|e| AppError::QueryError(e.to_string()))
+            })
+            .await
+            .map_err(|_| AppError::ConnectionFailed("Operation timed out".to_string()))?
+        })?;
+
+        let mut functions: Vec<SqlFunction> = crate::dialect::MYSQL_BUILTIN_FUNCTIONS
+            .iter()
+            .map(|name| SqlFunction {
+                name: name.to_string(),
+                signature: None,
+                builtin: true,
+            })
+            .collect();
+        functions.extend(routines.into_iter().map(|(name, routine_type, returns)| SqlFunction {
+            signature: Some(match returns {
+                Some(ret) => format!("{} {}(...) → {}", routine_type, name, ret),
+                None => format!("{} {}(...)", routine_type, name),
+            }),
+            name,
+            builtin: false,
+        }));
+        Ok(functions)
+    }
+
+    fn get_server_info(&self) -> Result<ServerInfo, AppError> {
+        self.runtime.block_on(async {
+            tokio::time::timeout(self.query_timeout, async {
+                use mysql_async::prelude::Queryable;
+                let mut conn = self.pooled_conn().await?;
+
+                // Our own sessions are pinned to UTC, so report the server's
+                // default zone instead. CONVERT_TZ yields NULL for a named
+                // zone when the server has no time zone tables loaded.
+                let row: Option<(String, String, String, Option<i32>)> = conn
+                    .query_first(
+                        "SELECT VERSION(), @@global.time_zone, @@system_time_zone,
+                                TIMESTAMPDIFF(SECOND, UTC_TIMESTAMP(),
+                                    CONVERT_TZ(UTC_TIMESTAMP(), '+00:00', @@global.time_zone))",
+                    )
+                    .await
+                    .map_err(|e| AppError::QueryError(e.to_string()))?;
+
+                let (version, global_tz, system_tz, offset) = row
+                    .ok_or_else(|| AppError::QueryError("Server info query returned no rows".to_string()))?;
+                // "SYSTEM" means the server follows its host's zone.
+                let timezone = if global_tz.eq_ignore_ascii_case("SYSTEM") {
+                    system_tz
+                } else {
+                    global_tz
+                };
+
+                Ok(ServerInfo {
+                    kind: DatabaseKind::MySQL,
+                    version: Some(version),
+                    timezone: Some(timezone),
+                    utc_offset_seconds: offset,
+                    current_role: None,
+                })
+            })
+            .await
+            .map_err(|_| AppError::ConnectionFailed("Operation timed out".to_string()))?
+        })
+    }
+
     fn disconnect(&self) -> Result<(), AppError> {
         let pool = self.pool.clone();
         self.runtime.block_on(async {
-            tokio::time::timeout(QUERY_TIMEOUT, async {
+            // Hand back the transaction's connection so the pool can shut
+            // down; closing it rolls the transaction back.
+            self.transaction.lock().await.take();
+            tokio::time::timeout(self.query_timeout, async {
                 pool.disconnect()
                     .await
                     .map_err(|e| AppError::ConnectionFailed(e.to_string()))
@@ -1315,7 +4506,95 @@ impl DatabaseAdapter for MySqlAdapter {
     }
 }
 
-fn mysql_value_to_json(val: mysql_async::Value) -> serde_json::Value {
+/// Positional MySQL parameters for `?` placeholders. The server converts
+/// the values to the column types, so JSON only needs a faithful scalar.
+fn mysql_params(params: &[serde_json::Value]) -> mysql_async::Params {
+    use serde_json::Value;
+
+    if params.is_empty() {
+        return mysql_async::Params::Empty;
+    }
+    let values = params
+        .iter()
+        .map(|value| match value {
+            Value::Null => mysql_async::Value::NULL,
+            Value::Bool(b) => mysql_async::Value::Int(*b as i64),
+            Value::Number(n) => match (n.as_i64(), n.as_u64()) {
+                (Some(i), _) => mysql_async::Value::Int(i),
+                (None, Some(u)) => mysql_async::Value::UInt(u),
+                _ => mysql_async::Value::Double(n.as_f64().unwrap_or_default()),
+            },
+            Value::String(s) => mysql_async::Value::Bytes(s.clone().into_bytes()),
+            other => mysql_async::Value::Bytes(other.to_string().into_bytes()),
+        })
+        .collect();
+    mysql_async::Params::Positional(values)
+}
+
+/// How `mysql_value_to_json` reads a column's values, from its metadata.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MySqlColumnKind {
+    /// Returned in session time, which is UTC.
+    Timestamp,
+    /// DECIMAL/NUMERIC, sent as text.
+    Decimal,
+    /// BLOB/BINARY/VARBINARY and other byte strings with the binary charset.
+    Binary,
+    Other,
+}
+
+/// The column's wire type, e.g. `longlong` or `newdecimal`.
+fn mysql_type_name(column: &mysql_async::Column) -> String {
+    let name = format!("{:?}", column.column_type());
+    name.trim_start_matches("MYSQL_TYPE_").to_ascii_lowercase()
+}
+
+fn mysql_column_kind(column: &mysql_async::Column) -> MySqlColumnKind {
+    use mysql_async::consts::ColumnType;
+
+    /// The `binary` pseudo-charset, used for byte strings.
+    const BINARY_CHARSET: u16 = 63;
+    match column.column_type() {
+        ColumnType::MYSQL_TYPE_TIMESTAMP | ColumnType::MYSQL_TYPE_TIMESTAMP2 => MySqlColumnKind::Timestamp,
+        ColumnType::MYSQL_TYPE_DECIMAL | ColumnType::MYSQL_TYPE_NEWDECIMAL => MySqlColumnKind::Decimal,
+        ColumnType::MYSQL_TYPE_TINY_BLOB
+        | ColumnType::MYSQL_TYPE_MEDIUM_BLOB
+        | ColumnType::MYSQL_TYPE_LONG_BLOB
+        | ColumnType::MYSQL_TYPE_BLOB
+        | ColumnType::MYSQL_TYPE_STRING
+        | ColumnType::MYSQL_TYPE_VAR_STRING
+        | ColumnType::MYSQL_TYPE_VARCHAR
+        | ColumnType::MYSQL_TYPE_GEOMETRY
+            if column.character_set() == BINARY_CHARSET =>
+        {
+            MySqlColumnKind::Binary
+        }
+        _ => MySqlColumnKind::Other,
+    }
+}
+
+/// A DECIMAL's text: a JSON number when it's a whole number that fits in
+/// an i64, otherwise kept as a string so no digits are lost (as for
+/// Postgres NUMERIC).
+fn mysql_decimal_to_json(text: String) -> serde_json::Value {
+    match text.parse::<i64>() {
+        Ok(n) => serde_json::Value::Number(n.into()),
+        Err(_) => serde_json::Value::String(text),
+    }
+}
+
+/// Convert a MySQL value to JSON.
+///
+/// Date/time convention (shared with `pg_value_to_json`): DATE is
+/// `YYYY-MM-DD`, DATETIME is an ISO 8601 local date-time with no offset,
+/// meaning "wall-clock time as stored". TIMESTAMP columns are zone-aware;
+/// sessions run with `time_zone = '+00:00'`, so they arrive in UTC and are
+/// rendered with a `Z` suffix.
+fn mysql_value_to_json(
+    val: mysql_async::Value,
+    kind: MySqlColumnKind,
+    encoding: BinaryEncoding,
+) -> serde_json::Value {
     use serde_json::Value;
 
     let f64_to_json = |v: f64| {
@@ -1330,10 +4609,24 @@ fn mysql_value_to_json(val: mysql_async::Value) -> serde_json::Value {
         mysql_async::Value::UInt(u) => Value::Number(u.into()),
         mysql_async::Value::Float(f) => f64_to_json(f as f64),
         mysql_async::Value::Double(f) => f64_to_json(f),
-        mysql_async::Value::Bytes(bytes) => match String::from_utf8(bytes.clone()) {
+        mysql_async::Value::Bytes(bytes) if kind == MySqlColumnKind::Binary => {
+            encoding.encode(&bytes, blob_placeholder)
+        }
+        mysql_async::Value::Bytes(bytes) => match String::from_utf8(bytes) {
+            Ok(s) if kind == MySqlColumnKind::Decimal => mysql_decimal_to_json(s),
             Ok(s) => Value::String(s),
-            Err(_) => Value::String(format!("0x{}", pg_bytes_to_hex(&bytes))),
+            // Not text despite the column type (e.g. a computed expression
+            // over binary data).
+            Err(e) => encoding.encode(e.as_bytes(), blob_placeholder),
         },
+        mysql_async::Value::Date(year, month, day, hour, minute, second, micros)
+            if kind == MySqlColumnKind::Timestamp =>
+        {
+            let fraction = if micros > 0 { format!(".{micros:06}") } else { String::new() };
+            Value::String(format!(
+                "{year:04}-{month:02}-{day:02}T{hour:02}:{minute:02}:{second:02}{fraction}Z"
+            ))
+        }
         mysql_async::Value::Date(year, month, day, hour, minute, second, micros) => {
             // DATE-only values come through with a zeroed time component.
             if hour == 0 && minute == 0 && second == 0 && micros == 0 {
@@ -1366,22 +4659,237 @@ fn mysql_value_to_json(val: mysql_async::Value) -> serde_json::Value {
 // SQLite adapter (user databases, not the app's internal SQLite)
 // ---------------------------------------------------------------------------
 
-pub struct SqliteAdapter {
-    conn: std::sync::Mutex<rusqlite::Connection>,
-}
+pub struct SqliteAdapter {
+    conn: std::sync::Mutex<rusqlite::Connection>,
+    /// Usable while `conn` is locked by a running query.
+    interrupt: rusqlite::InterruptHandle,
+    quote_all_identifiers: bool,
+    read_only: bool,
+}
+
+/// Map a query error, reporting `sqlite3_interrupt` the same way on every
+/// backend.
+fn sqlite_query_error(e: rusqlite::Error) -> AppError {
+    if e.sqlite_error_code() == Some(rusqlite::ErrorCode::OperationInterrupted) {
+        return query_cancelled();
+    }
+    AppError::QueryError(e.to_string())
+}
+
+impl SqliteAdapter {
+    pub fn connect(params: &ConnectParams) -> Result<Self, AppError> {
+        // For SQLite, `database` field is the file path
+        let connection = if params.read_only {
+            rusqlite::Connection::open_with_flags(
+                &params.database,
+                rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY
+                    | rusqlite::OpenFlags::SQLITE_OPEN_URI
+                    | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
+            )
+        } else {
+            rusqlite::Connection::open(&params.database)
+        }
+        .map_err(|e| AppError::ConnectionFailed(e.to_string()))?;
+
+        // Switching to WAL writes to the file and creates sidecar files, so
+        // a read-only connection keeps whatever journal mode it finds.
+        let pragmas = if params.read_only {
+            "PRAGMA foreign_keys=ON;"
+        } else {
+            "PRAGMA journal_mode=WAL; PRAGMA foreign_keys=ON;"
+        };
+        connection
+            .execute_batch(pragmas)
+            .map_err(|e| AppError::ConnectionFailed(e.to_string()))?;
+        // SQLite queries run in-process and can't be cut off by a timer; the
+        // query timeout bounds how long a statement waits on another
+        // writer's lock instead.
+        connection
+            .busy_timeout(params.query_timeout())
+            .map_err(|e| AppError::ConnectionFailed(e.to_string()))?;
+
+        Ok(Self {
+            interrupt: connection.get_interrupt_handle(),
+            conn: std::sync::Mutex::new(connection),
+            quote_all_identifiers: params.quote_all_identifiers,
+            read_only: params.read_only,
+        })
+    }
+
+    /// Check that `params.database` is an existing SQLite database without
+    /// changing it: it is opened read-only, so a mistyped path is not
+    /// created and no WAL files appear next to it.
+    pub fn probe(params: &ConnectParams) -> Result<bool, AppError> {
+        let classify = |e: rusqlite::Error| match e.sqlite_error_code() {
+            Some(rusqlite::ErrorCode::CannotOpen) => {
+                connect_rejected(ConnectionTestStatus::Unreachable, e.to_string())
+            }
+            _ => AppError::ConnectionFailed(e.to_string()),
+        };
+        let connection = rusqlite::Connection::open_with_flags(
+            &params.database,
+            rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY
+                | rusqlite::OpenFlags::SQLITE_OPEN_URI
+                | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )
+        .map_err(classify)?;
+        // Opening is lazy; reading the schema fails on a file that isn't
+        // a database.
+        connection
+            .query_row("SELECT COUNT(*) FROM sqlite_master", [], |row| row.get::<_, i64>(0))
+            .map(|_| true)
+            .map_err(classify)
+    }
+
+    /// Aliases of attached databases, in attach order.
+    fn attached_aliases(conn: &rusqlite::Connection) -> Result<Vec<String>, AppError> {
+        let mut stmt = conn
+            .prepare("PRAGMA database_list")
+            .map_err(|e| AppError::QueryError(e.to_string()))?;
+        let aliases = stmt
+            .query_map([], |row| row.get::<_, String>(1))
+            .map_err(|e| AppError::QueryError(e.to_string()))?
+            .filter_map(|r| r.ok())
+            .filter(|name| name != "main" && name != "temp")
+            .collect();
+        Ok(aliases)
+    }
+
+    /// Find `table` (as listed by `get_schema`, so `alias.name` for attached
+    /// databases) among `table_refs`.
+    fn resolve_table(
+        conn: &rusqlite::Connection,
+        table: &str,
+    ) -> Result<(Option<String>, String), AppError> {
+        Self::table_refs(conn)?
+            .into_iter()
+            .find(|(alias, name)| match alias {
+                Some(alias) => table
+                    .strip_prefix(alias.as_str())
+                    .and_then(|rest| rest.strip_prefix('.'))
+                    == Some(name.as_str()),
+                None => table == name,
+            })
+            .ok_or_else(|| AppError::NotFound(format!("Table {table} not found")))
+    }
+
+    /// `"alias".` to put before a pragma or table name so that it targets
+    /// an attached database; empty for the main file.
+    fn alias_prefix(alias: Option<&str>) -> String {
+        alias
+            .map(|a| format!("\"{}\".", a.replace('"', "\"\"")))
+            .unwrap_or_default()
+    }
+
+    /// Every table as its attached database's alias (`None` for the main
+    /// file) and name.
+    fn table_refs(conn: &rusqlite::Connection) -> Result<Vec<(Option<String>, String)>, AppError> {
+        let mut refs = Vec::new();
+        let aliases = Self::attached_aliases(conn)?;
+        for alias in std::iter::once(None).chain(aliases.into_iter().map(Some)) {
+            let master = match &alias {
+                Some(alias) => format!("\"{}\".sqlite_master", alias.replace('"', "\"\"")),
+                None => "sqlite_master".to_string(),
+            };
+            let mut stmt = conn
+                .prepare(&format!(
+                    "SELECT name FROM {master}
+                     WHERE type='table' AND name NOT LIKE 'sqlite_%' ORDER BY name"
+                ))
+                .map_err(|e| AppError::QueryError(e.to_string()))?;
+            let names: Vec<String> = stmt
+                .query_map([], |row| row.get(0))
+                .map_err(|e| AppError::QueryError(e.to_string()))?
+                .filter_map(|r| r.ok())
+                .collect();
+            refs.extend(names.into_iter().map(|name| (alias.clone(), name)));
+        }
+        Ok(refs)
+    }
+
+    /// Run `sql` with `params` bound, keeping at most `max_rows` rows.
+    fn query_with(
+        &self,
+        sql: &str,
+        params: &[rusqlite::types::Value],
+        max_rows: Option<usize>,
+        encoding: BinaryEncoding,
+    ) -> Result<QueryResult, AppError> {
+        let conn = self.conn.lock().unwrap();
+        let start = std::time::Instant::now();
+
+        let mut stmt = conn
+            .prepare(sql)
+            .map_err(sqlite_query_error)?;
+
+        // INSERT/UPDATE/DDL (and PRAGMAs that only set something) have no
+        // result columns; report how many rows they changed instead.
+        if stmt.column_count() == 0 {
+            let affected = stmt
+                .execute(rusqlite::params_from_iter(params))
+                .map_err(sqlite_query_error)?;
+            return Ok(QueryResult {
+                columns: vec!["rows_affected".to_string()],
+                rows: vec![vec![serde_json::Value::from(affected as u64)]],
+                row_count: 1,
+                execution_time_ms: start.elapsed().as_millis() as u64,
+                truncated: false,
+                applied_limit: None,
+                environment: None,
+                columns_binary: vec![false],
+                column_types: Vec::new(),
+                warning: None,
+            });
+        }
+
+        let columns: Vec<String> = stmt.column_names().iter().map(|s| s.to_string()).collect();
+        // SQLite columns are untyped, so a column counts as binary once it
+        // holds a BLOB value.
+        let mut columns_binary = vec![false; columns.len()];
+        let column_types: Vec<String> = stmt
+            .columns()
+            .iter()
+            .map(|c| c.decl_type().unwrap_or_default().to_string())
+            .collect();
 
-impl SqliteAdapter {
-    pub fn connect(params: &ConnectParams) -> Result<Self, AppError> {
-        // For SQLite, `database` field is the file path
-        let connection = rusqlite::Connection::open(&params.database)
-            .map_err(|e| AppError::ConnectionFailed(e.to_string()))?;
+        let mut result_rows = stmt
+            .query(rusqlite::params_from_iter(params))
+            .map_err(sqlite_query_error)?;
+
+        let mut rows: Vec<Vec<serde_json::Value>> = Vec::new();
+        let mut truncated = false;
+        while let Some(row) = result_rows
+            .next()
+            .map_err(sqlite_query_error)?
+        {
+            if max_rows.is_some_and(|max| rows.len() >= max) {
+                truncated = true;
+                break;
+            }
+            let mut values = Vec::new();
+            for (i, binary) in columns_binary.iter_mut().enumerate() {
+                if let Ok(rusqlite::types::ValueRef::Blob(_)) = row.get_ref(i) {
+                    *binary = true;
+                }
+                values.push(sqlite_value_to_json(row, i, encoding));
+            }
+            rows.push(values);
+        }
 
-        connection
-            .execute_batch("PRAGMA journal_mode=WAL; PRAGMA foreign_keys=ON; PRAGMA busy_timeout=30000;")
-            .map_err(|e| AppError::ConnectionFailed(e.to_string()))?;
+        let elapsed = start.elapsed().as_millis() as u64;
+        let row_count = rows.len();
 
-        Ok(Self {
-            conn: std::sync::Mutex::new(connection),
+        Ok(QueryResult {
+            columns,
+            rows,
+            row_count,
+            execution_time_ms: elapsed,
+            truncated,
+            applied_limit: None,
+            environment: None,
+            columns_binary,
+            column_types,
+            warning: None,
         })
     }
 }
@@ -1391,6 +4899,10 @@ impl DatabaseAdapter for SqliteAdapter {
         DatabaseKind::SQLite
     }
 
+    fn quote_all_identifiers(&self) -> bool {
+        self.quote_all_identifiers
+    }
+
     fn test_connection(&self) -> Result<bool, AppError> {
         let conn = self.conn.lock().unwrap();
         conn.execute_batch("SELECT 1")
@@ -1399,22 +4911,50 @@ impl DatabaseAdapter for SqliteAdapter {
     }
 
     fn get_schema(&self) -> Result<Vec<TableSchema>, AppError> {
+        self.get_schema_with(&SchemaOptions::default())
+    }
+
+    /// Tables of attached databases are quoted as `"alias"."table"`.
+    fn quote_table(&self, name: &str) -> String {
         let conn = self.conn.lock().unwrap();
+        match Self::resolve_table(&conn, name) {
+            Ok((Some(alias), table)) => {
+                format!("{}{}", Self::alias_prefix(Some(&alias)), self.quote_ident(&table))
+            }
+            _ => self.quote_ident(name),
+        }
+    }
 
-        let mut stmt = conn
-            .prepare("SELECT name FROM sqlite_master WHERE type='table' AND name NOT LIKE 'sqlite_%' ORDER BY name")
-            .map_err(|e| AppError::QueryError(e.to_string()))?;
+    /// Tables of attached databases are listed as `alias.table`.
+    fn list_table_names(&self) -> Result<Vec<String>, AppError> {
+        let conn = self.conn.lock().unwrap();
+        Ok(Self::table_refs(&conn)?
+            .into_iter()
+            .map(|(alias, name)| match alias {
+                Some(alias) => format!("{alias}.{name}"),
+                None => name,
+            })
+            .collect())
+    }
 
-        let table_names: Vec<String> = stmt
-            .query_map([], |row| row.get(0))
-            .map_err(|e| AppError::QueryError(e.to_string()))?
-            .filter_map(|r| r.ok())
-            .collect();
+    fn get_schema_with(&self, options: &SchemaOptions) -> Result<Vec<TableSchema>, AppError> {
+        let conn = self.conn.lock().unwrap();
 
         let mut tables = Vec::new();
-        for table_name in &table_names {
+        for (alias, name) in Self::table_refs(&conn)? {
+            let table_name = match &alias {
+                Some(alias) => format!("{alias}.{name}"),
+                None => name.clone(),
+            };
+            if !options.includes(&table_name) {
+                continue;
+            }
+            // `"alias".` before the pragma or table picks the attached file.
+            let prefix = Self::alias_prefix(alias.as_deref());
+            let quoted = name.replace('"', "\"\"");
+
             let mut pragma_stmt = conn
-                .prepare(&format!("PRAGMA table_info(\"{}\")", table_name))
+                .prepare(&format!("PRAGMA {prefix}table_info(\"{quoted}\")"))
                 .map_err(|e| AppError::QueryError(e.to_string()))?;
 
             let columns: Vec<ColumnInfo> = pragma_stmt
@@ -1432,34 +4972,93 @@ impl DatabaseAdapter for SqliteAdapter {
 
             let count: u64 = conn
                 .query_row(
-                    &format!("SELECT COUNT(*) FROM \"{}\"", table_name),
+                    &format!("SELECT COUNT(*) FROM {prefix}\"{quoted}\""),
                     [],
                     |row| row.get(0),
                 )
                 .unwrap_or(0);
 
             tables.push(TableSchema {
-                name: table_name.clone(),
+                name: table_name,
+                schema: alias,
                 columns,
                 row_count: count,
+                is_estimate: false,
             });
         }
 
         Ok(tables)
     }
 
+    fn stored_table_ddl(&self, table: &str) -> Result<Option<String>, AppError> {
+        let conn = self.conn.lock().unwrap();
+        let (alias, name) = Self::resolve_table(&conn, table)?;
+        let master = match &alias {
+            Some(alias) => format!("\"{}\".sqlite_master", alias.replace('"', "\"\"")),
+            None => "sqlite_master".to_string(),
+        };
+        // Automatic indexes (for UNIQUE and PRIMARY KEY) have no `sql`.
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT sql FROM {master}
+                 WHERE tbl_name = ?1 AND type IN ('table', 'index') AND sql IS NOT NULL
+                 ORDER BY type = 'index', name"
+            ))
+            .map_err(|e| AppError::QueryError(e.to_string()))?;
+        let statements: Vec<String> = stmt
+            .query_map([name.as_str()], |row| row.get(0))
+            .map_err(|e| AppError::QueryError(e.to_string()))?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(Some(
+            statements
+                .iter()
+                .map(|s| format!("{s};"))
+                .collect::<Vec<_>>()
+                .join("\n\n"),
+        ))
+    }
+
+    fn generated_columns(&self, table: &str) -> Result<Vec<String>, AppError> {
+        let conn = self.conn.lock().unwrap();
+        let (alias, name) = Self::resolve_table(&conn, table)?;
+        let mut stmt = conn
+            .prepare("SELECT name, type, pk, hidden FROM pragma_table_xinfo(?1, ?2)")
+            .map_err(|e| AppError::QueryError(e.to_string()))?;
+        let columns: Vec<(String, String, i64, i64)> = stmt
+            .query_map(rusqlite::params![name, alias.as_deref().unwrap_or("main")], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })
+            .map_err(|e| AppError::QueryError(e.to_string()))?
+            .filter_map(|r| r.ok())
+            .collect();
+        // A lone INTEGER PRIMARY KEY aliases the rowid and is assigned
+        // automatically; `hidden` is 2 or 3 for generated columns.
+        let rowid_alias = columns.iter().filter(|c| c.2 > 0).count() == 1;
+        Ok(columns
+            .into_iter()
+            .filter(|(_, ty, pk, hidden)| {
+                matches!(hidden, 2 | 3) || (rowid_alias && *pk > 0 && ty.eq_ignore_ascii_case("INTEGER"))
+            })
+            .map(|(name, ..)| name)
+            .collect())
+    }
+
     fn get_table_metadata(&self, table: &str) -> Result<TableMetadata, AppError> {
-        let schema_tables = self.get_schema()?;
+        let schema_tables = self.get_schema_with(&SchemaOptions::only(table))?;
         let table_schema = schema_tables
             .into_iter()
             .find(|t| t.name == table)
             .ok_or_else(|| AppError::NotFound(format!("Table {table} not found")))?;
 
         let conn = self.conn.lock().unwrap();
+        let (alias, name) = Self::resolve_table(&conn, table)?;
+        let prefix = Self::alias_prefix(alias.as_deref());
+        let quoted = name.replace('"', "\"\"");
 
         // Indexes
         let mut idx_stmt = conn
-            .prepare(&format!("PRAGMA index_list(\"{}\")", table))
+            .prepare(&format!("PRAGMA {prefix}index_list(\"{quoted}\")"))
             .map_err(|e| AppError::QueryError(e.to_string()))?;
 
         let idx_info: Vec<(String, bool)> = idx_stmt
@@ -1471,7 +5070,10 @@ impl DatabaseAdapter for SqliteAdapter {
         let mut indexes = Vec::new();
         for (idx_name, unique) in &idx_info {
             let mut info_stmt = conn
-                .prepare(&format!("PRAGMA index_info(\"{}\")", idx_name))
+                .prepare(&format!(
+                    "PRAGMA {prefix}index_info(\"{}\")",
+                    idx_name.replace('"', "\"\"")
+                ))
                 .map_err(|e| AppError::QueryError(e.to_string()))?;
 
             let cols: Vec<String> = info_stmt
@@ -1489,7 +5091,7 @@ impl DatabaseAdapter for SqliteAdapter {
 
         // Foreign keys
         let mut fk_stmt = conn
-            .prepare(&format!("PRAGMA foreign_key_list(\"{}\")", table))
+            .prepare(&format!("PRAGMA {prefix}foreign_key_list(\"{quoted}\")"))
             .map_err(|e| AppError::QueryError(e.to_string()))?;
 
         let foreign_keys: Vec<ForeignKeyInfo> = fk_stmt
@@ -1513,40 +5115,60 @@ impl DatabaseAdapter for SqliteAdapter {
     }
 
     fn execute_query(&self, sql: &str) -> Result<QueryResult, AppError> {
-        let conn = self.conn.lock().unwrap();
-        let start = std::time::Instant::now();
+        self.execute_query_limited(sql, None)
+    }
 
+    fn execute_query_limited(&self, sql: &str, max_rows: Option<usize>) -> Result<QueryResult, AppError> {
+        self.query_with(sql, &[], max_rows, BinaryEncoding::Placeholder)
+    }
+
+    fn execute_query_encoded(
+        &self,
+        sql: &str,
+        max_rows: Option<usize>,
+        encoding: BinaryEncoding,
+    ) -> Result<QueryResult, AppError> {
+        self.query_with(sql, &[], max_rows, encoding)
+    }
+
+    fn stream_query(
+        &self,
+        sql: &str,
+        encoding: BinaryEncoding,
+        sink: &mut dyn RowSink,
+    ) -> Result<u64, AppError> {
+        let conn = self.conn.lock().unwrap();
         let mut stmt = conn
             .prepare(sql)
             .map_err(|e| AppError::QueryError(e.to_string()))?;
 
         let columns: Vec<String> = stmt.column_names().iter().map(|s| s.to_string()).collect();
+        sink.columns(&columns)?;
 
-        let rows: Vec<Vec<serde_json::Value>> = stmt
-            .query_map([], |row| {
-                let mut values = Vec::new();
-                for i in 0..columns.len() {
-                    let val = sqlite_value_to_json(row, i);
-                    values.push(val);
-                }
-                Ok(values)
-            })
+        let mut rows = stmt
+            .query([])
+            .map_err(|e| AppError::QueryError(e.to_string()))?;
+        let mut delivered = 0u64;
+        while let Some(row) = rows
+            .next()
             .map_err(|e| AppError::QueryError(e.to_string()))?
-            .filter_map(|r| r.ok())
-            .collect();
-
-        let elapsed = start.elapsed().as_millis() as u64;
-        let row_count = rows.len();
-
-        Ok(QueryResult {
-            columns,
-            rows,
-            row_count,
-            execution_time_ms: elapsed,
-        })
+        {
+            let values = (0..columns.len())
+                .map(|i| sqlite_value_to_json(row, i, encoding))
+                .collect();
+            sink.row(values)?;
+            delivered += 1;
+        }
+        Ok(delivered)
     }
 
     fn execute_statement(&self, sql: &str) -> Result<u64, AppError> {
+        if self.read_only {
+            return Err(AppError::QueryError(
+                "This connection is read-only; reconnect without read-only mode to make changes"
+                    .to_string(),
+            ));
+        }
         let conn = self.conn.lock().unwrap();
         let affected = conn
             .execute(sql, [])
@@ -1554,6 +5176,128 @@ impl DatabaseAdapter for SqliteAdapter {
         Ok(affected as u64)
     }
 
+    fn execute_query_params(
+        &self,
+        sql: &str,
+        params: &[serde_json::Value],
+    ) -> Result<QueryResult, AppError> {
+        self.query_with(sql, &sqlite_params(params), None, BinaryEncoding::Placeholder)
+    }
+
+    fn cancel_query(&self) -> Result<(), AppError> {
+        self.interrupt.interrupt();
+        Ok(())
+    }
+
+    fn execute_statement_params(
+        &self,
+        sql: &str,
+        params: &[serde_json::Value],
+    ) -> Result<u64, AppError> {
+        let conn = self.conn.lock().unwrap();
+        let affected = conn
+            .execute(sql, rusqlite::params_from_iter(sqlite_params(params)))
+            .map_err(|e| AppError::QueryError(e.to_string()))?;
+        Ok(affected as u64)
+    }
+
+    fn execute_transaction(&self, statements: &[String]) -> Result<Vec<StatementOutcome>, AppError> {
+        // A savepoint nests inside a transaction from `begin_transaction`,
+        // and acts as a transaction of its own when there is none.
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn
+            .savepoint()
+            .map_err(|e| AppError::QueryError(e.to_string()))?;
+
+        let mut outcomes = Vec::with_capacity(statements.len());
+        for (index, sql) in statements.iter().enumerate() {
+            let result = tx.prepare(sql).and_then(|mut stmt| {
+                if stmt.column_count() == 0 {
+                    stmt.execute([]).map(|n| Some(n as u64))
+                } else {
+                    let mut rows = stmt.query([])?;
+                    while rows.next()?.is_some() {}
+                    Ok(None)
+                }
+            });
+            match result {
+                Ok(affected) => outcomes.push(StatementOutcome::ok(index, sql, affected)),
+                Err(e) => {
+                    outcomes.push(StatementOutcome::failed(index, sql, e.to_string()));
+                    // Dropping the savepoint rolls it back.
+                    return Ok(outcomes);
+                }
+            }
+        }
+
+        tx.commit().map_err(|e| AppError::QueryError(e.to_string()))?;
+        Ok(outcomes)
+    }
+
+    // rusqlite's `Transaction` borrows the connection, so it can't stay
+    // open between calls; issue the statements it would.
+    fn begin_transaction(&self) -> Result<(), AppError> {
+        let conn = self.conn.lock().unwrap();
+        if !conn.is_autocommit() {
+            return Err(transaction_already_open());
+        }
+        conn.execute_batch("BEGIN")
+            .map_err(|e| AppError::QueryError(e.to_string()))
+    }
+
+    fn commit_transaction(&self) -> Result<(), AppError> {
+        let conn = self.conn.lock().unwrap();
+        if conn.is_autocommit() {
+            return Err(no_open_transaction());
+        }
+        conn.execute_batch("COMMIT")
+            .map_err(|e| AppError::QueryError(e.to_string()))
+    }
+
+    fn rollback_transaction(&self) -> Result<(), AppError> {
+        let conn = self.conn.lock().unwrap();
+        if conn.is_autocommit() {
+            return Err(no_open_transaction());
+        }
+        conn.execute_batch("ROLLBACK")
+            .map_err(|e| AppError::QueryError(e.to_string()))
+    }
+
+    fn in_transaction(&self) -> bool {
+        !self.conn.lock().unwrap().is_autocommit()
+    }
+
+    /// ATTACH creates a missing file, so the path is checked first rather
+    /// than leaving an empty database behind after a typo.
+    fn attach_database(&self, file_path: &str, alias: &str) -> Result<Vec<String>, AppError> {
+        if !std::path::Path::new(file_path).is_file() {
+            return Err(AppError::NotFound(format!("Database file {file_path} not found")));
+        }
+        let conn = self.conn.lock().unwrap();
+        let attached = Self::attached_aliases(&conn)?;
+        if alias.eq_ignore_ascii_case("main")
+            || alias.eq_ignore_ascii_case("temp")
+            || attached.iter().any(|a| a.eq_ignore_ascii_case(alias))
+        {
+            return Err(AppError::QueryError(format!(
+                "A database is already attached as {alias}"
+            )));
+        }
+        conn.execute("ATTACH DATABASE ?1 AS ?2", rusqlite::params![file_path, alias])
+            .map_err(|e| AppError::QueryError(e.to_string()))?;
+        Self::attached_aliases(&conn)
+    }
+
+    fn detach_database(&self, alias: &str) -> Result<Vec<String>, AppError> {
+        let conn = self.conn.lock().unwrap();
+        if !Self::attached_aliases(&conn)?.iter().any(|a| a.eq_ignore_ascii_case(alias)) {
+            return Err(AppError::NotFound(format!("No database attached as {alias}")));
+        }
+        conn.execute("DETACH DATABASE ?1", rusqlite::params![alias])
+            .map_err(|e| AppError::QueryError(e.to_string()))?;
+        Self::attached_aliases(&conn)
+    }
+
     fn get_stats(&self) -> Result<AdapterStats, AppError> {
         let schema = self.get_schema()?;
         let table_count = schema.len() as u32;
@@ -1574,64 +5318,576 @@ impl DatabaseAdapter for SqliteAdapter {
         })
     }
 
+    fn get_server_info(&self) -> Result<ServerInfo, AppError> {
+        // SQLite runs in-process, so there is no separate server time zone.
+        let conn = self.conn.lock().unwrap();
+        let version: Option<String> = conn
+            .query_row("SELECT sqlite_version()", [], |row| row.get(0))
+            .ok();
+        Ok(ServerInfo {
+            kind: DatabaseKind::SQLite,
+            version,
+            timezone: None,
+            utc_offset_seconds: None,
+            current_role: None,
+        })
+    }
+
+    fn get_table_size(&self, table: &str) -> Result<TableSizeInfo, AppError> {
+        let conn = self.conn.lock().unwrap();
+
+        let exists: bool = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?1",
+                [table],
+                |row| row.get::<_, i64>(0),
+            )
+            .map(|n| n > 0)
+            .map_err(|e| AppError::QueryError(e.to_string()))?;
+        if !exists {
+            return Err(AppError::NotFound(format!("Table {table} not found")));
+        }
+
+        let live_rows: Option<u64> = conn
+            .query_row(&format!("SELECT COUNT(*) FROM \"{}\"", table), [], |row| row.get(0))
+            .ok();
+
+        // dbstat is a compile-time option; builds without it only get row counts.
+        let table_usage: Option<(u64, u64, u64)> = conn
+            .query_row(
+                "SELECT COALESCE(SUM(pgsize), 0), COALESCE(SUM(unused), 0), COUNT(*)
+                 FROM dbstat WHERE name = ?1",
+                [table],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .ok();
+        let index_bytes: Option<u64> = table_usage.and_then(|_| {
+            conn.query_row(
+                "SELECT COALESCE(SUM(d.pgsize), 0) FROM dbstat d
+                 JOIN sqlite_master m ON m.name = d.name
+                 WHERE m.type = 'index' AND m.tbl_name = ?1",
+                [table],
+                |row| row.get(0),
+            )
+            .ok()
+        });
+
+        Ok(TableSizeInfo {
+            table: table.to_string(),
+            total_bytes: table_usage
+                .map(|(bytes, _, _)| bytes + index_bytes.unwrap_or(0)),
+            table_bytes: table_usage.map(|(bytes, _, _)| bytes),
+            index_bytes,
+            toast_bytes: None,
+            free_bytes: table_usage.map(|(_, unused, _)| unused),
+            live_rows,
+            dead_rows: None,
+            page_count: table_usage.map(|(_, _, pages)| pages),
+        })
+    }
+
     fn disconnect(&self) -> Result<(), AppError> {
         Ok(())
     }
 }
 
-fn sqlite_value_to_json(row: &rusqlite::Row, idx: usize) -> serde_json::Value {
-    // rusqlite ValueRef enum
-    use rusqlite::types::ValueRef;
-    match row.get_ref(idx) {
-        Ok(ValueRef::Null) => serde_json::Value::Null,
-        Ok(ValueRef::Integer(i)) => serde_json::Value::Number(i.into()),
-        Ok(ValueRef::Real(f)) => serde_json::Number::from_f64(f)
-            .map(serde_json::Value::Number)
-            .unwrap_or(serde_json::Value::Null),
-        Ok(ValueRef::Text(t)) => {
-            serde_json::Value::String(String::from_utf8_lossy(t).to_string())
-        }
-        Ok(ValueRef::Blob(b)) => {
-            serde_json::Value::String(format!("<blob {} bytes>", b.len()))
-        }
-        Err(_) => serde_json::Value::Null,
+/// SQLite values for bound parameters; SQLite has no booleans, so they
+/// bind as 0/1 like the rest of the app stores them.
+fn sqlite_params(params: &[serde_json::Value]) -> Vec<rusqlite::types::Value> {
+    use rusqlite::types::Value as Sql;
+    use serde_json::Value;
+
+    params
+        .iter()
+        .map(|value| match value {
+            Value::Null => Sql::Null,
+            Value::Bool(b) => Sql::Integer(*b as i64),
+            Value::Number(n) => match n.as_i64() {
+                Some(i) => Sql::Integer(i),
+                None => Sql::Real(n.as_f64().unwrap_or_default()),
+            },
+            Value::String(s) => Sql::Text(s.clone()),
+            other => Sql::Text(other.to_string()),
+        })
+        .collect()
+}
+
+fn sqlite_value_to_json(
+    row: &rusqlite::Row,
+    idx: usize,
+    encoding: BinaryEncoding,
+) -> serde_json::Value {
+    // rusqlite ValueRef enum
+    use rusqlite::types::ValueRef;
+    match row.get_ref(idx) {
+        Ok(ValueRef::Null) => serde_json::Value::Null,
+        Ok(ValueRef::Integer(i)) => serde_json::Value::Number(i.into()),
+        Ok(ValueRef::Real(f)) => serde_json::Number::from_f64(f)
+            .map(serde_json::Value::Number)
+            .unwrap_or(serde_json::Value::Null),
+        Ok(ValueRef::Text(t)) => {
+            serde_json::Value::String(String::from_utf8_lossy(t).to_string())
+        }
+        Ok(ValueRef::Blob(b)) => encoding.encode(b, blob_placeholder),
+        Err(_) => serde_json::Value::Null,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Redis adapter — key-value mapped to a relational-style interface
+// ---------------------------------------------------------------------------
+
+pub struct RedisAdapter {
+    client: redis::Client,
+    runtime: tokio::runtime::Handle,
+    query_timeout: Duration,
+    /// Logical database (`SELECT` index) taken from the connection's
+    /// database field; 0 when that is empty or not a number.
+    db_index: i64,
+    /// Where `execute_query_page` left off, so the next page resumes the
+    /// SCAN instead of starting it over.
+    scan_page: Mutex<Option<RedisScanPage>>,
+}
+
+/// A SCAN over one key pattern, paused at a page boundary.
+struct RedisScanPage {
+    pattern: String,
+    /// Cursor to resume from; `None` once the scan has completed.
+    cursor: Option<u64>,
+    /// Keys fetched but not yet paged past, starting at position `offset`.
+    pending: Vec<String>,
+    offset: usize,
+    /// Every key returned so far, since SCAN may repeat one.
+    seen: HashSet<String>,
+}
+
+impl RedisScanPage {
+    fn new(pattern: &str) -> Self {
+        Self {
+            pattern: pattern.to_string(),
+            cursor: Some(0),
+            pending: Vec::new(),
+            offset: 0,
+            seen: HashSet::new(),
+        }
+    }
+
+    /// Scan on until `end` keys are known or the scan completes.
+    async fn fill(
+        &mut self,
+        conn: &mut redis::aio::MultiplexedConnection,
+        end: usize,
+    ) -> redis::RedisResult<()> {
+        while self.offset + self.pending.len() < end {
+            let Some(cursor) = self.cursor else {
+                break;
+            };
+            let (next, batch): (u64, Vec<String>) = redis::cmd("SCAN")
+                .cursor_arg(cursor)
+                .arg("MATCH")
+                .arg(&self.pattern)
+                .arg("COUNT")
+                .arg(REDIS_SCAN_COUNT)
+                .query_async(conn)
+                .await?;
+            for key in batch {
+                if self.seen.insert(key.clone()) {
+                    self.pending.push(key);
+                }
+            }
+            self.cursor = (next != 0).then_some(next);
+        }
+        Ok(())
+    }
+
+    /// Drop the pending keys before position `offset`.
+    fn skip_to(&mut self, offset: usize) {
+        let skipped = offset.saturating_sub(self.offset).min(self.pending.len());
+        self.pending.drain(..skipped);
+        self.offset += skipped;
+    }
+}
+
+/// Classify a failed Redis connect by error kind and server error code.
+fn redis_connect_error(e: redis::RedisError) -> AppError {
+    let status = if e.kind() == redis::ErrorKind::AuthenticationFailed
+        || matches!(e.code(), Some("NOAUTH" | "WRONGPASS"))
+    {
+        ConnectionTestStatus::AuthFailed
+    } else if e.is_connection_refusal() || e.is_io_error() {
+        ConnectionTestStatus::Unreachable
+    } else {
+        return AppError::ConnectionFailed(e.to_string());
+    };
+    connect_rejected(status, e.to_string())
+}
+
+impl RedisAdapter {
+    pub fn connect(params: &ConnectParams, rt: tokio::runtime::Handle) -> Result<Self, AppError> {
+        let db_index = params
+            .database
+            .trim()
+            .parse::<i64>()
+            .ok()
+            .filter(|n| *n >= 0)
+            .unwrap_or(0);
+        // Built directly rather than as a URL, so credentials containing
+        // `@`, `:` or `/` need no escaping. A username is only sent for
+        // Redis 6 ACL auth.
+        let addr = if params.use_ssl {
+            redis::ConnectionAddr::TcpTls {
+                host: params.host.clone(),
+                port: params.port,
+                insecure: false,
+                tls_params: None,
+            }
+        } else {
+            redis::ConnectionAddr::Tcp(params.host.clone(), params.port)
+        };
+        let info = redis::ConnectionInfo {
+            addr,
+            redis: redis::RedisConnectionInfo {
+                db: db_index,
+                username: Some(params.username.clone()).filter(|u| !u.is_empty()),
+                password: Some(params.password.clone()).filter(|p| !p.is_empty()),
+                ..Default::default()
+            },
+        };
+
+        let client = redis::Client::open(info)
+            .map_err(|e| AppError::ConnectionFailed(e.to_string()))?;
+
+        let adapter = Self {
+            client,
+            runtime: rt,
+            query_timeout: params.query_timeout(),
+            db_index,
+            scan_page: Mutex::new(None),
+        };
+
+        // Test connectivity with timeout; this also fails on a database
+        // index the server doesn't have.
+        adapter.runtime.block_on(async {
+            let mut conn = tokio::time::timeout(params.connect_timeout(), adapter.connection())
+                .await
+                .map_err(|_| connect_timed_out())?
+                .map_err(redis_connect_error)?;
+            tokio::time::timeout(params.connect_timeout(), redis::cmd("PING").query_async::<String>(&mut conn))
+                .await
+                .map_err(|_| connect_timed_out())?
+                .map_err(redis_connect_error)?;
+            Ok::<(), AppError>(())
+        })?;
+
+        Ok(adapter)
+    }
+
+    /// A multiplexed connection switched to `db_index`. SELECT is issued on
+    /// every new connection since it does not carry over between them.
+    async fn connection(&self) -> Result<redis::aio::MultiplexedConnection, redis::RedisError> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        if self.db_index != 0 {
+            redis::cmd("SELECT")
+                .arg(self.db_index)
+                .query_async::<()>(&mut conn)
+                .await?;
+        }
+        Ok(conn)
+    }
+}
+
+/// Columns of a Redis key listing, matching `redis_key_rows`.
+fn redis_key_columns() -> Vec<String> {
+    vec![
+        "key".to_string(),
+        "value".to_string(),
+        "type".to_string(),
+        "ttl".to_string(),
+    ]
+}
+
+/// Describe each key: its value (or a size summary for collections),
+/// type and TTL. Lookups are pipelined, one round trip for the types and
+/// TTLs and one for the values; a failed pipeline leaves its fields blank.
+async fn redis_key_rows(
+    conn: &mut redis::aio::MultiplexedConnection,
+    keys: impl Iterator<Item = &String>,
+) -> Vec<Vec<serde_json::Value>> {
+    let keys: Vec<&String> = keys.collect();
+    if keys.is_empty() {
+        return Vec::new();
+    }
+
+    let mut meta = redis::pipe();
+    for key in &keys {
+        meta.cmd("TYPE").arg(key.as_str()).cmd("TTL").arg(key.as_str());
+    }
+    let meta: Vec<redis::Value> = meta.query_async(conn).await.unwrap_or_default();
+    let types: Vec<String> = (0..keys.len())
+        .map(|i| {
+            meta.get(2 * i)
+                .and_then(|v| redis::from_redis_value(v).ok())
+                .unwrap_or_else(|| "unknown".to_string())
+        })
+        .collect();
+
+    let mut lookups = redis::pipe();
+    for (key, key_type) in keys.iter().zip(&types) {
+        let command = match key_type.as_str() {
+            "string" => "GET",
+            "list" => "LLEN",
+            "set" => "SCARD",
+            "hash" => "HLEN",
+            "zset" => "ZCARD",
+            _ => "EXISTS",
+        };
+        lookups.cmd(command).arg(key.as_str());
+    }
+    let values: Vec<redis::Value> = lookups.query_async(conn).await.unwrap_or_default();
+
+    keys.iter()
+        .zip(types)
+        .enumerate()
+        .map(|(i, (key, key_type))| {
+            let lookup = values.get(i);
+            let len = || lookup.and_then(|v| redis::from_redis_value::<i64>(v).ok()).unwrap_or(0);
+            let value = match key_type.as_str() {
+                "string" => lookup
+                    .and_then(|v| redis::from_redis_value::<String>(v).ok())
+                    .unwrap_or_else(|| "<error>".to_string()),
+                "list" => format!("<list: {} items>", len()),
+                "set" => format!("<set: {} members>", len()),
+                "hash" => format!("<hash: {} fields>", len()),
+                "zset" => format!("<sorted set: {} members>", len()),
+                _ => format!("<{}>", key_type),
+            };
+            let ttl = meta
+                .get(2 * i + 1)
+                .and_then(|v| redis::from_redis_value::<i64>(v).ok())
+                .unwrap_or(-1);
+
+            vec![
+                serde_json::Value::String((*key).clone()),
+                serde_json::Value::String(value),
+                serde_json::Value::String(key_type),
+                if ttl >= 0 {
+                    serde_json::Value::Number(ttl.into())
+                } else {
+                    serde_json::Value::Null
+                },
+            ]
+        })
+        .collect()
+}
+
+/// Keys listed by KEYS, SCAN or SELECT unless `LIMIT n` asks otherwise.
+const REDIS_KEY_DISPLAY_LIMIT: usize = 100;
+
+const REDIS_KEYS_WARNING: &str =
+    "KEYS blocks the server while it walks every key; prefer SCAN on large databases.";
+
+/// Split `<pattern> [LIMIT n]` into the pattern (default `*`) and limit.
+fn redis_listing_args(arg: &str) -> Result<(&str, usize), AppError> {
+    let tokens: Vec<&str> = arg.split_whitespace().collect();
+    match tokens.as_slice() {
+        [] => Ok(("*", REDIS_KEY_DISPLAY_LIMIT)),
+        [pattern] => Ok((*pattern, REDIS_KEY_DISPLAY_LIMIT)),
+        [pattern, keyword, n] if keyword.eq_ignore_ascii_case("LIMIT") => n
+            .parse::<usize>()
+            .ok()
+            .filter(|n| *n > 0)
+            .map(|n| (*pattern, n))
+            .ok_or_else(|| AppError::QueryError(format!("Invalid LIMIT: {n}"))),
+        _ => Err(AppError::QueryError("Expected <pattern> [LIMIT n]".to_string())),
+    }
+}
+
+/// Drop keys SCAN returned more than once, keeping first-seen order.
+fn dedup_keys(keys: Vec<String>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    keys.into_iter().filter(|k| seen.insert(k.clone())).collect()
+}
+
+/// Most elements a collection read (LRANGE, SMEMBERS, HGETALL, ZRANGE)
+/// returns; larger collections are cut off and flagged as truncated.
+const REDIS_MAX_ELEMENTS: usize = 1000;
+
+/// COUNT hint passed to SCAN-family commands.
+const REDIS_SCAN_COUNT: usize = 500;
+
+/// Run a SCAN-family command (`SCAN`, or `SSCAN`/`HSCAN` on `key`) until the
+/// cursor wraps around or more than `limit` items have come back. HSCAN
+/// returns fields and values interleaved.
+async fn redis_scan(
+    conn: &mut redis::aio::MultiplexedConnection,
+    command: &str,
+    key: Option<&str>,
+    pattern: Option<&str>,
+    limit: usize,
+) -> redis::RedisResult<Vec<String>> {
+    let mut items = Vec::new();
+    let mut cursor: u64 = 0;
+    loop {
+        let mut cmd = redis::cmd(command);
+        if let Some(key) = key {
+            cmd.arg(key);
+        }
+        cmd.cursor_arg(cursor);
+        if let Some(pattern) = pattern {
+            cmd.arg("MATCH").arg(pattern);
+        }
+        cmd.arg("COUNT").arg(REDIS_SCAN_COUNT);
+        let (next, batch): (u64, Vec<String>) = cmd.query_async(conn).await?;
+        items.extend(batch);
+        cursor = next;
+        if cursor == 0 || items.len() > limit {
+            return Ok(items);
+        }
+    }
+}
+
+/// The `stop` to send for a range read from `start` to `stop` so at most
+/// one element past the cap comes back, which is enough to detect it.
+fn capped_range_stop(start: i64, stop: i64) -> i64 {
+    let cap = REDIS_MAX_ELEMENTS as i64;
+    if start >= 0 && (stop < 0 || stop - start > cap) {
+        start + cap
+    } else {
+        stop
     }
 }
 
-// ---------------------------------------------------------------------------
-// Redis adapter — key-value mapped to a relational-style interface
-// ---------------------------------------------------------------------------
+/// `start` and `stop` counted from the front of a collection of `len`
+/// elements, then capped like `capped_range_stop`. A range that selects
+/// nothing comes back as one past the end.
+fn resolve_capped_range(start: i64, stop: i64, len: i64) -> (i64, i64) {
+    let from_front = |i: i64| if i < 0 { len + i } else { i };
+    let (start, stop) = (from_front(start).max(0), from_front(stop));
+    if stop < start {
+        return (len, len);
+    }
+    (start, capped_range_stop(start, stop))
+}
 
-pub struct RedisAdapter {
-    client: redis::Client,
-    runtime: tokio::runtime::Handle,
+/// The bounds to send for a range read of `key`. A negative `start` is
+/// resolved against the length from `len_cmd`, so ranges counted from the
+/// end are capped on the server too.
+async fn redis_range_bounds(
+    conn: &mut redis::aio::MultiplexedConnection,
+    len_cmd: &str,
+    key: &str,
+    start: i64,
+    stop: i64,
+) -> redis::RedisResult<(i64, i64)> {
+    if start >= 0 {
+        return Ok((start, capped_range_stop(start, stop)));
+    }
+    let len: i64 = redis::cmd(len_cmd).arg(key).query_async(conn).await?;
+    Ok(resolve_capped_range(start, stop, len))
 }
 
-impl RedisAdapter {
-    pub fn connect(params: &ConnectParams, rt: tokio::runtime::Handle) -> Result<Self, AppError> {
-        let url = if params.password.is_empty() {
-            format!("redis://{}:{}", params.host, params.port)
-        } else {
-            format!("redis://:{}@{}:{}", params.password, params.host, params.port)
+/// Read the contents of a list, set, hash or sorted set. Returns the
+/// columns, rows, and whether the cap cut the result short.
+async fn redis_collection_rows(
+    conn: &mut redis::aio::MultiplexedConnection,
+    cmd: &str,
+    args: &str,
+) -> Result<(Vec<String>, Vec<Vec<serde_json::Value>>, bool), AppError> {
+    let args: Vec<&str> = args.split_whitespace().collect();
+    let key = *args
+        .first()
+        .ok_or_else(|| AppError::QueryError(format!("{cmd} needs a key")))?;
+    let range = || -> Result<(i64, i64), AppError> {
+        let bound = |i: usize, default: i64| {
+            args.get(i).map_or(Ok(default), |s| {
+                s.parse::<i64>()
+                    .map_err(|_| AppError::QueryError(format!("Invalid {cmd} index: {s}")))
+            })
         };
+        Ok((bound(1, 0)?, bound(2, -1)?))
+    };
+    let query_error = |e: redis::RedisError| AppError::QueryError(e.to_string());
+    let text = |s: String| serde_json::Value::String(s);
 
-        let client = redis::Client::open(url.as_str())
-            .map_err(|e| AppError::ConnectionFailed(e.to_string()))?;
-
-        // Test connectivity with timeout
-        rt.block_on(async {
-            let mut conn = tokio::time::timeout(CONNECT_TIMEOUT, client.get_multiplexed_async_connection())
+    let (columns, mut rows): (&[&str], Vec<Vec<serde_json::Value>>) = match cmd {
+        "LRANGE" => {
+            let (start, stop) = range()?;
+            let (start, stop) = redis_range_bounds(conn, "LLEN", key, start, stop)
                 .await
-                .map_err(|_| AppError::ConnectionFailed("Connection timed out".to_string()))?
-                .map_err(|e| AppError::ConnectionFailed(e.to_string()))?;
-            tokio::time::timeout(CONNECT_TIMEOUT, redis::cmd("PING").query_async::<String>(&mut conn))
+                .map_err(query_error)?;
+            let values: Vec<String> = redis::cmd("LRANGE")
+                .arg(key)
+                .arg(start)
+                .arg(stop)
+                .query_async(conn)
                 .await
-                .map_err(|_| AppError::ConnectionFailed("Connection timed out".to_string()))?
-                .map_err(|e| AppError::ConnectionFailed(e.to_string()))?;
-            Ok::<(), AppError>(())
-        })?;
+                .map_err(query_error)?;
+            let rows = values
+                .into_iter()
+                .enumerate()
+                .map(|(i, v)| vec![serde_json::Value::from(start + i as i64), text(v)])
+                .collect();
+            (["index", "value"].as_slice(), rows)
+        }
+        "SMEMBERS" => {
+            let members = redis_scan(conn, "SSCAN", Some(key), None, REDIS_MAX_ELEMENTS)
+                .await
+                .map_err(query_error)?;
+            (["member"].as_slice(), members.into_iter().map(|m| vec![text(m)]).collect())
+        }
+        "HGETALL" => {
+            let flat = redis_scan(conn, "HSCAN", Some(key), None, REDIS_MAX_ELEMENTS * 2)
+                .await
+                .map_err(query_error)?;
+            let rows = flat
+                .chunks_exact(2)
+                .map(|pair| vec![text(pair[0].clone()), text(pair[1].clone())])
+                .collect();
+            (["field", "value"].as_slice(), rows)
+        }
+        "ZRANGE" => {
+            let (start, stop) = range()?;
+            let (start, stop) = redis_range_bounds(conn, "ZCARD", key, start, stop)
+                .await
+                .map_err(query_error)?;
+            let flat: Vec<String> = redis::cmd("ZRANGE")
+                .arg(key)
+                .arg(start)
+                .arg(stop)
+                .arg("WITHSCORES")
+                .query_async(conn)
+                .await
+                .map_err(query_error)?;
+            let rows = flat
+                .chunks_exact(2)
+                .map(|pair| {
+                    let score = pair[1]
+                        .parse::<f64>()
+                        .ok()
+                        .and_then(serde_json::Number::from_f64)
+                        .map_or_else(|| text(pair[1].clone()), serde_json::Value::Number);
+                    vec![text(pair[0].clone()), score]
+                })
+                .collect();
+            (["member", "score"].as_slice(), rows)
+        }
+        other => {
+            return Err(AppError::QueryError(format!("Not a collection read: {other}")));
+        }
+    };
+
+    let truncated = rows.len() > REDIS_MAX_ELEMENTS;
+    rows.truncate(REDIS_MAX_ELEMENTS);
+    Ok((columns.iter().map(|c| c.to_string()).collect(), rows, truncated))
+}
 
-        Ok(Self { client, runtime: rt })
+/// The key pattern of a KEYS, SCAN or SELECT query, which list keys.
+fn redis_key_pattern(sql: &str) -> Option<&str> {
+    let (cmd, arg) = sql.trim().split_once(' ').unwrap_or((sql.trim(), "*"));
+    match cmd.to_uppercase().as_str() {
+        "SELECT" => Some("*"),
+        "KEYS" | "SCAN" => Some(arg.split_whitespace().next().unwrap_or("*")),
+        _ => None,
     }
 }
 
@@ -1642,10 +5898,9 @@ impl DatabaseAdapter for RedisAdapter {
 
     fn test_connection(&self) -> Result<bool, AppError> {
         self.runtime.block_on(async {
-            tokio::time::timeout(QUERY_TIMEOUT, async {
+            tokio::time::timeout(self.query_timeout, async {
                 let mut conn = self
-                    .client
-                    .get_multiplexed_async_connection()
+                    .connection()
                     .await
                     .map_err(|e| AppError::ConnectionFailed(e.to_string()))?;
                 redis::cmd("PING")
@@ -1661,10 +5916,9 @@ impl DatabaseAdapter for RedisAdapter {
 
     fn get_schema(&self) -> Result<Vec<TableSchema>, AppError> {
         self.runtime.block_on(async {
-            tokio::time::timeout(QUERY_TIMEOUT, async {
+            tokio::time::timeout(self.query_timeout, async {
                 let mut conn = self
-                    .client
-                    .get_multiplexed_async_connection()
+                    .connection()
                     .await
                     .map_err(|e| AppError::QueryError(e.to_string()))?;
 
@@ -1675,6 +5929,7 @@ impl DatabaseAdapter for RedisAdapter {
 
                 Ok(vec![TableSchema {
                     name: "keys".to_string(),
+                    schema: None,
                     columns: vec![
                         ColumnInfo {
                             name: "key".to_string(),
@@ -1702,6 +5957,7 @@ impl DatabaseAdapter for RedisAdapter {
                         },
                     ],
                     row_count: db_size,
+                    is_estimate: false,
                 }])
             })
             .await
@@ -1720,13 +5976,15 @@ impl DatabaseAdapter for RedisAdapter {
 
     fn execute_query(&self, sql: &str) -> Result<QueryResult, AppError> {
         // Redis "queries" are interpreted as key pattern scans
-        // Syntax: SCAN <pattern> or GET <key> or KEYS <pattern>
+        // Syntax: SCAN <pattern> [LIMIT n] or GET <key> or
+        // KEYS <pattern> [LIMIT n], or a read
+        // of one collection's contents: LRANGE <key> [start stop],
+        // SMEMBERS <key>, HGETALL <key>, ZRANGE <key> [start stop]
         let sql = sql.trim();
         self.runtime.block_on(async {
-            tokio::time::timeout(QUERY_TIMEOUT, async {
+            tokio::time::timeout(self.query_timeout, async {
                 let mut conn = self
-                    .client
-                    .get_multiplexed_async_connection()
+                    .connection()
                     .await
                     .map_err(|e| AppError::QueryError(e.to_string()))?;
 
@@ -1738,85 +5996,45 @@ impl DatabaseAdapter for RedisAdapter {
 
                 match cmd.as_str() {
                     "KEYS" | "SCAN" | "SELECT" => {
-                        let pattern = if cmd == "SELECT" { "*" } else { arg };
-                        let keys: Vec<String> = redis::cmd("KEYS")
-                            .arg(pattern)
-                            .query_async(&mut conn)
-                            .await
-                            .map_err(|e| AppError::QueryError(e.to_string()))?;
-
-                        let mut rows = Vec::new();
-                        for key in keys.iter().take(100) {
-                            let key_type: String = redis::cmd("TYPE")
-                                .arg(key.as_str())
+                        let (pattern, limit) = if cmd == "SELECT" {
+                            ("*", REDIS_KEY_DISPLAY_LIMIT)
+                        } else {
+                            redis_listing_args(arg)?
+                        };
+                        // KEYS is kept for those who ask for it, but SCAN
+                        // walks the keyspace in small steps without blocking.
+                        let (keys, warning) = if cmd == "KEYS" {
+                            let keys: Vec<String> = redis::cmd("KEYS")
+                                .arg(pattern)
                                 .query_async(&mut conn)
                                 .await
-                                .unwrap_or_else(|_| "unknown".to_string());
-
-                            let value: String = match key_type.as_str() {
-                                "string" => redis::cmd("GET")
-                                    .arg(key.as_str())
-                                    .query_async(&mut conn)
-                                    .await
-                                    .unwrap_or_else(|_| "<error>".to_string()),
-                                "list" => {
-                                    let len: i64 = redis::cmd("LLEN")
-                                        .arg(key.as_str())
-                                        .query_async(&mut conn)
-                                        .await
-                                        .unwrap_or(0);
-                                    format!("<list: {} items>", len)
-                                }
-                                "set" => {
-                                    let len: i64 = redis::cmd("SCARD")
-                                        .arg(key.as_str())
-                                        .query_async(&mut conn)
-                                        .await
-                                        .unwrap_or(0);
-                                    format!("<set: {} members>", len)
-                                }
-                                "hash" => {
-                                    let len: i64 = redis::cmd("HLEN")
-                                        .arg(key.as_str())
-                                        .query_async(&mut conn)
-                                        .await
-                                        .unwrap_or(0);
-                                    format!("<hash: {} fields>", len)
-                                }
-                                _ => format!("<{}>", key_type),
-                            };
-
-                            let ttl: i64 = redis::cmd("TTL")
-                                .arg(key.as_str())
-                                .query_async(&mut conn)
+                                .map_err(|e| AppError::QueryError(e.to_string()))?;
+                            (keys, Some(REDIS_KEYS_WARNING.to_string()))
+                        } else {
+                            let keys = redis_scan(&mut conn, "SCAN", None, Some(pattern), limit)
                                 .await
-                                .unwrap_or(-1);
-
-                            rows.push(vec![
-                                serde_json::Value::String(key.clone()),
-                                serde_json::Value::String(value),
-                                serde_json::Value::String(key_type),
-                                if ttl >= 0 {
-                                    serde_json::Value::Number(ttl.into())
-                                } else {
-                                    serde_json::Value::Null
-                                },
-                            ]);
-                        }
+                                .map_err(|e| AppError::QueryError(e.to_string()))?;
+                            (dedup_keys(keys), None)
+                        };
+
+                        let truncated = keys.len() > limit;
+                        let rows = redis_key_rows(&mut conn, keys.iter().take(limit)).await;
 
                         let elapsed = start.elapsed().as_millis() as u64;
                         let row_count = rows.len();
 
+                        let columns = redis_key_columns();
                         Ok(QueryResult {
-                            columns: vec![
-                                "key".to_string(),
-                                "value".to_string(),
-                                "type".to_string(),
-                                "ttl".to_string(),
-                            ],
+                            columns_binary: vec![false; columns.len()],
+                            column_types: Vec::new(),
+                            columns,
                             rows,
                             row_count,
                             execution_time_ms: elapsed,
+                            truncated,
+                            applied_limit: None,
+                            environment: None,
+                            warning,
                         })
                     }
                     "GET" => {
@@ -1835,99 +6053,598 @@ impl DatabaseAdapter for RedisAdapter {
                             ]],
                             row_count: 1,
                             execution_time_ms: elapsed,
+                            truncated: false,
+                            applied_limit: None,
+                            environment: None,
+                            columns_binary: vec![false; 2],
+                            column_types: Vec::new(),
+                            warning: None,
+                        })
+                    }
+                    "LRANGE" | "SMEMBERS" | "HGETALL" | "ZRANGE" => {
+                        let (columns, rows, truncated) =
+                            redis_collection_rows(&mut conn, &cmd, arg).await?;
+                        Ok(QueryResult {
+                            columns_binary: vec![false; columns.len()],
+                            column_types: Vec::new(),
+                            columns,
+                            row_count: rows.len(),
+                            rows,
+                            execution_time_ms: start.elapsed().as_millis() as u64,
+                            truncated,
+                            applied_limit: None,
+                            environment: None,
+                            warning: None,
                         })
                     }
                     _ => Err(AppError::QueryError(format!(
-                        "Unsupported Redis command: {}. Use KEYS, SCAN, GET, or SELECT.",
+                        "Unsupported Redis command: {}. Use KEYS, SCAN, GET, SELECT, \
+                         LRANGE, SMEMBERS, HGETALL or ZRANGE.",
+                        cmd
+                    ))),
+                }
+            })
+            .await
+            .map_err(|_| AppError::ConnectionFailed("Operation timed out".to_string()))?
+        })
+    }
+
+    /// Pages through the keys matching a KEYS, SCAN or SELECT pattern in
+    /// SCAN order. Reading the next page (or the same one again) resumes the
+    /// previous scan; jumping back or to another pattern starts a new one,
+    /// which only reads as far as the requested page.
+    fn execute_query_page(
+        &self,
+        sql: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<QueryResult, AppError> {
+        let pattern = redis_key_pattern(sql).ok_or_else(|| {
+            AppError::QueryError("Only KEYS, SCAN or SELECT can be paginated on Redis".to_string())
+        })?;
+        self.runtime.block_on(async {
+            tokio::time::timeout(self.query_timeout, async {
+                let mut conn = self
+                    .connection()
+                    .await
+                    .map_err(|e| AppError::QueryError(e.to_string()))?;
+
+                let start = std::time::Instant::now();
+                let resumed = self
+                    .scan_page
+                    .lock()
+                    .ok()
+                    .and_then(|mut page| page.take())
+                    .filter(|page| page.pattern == pattern && page.offset <= offset);
+                let mut page = resumed.unwrap_or_else(|| RedisScanPage::new(pattern));
+                page.fill(&mut conn, offset + limit)
+                    .await
+                    .map_err(|e| AppError::QueryError(e.to_string()))?;
+                page.skip_to(offset);
+                let rows = redis_key_rows(&mut conn, page.pending.iter().take(limit)).await;
+                if let Ok(mut saved) = self.scan_page.lock() {
+                    *saved = Some(page);
+                }
+
+                let columns = redis_key_columns();
+                Ok(QueryResult {
+                    columns_binary: vec![false; columns.len()],
+                    column_types: Vec::new(),
+                    columns,
+                    row_count: rows.len(),
+                    rows,
+                    execution_time_ms: start.elapsed().as_millis() as u64,
+                    truncated: false,
+                    applied_limit: None,
+                    environment: None,
+                    warning: None,
+                })
+            })
+            .await
+            .map_err(|_| AppError::ConnectionFailed("Operation timed out".to_string()))?
+        })
+    }
+
+    fn execute_statement(&self, sql: &str) -> Result<u64, AppError> {
+        let sql = sql.trim();
+        self.runtime.block_on(async {
+            tokio::time::timeout(self.query_timeout, async {
+                let mut conn = self
+                    .connection()
+                    .await
+                    .map_err(|e| AppError::QueryError(e.to_string()))?;
+
+                let parts: Vec<&str> = sql.splitn(3, ' ').collect();
+                let cmd = parts.first().map(|s| s.to_uppercase()).unwrap_or_default();
+
+                match cmd.as_str() {
+                    "SET" => {
+                        let key = parts.get(1).unwrap_or(&"");
+                        let val = parts.get(2).unwrap_or(&"");
+                        redis::cmd("SET")
+                            .arg(*key)
+                            .arg(*val)
+                            .query_async::<String>(&mut conn)
+                            .await
+                            .map_err(|e| AppError::QueryError(e.to_string()))?;
+                        Ok(1)
+                    }
+                    "DEL" | "DELETE" => {
+                        let key = parts.get(1).unwrap_or(&"");
+                        let deleted: u64 = redis::cmd("DEL")
+                            .arg(*key)
+                            .query_async(&mut conn)
+                            .await
+                            .map_err(|e| AppError::QueryError(e.to_string()))?;
+                        Ok(deleted)
+                    }
+                    _ => Err(AppError::QueryError(format!(
+                        "Unsupported Redis write command: {}. Use SET or DEL.",
                         cmd
                     ))),
                 }
             })
-            .await
-            .map_err(|_| AppError::ConnectionFailed("Operation timed out".to_string()))?
+            .await
+            .map_err(|_| AppError::ConnectionFailed("Operation timed out".to_string()))?
+        })
+    }
+
+    fn get_stats(&self) -> Result<AdapterStats, AppError> {
+        self.runtime.block_on(async {
+            tokio::time::timeout(self.query_timeout, async {
+                let mut conn = self
+                    .connection()
+                    .await
+                    .map_err(|e| AppError::QueryError(e.to_string()))?;
+
+                let db_size: u64 = redis::cmd("DBSIZE")
+                    .query_async(&mut conn)
+                    .await
+                    .map_err(|e| AppError::QueryError(e.to_string()))?;
+
+                let info: String = redis::cmd("INFO")
+                    .arg("memory")
+                    .query_async(&mut conn)
+                    .await
+                    .map_err(|e| AppError::QueryError(e.to_string()))?;
+
+                let memory_bytes = info
+                    .lines()
+                    .find(|l| l.starts_with("used_memory:"))
+                    .and_then(|l| l.split(':').nth(1))
+                    .and_then(|v| v.trim().parse::<u64>().ok())
+                    .unwrap_or(0);
+
+                Ok(AdapterStats {
+                    table_count: 1,
+                    total_row_count: db_size,
+                    disk_usage_bytes: memory_bytes,
+                })
+            })
+            .await
+            .map_err(|_| AppError::ConnectionFailed("Operation timed out".to_string()))?
+        })
+    }
+
+    fn get_server_info(&self) -> Result<ServerInfo, AppError> {
+        self.runtime.block_on(async {
+            tokio::time::timeout(self.query_timeout, async {
+                let mut conn = self
+                    .connection()
+                    .await
+                    .map_err(|e| AppError::QueryError(e.to_string()))?;
+
+                let info: String = redis::cmd("INFO")
+                    .arg("server")
+                    .query_async(&mut conn)
+                    .await
+                    .map_err(|e| AppError::QueryError(e.to_string()))?;
+
+                let version = info
+                    .lines()
+                    .find(|l| l.starts_with("redis_version:"))
+                    .and_then(|l| l.split(':').nth(1))
+                    .map(|v| v.trim().to_string());
+
+                Ok(ServerInfo {
+                    kind: DatabaseKind::Redis,
+                    version,
+                    timezone: None,
+                    utc_offset_seconds: None,
+                    current_role: None,
+                })
+            })
+            .await
+            .map_err(|_| AppError::ConnectionFailed("Operation timed out".to_string()))?
+        })
+    }
+
+    fn disconnect(&self) -> Result<(), AppError> {
+        Ok(())
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Elasticsearch / OpenSearch adapter — indices mapped to tables
+// ---------------------------------------------------------------------------
+
+/// Default number of hits returned when a search doesn't set `size`.
+const ELASTIC_DEFAULT_SIZE: u64 = 100;
+
+pub struct ElasticAdapter {
+    client: reqwest::Client,
+    base_url: String,
+    username: String,
+    password: String,
+    runtime: tokio::runtime::Handle,
+    query_timeout: Duration,
+}
+
+impl ElasticAdapter {
+    pub fn connect(params: &ConnectParams, rt: tokio::runtime::Handle) -> Result<Self, AppError> {
+        let scheme = if params.use_ssl { "https" } else { "http" };
+        let port = if params.port == 0 { 9200 } else { params.port };
+        let client = reqwest::Client::builder()
+            .connect_timeout(params.connect_timeout())
+            .build()
+            .map_err(|e| AppError::ConnectionFailed(e.to_string()))?;
+
+        let adapter = Self {
+            client,
+            base_url: format!("{}://{}:{}", scheme, params.host, port),
+            username: params.username.clone(),
+            password: params.password.clone(),
+            runtime: rt,
+            query_timeout: params.query_timeout(),
+        };
+
+        // Test connectivity with timeout
+        adapter.runtime.block_on(async {
+            let resp = tokio::time::timeout(
+                params.connect_timeout(),
+                adapter.request(reqwest::Method::GET, "/").send(),
+            )
+            .await
+            .map_err(|_| connect_timed_out())?
+            .map_err(|e| {
+                if e.is_connect() {
+                    connect_rejected(ConnectionTestStatus::Unreachable, e.to_string())
+                } else {
+                    AppError::ConnectionFailed(e.to_string())
+                }
+            })?;
+            let status = resp.status();
+            match status {
+                reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN => {
+                    Err(connect_rejected(ConnectionTestStatus::AuthFailed, status.to_string()))
+                }
+                _ if !status.is_success() => Err(AppError::ConnectionFailed(status.to_string())),
+                _ => Ok(()),
+            }
+        })?;
+
+        Ok(adapter)
+    }
+
+    /// A request against the cluster, with credentials when configured.
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let req = self
+            .client
+            .request(method, format!("{}{}", self.base_url, path));
+        if self.username.is_empty() {
+            req
+        } else {
+            req.basic_auth(&self.username, Some(&self.password))
+        }
+    }
+
+    /// Issue a request against the cluster and decode the JSON response.
+    /// Non-2xx responses are turned into a `QueryError` carrying the
+    /// cluster's `error.reason` when one is present.
+    async fn send(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        body: Option<&serde_json::Value>,
+    ) -> Result<serde_json::Value, AppError> {
+        let mut req = self.request(method, path);
+        if let Some(body) = body {
+            req = req.json(body);
+        }
+
+        let resp = req
+            .send()
+            .await
+            .map_err(|e| AppError::ConnectionFailed(e.to_string()))?;
+        let status = resp.status();
+        let text = resp
+            .text()
+            .await
+            .map_err(|e| AppError::QueryError(e.to_string()))?;
+        let json: serde_json::Value =
+            serde_json::from_str(&text).unwrap_or(serde_json::Value::String(text));
+
+        if !status.is_success() {
+            let reason = json
+                .pointer("/error/reason")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string())
+                .unwrap_or_else(|| json.to_string());
+            return Err(AppError::QueryError(format!("{}: {}", status, reason)));
+        }
+        Ok(json)
+    }
+
+    /// Run `send` on the runtime with the standard query timeout.
+    fn call(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        body: Option<&serde_json::Value>,
+    ) -> Result<serde_json::Value, AppError> {
+        self.runtime.block_on(async {
+            tokio::time::timeout(self.query_timeout, self.send(method, path, body))
+                .await
+                .map_err(|_| AppError::ConnectionFailed("Operation timed out".to_string()))?
+        })
+    }
+
+    /// `_cat/indices` rows for user (non-hidden) indices, sorted by name.
+    fn cat_indices(&self) -> Result<Vec<serde_json::Value>, AppError> {
+        let resp = self.call(
+            reqwest::Method::GET,
+            "/_cat/indices?format=json&bytes=b&h=index,docs.count,store.size",
+            None,
+        )?;
+        let mut indices: Vec<serde_json::Value> = resp
+            .as_array()
+            .cloned()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|i| {
+                i.get("index")
+                    .and_then(|v| v.as_str())
+                    .map(|name| !name.starts_with('.'))
+                    .unwrap_or(false)
+            })
+            .collect();
+        indices.sort_by(|a, b| a["index"].as_str().cmp(&b["index"].as_str()));
+        Ok(indices)
+    }
+}
+
+/// `_cat` APIs return numbers as strings; accept either form.
+fn elastic_cat_number(v: Option<&serde_json::Value>) -> u64 {
+    match v {
+        Some(serde_json::Value::Number(n)) => n.as_u64().unwrap_or(0),
+        Some(serde_json::Value::String(s)) => s.parse().unwrap_or(0),
+        _ => 0,
+    }
+}
+
+/// Flatten a mapping's `properties` into dotted column names.
+fn elastic_flatten_mapping(props: &serde_json::Value, prefix: &str, out: &mut Vec<ColumnInfo>) {
+    let Some(map) = props.as_object() else {
+        return;
+    };
+    for (name, def) in map {
+        let full = if prefix.is_empty() {
+            name.clone()
+        } else {
+            format!("{}.{}", prefix, name)
+        };
+        if let Some(children) = def.get("properties") {
+            elastic_flatten_mapping(children, &full, out);
+        } else {
+            out.push(ColumnInfo {
+                name: full,
+                data_type: def
+                    .get("type")
+                    .and_then(|t| t.as_str())
+                    .unwrap_or("object")
+                    .to_string(),
+                nullable: true,
+                primary_key: false,
+            });
+        }
+    }
+}
+
+/// Flatten a document `_source` into dotted keys. Arrays are kept as JSON.
+fn elastic_flatten_source(
+    value: &serde_json::Value,
+    prefix: &str,
+    out: &mut Vec<(String, serde_json::Value)>,
+) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (k, v) in map {
+                let full = if prefix.is_empty() {
+                    k.clone()
+                } else {
+                    format!("{}.{}", prefix, k)
+                };
+                elastic_flatten_source(v, &full, out);
+            }
+        }
+        other => out.push((prefix.to_string(), other.clone())),
+    }
+}
+
+impl DatabaseAdapter for ElasticAdapter {
+    fn kind(&self) -> DatabaseKind {
+        DatabaseKind::Elasticsearch
+    }
+
+    fn test_connection(&self) -> Result<bool, AppError> {
+        self.call(reqwest::Method::GET, "/", None)
+            .map(|_| true)
+            .map_err(|e| AppError::ConnectionFailed(e.to_string()))
+    }
+
+    fn get_schema(&self) -> Result<Vec<TableSchema>, AppError> {
+        let indices = self.cat_indices()?;
+        let mappings = self.call(reqwest::Method::GET, "/_mapping", None)?;
+
+        Ok(indices
+            .iter()
+            .map(|idx| {
+                let name = idx["index"].as_str().unwrap_or_default().to_string();
+                let mut columns = vec![ColumnInfo {
+                    name: "_id".to_string(),
+                    data_type: "keyword".to_string(),
+                    nullable: false,
+                    primary_key: true,
+                }];
+                if let Some(props) = mappings.get(&name).and_then(|m| m.pointer("/mappings/properties")) {
+                    elastic_flatten_mapping(props, "", &mut columns);
+                }
+                TableSchema {
+                    name,
+                    schema: None,
+                    columns,
+                    row_count: elastic_cat_number(idx.get("docs.count")),
+                    is_estimate: false,
+                }
+            })
+            .collect())
+    }
+
+    fn get_table_metadata(&self, table: &str) -> Result<TableMetadata, AppError> {
+        let table_schema = self
+            .get_schema()?
+            .into_iter()
+            .find(|t| t.name == table)
+            .ok_or_else(|| AppError::NotFound(format!("Index {table} not found")))?;
+        Ok(TableMetadata {
+            schema: table_schema,
+            indexes: vec![],
+            foreign_keys: vec![],
         })
     }
 
-    fn execute_statement(&self, sql: &str) -> Result<u64, AppError> {
-        let sql = sql.trim();
-        self.runtime.block_on(async {
-            tokio::time::timeout(QUERY_TIMEOUT, async {
-                let mut conn = self
-                    .client
-                    .get_multiplexed_async_connection()
-                    .await
-                    .map_err(|e| AppError::QueryError(e.to_string()))?;
+    fn execute_query(&self, sql: &str) -> Result<QueryResult, AppError> {
+        // Accepted forms:
+        //   { ...query DSL... }                 searches all indices
+        //   [GET|POST] <index>[/_search] { ... } query DSL against an index
+        //   [GET|POST] <index>[/_search] <lucene query string>
+        //   [GET|POST] <index>[/_search]        match_all
+        let mut text = sql.trim();
+        for verb in ["GET ", "POST "] {
+            if text.get(..verb.len()).is_some_and(|p| p.eq_ignore_ascii_case(verb)) {
+                text = text[verb.len()..].trim_start();
+            }
+        }
 
-                let parts: Vec<&str> = sql.splitn(3, ' ').collect();
-                let cmd = parts.first().map(|s| s.to_uppercase()).unwrap_or_default();
+        let (index, rest) = if text.starts_with('{') {
+            ("_all".to_string(), text)
+        } else {
+            let (index, rest) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+            let index = index.trim_start_matches('/').trim_end_matches("/_search");
+            (index.to_string(), rest.trim())
+        };
+        if index.is_empty() {
+            return Err(AppError::QueryError("Expected an index name or a JSON query".to_string()));
+        }
 
-                match cmd.as_str() {
-                    "SET" => {
-                        let key = parts.get(1).unwrap_or(&"");
-                        let val = parts.get(2).unwrap_or(&"");
-                        redis::cmd("SET")
-                            .arg(*key)
-                            .arg(*val)
-                            .query_async::<String>(&mut conn)
-                            .await
-                            .map_err(|e| AppError::QueryError(e.to_string()))?;
-                        Ok(1)
-                    }
-                    "DEL" | "DELETE" => {
-                        let key = parts.get(1).unwrap_or(&"");
-                        let deleted: u64 = redis::cmd("DEL")
-                            .arg(*key)
-                            .query_async(&mut conn)
-                            .await
-                            .map_err(|e| AppError::QueryError(e.to_string()))?;
-                        Ok(deleted)
+        let mut body: serde_json::Value = if rest.is_empty() {
+            serde_json::json!({ "query": { "match_all": {} } })
+        } else if rest.starts_with('{') {
+            serde_json::from_str(rest)
+                .map_err(|e| AppError::QueryError(format!("Invalid query DSL: {e}")))?
+        } else {
+            serde_json::json!({ "query": { "query_string": { "query": rest } } })
+        };
+        if let Some(obj) = body.as_object_mut() {
+            obj.entry("size").or_insert(serde_json::json!(ELASTIC_DEFAULT_SIZE));
+        }
+
+        let start = std::time::Instant::now();
+        let resp = self.call(
+            reqwest::Method::POST,
+            &format!("/{}/_search", index),
+            Some(&body),
+        )?;
+        let elapsed = start.elapsed().as_millis() as u64;
+
+        let hits = resp
+            .pointer("/hits/hits")
+            .and_then(|h| h.as_array())
+            .cloned()
+            .unwrap_or_default();
+
+        let mut columns: Vec<String> = vec!["_index".to_string(), "_id".to_string()];
+        let mut flat_hits: Vec<Vec<(String, serde_json::Value)>> = Vec::with_capacity(hits.len());
+        for hit in &hits {
+            let mut fields = vec![
+                ("_index".to_string(), hit.get("_index").cloned().unwrap_or_default()),
+                ("_id".to_string(), hit.get("_id").cloned().unwrap_or_default()),
+            ];
+            if let Some(source) = hit.get("_source") {
+                elastic_flatten_source(source, "", &mut fields);
+            }
+            for (name, _) in &fields {
+                if !columns.contains(name) {
+                    columns.push(name.clone());
+                }
+            }
+            flat_hits.push(fields);
+        }
+
+        let rows: Vec<Vec<serde_json::Value>> = flat_hits
+            .into_iter()
+            .map(|fields| {
+                let mut row = vec![serde_json::Value::Null; columns.len()];
+                for (name, value) in fields {
+                    if let Some(i) = columns.iter().position(|c| *c == name) {
+                        row[i] = value;
                     }
-                    _ => Err(AppError::QueryError(format!(
-                        "Unsupported Redis write command: {}. Use SET or DEL.",
-                        cmd
-                    ))),
                 }
+                row
             })
-            .await
-            .map_err(|_| AppError::ConnectionFailed("Operation timed out".to_string()))?
+            .collect();
+
+        let row_count = rows.len();
+        Ok(QueryResult {
+            columns_binary: vec![false; columns.len()],
+            column_types: Vec::new(),
+            columns,
+            rows,
+            row_count,
+            execution_time_ms: elapsed,
+            truncated: false,
+            applied_limit: None,
+            environment: None,
+            warning: None,
         })
     }
 
-    fn get_stats(&self) -> Result<AdapterStats, AppError> {
-        self.runtime.block_on(async {
-            tokio::time::timeout(QUERY_TIMEOUT, async {
-                let mut conn = self
-                    .client
-                    .get_multiplexed_async_connection()
-                    .await
-                    .map_err(|e| AppError::QueryError(e.to_string()))?;
-
-                let db_size: u64 = redis::cmd("DBSIZE")
-                    .query_async(&mut conn)
-                    .await
-                    .map_err(|e| AppError::QueryError(e.to_string()))?;
-
-                let info: String = redis::cmd("INFO")
-                    .arg("memory")
-                    .query_async(&mut conn)
-                    .await
-                    .map_err(|e| AppError::QueryError(e.to_string()))?;
+    fn execute_statement(&self, _sql: &str) -> Result<u64, AppError> {
+        Err(AppError::QueryError(
+            "Elasticsearch connections are read-only".to_string(),
+        ))
+    }
 
-                let memory_bytes = info
-                    .lines()
-                    .find(|l| l.starts_with("used_memory:"))
-                    .and_then(|l| l.split(':').nth(1))
-                    .and_then(|v| v.trim().parse::<u64>().ok())
-                    .unwrap_or(0);
+    fn get_server_info(&self) -> Result<ServerInfo, AppError> {
+        let root = self.call(reqwest::Method::GET, "/", None)?;
+        Ok(ServerInfo {
+            kind: DatabaseKind::Elasticsearch,
+            version: root
+                .pointer("/version/number")
+                .and_then(|v| v.as_str())
+                .map(|v| v.to_string()),
+            timezone: None,
+            utc_offset_seconds: None,
+            current_role: None,
+        })
+    }
 
-                Ok(AdapterStats {
-                    table_count: 1,
-                    total_row_count: db_size,
-                    disk_usage_bytes: memory_bytes,
-                })
-            })
-            .await
-            .map_err(|_| AppError::ConnectionFailed("Operation timed out".to_string()))?
+    fn get_stats(&self) -> Result<AdapterStats, AppError> {
+        let indices = self.cat_indices()?;
+        Ok(AdapterStats {
+            table_count: indices.len() as u32,
+            total_row_count: indices.iter().map(|i| elastic_cat_number(i.get("docs.count"))).sum(),
+            disk_usage_bytes: indices.iter().map(|i| elastic_cat_number(i.get("store.size"))).sum(),
         })
     }
 
@@ -1940,8 +6657,113 @@ impl DatabaseAdapter for RedisAdapter {
 // Connection Manager — manages active adapters by connection ID
 // ---------------------------------------------------------------------------
 
+/// Counting semaphore capping the queries in flight on one connection, so a
+/// burst from the UI queues instead of landing on the server all at once.
+pub struct QueryGate {
+    state: Mutex<GateState>,
+    freed: Condvar,
+}
+
+struct GateState {
+    max: u32,
+    in_flight: u32,
+    waiting: u32,
+}
+
+impl QueryGate {
+    fn new(max: u32) -> Self {
+        Self {
+            state: Mutex::new(GateState {
+                max: max.max(1),
+                in_flight: 0,
+                waiting: 0,
+            }),
+            freed: Condvar::new(),
+        }
+    }
+
+    fn set_max(&self, max: u32) {
+        if let Ok(mut state) = self.state.lock() {
+            state.max = max.max(1);
+        }
+        self.freed.notify_all();
+    }
+
+    fn acquire(self: &Arc<Self>, timeout: Duration) -> Result<QueryPermit, AppError> {
+        let deadline = std::time::Instant::now() + timeout;
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| AppError::QueryError("Query gate lock poisoned".to_string()))?;
+        state.waiting += 1;
+        while state.in_flight >= state.max {
+            let now = std::time::Instant::now();
+            if now >= deadline {
+                state.waiting -= 1;
+                return Err(AppError::QueryError(format!(
+                    "{} queries are already running on this connection; gave up after waiting {}s for one to finish",
+                    state.in_flight,
+                    timeout.as_secs()
+                )));
+            }
+            state = self
+                .freed
+                .wait_timeout(state, deadline - now)
+                .map_err(|_| AppError::QueryError("Query gate lock poisoned".to_string()))?
+                .0;
+        }
+        state.waiting -= 1;
+        state.in_flight += 1;
+        Ok(QueryPermit(self.clone()))
+    }
+
+    fn stats(&self) -> PoolStats {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        PoolStats {
+            max_concurrent_queries: state.max,
+            in_flight: state.in_flight,
+            waiting: state.waiting,
+        }
+    }
+}
+
+/// One of a connection's query slots, given back when dropped.
+pub struct QueryPermit(Arc<QueryGate>);
+
+impl Drop for QueryPermit {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.0.state.lock() {
+            state.in_flight = state.in_flight.saturating_sub(1);
+        }
+        self.0.freed.notify_one();
+    }
+}
+
+/// Query concurrency on one connection, as reported by `get_pool_stats`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoolStats {
+    pub max_concurrent_queries: u32,
+    pub in_flight: u32,
+    /// Queries queued for a free slot.
+    pub waiting: u32,
+}
+
 pub struct ConnectionManager {
     connections: RwLock<HashMap<String, Arc<dyn DatabaseAdapter>>>,
+    /// Function catalogs per connection; they rarely change, so they are
+    /// fetched once per connect.
+    functions: RwLock<HashMap<String, Arc<Vec<SqlFunction>>>>,
+    /// IDs whose `connect` is in flight; `ready` is signalled whenever one
+    /// finishes, successfully or not. `get` waits on it even for IDs with no
+    /// connect started yet, since the UI can ask before auto-connect begins.
+    connecting: Mutex<HashSet<String>>,
+    ready: Condvar,
+    /// Parameters each active connection was opened with, for features that
+    /// need a second connection to the same database.
+    params: RwLock<HashMap<String, ConnectParams>>,
+    /// Concurrency caps per connection. Kept across reconnects so permits
+    /// held by queries on the old adapter are still counted.
+    gates: RwLock<HashMap<String, Arc<QueryGate>>>,
     runtime: tokio::runtime::Handle,
 }
 
@@ -1949,14 +6771,64 @@ impl ConnectionManager {
     pub fn new(runtime: tokio::runtime::Handle) -> Self {
         Self {
             connections: RwLock::new(HashMap::new()),
+            functions: RwLock::new(HashMap::new()),
+            connecting: Mutex::new(HashSet::new()),
+            ready: Condvar::new(),
+            params: RwLock::new(HashMap::new()),
+            gates: RwLock::new(HashMap::new()),
             runtime,
         }
     }
 
-    /// Open and register a new connection.
+    /// The function catalog for a connection, loaded on first use.
+    pub fn functions(&self, id: &str) -> Result<Arc<Vec<SqlFunction>>, AppError> {
+        if let Some(cached) = self.functions.read().ok().and_then(|f| f.get(id).cloned()) {
+            return Ok(cached);
+        }
+        let functions = Arc::new(self.get(id)?.list_functions()?);
+        if let Ok(mut cache) = self.functions.write() {
+            cache.insert(id.to_string(), functions.clone());
+        }
+        Ok(functions)
+    }
+
+    /// Forget a connection's cached function catalog, e.g. after installing
+    /// an extension that adds functions.
+    pub fn invalidate_functions(&self, id: &str) {
+        if let Ok(mut functions) = self.functions.write() {
+            functions.remove(id);
+        }
+    }
+
+    /// Open and register a new connection. Callers of `get` for the same ID
+    /// wait for this to finish instead of failing straight away.
     pub fn connect(&self, id: &str, params: &ConnectParams) -> Result<(), AppError> {
-        let adapter: Arc<dyn DatabaseAdapter> = match params.kind {
-            DatabaseKind::PostgreSQL => {
+        if let Ok(mut connecting) = self.connecting.lock() {
+            connecting.insert(id.to_string());
+        }
+        let result = self.open(id, params);
+        if let Ok(mut connecting) = self.connecting.lock() {
+            connecting.remove(id);
+        }
+        self.ready.notify_all();
+        result
+    }
+
+    /// Open a throwaway connection with `params`, check that it answers and
+    /// close it again, without registering it.
+    pub fn test_params(&self, params: &ConnectParams) -> Result<bool, AppError> {
+        if params.kind == DatabaseKind::SQLite {
+            return SqliteAdapter::probe(params);
+        }
+        let adapter = self.open_adapter(params)?;
+        let result = adapter.test_connection();
+        let _ = adapter.disconnect();
+        result
+    }
+
+    fn open_adapter(&self, params: &ConnectParams) -> Result<Arc<dyn DatabaseAdapter>, AppError> {
+        Ok(match params.kind {
+            DatabaseKind::PostgreSQL | DatabaseKind::CockroachDB => {
                 Arc::new(PostgresAdapter::connect(params, self.runtime.clone())?)
             }
             DatabaseKind::MySQL => {
@@ -1966,27 +6838,106 @@ impl ConnectionManager {
             DatabaseKind::Redis => {
                 Arc::new(RedisAdapter::connect(params, self.runtime.clone())?)
             }
-        };
+            DatabaseKind::Elasticsearch => {
+                Arc::new(ElasticAdapter::connect(params, self.runtime.clone())?)
+            }
+        })
+    }
+
+    fn open(&self, id: &str, params: &ConnectParams) -> Result<(), AppError> {
+        let adapter = self.open_adapter(params)?;
 
         let mut conns = self
             .connections
             .write()
             .map_err(|_| AppError::ConnectionFailed("Connection store lock poisoned".to_string()))?;
         conns.insert(id.to_string(), adapter);
+        if let Ok(mut functions) = self.functions.write() {
+            functions.remove(id);
+        }
+        if let Ok(mut gates) = self.gates.write() {
+            match gates.get(id) {
+                Some(gate) => gate.set_max(params.max_concurrent_queries),
+                None => {
+                    gates.insert(
+                        id.to_string(),
+                        Arc::new(QueryGate::new(params.max_concurrent_queries)),
+                    );
+                }
+            }
+        }
+        if let Ok(mut all_params) = self.params.write() {
+            all_params.insert(id.to_string(), params.clone());
+        }
 
         Ok(())
     }
 
-    /// Retrieve an active adapter by connection ID.
+    /// Retrieve an active adapter by connection ID. If it isn't registered
+    /// yet, wait up to `CONNECTION_READY_WAIT` for a connect to start and
+    /// finish; a connect that fails ends the wait early.
     pub fn get(&self, id: &str) -> Result<Arc<dyn DatabaseAdapter>, AppError> {
+        if let Some(adapter) = self.lookup(id)? {
+            return Ok(adapter);
+        }
+        self.wait_for(id, CONNECTION_READY_WAIT)?
+            .ok_or_else(|| AppError::NotFound(format!("Connection {id} not active")))
+    }
+
+    /// The adapter for `id` if it is registered right now, without waiting.
+    /// For checks like "is this connection open?".
+    pub fn active(&self, id: &str) -> Option<Arc<dyn DatabaseAdapter>> {
+        self.lookup(id).ok().flatten()
+    }
+
+    /// Block until the connection is registered or `timeout` elapses, even if
+    /// no connect for it has started yet. Returns whether it became active.
+    pub fn wait_for_connection(&self, id: &str, timeout: Duration) -> Result<bool, AppError> {
+        Ok(self.wait_for(id, timeout)?.is_some())
+    }
+
+    fn lookup(&self, id: &str) -> Result<Option<Arc<dyn DatabaseAdapter>>, AppError> {
         let conns = self
             .connections
             .read()
             .map_err(|_| AppError::ConnectionFailed("Connection store lock poisoned".to_string()))?;
-        conns
-            .get(id)
-            .cloned()
-            .ok_or_else(|| AppError::NotFound(format!("Connection {id} not active")))
+        Ok(conns.get(id).cloned())
+    }
+
+    /// Wait on `ready` until `id` is registered. The wait also ends once a
+    /// connect for `id` that was seen in flight finishes without one.
+    fn wait_for(
+        &self,
+        id: &str,
+        timeout: Duration,
+    ) -> Result<Option<Arc<dyn DatabaseAdapter>>, AppError> {
+        let deadline = std::time::Instant::now() + timeout;
+        let mut connecting = self
+            .connecting
+            .lock()
+            .map_err(|_| AppError::ConnectionFailed("Connection store lock poisoned".to_string()))?;
+        let mut seen_connecting = false;
+        loop {
+            // Checked under the `connecting` lock, which `connect` takes
+            // before notifying, so a registration can't slip in unseen.
+            if let Some(adapter) = self.lookup(id)? {
+                return Ok(Some(adapter));
+            }
+            if connecting.contains(id) {
+                seen_connecting = true;
+            } else if seen_connecting {
+                return Ok(None);
+            }
+            let now = std::time::Instant::now();
+            if now >= deadline {
+                return Ok(None);
+            }
+            connecting = self
+                .ready
+                .wait_timeout(connecting, deadline - now)
+                .map_err(|_| AppError::ConnectionFailed("Connection store lock poisoned".to_string()))?
+                .0;
+        }
     }
 
     /// Disconnect and remove a connection.
@@ -1995,12 +6946,77 @@ impl ConnectionManager {
             .connections
             .write()
             .map_err(|_| AppError::ConnectionFailed("Connection store lock poisoned".to_string()))?;
+        if let Ok(mut functions) = self.functions.write() {
+            functions.remove(id);
+        }
+        if let Ok(mut all_params) = self.params.write() {
+            all_params.remove(id);
+        }
         if let Some(adapter) = conns.remove(id) {
             adapter.disconnect()?;
         }
         Ok(())
     }
 
+    /// The parameters an active connection was opened with.
+    pub fn params(&self, id: &str) -> Result<ConnectParams, AppError> {
+        self.params
+            .read()
+            .ok()
+            .and_then(|p| p.get(id).cloned())
+            .ok_or_else(|| AppError::NotFound(format!("Connection {id} not active")))
+    }
+
+    /// Environment label of an active connection, if it has one.
+    pub fn environment(&self, id: &str) -> Option<String> {
+        self.params
+            .read()
+            .ok()
+            .and_then(|p| p.get(id).and_then(|params| params.environment.clone()))
+    }
+
+    /// Relabel a live connection; no-op if it isn't active.
+    pub fn set_environment(&self, id: &str, environment: Option<String>) {
+        if let Ok(mut params) = self.params.write() {
+            if let Some(params) = params.get_mut(id) {
+                params.environment = environment;
+            }
+        }
+    }
+
+    /// Wait for one of the connection's query slots. Hold the permit for as
+    /// long as the query runs.
+    pub fn acquire_query_slot(&self, id: &str) -> Result<QueryPermit, AppError> {
+        let gate = self
+            .gates
+            .read()
+            .ok()
+            .and_then(|g| g.get(id).cloned())
+            .ok_or_else(|| AppError::NotFound(format!("Connection {id} not active")))?;
+        gate.acquire(QUERY_SLOT_WAIT)
+    }
+
+    /// Change a live connection's concurrency cap. Queued queries are
+    /// re-checked straight away.
+    pub fn set_max_concurrent_queries(&self, id: &str, max: u32) {
+        if let Some(gate) = self.gates.read().ok().and_then(|g| g.get(id).cloned()) {
+            gate.set_max(max);
+        }
+    }
+
+    pub fn pool_stats(&self, id: &str) -> Result<PoolStats, AppError> {
+        self.gates
+            .read()
+            .ok()
+            .and_then(|g| g.get(id).map(|gate| gate.stats()))
+            .ok_or_else(|| AppError::NotFound(format!("Connection {id} not active")))
+    }
+
+    /// The runtime adapters run their async drivers on.
+    pub fn runtime(&self) -> tokio::runtime::Handle {
+        self.runtime.clone()
+    }
+
     /// List IDs of active connections.
     pub fn active_ids(&self) -> Vec<String> {
         if let Ok(conns) = self.connections.read() {
@@ -2010,3 +7026,223 @@ impl ConnectionManager {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sqlite_params(path: &std::path::Path) -> ConnectParams {
+        ConnectParams {
+            kind: DatabaseKind::SQLite,
+            host: String::new(),
+            port: 0,
+            database: path.to_string_lossy().into_owned(),
+            username: String::new(),
+            password: String::new(),
+            use_ssl: false,
+            quote_all_identifiers: false,
+            max_concurrent_queries: DEFAULT_MAX_CONCURRENT_QUERIES,
+            pool_size: 1,
+            connect_timeout_secs: None,
+            query_timeout_secs: None,
+            environment: None,
+            ssl_mode: None,
+            ca_cert_path: None,
+            client_cert_path: None,
+            client_key_path: None,
+            read_only: false,
+        }
+    }
+
+    #[test]
+    fn get_waits_for_a_connect_that_starts_later() {
+        let path = std::env::temp_dir().join(format!("arc-ready-{}.db", uuid::Uuid::new_v4()));
+        rusqlite::Connection::open(&path).unwrap();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let manager = ConnectionManager::new(runtime.handle().clone());
+        let params = sqlite_params(&path);
+
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                std::thread::sleep(Duration::from_millis(200));
+                manager.connect("late", &params).unwrap();
+            });
+            assert!(manager.active("late").is_none());
+            assert!(manager.get("late").is_ok());
+        });
+        manager.disconnect("late").unwrap();
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn numeric_parameters_round_trip_through_the_wire_format() {
+        for (text, expected) in [
+            ("0", "0"),
+            ("12.50", "12.50"),
+            ("-12345678.9", "-12345678.9"),
+            ("0.0001", "0.0001"),
+            ("100000000", "100000000"),
+            ("1e3", "1000"),
+            ("1.5e-3", "0.0015"),
+            ("123456789012345678901234567890.123", "123456789012345678901234567890.123"),
+            ("NaN", "NaN"),
+        ] {
+            let raw = pg_numeric_to_raw(text).unwrap();
+            assert_eq!(pg_numeric_from_raw(&raw).as_deref(), Some(expected), "{text}");
+        }
+        assert!(pg_numeric_to_raw("12a").is_none());
+        assert!(pg_numeric_to_raw(".").is_none());
+    }
+
+    #[test]
+    fn connection_environment_follows_connect_and_relabel() {
+        let path = std::env::temp_dir().join(format!("arc-env-{}.db", uuid::Uuid::new_v4()));
+        rusqlite::Connection::open(&path).unwrap();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let manager = ConnectionManager::new(runtime.handle().clone());
+        let params = ConnectParams {
+            environment: Some("production".to_string()),
+            ..sqlite_params(&path)
+        };
+
+        manager.connect("prod", &params).unwrap();
+        assert_eq!(manager.environment("prod").as_deref(), Some("production"));
+        manager.set_environment("prod", Some("staging".to_string()));
+        assert_eq!(manager.environment("prod").as_deref(), Some("staging"));
+        manager.set_environment("prod", None);
+        assert_eq!(manager.environment("prod"), None);
+        assert_eq!(manager.environment("missing"), None);
+
+        manager.disconnect("prod").unwrap();
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn hstore_and_composite_values_decode_to_objects() {
+        use tokio_postgres::types::{Field, Kind, Type};
+
+        let hstore = Type::new("hstore".to_string(), 90001, Kind::Simple, "public".to_string());
+        let mut raw = 2i32.to_be_bytes().to_vec();
+        for (key, value) in [("a", Some("1")), ("b", None)] {
+            raw.extend((key.len() as i32).to_be_bytes());
+            raw.extend(key.as_bytes());
+            match value {
+                Some(v) => {
+                    raw.extend((v.len() as i32).to_be_bytes());
+                    raw.extend(v.as_bytes());
+                }
+                None => raw.extend((-1i32).to_be_bytes()),
+            }
+        }
+        assert_eq!(
+            pg_decode_raw_by_type(Some(&raw), &hstore, 0),
+            serde_json::json!({"a": "1", "b": null})
+        );
+
+        let point = Type::new(
+            "point2".to_string(),
+            90002,
+            Kind::Composite(vec![
+                Field::new("x".to_string(), Type::INT4),
+                Field::new("label".to_string(), Type::TEXT),
+            ]),
+            "public".to_string(),
+        );
+        let mut raw = 2i32.to_be_bytes().to_vec();
+        raw.extend(Type::INT4.oid().to_be_bytes());
+        raw.extend(4i32.to_be_bytes());
+        raw.extend(7i32.to_be_bytes());
+        raw.extend(Type::TEXT.oid().to_be_bytes());
+        raw.extend((-1i32).to_be_bytes());
+        assert_eq!(
+            pg_decode_raw_by_type(Some(&raw), &point, 0),
+            serde_json::json!({"x": 7, "label": null})
+        );
+
+        // Without usable metadata the value comes back as its text.
+        let unknown = Type::new("ltree".to_string(), 90003, Kind::Simple, "public".to_string());
+        assert_eq!(
+            pg_decode_raw_by_type(Some(b"top.science"), &unknown, 0),
+            serde_json::json!("top.science")
+        );
+        assert_eq!(
+            pg_decode_raw_by_type(Some(&[0, 0, 0, 9]), &point, 0),
+            serde_json::json!("\0\0\0\t")
+        );
+    }
+
+    #[test]
+    fn acquire_and_execution_timeouts_are_reported_apart() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let limit = Duration::from_millis(20);
+
+        let stalled_pool = runtime.block_on(acquire_within(
+            limit,
+            "waiting for a pooled connection",
+            std::future::pending::<()>(),
+        ));
+        match stalled_pool {
+            Err(AppError::ConnectionFailed(message)) => {
+                assert!(message.contains("waiting for a pooled connection"), "{message}")
+            }
+            other => panic!("expected an acquire timeout, got {other:?}"),
+        }
+
+        // A prompt checkout leaves the query's own timeout to fire.
+        let slow_query = runtime.block_on(async {
+            acquire_within(limit, "waiting for a pooled connection", async {}).await?;
+            tokio::time::timeout(limit, std::future::pending::<()>())
+                .await
+                .map_err(|_| execution_timeout(limit))
+        });
+        match slow_query {
+            Err(AppError::QueryError(message)) => {
+                assert!(message.contains("execution timeout"), "{message}")
+            }
+            other => panic!("expected an execution timeout, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn sqlite_execute_query_handles_pragmas_and_writes() {
+        let path = std::env::temp_dir().join(format!("arc-pragma-{}.db", uuid::Uuid::new_v4()));
+        rusqlite::Connection::open(&path)
+            .unwrap()
+            .execute_batch(
+                "CREATE TABLE t (id INTEGER PRIMARY KEY, v TEXT);
+                 INSERT INTO t (v) VALUES ('a'), ('b');",
+            )
+            .unwrap();
+        let adapter = SqliteAdapter::connect(&sqlite_params(&path)).unwrap();
+
+        let mode = adapter.execute_query("PRAGMA journal_mode;").unwrap();
+        assert_eq!(mode.columns, ["journal_mode"]);
+        assert_eq!(mode.rows.len(), 1);
+
+        let info = adapter.execute_query("PRAGMA table_info(t)").unwrap();
+        assert!(info.columns.iter().any(|c| c == "name"));
+        assert_eq!(info.rows.len(), 2);
+
+        // A write sent down the query path runs and reports what it changed.
+        let update = adapter.execute_query("UPDATE t SET v = 'z'").unwrap();
+        assert_eq!(update.columns, ["rows_affected"]);
+        assert_eq!(update.rows, [[serde_json::json!(2)]]);
+        let check = adapter.execute_query("SELECT count(*) FROM t WHERE v = 'z'").unwrap();
+        assert_eq!(check.rows[0][0], serde_json::json!(2));
+
+        drop(adapter);
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn range_reads_counted_from_the_end_are_capped() {
+        let cap = REDIS_MAX_ELEMENTS as i64;
+        let len = 3 * cap;
+        assert_eq!(resolve_capped_range(-1_000_000, -1, len), (0, cap));
+        assert_eq!(resolve_capped_range(-10, -1, len), (len - 10, len - 1));
+        assert_eq!(resolve_capped_range(-5, 2, len), (len, len));
+        assert_eq!(resolve_capped_range(-10, -20, len), (len, len));
+        assert_eq!(capped_range_stop(0, -1), cap);
+        assert_eq!(capped_range_stop(5, 10), 10);
+    }
+}
@@ -0,0 +1,367 @@
+// Arc columnar export
+//
+// Converts a materialized `QueryResult` into an Arrow record batch for IPC
+// or Parquet output. Each column's Arrow type comes from the type the
+// backend reported for it (`QueryResult::column_types`): booleans, integers,
+// floats, dates and timestamps map to their Arrow counterparts, binary
+// columns (fetched base64-encoded) become Arrow binary and everything else
+// (text, decimals, nested JSON) becomes UTF-8. Where no type was reported,
+// or a value doesn't fit the reported type (SQLite columns are untyped), the
+// type is inferred from the values the column holds.
+
+use std::sync::Arc;
+
+use arrow::array::{
+    ArrayRef, BinaryBuilder, BooleanBuilder, Date32Builder, Float64Builder, Int64Builder,
+    StringBuilder, TimestampMicrosecondBuilder,
+};
+use base64::Engine;
+use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+use arrow::record_batch::RecordBatch;
+use serde_json::Value;
+
+use crate::commands::{AppError, QueryResult};
+
+fn arrow_error(e: impl std::fmt::Display) -> AppError {
+    AppError::QueryError(format!("Arrow conversion failed: {e}"))
+}
+
+/// The Arrow type for a backend type name: Postgres (`int8`, `timestamptz`),
+/// MySQL wire types (`longlong`, `datetime`) or a SQLite declared type,
+/// which is matched by affinity.
+fn declared_type(type_name: &str) -> Option<DataType> {
+    let name = type_name.trim().to_ascii_lowercase();
+    let data_type = match name.as_str() {
+        "" => return None,
+        "bool" | "boolean" => DataType::Boolean,
+        "int2" | "int4" | "int8" | "oid" | "tiny" | "short" | "int24" | "long" | "longlong"
+        | "year" => DataType::Int64,
+        "float4" | "float8" | "float" | "double" => DataType::Float64,
+        "date" | "newdate" => DataType::Date32,
+        "bytea" => DataType::Binary,
+        "timestamptz" => DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+        "timestamp" | "timestamp2" | "datetime" | "datetime2" => {
+            DataType::Timestamp(TimeUnit::Microsecond, None)
+        }
+        _ if name.contains("int") => DataType::Int64,
+        _ if name.contains("real") || name.contains("floa") || name.contains("doub") => {
+            DataType::Float64
+        }
+        _ => DataType::Utf8,
+    };
+    Some(data_type)
+}
+
+/// Days since the Unix epoch for a `YYYY-MM-DD` date.
+fn date_value(value: &Value) -> Option<i32> {
+    let date = chrono::NaiveDate::parse_from_str(value.as_str()?, "%Y-%m-%d").ok()?;
+    let epoch = chrono::NaiveDate::from_ymd_opt(1970, 1, 1)?;
+    i32::try_from((date - epoch).num_days()).ok()
+}
+
+/// Microseconds since the Unix epoch for an ISO 8601 date-time. One with an
+/// offset is converted to UTC; one without is taken as it stands.
+fn timestamp_value(value: &Value) -> Option<i64> {
+    let text = value.as_str()?;
+    if let Ok(time) = chrono::DateTime::parse_from_rfc3339(text) {
+        return Some(time.timestamp_micros());
+    }
+    ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"]
+        .iter()
+        .find_map(|format| chrono::NaiveDateTime::parse_from_str(text, format).ok())
+        .map(|time| time.and_utc().timestamp_micros())
+}
+
+/// The bytes of a base64-encoded binary value.
+fn binary_value(value: &Value) -> Option<Vec<u8>> {
+    base64::engine::general_purpose::STANDARD
+        .decode(value.as_str()?)
+        .ok()
+}
+
+/// Whether `value` (not null) can be stored in a column of `data_type`.
+fn fits(value: &Value, data_type: &DataType) -> bool {
+    match data_type {
+        DataType::Boolean => value.is_boolean(),
+        DataType::Int64 => value.is_i64(),
+        DataType::Float64 => value.is_number(),
+        DataType::Date32 => date_value(value).is_some(),
+        DataType::Timestamp(..) => timestamp_value(value).is_some(),
+        DataType::Binary => binary_value(value).is_some(),
+        _ => true,
+    }
+}
+
+/// Column `i`'s Arrow type: the reported type when every value fits it,
+/// otherwise one inferred from the values. A column the backend flagged as
+/// binary is binary whatever its declared type (MySQL reports TEXT and BLOB
+/// alike as `blob`).
+fn column_type(result: &QueryResult, i: usize) -> DataType {
+    let reported = if result.columns_binary.get(i).copied().unwrap_or(false) {
+        Some(DataType::Binary)
+    } else {
+        result.column_types.get(i).and_then(|name| declared_type(name))
+    };
+    match reported {
+        Some(data_type)
+            if result
+                .rows
+                .iter()
+                .filter_map(|row| row.get(i))
+                .filter(|v| !v.is_null())
+                .all(|v| fits(v, &data_type)) =>
+        {
+            data_type
+        }
+        _ => infer_type(&result.rows, i),
+    }
+}
+
+/// The narrowest Arrow type that holds every non-null value of column `i`.
+fn infer_type(rows: &[Vec<Value>], i: usize) -> DataType {
+    let mut values = rows
+        .iter()
+        .filter_map(|row| row.get(i))
+        .filter(|v| !v.is_null())
+        .peekable();
+    if values.peek().is_none() {
+        return DataType::Utf8;
+    }
+    let (mut bools, mut ints, mut numbers) = (true, true, true);
+    for value in values {
+        bools &= value.is_boolean();
+        ints &= value.is_i64();
+        numbers &= value.is_number();
+    }
+    if bools {
+        DataType::Boolean
+    } else if ints {
+        DataType::Int64
+    } else if numbers {
+        DataType::Float64
+    } else {
+        DataType::Utf8
+    }
+}
+
+fn build_column(rows: &[Vec<Value>], i: usize, data_type: &DataType) -> ArrayRef {
+    let cells = rows.iter().map(|row| row.get(i).filter(|v| !v.is_null()));
+    match data_type {
+        DataType::Boolean => {
+            let mut builder = BooleanBuilder::with_capacity(rows.len());
+            cells.for_each(|v| builder.append_option(v.and_then(Value::as_bool)));
+            Arc::new(builder.finish())
+        }
+        DataType::Int64 => {
+            let mut builder = Int64Builder::with_capacity(rows.len());
+            cells.for_each(|v| builder.append_option(v.and_then(Value::as_i64)));
+            Arc::new(builder.finish())
+        }
+        DataType::Float64 => {
+            let mut builder = Float64Builder::with_capacity(rows.len());
+            cells.for_each(|v| builder.append_option(v.and_then(Value::as_f64)));
+            Arc::new(builder.finish())
+        }
+        DataType::Date32 => {
+            let mut builder = Date32Builder::with_capacity(rows.len());
+            cells.for_each(|v| builder.append_option(v.and_then(date_value)));
+            Arc::new(builder.finish())
+        }
+        DataType::Timestamp(_, zone) => {
+            let mut builder = TimestampMicrosecondBuilder::with_capacity(rows.len())
+                .with_timezone_opt(zone.clone());
+            cells.for_each(|v| builder.append_option(v.and_then(timestamp_value)));
+            Arc::new(builder.finish())
+        }
+        DataType::Binary => {
+            let mut builder = BinaryBuilder::new();
+            cells.for_each(|v| builder.append_option(v.and_then(binary_value)));
+            Arc::new(builder.finish())
+        }
+        _ => {
+            let mut builder = StringBuilder::new();
+            cells.for_each(|v| match v {
+                Some(Value::String(s)) => builder.append_value(s),
+                Some(other) => builder.append_value(other.to_string()),
+                None => builder.append_null(),
+            });
+            Arc::new(builder.finish())
+        }
+    }
+}
+
+/// One record batch holding the whole result, every column nullable.
+pub fn record_batch(result: &QueryResult) -> Result<RecordBatch, AppError> {
+    let types: Vec<DataType> = (0..result.columns.len())
+        .map(|i| column_type(result, i))
+        .collect();
+    let schema = Schema::new(
+        result
+            .columns
+            .iter()
+            .zip(&types)
+            .map(|(name, data_type)| Field::new(name, data_type.clone(), true))
+            .collect::<Vec<_>>(),
+    );
+    let arrays = types
+        .iter()
+        .enumerate()
+        .map(|(i, data_type)| build_column(&result.rows, i, data_type))
+        .collect();
+    RecordBatch::try_new(Arc::new(schema), arrays).map_err(arrow_error)
+}
+
+/// The result as an Arrow IPC stream.
+pub fn to_ipc_bytes(result: &QueryResult) -> Result<Vec<u8>, AppError> {
+    let batch = record_batch(result)?;
+    let mut bytes = Vec::new();
+    let mut writer = arrow::ipc::writer::StreamWriter::try_new(&mut bytes, &batch.schema())
+        .map_err(arrow_error)?;
+    writer.write(&batch).map_err(arrow_error)?;
+    writer.finish().map_err(arrow_error)?;
+    drop(writer);
+    Ok(bytes)
+}
+
+/// Write the result to `file` as a single-row-group Parquet file.
+pub fn write_parquet(result: &QueryResult, file: std::fs::File) -> Result<(), AppError> {
+    let batch = record_batch(result)?;
+    let mut writer =
+        parquet::arrow::ArrowWriter::try_new(file, batch.schema(), None).map_err(arrow_error)?;
+    writer.write(&batch).map_err(arrow_error)?;
+    writer.close().map_err(arrow_error)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{
+        Array, BinaryArray, BooleanArray, Date32Array, Float64Array, Int64Array, StringArray,
+        TimestampMicrosecondArray,
+    };
+    use serde_json::json;
+
+    fn result(columns: &[(&str, &str)], rows: Vec<Vec<Value>>) -> QueryResult {
+        QueryResult {
+            columns: columns.iter().map(|(name, _)| name.to_string()).collect(),
+            row_count: rows.len(),
+            rows,
+            execution_time_ms: 0,
+            truncated: false,
+            applied_limit: None,
+            environment: None,
+            columns_binary: vec![false; columns.len()],
+            column_types: columns.iter().map(|(_, ty)| ty.to_string()).collect(),
+            warning: None,
+        }
+    }
+
+    fn read_back(bytes: &[u8]) -> RecordBatch {
+        let mut reader = arrow::ipc::reader::StreamReader::try_new(bytes, None).unwrap();
+        reader.next().unwrap().unwrap()
+    }
+
+    #[test]
+    fn mixed_result_round_trips_through_ipc() {
+        let result = result(
+            &[
+                ("id", "int8"),
+                ("price", "float8"),
+                ("active", "bool"),
+                ("name", "text"),
+                ("total", "numeric"),
+                ("born", "date"),
+                ("seen", "timestamptz"),
+            ],
+            vec![
+                vec![
+                    json!(1),
+                    json!(2.5),
+                    json!(true),
+                    json!("a"),
+                    json!("12.50"),
+                    json!("2024-02-29"),
+                    json!("2024-02-29T12:00:00Z"),
+                ],
+                vec![Value::Null; 7],
+            ],
+        );
+        let batch = read_back(&to_ipc_bytes(&result).unwrap());
+        let schema = batch.schema();
+        let types: Vec<&DataType> = schema.fields().iter().map(|f| f.data_type()).collect();
+        assert_eq!(
+            types,
+            [
+                &DataType::Int64,
+                &DataType::Float64,
+                &DataType::Boolean,
+                &DataType::Utf8,
+                &DataType::Utf8,
+                &DataType::Date32,
+                &DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+            ]
+        );
+
+        let column = |i: usize| batch.column(i).as_any();
+        assert_eq!(column(0).downcast_ref::<Int64Array>().unwrap().value(0), 1);
+        assert_eq!(column(1).downcast_ref::<Float64Array>().unwrap().value(0), 2.5);
+        assert!(column(2).downcast_ref::<BooleanArray>().unwrap().value(0));
+        assert_eq!(column(3).downcast_ref::<StringArray>().unwrap().value(0), "a");
+        assert_eq!(column(4).downcast_ref::<StringArray>().unwrap().value(0), "12.50");
+        assert_eq!(column(5).downcast_ref::<Date32Array>().unwrap().value(0), 19782);
+        assert_eq!(
+            column(6).downcast_ref::<TimestampMicrosecondArray>().unwrap().value(0),
+            1_709_208_000_000_000
+        );
+        for i in 0..7 {
+            assert!(batch.column(i).is_null(1));
+        }
+    }
+
+    #[test]
+    fn binary_columns_round_trip_through_parquet() {
+        let mut result = result(
+            &[("id", "int8"), ("data", "blob"), ("note", "blob")],
+            vec![
+                vec![json!(1), json!("AP8Q"), json!("plain text")],
+                vec![json!(2), Value::Null, Value::Null],
+            ],
+        );
+        // MySQL reports TEXT as `blob` too; only the flag marks real bytes.
+        result.columns_binary = vec![false, true, false];
+
+        let path = std::env::temp_dir().join(format!("arc-{}.parquet", uuid::Uuid::new_v4()));
+        write_parquet(&result, std::fs::File::create(&path).unwrap()).unwrap();
+        let file = std::fs::File::open(&path).unwrap();
+        let mut reader =
+            parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder::try_new(file)
+                .unwrap()
+                .build()
+                .unwrap();
+        let batch = reader.next().unwrap().unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(batch.num_rows(), 2);
+        let column = |i: usize| batch.column(i).as_any();
+        assert_eq!(column(0).downcast_ref::<Int64Array>().unwrap().value(1), 2);
+        let data = column(1).downcast_ref::<BinaryArray>().unwrap();
+        assert_eq!(data.value(0), [0x00, 0xff, 0x10]);
+        assert!(data.is_null(1));
+        let note = column(2).downcast_ref::<StringArray>().unwrap();
+        assert_eq!(note.value(0), "plain text");
+    }
+
+    #[test]
+    fn values_that_do_not_fit_the_reported_type_fall_back_to_inference() {
+        // SQLite lets text into an INTEGER column.
+        let result = result(
+            &[("n", "INTEGER"), ("m", "")],
+            vec![vec![json!(1), json!(1.5)], vec![json!("two"), json!(2)]],
+        );
+        let batch = record_batch(&result).unwrap();
+        assert_eq!(batch.schema().field(0).data_type(), &DataType::Utf8);
+        assert_eq!(batch.schema().field(1).data_type(), &DataType::Float64);
+    }
+}
+
@@ -1,11 +1,14 @@
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::Path;
-use tauri::{AppHandle, Manager, State};
+use tauri::{AppHandle, Emitter, Manager, State};
 use thiserror::Error;
 
-use crate::adapter::{ConnectParams, ConnectionManager, DatabaseKind};
-use crate::db::Database;
+use crate::adapter::{
+    BinaryEncoding, ConnectParams, ConnectionManager, DatabaseKind, DbExtension, ObjectMatch,
+    PoolStats, SchemaOptions, ServerInfo, SqlFunction, SslMode, StatementOutcome, TableSizeInfo,
+};
+use crate::db::{Database, LLM_API_KEY_SETTING, SECRET_SETTINGS};
 
 // --- Error handling ---
 
@@ -15,6 +18,13 @@ pub enum AppError {
     NotFound(String),
     #[error("Connection failed: {0}")]
     ConnectionFailed(String),
+    /// A connect failure the driver identified as a bad login, an
+    /// unreachable server or a timeout; shown like `ConnectionFailed`.
+    #[error("Connection failed: {message}")]
+    ConnectRejected {
+        status: ConnectionTestStatus,
+        message: String,
+    },
     #[error("Query error: {0}")]
     QueryError(String),
     #[error("CSV parse error: {0}")]
@@ -48,13 +58,51 @@ pub struct DatabaseConnection {
     pub connected: bool,
     pub password: String,
     pub use_ssl: bool,
+    #[serde(default)]
+    pub quote_all_identifiers: bool,
+    #[serde(default = "default_max_concurrent_queries")]
+    pub max_concurrent_queries: u32,
+    /// Unset means the adapter default.
+    #[serde(default)]
+    pub connect_timeout_secs: Option<u64>,
+    #[serde(default)]
+    pub query_timeout_secs: Option<u64>,
+    /// Free-form label such as "production" or "staging".
+    #[serde(default)]
+    pub environment: Option<String>,
+    /// Unset derives the mode from `use_ssl`.
+    #[serde(default)]
+    pub ssl_mode: Option<SslMode>,
+    #[serde(default)]
+    pub ca_cert_path: Option<String>,
+    #[serde(default)]
+    pub client_cert_path: Option<String>,
+    #[serde(default)]
+    pub client_key_path: Option<String>,
+    /// SQLite only: open the file read-only.
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+fn default_max_concurrent_queries() -> u32 {
+    crate::adapter::DEFAULT_MAX_CONCURRENT_QUERIES
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TableSchema {
+    /// For PostgreSQL, qualified as `schema.table` outside `public`; for
+    /// SQLite, as `alias.table` in an attached database.
     pub name: String,
+    /// The PostgreSQL schema or attached SQLite database the table lives
+    /// in; `None` for other backends.
+    #[serde(default)]
+    pub schema: Option<String>,
     pub columns: Vec<ColumnInfo>,
     pub row_count: u64,
+    /// `row_count` is the planner's estimate (PostgreSQL `reltuples`,
+    /// MySQL `table_rows`) rather than a `COUNT(*)`.
+    #[serde(default)]
+    pub is_estimate: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -71,6 +119,28 @@ pub struct QueryResult {
     pub rows: Vec<Vec<serde_json::Value>>,
     pub row_count: usize,
     pub execution_time_ms: u64,
+    /// Set when the row cap for the query was hit and rows were dropped.
+    #[serde(default)]
+    pub truncated: bool,
+    /// The LIMIT added to the query by the `auto_limit` setting, if any.
+    #[serde(default)]
+    pub applied_limit: Option<usize>,
+    /// Environment label of the connection the query ran on, so result
+    /// panels can show e.g. a production banner.
+    #[serde(default)]
+    pub environment: Option<String>,
+    /// Per column, whether it holds binary data, so the UI knows which
+    /// strings are base64 or blob placeholders rather than text.
+    #[serde(default)]
+    pub columns_binary: Vec<bool>,
+    /// Per column, the type the backend reported for it (e.g. `int8`,
+    /// `newdecimal`, `INTEGER`); empty when the backend reports none.
+    #[serde(default)]
+    pub column_types: Vec<String>,
+    /// Caveat about how the result was produced, e.g. that the command
+    /// blocks the server, for the UI to show alongside it.
+    #[serde(default)]
+    pub warning: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -89,6 +159,9 @@ pub struct Exploration {
     pub title: String,
     pub created_at: String,
     pub message_count: u32,
+    /// When the exploration was moved to the trash.
+    #[serde(default)]
+    pub deleted_at: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -101,6 +174,12 @@ pub struct ExplorationMessage {
     pub created_at: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExplorationCounters {
+    pub token_count: u64,
+    pub turn_count: u64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SavedQuery {
     pub id: String,
@@ -109,6 +188,32 @@ pub struct SavedQuery {
     pub sql: String,
     pub connection_id: String,
     pub created_at: String,
+    /// Last edit; `None` for queries saved before edits were tracked.
+    #[serde(default)]
+    pub updated_at: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// A tag used on saved queries, with how many carry it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedQueryTag {
+    pub tag: String,
+    pub count: u32,
+}
+
+/// One run of `execute_query`, as kept in the query history.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryHistoryEntry {
+    pub id: String,
+    pub connection_id: String,
+    pub sql: String,
+    pub executed_at: String,
+    pub duration_ms: u64,
+    /// Rows returned; `None` when the query failed.
+    pub row_count: Option<usize>,
+    pub success: bool,
+    pub error: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -123,6 +228,29 @@ pub struct SavedChart {
     pub sql: Option<String>,
     pub data: serde_json::Value,
     pub created_at: String,
+    /// Last edit; `None` for charts saved before edits were tracked.
+    #[serde(default)]
+    pub updated_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Dashboard {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub created_at: String,
+}
+
+/// A reusable piece of SQL text, expanded in the editor from `@name`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snippet {
+    pub id: String,
+    pub name: String,
+    pub body: String,
+    /// `None` for snippets available on every connection.
+    pub connection_id: Option<String>,
+    pub tags: Vec<String>,
+    pub created_at: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -139,6 +267,16 @@ pub struct DatabaseStats {
     pub total_row_count: u64,
     pub disk_usage_bytes: u64,
     pub connected: bool,
+    /// Endpoint the figures came from: the live connection's parameters, or
+    /// the stored connection record when it isn't connected.
+    #[serde(default)]
+    pub kind: Option<DatabaseKind>,
+    #[serde(default)]
+    pub host: Option<String>,
+    #[serde(default)]
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub database: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -149,6 +287,48 @@ pub struct ScanResult {
     pub query_type: String,
 }
 
+/// What a `search_workspace` hit refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkspaceItemKind {
+    SavedQuery,
+    ConnectionNote,
+    Message,
+}
+
+impl WorkspaceItemKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::SavedQuery => "saved_query",
+            Self::ConnectionNote => "connection_note",
+            Self::Message => "message",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "saved_query" => Some(Self::SavedQuery),
+            "connection_note" => Some(Self::ConnectionNote),
+            "message" => Some(Self::Message),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceSearchResult {
+    pub kind: WorkspaceItemKind,
+    /// The saved query or message id; for a note, its connection's id.
+    pub id: String,
+    /// The exploration a message belongs to.
+    pub parent_id: Option<String>,
+    /// The query's name, the note's connection name or the message's
+    /// exploration title.
+    pub title: Option<String>,
+    /// Text around the match, with matched words wrapped in `**`.
+    pub snippet: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TableLink {
     pub id: String,
@@ -187,7 +367,31 @@ pub struct FunctionCallInfo {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChatCompletionRequest {
     pub messages: Vec<ChatMessage>,
+    /// Tool specs in the OpenAI function-tool shape. Kept untyped here so a
+    /// malformed spec can be reported precisely by `parse_tool_specs`.
     pub tools: Option<Vec<serde_json::Value>>,
+    /// The connection the exploration is working against, when one is
+    /// selected. Used when the conversation itself doesn't name one.
+    #[serde(default)]
+    pub connection_id: Option<String>,
+}
+
+/// A tool offered to the assistant: `{"type": "function", "function": {...}}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolSpec {
+    #[serde(rename = "type")]
+    pub tool_type: String,
+    pub function: ToolFunctionSpec,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolFunctionSpec {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// JSON Schema for the arguments; must describe an object.
+    #[serde(default)]
+    pub parameters: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -195,6 +399,10 @@ pub struct ChatCompletionResponse {
     pub content: Option<String>,
     pub tool_calls: Option<Vec<ToolCallInfo>>,
     pub finish_reason: String,
+    /// Set when the tool calls were planned but must be approved (and
+    /// possibly edited) by the user before they are executed.
+    #[serde(default)]
+    pub requires_approval: bool,
 }
 
 // --- Commands ---
@@ -204,17 +412,303 @@ pub fn list_connections(db: State<'_, Database>) -> Result<Vec<DatabaseConnectio
     db.list_connections()
 }
 
+/// Values `add_connection` falls back to, per project, for fields the caller
+/// leaves out. Anything passed explicitly still wins.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConnectionDefaults {
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    pub username: Option<String>,
+    pub use_ssl: Option<bool>,
+    pub quote_all_identifiers: Option<bool>,
+    pub max_concurrent_queries: Option<u32>,
+    pub connect_timeout_secs: Option<u64>,
+    pub query_timeout_secs: Option<u64>,
+    /// Applies to SQLite connections only; see `set_connection_read_only`.
+    pub read_only: Option<bool>,
+    pub environment: Option<String>,
+}
+
+/// Reject zero timeouts, under which every connect or query would fail.
+fn check_timeouts(
+    connect_timeout_secs: Option<u64>,
+    query_timeout_secs: Option<u64>,
+) -> Result<(), AppError> {
+    if connect_timeout_secs == Some(0) || query_timeout_secs == Some(0) {
+        return Err(AppError::QueryError(
+            "Timeouts must be at least one second".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn connection_defaults_key(project_id: &str) -> String {
+    format!("project_connection_defaults:{project_id}")
+}
+
+fn load_connection_defaults(db: &Database, project_id: &str) -> Result<ConnectionDefaults, AppError> {
+    let Some(raw) = db.get_setting(&connection_defaults_key(project_id))? else {
+        return Ok(ConnectionDefaults::default());
+    };
+    Ok(serde_json::from_str(&raw).unwrap_or_default())
+}
+
+/// Create a connection. With `project_id`, the connection is added to that
+/// project and fields left out are taken from its connection defaults.
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub fn add_connection(
     name: String,
     db_type: String,
-    host: String,
-    port: u16,
+    host: Option<String>,
+    port: Option<u16>,
     database: String,
-    username: String,
+    username: Option<String>,
+    use_ssl: Option<bool>,
+    connect_timeout_secs: Option<u64>,
+    query_timeout_secs: Option<u64>,
+    read_only: Option<bool>,
+    environment: Option<String>,
+    project_id: Option<String>,
+    db: State<'_, Database>,
+) -> Result<DatabaseConnection, AppError> {
+    check_timeouts(connect_timeout_secs, query_timeout_secs)?;
+    let defaults = match &project_id {
+        Some(project_id) => load_connection_defaults(&db, project_id)?,
+        None => ConnectionDefaults::default(),
+    };
+
+    let host = host
+        .or(defaults.host)
+        .ok_or_else(|| AppError::QueryError("A host is required".to_string()))?;
+    let port = port
+        .or(defaults.port)
+        .ok_or_else(|| AppError::QueryError("A port is required".to_string()))?;
+    let is_sqlite = DatabaseKind::from_str_loose(&db_type) == Some(DatabaseKind::SQLite);
+    if read_only == Some(true) && !is_sqlite {
+        return Err(AppError::QueryError(
+            "Only SQLite connections can be opened read-only".to_string(),
+        ));
+    }
+    // A project-wide read-only default only makes sense for SQLite.
+    let read_only = read_only.or(defaults.read_only.filter(|_| is_sqlite)).unwrap_or(false);
+    let environment = environment
+        .or(defaults.environment)
+        .map(|e| e.trim().to_string())
+        .filter(|e| !e.is_empty());
+
+    let connection = db.create_connection(DatabaseConnection {
+        id: String::new(),
+        name,
+        db_type,
+        host,
+        port,
+        database,
+        username: username.or(defaults.username).unwrap_or_default(),
+        connected: false,
+        password: String::new(),
+        use_ssl: use_ssl.or(defaults.use_ssl).unwrap_or(false),
+        quote_all_identifiers: defaults.quote_all_identifiers.unwrap_or(false),
+        max_concurrent_queries: defaults
+            .max_concurrent_queries
+            .unwrap_or(crate::adapter::DEFAULT_MAX_CONCURRENT_QUERIES)
+            .max(1),
+        connect_timeout_secs: connect_timeout_secs.or(defaults.connect_timeout_secs),
+        query_timeout_secs: query_timeout_secs.or(defaults.query_timeout_secs),
+        environment,
+        ssl_mode: None,
+        ca_cert_path: None,
+        client_cert_path: None,
+        client_key_path: None,
+        read_only,
+    })?;
+    if let Some(project_id) = &project_id {
+        db.link_connection_to_project(project_id, &connection.id)?;
+    }
+    Ok(connection)
+}
+
+#[tauri::command]
+pub fn get_project_connection_defaults(
+    project_id: String,
+    db: State<'_, Database>,
+) -> Result<ConnectionDefaults, AppError> {
+    load_connection_defaults(&db, &project_id)
+}
+
+#[tauri::command]
+pub fn set_project_connection_defaults(
+    project_id: String,
+    defaults: ConnectionDefaults,
+    db: State<'_, Database>,
+) -> Result<(), AppError> {
+    check_timeouts(defaults.connect_timeout_secs, defaults.query_timeout_secs)?;
+    let value = serde_json::to_string(&defaults).map_err(|e| {
+        AppError::DatabaseError(format!("Failed to serialize connection defaults: {e}"))
+    })?;
+    db.set_setting(&connection_defaults_key(&project_id), &value)
+}
+
+/// Fields a cloned connection may change from its source; anything left
+/// unset is copied.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConnectionOverrides {
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    pub database: Option<String>,
+    pub username: Option<String>,
+    pub use_ssl: Option<bool>,
+}
+
+/// Copy a connection, e.g. to point at another database on the same server.
+/// The password is not copied; the clone asks for it on first connect.
+#[tauri::command]
+pub fn clone_connection(
+    id: String,
+    new_name: String,
+    overrides: Option<ConnectionOverrides>,
+    copy_note: Option<bool>,
+    db: State<'_, Database>,
+) -> Result<DatabaseConnection, AppError> {
+    let new_name = new_name.trim();
+    if new_name.is_empty() {
+        return Err(AppError::QueryError("Connection name cannot be empty".to_string()));
+    }
+    db.clone_connection(
+        &id,
+        new_name,
+        &overrides.unwrap_or_default(),
+        copy_note.unwrap_or(false),
+    )
+}
+
+/// `clone_connection` with nothing overridden: an exact copy under a new
+/// name, minus the password, to tweak afterwards with `update_connection`.
+/// The copy is not added to any project.
+#[tauri::command]
+pub fn duplicate_connection(
+    id: String,
+    new_name: String,
+    copy_note: Option<bool>,
+    db: State<'_, Database>,
+) -> Result<DatabaseConnection, AppError> {
+    clone_connection(id, new_name, None, copy_note, db)
+}
+
+/// Edit a connection's name or target. An open connection is closed so the
+/// next connect uses the new settings, and a new host, port or username
+/// clears the saved password.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn update_connection(
+    id: String,
+    name: Option<String>,
+    host: Option<String>,
+    port: Option<u16>,
+    database: Option<String>,
+    username: Option<String>,
     db: State<'_, Database>,
+    listeners: State<'_, PgListeners>,
+    conn_manager: State<'_, ConnectionManager>,
 ) -> Result<DatabaseConnection, AppError> {
-    db.add_connection(&name, &db_type, &host, port, &database, &username)
+    let name = name.map(|n| n.trim().to_string());
+    if name.as_deref().is_some_and(str::is_empty) {
+        return Err(AppError::QueryError("Connection name cannot be empty".to_string()));
+    }
+    let mut updated = db.update_connection(
+        &id,
+        name.as_deref(),
+        host.as_deref(),
+        port,
+        database.as_deref(),
+        username.as_deref(),
+    )?;
+    if conn_manager.active(&id).is_some() {
+        listeners.remove_connection(&id);
+        conn_manager.disconnect(&id)?;
+        db.set_connection_status(&id, false)?;
+        updated.connected = false;
+    }
+    Ok(updated)
+}
+
+/// Force every identifier in SQL that Arc generates for this connection
+/// (imports, mock data, deletes) to be quoted. Takes effect on next connect.
+#[tauri::command]
+pub fn set_connection_quoting(
+    id: String,
+    quote_all_identifiers: bool,
+    db: State<'_, Database>,
+) -> Result<(), AppError> {
+    db.set_connection_quoting(&id, quote_all_identifiers)
+}
+
+/// Open this SQLite connection read-only: writes are rejected and the file
+/// is never switched to WAL. Takes effect on next connect. Other backends
+/// don't honour the flag, so turning it on for them is refused.
+#[tauri::command]
+pub fn set_connection_read_only(
+    id: String,
+    read_only: bool,
+    db: State<'_, Database>,
+) -> Result<(), AppError> {
+    let connections = db.list_connections()?;
+    let conn_info = connections
+        .iter()
+        .find(|c| c.id == id)
+        .ok_or_else(|| AppError::NotFound(format!("Connection {} not found", id)))?;
+    if read_only && DatabaseKind::from_str_loose(&conn_info.db_type) != Some(DatabaseKind::SQLite) {
+        return Err(AppError::QueryError(
+            "Only SQLite connections can be opened read-only".to_string(),
+        ));
+    }
+    db.set_connection_read_only(&id, read_only)
+}
+
+/// Label the connection with an environment (e.g. "production"); `None`
+/// or an empty label clears it. Applies immediately if the connection is
+/// open.
+#[tauri::command]
+pub fn set_connection_environment(
+    id: String,
+    environment: Option<String>,
+    db: State<'_, Database>,
+    conn_manager: State<'_, ConnectionManager>,
+) -> Result<(), AppError> {
+    let environment = environment
+        .map(|e| e.trim().to_string())
+        .filter(|e| !e.is_empty());
+    db.set_connection_environment(&id, environment.as_deref())?;
+    conn_manager.set_environment(&id, environment);
+    Ok(())
+}
+
+/// Cap how many queries Arc runs at once on this connection; extra ones
+/// queue. Applies immediately if the connection is open.
+#[tauri::command]
+pub fn set_connection_concurrency(
+    id: String,
+    max_concurrent_queries: u32,
+    db: State<'_, Database>,
+    conn_manager: State<'_, ConnectionManager>,
+) -> Result<(), AppError> {
+    if max_concurrent_queries == 0 {
+        return Err(AppError::QueryError(
+            "max_concurrent_queries must be at least 1".to_string(),
+        ));
+    }
+    db.set_connection_concurrency(&id, max_concurrent_queries)?;
+    conn_manager.set_max_concurrent_queries(&id, max_concurrent_queries);
+    Ok(())
+}
+
+/// In-flight and queued query counts for an active connection.
+#[tauri::command]
+pub fn get_pool_stats(
+    connection_id: String,
+    conn_manager: State<'_, ConnectionManager>,
+) -> Result<PoolStats, AppError> {
+    conn_manager.pool_stats(&connection_id)
 }
 
 #[tauri::command]
@@ -243,14 +737,19 @@ pub fn remove_connection(
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub fn create_csv_connection(
     csv_content: String,
     file_name: String,
     project_id: String,
+    primary_key: Option<Vec<String>>,
+    delimiter: Option<char>,
+    quote: Option<char>,
     app_handle: AppHandle,
     db: State<'_, Database>,
     conn_manager: State<'_, ConnectionManager>,
 ) -> Result<DatabaseConnection, AppError> {
+    let format = CsvFormat::new(delimiter, quote)?;
     let table_name = file_name
         .trim_end_matches(".csv")
         .replace(|c: char| !c.is_alphanumeric() && c != '_', "_");
@@ -281,122 +780,111 @@ pub fn create_csv_connection(
         username: String::new(),
         password: String::new(),
         use_ssl: false,
+        quote_all_identifiers: false,
+        max_concurrent_queries: conn.max_concurrent_queries,
+        pool_size: conn.max_concurrent_queries as usize,
+        connect_timeout_secs: conn.connect_timeout_secs,
+        query_timeout_secs: conn.query_timeout_secs,
+        environment: conn.environment.clone(),
+        ssl_mode: None,
+        ca_cert_path: None,
+        client_cert_path: None,
+        client_key_path: None,
+        read_only: false,
     };
     conn_manager.connect(&conn.id, &params)?;
     db.set_connection_status(&conn.id, true)?;
 
     // Import CSV data using the same logic as import_csv
-    let mut lines = csv_content.lines();
-    let header_line = lines.next().ok_or_else(|| {
-        AppError::CsvParseError("CSV is empty, no header row found".into())
-    })?;
-
-    let columns: Vec<String> = header_line
-        .split(',')
-        .map(|s| s.trim().trim_matches('"').to_string())
-        .collect();
-
-    if columns.is_empty() {
-        return Err(AppError::CsvParseError("No columns found in CSV header".into()));
-    }
-
-    let mut rows: Vec<Vec<serde_json::Value>> = Vec::new();
-    for line in lines {
-        let trimmed = line.trim();
-        if trimmed.is_empty() {
-            continue;
-        }
-        let values: Vec<serde_json::Value> = trimmed
-            .split(',')
-            .map(|s| {
-                let s = s.trim().trim_matches('"');
-                if let Ok(n) = s.parse::<i64>() {
-                    serde_json::Value::from(n)
-                } else if let Ok(f) = s.parse::<f64>() {
-                    serde_json::json!(f)
-                } else if s == "true" {
-                    serde_json::Value::Bool(true)
-                } else if s == "false" {
-                    serde_json::Value::Bool(false)
-                } else {
-                    serde_json::Value::String(s.to_string())
-                }
-            })
-            .collect();
-        rows.push(values);
-    }
-
-    // Create table and insert rows
+    let parsed = parse_csv(&csv_content, format)?;
     let adapter = conn_manager.get(&conn.id)?;
+    create_csv_table(&adapter, &table_name, &parsed, primary_key.as_deref())?;
 
-    let col_defs: Vec<String> = columns
-        .iter()
-        .enumerate()
-        .map(|(i, name)| {
-            let sample = rows
-                .first()
-                .and_then(|r| r.get(i))
-                .unwrap_or(&serde_json::Value::Null);
-            let type_str = match sample {
-                serde_json::Value::Number(n) => {
-                    if n.is_f64() { "REAL" } else { "INTEGER" }
-                }
-                serde_json::Value::Bool(_) => "BOOLEAN",
-                _ => "TEXT",
-            };
-            format!("\"{}\" {}", name, type_str)
-        })
-        .collect();
+    // Link to project
+    db.link_connection_to_project(&project_id, &conn.id)?;
 
-    let create_sql = format!(
-        "CREATE TABLE IF NOT EXISTS \"{}\" ({})",
-        table_name,
-        col_defs.join(", ")
-    );
-    adapter.execute_statement(&create_sql)?;
+    Ok(DatabaseConnection {
+        connected: true,
+        ..conn
+    })
+}
 
-    let col_list = columns
-        .iter()
-        .map(|c| format!("\"{}\"", c))
-        .collect::<Vec<_>>()
-        .join(", ");
+/// Outcome of `test_connection_params`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionTestStatus {
+    Success,
+    AuthFailed,
+    Unreachable,
+    Timeout,
+    Failed,
+}
 
-    const BATCH_SIZE: usize = 50;
-    for chunk in rows.chunks(BATCH_SIZE) {
-        let value_groups: Vec<String> = chunk
-            .iter()
-            .map(|row| {
-                let placeholders: Vec<String> = row
-                    .iter()
-                    .map(|v| match v {
-                        serde_json::Value::Null => "NULL".to_string(),
-                        serde_json::Value::Number(n) => n.to_string(),
-                        serde_json::Value::Bool(b) => b.to_string(),
-                        serde_json::Value::String(s) => {
-                            format!("'{}'", s.replace('\'', "''"))
-                        }
-                        _ => format!("'{}'", v),
-                    })
-                    .collect();
-                format!("({})", placeholders.join(", "))
-            })
-            .collect();
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectionTestResult {
+    pub status: ConnectionTestStatus,
+    /// The driver's error, when the test failed.
+    pub message: Option<String>,
+    pub elapsed_ms: u64,
+}
 
-        let insert_sql = format!(
-            "INSERT INTO \"{}\" ({}) VALUES {}",
-            table_name,
-            col_list,
-            value_groups.join(", ")
-        );
-        adapter.execute_statement(&insert_sql)?;
+/// The failure the user can act on, as classified by the adapter from the
+/// driver's error.
+fn connection_test_status(error: &AppError) -> ConnectionTestStatus {
+    match error {
+        AppError::ConnectRejected { status, .. } => *status,
+        _ => ConnectionTestStatus::Failed,
     }
+}
 
-    // Link to project
-    db.link_connection_to_project(&project_id, &conn.id)?;
+/// Try connection settings before they are saved, e.g. from a
+/// new-connection form. Nothing is registered or stored.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn test_connection_params(
+    db_type: String,
+    host: String,
+    port: u16,
+    database: String,
+    username: String,
+    password: String,
+    use_ssl: bool,
+    conn_manager: State<'_, ConnectionManager>,
+) -> Result<ConnectionTestResult, AppError> {
+    let kind = DatabaseKind::from_str_loose(&db_type).ok_or_else(|| {
+        AppError::ConnectionFailed(format!("Unsupported database type: {db_type}"))
+    })?;
+    let params = ConnectParams {
+        kind,
+        host,
+        port,
+        database,
+        username,
+        password,
+        use_ssl,
+        quote_all_identifiers: false,
+        max_concurrent_queries: 1,
+        pool_size: 1,
+        connect_timeout_secs: None,
+        query_timeout_secs: None,
+        environment: None,
+        ssl_mode: None,
+        ca_cert_path: None,
+        client_cert_path: None,
+        client_key_path: None,
+        read_only: false,
+    };
 
-    Ok(DatabaseConnection {
-        connected: true,
-        ..conn
+    let start = std::time::Instant::now();
+    let (status, message) = match conn_manager.test_params(&params) {
+        Ok(true) => (ConnectionTestStatus::Success, None),
+        Ok(false) => (ConnectionTestStatus::Failed, Some("The server did not respond".to_string())),
+        Err(e) => (connection_test_status(&e), Some(e.to_string())),
+    };
+    Ok(ConnectionTestResult {
+        status,
+        message,
+        elapsed_ms: start.elapsed().as_millis() as u64,
     })
 }
 
@@ -414,14 +902,26 @@ pub fn test_connection(
     }
 }
 
+/// Connect a saved connection. With `persist_credentials` false (it
+/// defaults to true) the password is kept only by the live adapter and any
+/// previously saved one is cleared, so auto-connect skips the connection.
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub fn connect_database(
     id: String,
     password: String,
     use_ssl: bool,
+    persist_credentials: Option<bool>,
+    connect_timeout_secs: Option<u64>,
+    query_timeout_secs: Option<u64>,
+    ssl_mode: Option<SslMode>,
+    ca_cert_path: Option<String>,
+    client_cert_path: Option<String>,
+    client_key_path: Option<String>,
     db: State<'_, Database>,
     conn_manager: State<'_, ConnectionManager>,
 ) -> Result<bool, AppError> {
+    check_timeouts(connect_timeout_secs, query_timeout_secs)?;
     let connections = db.list_connections()?;
     let conn_info = connections
         .iter()
@@ -439,11 +939,36 @@ pub fn connect_database(
         username: conn_info.username.clone(),
         password: password.clone(),
         use_ssl,
+        quote_all_identifiers: conn_info.quote_all_identifiers,
+        max_concurrent_queries: conn_info.max_concurrent_queries,
+        pool_size: conn_info.max_concurrent_queries as usize,
+        connect_timeout_secs: connect_timeout_secs.or(conn_info.connect_timeout_secs),
+        query_timeout_secs: query_timeout_secs.or(conn_info.query_timeout_secs),
+        environment: conn_info.environment.clone(),
+        ssl_mode: ssl_mode.or(conn_info.ssl_mode),
+        ca_cert_path: ca_cert_path.clone().or_else(|| conn_info.ca_cert_path.clone()),
+        client_cert_path: client_cert_path.clone().or_else(|| conn_info.client_cert_path.clone()),
+        client_key_path: client_key_path.clone().or_else(|| conn_info.client_key_path.clone()),
+        read_only: conn_info.read_only,
     };
 
     conn_manager.connect(&id, &params)?;
     db.set_connection_status(&id, true)?;
-    db.save_connection_credentials(&id, &password, use_ssl)?;
+    let saved_password = if persist_credentials.unwrap_or(true) { password.as_str() } else { "" };
+    db.save_connection_credentials(&id, saved_password, use_ssl)?;
+    if connect_timeout_secs.is_some() || query_timeout_secs.is_some() {
+        db.set_connection_timeouts(&id, params.connect_timeout_secs, params.query_timeout_secs)?;
+    }
+    if ssl_mode.is_some() || ca_cert_path.is_some() {
+        db.set_connection_tls(&id, params.ssl_mode, params.ca_cert_path.as_deref())?;
+    }
+    if client_cert_path.is_some() || client_key_path.is_some() {
+        db.set_connection_client_cert(
+            &id,
+            params.client_cert_path.as_deref(),
+            params.client_key_path.as_deref(),
+        )?;
+    }
     Ok(true)
 }
 
@@ -459,11 +984,12 @@ pub fn auto_connect_project_connections(
     let mut to_connect: Vec<(String, ConnectParams)> = Vec::new();
 
     for conn_info in &connections {
+        // No saved password: never entered, or kept in memory only.
         if conn_info.password.is_empty() && conn_info.db_type != "SQLite" {
             continue;
         }
 
-        if conn_manager.get(&conn_info.id).is_ok() {
+        if conn_manager.active(&conn_info.id).is_some() {
             already_connected.push(conn_info.id.clone());
             continue;
         }
@@ -483,6 +1009,17 @@ pub fn auto_connect_project_connections(
                 username: conn_info.username.clone(),
                 password: conn_info.password.clone(),
                 use_ssl: conn_info.use_ssl,
+                quote_all_identifiers: conn_info.quote_all_identifiers,
+                max_concurrent_queries: conn_info.max_concurrent_queries,
+                pool_size: conn_info.max_concurrent_queries as usize,
+                connect_timeout_secs: conn_info.connect_timeout_secs,
+                query_timeout_secs: conn_info.query_timeout_secs,
+                environment: conn_info.environment.clone(),
+                ssl_mode: conn_info.ssl_mode,
+                ca_cert_path: conn_info.ca_cert_path.clone(),
+                client_cert_path: conn_info.client_cert_path.clone(),
+                client_key_path: conn_info.client_key_path.clone(),
+                read_only: conn_info.read_only,
             },
         ));
     }
@@ -517,35 +1054,83 @@ pub fn auto_connect_project_connections(
     Ok(connected_ids)
 }
 
+/// Wait for a connection that is being opened (e.g. by auto-connect) to
+/// become active. Returns false if it isn't active within `timeout_ms`.
+#[tauri::command]
+pub fn wait_for_connection(
+    id: String,
+    timeout_ms: Option<u64>,
+    conn_manager: State<'_, ConnectionManager>,
+) -> Result<bool, AppError> {
+    let timeout = timeout_ms
+        .map(std::time::Duration::from_millis)
+        .unwrap_or(crate::adapter::CONNECTION_READY_WAIT);
+    conn_manager.wait_for_connection(&id, timeout)
+}
+
 #[tauri::command]
 pub fn disconnect_database(
     id: String,
     db: State<'_, Database>,
+    listeners: State<'_, PgListeners>,
     conn_manager: State<'_, ConnectionManager>,
 ) -> Result<(), AppError> {
+    listeners.remove_connection(&id);
     conn_manager.disconnect(&id)?;
     db.set_connection_status(&id, false)?;
     Ok(())
 }
 
-fn schema_cache_key(connection_id: &str) -> String {
-    format!("schema_cache:{connection_id}")
-}
+/// How long `get_schema` trusts a cached schema when the caller doesn't say.
+const DEFAULT_SCHEMA_CACHE_TTL_SECS: u64 = 300;
 
 fn load_cached_schema(db: &Database, connection_id: &str) -> Result<Option<Vec<TableSchema>>, AppError> {
-    let key = schema_cache_key(connection_id);
-    let Some(raw) = db.get_setting(&key)? else {
+    load_cached_schema_within(db, connection_id, None)
+}
+
+/// The cached schema, unless it is older than `max_age`. A cache whose
+/// timestamp doesn't parse counts as stale.
+fn load_cached_schema_within(
+    db: &Database,
+    connection_id: &str,
+    max_age: Option<std::time::Duration>,
+) -> Result<Option<Vec<TableSchema>>, AppError> {
+    let Some((raw, cached_at)) = db.get_schema_cache(connection_id)? else {
         return Ok(None);
     };
+    if let Some(max_age) = max_age {
+        let age = chrono::DateTime::parse_from_rfc3339(&cached_at)
+            .ok()
+            .and_then(|at| (chrono::Utc::now() - at.with_timezone(&chrono::Utc)).to_std().ok());
+        if age.is_none_or(|age| age > max_age) {
+            return Ok(None);
+        }
+    }
 
     Ok(serde_json::from_str::<Vec<TableSchema>>(&raw).ok())
 }
 
 fn save_cached_schema(db: &Database, connection_id: &str, schema: &[TableSchema]) -> Result<(), AppError> {
-    let key = schema_cache_key(connection_id);
     let value = serde_json::to_string(schema)
         .map_err(|e| AppError::DatabaseError(format!("Failed to serialize schema cache: {e}")))?;
-    db.set_setting(&key, &value)
+    db.save_schema_cache(connection_id, &value)
+}
+
+/// Drop `connection_id`'s cached schema when `sql` may have changed it.
+/// Every command that runs user SQL calls this once the SQL has run.
+fn forget_schema_after(db: &Database, kind: DatabaseKind, connection_id: &str, sql: &str) {
+    if crate::dialect::is_schema_change(kind, sql) {
+        let _ = db.delete_schema_cache(connection_id);
+    }
+}
+
+/// Drop the cached schema so the next `get_schema` introspects afresh.
+#[tauri::command]
+pub fn invalidate_schema_cache(
+    connection_id: String,
+    db: State<'_, Database>,
+) -> Result<(), AppError> {
+    db.delete_schema_cache(&connection_id)
 }
 
 #[tauri::command]
@@ -556,27 +1141,147 @@ pub fn get_cached_schema(
     load_cached_schema(&db, &connection_id)
 }
 
+/// Fetch the schema, optionally only for the tables in `tables`. A filtered
+/// request is answered from the cached full schema when there is one, but a
+/// freshly introspected partial schema is never written back to the cache.
+/// `exact_counts` counts every table instead of using estimates, bypassing
+/// the cache. A cache older than `ttl_secs` (five minutes by default) is
+/// refreshed.
 #[tauri::command]
 pub fn get_schema(
     connection_id: String,
     force_refresh: Option<bool>,
+    tables: Option<Vec<String>>,
+    exact_counts: Option<bool>,
+    ttl_secs: Option<u64>,
     db: State<'_, Database>,
     conn_manager: State<'_, ConnectionManager>,
 ) -> Result<Vec<TableSchema>, AppError> {
-    let force_refresh = force_refresh.unwrap_or(false);
+    let options = SchemaOptions {
+        tables,
+        exact_counts: exact_counts.unwrap_or(false),
+    };
+    let force_refresh = force_refresh.unwrap_or(false) || options.exact_counts;
 
     if !force_refresh {
-        if let Some(cached) = load_cached_schema(&db, &connection_id)? {
-            return Ok(cached);
+        let ttl = std::time::Duration::from_secs(ttl_secs.unwrap_or(DEFAULT_SCHEMA_CACHE_TTL_SECS));
+        if let Some(cached) = load_cached_schema_within(&db, &connection_id, Some(ttl))? {
+            return Ok(cached.into_iter().filter(|t| options.includes(&t.name)).collect());
         }
     }
 
     let adapter = conn_manager.get(&connection_id)?;
-    let schema = adapter.get_schema()?;
+    if options.tables.is_some() {
+        return adapter.get_schema_with(&options);
+    }
+    let schema = adapter.get_schema_with(&options)?;
     save_cached_schema(&db, &connection_id, &schema)?;
     Ok(schema)
 }
 
+/// Find tables and columns (and, when asked, user-defined routines) whose
+/// names contain `term`. Answered from the cached schema when there is one,
+/// otherwise from a live catalog query.
+#[tauri::command]
+pub fn search_objects(
+    connection_id: String,
+    term: String,
+    include_routines: Option<bool>,
+    db: State<'_, Database>,
+    conn_manager: State<'_, ConnectionManager>,
+) -> Result<Vec<ObjectMatch>, AppError> {
+    let term = term.trim();
+    if term.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut matches = match load_cached_schema(&db, &connection_id)? {
+        Some(schema) => crate::adapter::search_schema(&schema, term),
+        None => conn_manager.get(&connection_id)?.search_objects(term)?,
+    };
+
+    if include_routines.unwrap_or(false) {
+        let needle = term.to_lowercase();
+        // Overloads share a name; report each routine once.
+        let routines: std::collections::BTreeSet<String> = conn_manager
+            .functions(&connection_id)?
+            .iter()
+            .filter(|f| !f.builtin && f.name.to_lowercase().contains(&needle))
+            .map(|f| f.name.clone())
+            .collect();
+        matches.extend(routines.iter().map(|name| ObjectMatch::routine(name)));
+    }
+    Ok(matches)
+}
+
+/// Built-in and user-defined SQL functions for editor autocomplete and
+/// validation. Cached per connection until it reconnects.
+#[tauri::command]
+pub fn list_sql_functions(
+    connection_id: String,
+    conn_manager: State<'_, ConnectionManager>,
+) -> Result<Vec<SqlFunction>, AppError> {
+    Ok(conn_manager.functions(&connection_id)?.as_ref().clone())
+}
+
+/// Extensions available on the server, installed ones first. Empty for
+/// backends without extensions.
+#[tauri::command]
+pub fn list_extensions(
+    connection_id: String,
+    conn_manager: State<'_, ConnectionManager>,
+) -> Result<Vec<DbExtension>, AppError> {
+    let adapter = conn_manager.get(&connection_id)?;
+    adapter.list_extensions()
+}
+
+#[tauri::command]
+pub fn create_extension(
+    connection_id: String,
+    name: String,
+    db: State<'_, Database>,
+    conn_manager: State<'_, ConnectionManager>,
+) -> Result<(), AppError> {
+    let adapter = conn_manager.get(&connection_id)?;
+    adapter.create_extension(&name)?;
+    invalidate_extension_caches(&connection_id, &db, &conn_manager);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn drop_extension(
+    connection_id: String,
+    name: String,
+    db: State<'_, Database>,
+    conn_manager: State<'_, ConnectionManager>,
+) -> Result<(), AppError> {
+    let adapter = conn_manager.get(&connection_id)?;
+    adapter.drop_extension(&name)?;
+    invalidate_extension_caches(&connection_id, &db, &conn_manager);
+    Ok(())
+}
+
+/// Extensions bring their own functions, types and sometimes tables.
+fn invalidate_extension_caches(
+    connection_id: &str,
+    db: &Database,
+    conn_manager: &ConnectionManager,
+) {
+    conn_manager.invalidate_functions(connection_id);
+    let _ = db.delete_schema_cache(connection_id);
+}
+
+/// Table names only, for rendering the schema tree before any table is
+/// expanded.
+#[tauri::command]
+pub fn list_table_names(
+    connection_id: String,
+    conn_manager: State<'_, ConnectionManager>,
+) -> Result<Vec<String>, AppError> {
+    let adapter = conn_manager.get(&connection_id)?;
+    adapter.list_table_names()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TableMetadataResult {
     pub schema: TableSchema,
@@ -599,6 +1304,25 @@ pub struct ForeignKeyResult {
     pub to_column: String,
 }
 
+impl From<crate::adapter::TableMetadata> for TableMetadataResult {
+    fn from(meta: crate::adapter::TableMetadata) -> Self {
+        TableMetadataResult {
+            schema: meta.schema,
+            indexes: meta.indexes.into_iter().map(|i| IndexInfoResult {
+                name: i.name,
+                columns: i.columns,
+                unique: i.unique,
+            }).collect(),
+            foreign_keys: meta.foreign_keys.into_iter().map(|fk| ForeignKeyResult {
+                name: fk.name,
+                from_column: fk.from_column,
+                to_table: fk.to_table,
+                to_column: fk.to_column,
+            }).collect(),
+        }
+    }
+}
+
 #[tauri::command]
 pub fn get_table_metadata(
     connection_id: String,
@@ -606,415 +1330,3742 @@ pub fn get_table_metadata(
     conn_manager: State<'_, ConnectionManager>,
 ) -> Result<TableMetadataResult, AppError> {
     let adapter = conn_manager.get(&connection_id)?;
-    let meta = adapter.get_table_metadata(&table_name)?;
-    Ok(TableMetadataResult {
-        schema: meta.schema,
-        indexes: meta.indexes.into_iter().map(|i| IndexInfoResult {
-            name: i.name,
-            columns: i.columns,
-            unique: i.unique,
-        }).collect(),
-        foreign_keys: meta.foreign_keys.into_iter().map(|fk| ForeignKeyResult {
-            name: fk.name,
-            from_column: fk.from_column,
-            to_table: fk.to_table,
-            to_column: fk.to_column,
-        }).collect(),
-    })
+    Ok(adapter.get_table_metadata(&table_name)?.into())
 }
 
+/// SQL that recreates `table_name`: its `CREATE TABLE` with primary and
+/// foreign keys, then a `CREATE INDEX` per secondary index. SQLite returns
+/// the statements it stored; other SQL backends are reconstructed from
+/// metadata, so defaults, checks and other constraints are not included.
 #[tauri::command]
-pub fn explain_query(
+pub fn get_table_ddl(
     connection_id: String,
-    sql: String,
+    table_name: String,
     conn_manager: State<'_, ConnectionManager>,
-) -> Result<QueryResult, AppError> {
-    let explain_sql = format!("EXPLAIN {}", sql);
+) -> Result<String, AppError> {
     let adapter = conn_manager.get(&connection_id)?;
-    adapter.execute_query(&explain_sql)
+    if matches!(adapter.kind(), DatabaseKind::Redis | DatabaseKind::Elasticsearch) {
+        return Err(AppError::QueryError(format!(
+            "Table DDL is not supported for {:?}",
+            adapter.kind()
+        )));
+    }
+    if let Some(ddl) = adapter.stored_table_ddl(&table_name)? {
+        return Ok(ddl);
+    }
+    let mut metadata = adapter.get_table_metadata(&table_name)?;
+    let declared = adapter.declared_column_types(&table_name)?;
+    for column in &mut metadata.schema.columns {
+        if let Some((_, data_type)) = declared.iter().find(|(name, _)| *name == column.name) {
+            column.data_type = data_type.clone();
+        }
+    }
+    Ok(table_ddl(adapter.as_ref(), &metadata))
 }
 
-#[tauri::command]
-pub fn write_file(path: String, contents: String) -> Result<(), AppError> {
-    fs::write(&path, contents).map_err(|e| {
-        AppError::DatabaseError(format!("Failed to write file {}: {}", path, e))
-    })
+fn table_ddl(
+    adapter: &dyn crate::adapter::DatabaseAdapter,
+    metadata: &crate::adapter::TableMetadata,
+) -> String {
+    let table = &metadata.schema;
+    let table_name = adapter.quote_listed_table(table);
+    let mut defs: Vec<String> = create_table_definitions(adapter, &table.columns)
+        .into_iter()
+        .map(|def| format!("    {def}"))
+        .collect();
+    let key: Vec<&str> = table
+        .columns
+        .iter()
+        .filter(|c| c.primary_key)
+        .map(|c| c.name.as_str())
+        .collect();
+
+    // A composite foreign key is reported as one entry per column.
+    let mut foreign_keys: Vec<(&str, Vec<&crate::adapter::ForeignKeyInfo>)> = Vec::new();
+    for fk in &metadata.foreign_keys {
+        match foreign_keys.iter_mut().find(|(name, _)| *name == fk.name) {
+            Some((_, parts)) => parts.push(fk),
+            None => foreign_keys.push((&fk.name, vec![fk])),
+        }
+    }
+    for (name, parts) in foreign_keys {
+        let from: Vec<String> = parts.iter().map(|p| adapter.quote_ident(&p.from_column)).collect();
+        let to: Vec<String> = parts.iter().map(|p| adapter.quote_ident(&p.to_column)).collect();
+        defs.push(format!(
+            "    CONSTRAINT {} FOREIGN KEY ({}) REFERENCES {} ({})",
+            adapter.quote_ident(name),
+            from.join(", "),
+            adapter.quote_table(&parts[0].to_table),
+            to.join(", ")
+        ));
+    }
+
+    let mut statements = vec![format!("CREATE TABLE {} (\n{}\n);", table_name, defs.join(",\n"))];
+    let mut indexes: Vec<&crate::adapter::IndexInfo> = metadata
+        .indexes
+        .iter()
+        // The primary key's own index comes with the table.
+        .filter(|i| {
+            !(i.unique
+                && i.columns.len() == key.len()
+                && i.columns.iter().all(|c| key.contains(&c.as_str())))
+        })
+        .collect();
+    indexes.sort_by(|a, b| a.name.cmp(&b.name));
+    for index in indexes {
+        let columns: Vec<String> = index.columns.iter().map(|c| adapter.quote_ident(c)).collect();
+        statements.push(format!(
+            "CREATE {}INDEX {} ON {} ({});",
+            if index.unique { "UNIQUE " } else { "" },
+            adapter.quote_ident(&index.name),
+            table_name,
+            columns.join(", ")
+        ));
+    }
+    statements.join("\n\n")
 }
 
+/// Metadata for many tables in a few round trips, keyed by table name, for
+/// bulk consumers like the ER diagram. Unknown tables are left out.
 #[tauri::command]
-pub fn execute_query(
+pub fn get_tables_metadata(
     connection_id: String,
-    sql: String,
+    tables: Vec<String>,
     conn_manager: State<'_, ConnectionManager>,
-) -> Result<QueryResult, AppError> {
+) -> Result<std::collections::HashMap<String, TableMetadataResult>, AppError> {
     let adapter = conn_manager.get(&connection_id)?;
-    adapter.execute_query(&sql)
+    Ok(adapter
+        .get_tables_metadata(&tables)?
+        .into_iter()
+        .map(|(table, meta)| (table, meta.into()))
+        .collect())
 }
 
 #[tauri::command]
-pub fn list_projects(db: State<'_, Database>) -> Result<Vec<Project>, AppError> {
-    db.list_projects()
+pub fn get_table_size(
+    connection_id: String,
+    table_name: String,
+    conn_manager: State<'_, ConnectionManager>,
+) -> Result<TableSizeInfo, AppError> {
+    let adapter = conn_manager.get(&connection_id)?;
+    adapter.get_table_size(&table_name)
 }
 
-#[tauri::command]
-pub fn create_project(
-    name: String,
-    description: String,
-    db: State<'_, Database>,
-) -> Result<Project, AppError> {
-    db.create_project(&name, &description)
-}
+const DEFAULT_DISTINCT_LIMIT: usize = 100;
+const MAX_DISTINCT_LIMIT: usize = 10_000;
 
-#[tauri::command]
-pub fn update_project(
-    id: String,
-    name: Option<String>,
-    description: Option<String>,
-    db: State<'_, Database>,
-) -> Result<Project, AppError> {
-    db.update_project(&id, name.as_deref(), description.as_deref())
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DistinctValues {
+    pub values: Vec<serde_json::Value>,
+    /// True when the column has more distinct values than were returned.
+    pub truncated: bool,
 }
 
+/// Distinct values of one column, sorted, for filter dropdowns. The table and
+/// column are checked against the live schema before being spliced into SQL,
+/// and the query runs under the adapter's usual timeout.
 #[tauri::command]
-pub fn link_connection_to_project(
-    project_id: String,
+pub fn distinct_values(
     connection_id: String,
-    db: State<'_, Database>,
-) -> Result<(), AppError> {
-    db.link_connection_to_project(&project_id, &connection_id)
-}
+    table: String,
+    column: String,
+    limit: Option<usize>,
+    conn_manager: State<'_, ConnectionManager>,
+) -> Result<DistinctValues, AppError> {
+    let adapter = conn_manager.get(&connection_id)?;
+    let _permit = conn_manager.acquire_query_slot(&connection_id)?;
+    if matches!(adapter.kind(), DatabaseKind::Redis | DatabaseKind::Elasticsearch) {
+        return Err(AppError::QueryError(format!(
+            "Distinct values are not supported for {:?}",
+            adapter.kind()
+        )));
+    }
 
-#[tauri::command]
-pub fn unlink_connection_from_project(
-    project_id: String,
-    connection_id: String,
-    db: State<'_, Database>,
-) -> Result<(), AppError> {
-    db.unlink_connection_from_project(&project_id, &connection_id)
+    let schema = adapter.get_schema_with(&SchemaOptions::only(&table))?;
+    let table_schema = schema
+        .iter()
+        .find(|t| t.name == table)
+        .ok_or_else(|| AppError::NotFound(format!("Table {table} not found")))?;
+    if !table_schema.columns.iter().any(|c| c.name == column) {
+        return Err(AppError::NotFound(format!(
+            "Column {column} not found in table {table}"
+        )));
+    }
+
+    let limit = limit
+        .unwrap_or(DEFAULT_DISTINCT_LIMIT)
+        .clamp(1, MAX_DISTINCT_LIMIT);
+    let column = adapter.quote_ident(&column);
+    // One extra row tells us whether the list was cut short.
+    let sql = format!(
+        "SELECT DISTINCT {column} FROM {} ORDER BY 1 LIMIT {}",
+        adapter.quote_table(&table),
+        limit + 1
+    );
+    let result = adapter.execute_query_limited(&sql, Some(limit))?;
+
+    Ok(DistinctValues {
+        values: result
+            .rows
+            .into_iter()
+            .filter_map(|row| row.into_iter().next())
+            .collect(),
+        truncated: result.truncated,
+    })
 }
 
-#[tauri::command]
-pub fn list_project_connections(
-    project_id: String,
-    db: State<'_, Database>,
-) -> Result<Vec<DatabaseConnection>, AppError> {
-    db.list_project_connections(&project_id)
+const DEFAULT_BROWSE_LIMIT: usize = 100;
+
+/// The SELECT list for `columns` of `table`: every column when `columns` is
+/// `None`, otherwise the named ones after checking each exists.
+fn projection_list(
+    adapter: &dyn crate::adapter::DatabaseAdapter,
+    table: &TableSchema,
+    columns: Option<&[String]>,
+) -> Result<String, AppError> {
+    let Some(columns) = columns else {
+        return Ok("*".to_string());
+    };
+    if columns.is_empty() {
+        return Err(AppError::QueryError("No columns selected".to_string()));
+    }
+    let mut quoted = Vec::with_capacity(columns.len());
+    for column in columns {
+        if !table.columns.iter().any(|c| &c.name == column) {
+            return Err(AppError::NotFound(format!(
+                "Column {column} not found in table {}",
+                table.name
+            )));
+        }
+        quoted.push(adapter.quote_ident(column));
+    }
+    Ok(quoted.join(", "))
 }
 
+/// A sample of rows from `table`. With `columns`, only those (validated)
+/// columns are selected, which keeps payloads small for wide tables.
 #[tauri::command]
-pub fn list_explorations(
-    project_id: String,
-    db: State<'_, Database>,
-) -> Result<Vec<Exploration>, AppError> {
-    db.list_explorations(&project_id)
+pub fn browse_table(
+    connection_id: String,
+    table: String,
+    columns: Option<Vec<String>>,
+    limit: Option<usize>,
+    conn_manager: State<'_, ConnectionManager>,
+) -> Result<QueryResult, AppError> {
+    let adapter = conn_manager.get(&connection_id)?;
+    let _permit = conn_manager.acquire_query_slot(&connection_id)?;
+    if matches!(adapter.kind(), DatabaseKind::Redis | DatabaseKind::Elasticsearch) {
+        return Err(AppError::QueryError(format!(
+            "Browsing tables is not supported for {:?}",
+            adapter.kind()
+        )));
+    }
+
+    let schema = adapter.get_schema_with(&SchemaOptions::only(&table))?;
+    let table_schema = schema
+        .iter()
+        .find(|t| t.name == table)
+        .ok_or_else(|| AppError::NotFound(format!("Table {table} not found")))?;
+    let projection = projection_list(adapter.as_ref(), table_schema, columns.as_deref())?;
+
+    let limit = limit.unwrap_or(DEFAULT_BROWSE_LIMIT).max(1);
+    let sql = format!(
+        "SELECT {projection} FROM {} LIMIT {limit}",
+        adapter.quote_table(&table)
+    );
+    let mut result = adapter.execute_query_limited(&sql, Some(limit))?;
+    result.environment = conn_manager.environment(&connection_id);
+    Ok(result)
 }
 
-#[tauri::command]
-pub fn create_exploration(
-    project_id: String,
-    title: String,
-    db: State<'_, Database>,
-) -> Result<Exploration, AppError> {
-    db.create_exploration(&project_id, &title)
+/// Connection IDs that have already been sent a `timezone-mismatch` notice
+/// during this app session.
+#[derive(Default)]
+pub struct TimezoneNotices(std::sync::Mutex<std::collections::HashSet<String>>);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerInfoResult {
+    #[serde(flatten)]
+    pub server: ServerInfo,
+    /// The app's local offset from UTC, in seconds.
+    pub client_utc_offset_seconds: i32,
+    /// True when the server's current UTC offset differs from the client's.
+    pub timezone_mismatch: bool,
 }
 
+/// Report server version and time zone. The first time a connection's zone
+/// is found to differ from the local one, a `timezone-mismatch` event is
+/// emitted so the UI can warn about timestamp interpretation.
 #[tauri::command]
-pub fn update_exploration(
-    id: String,
-    title: String,
-    db: State<'_, Database>,
-) -> Result<Exploration, AppError> {
-    db.update_exploration(&id, &title)
+pub fn get_server_info(
+    connection_id: String,
+    app_handle: AppHandle,
+    notices: State<'_, TimezoneNotices>,
+    conn_manager: State<'_, ConnectionManager>,
+) -> Result<ServerInfoResult, AppError> {
+    let adapter = conn_manager.get(&connection_id)?;
+    let server = adapter.get_server_info()?;
+
+    let client_utc_offset_seconds = chrono::Local::now().offset().local_minus_utc();
+    let timezone_mismatch = server
+        .utc_offset_seconds
+        .is_some_and(|offset| offset != client_utc_offset_seconds);
+
+    if timezone_mismatch && notices.0.lock().unwrap().insert(connection_id.clone()) {
+        app_handle
+            .emit(
+                "timezone-mismatch",
+                serde_json::json!({
+                    "connectionId": connection_id,
+                    "serverTimezone": server.timezone,
+                    "serverUtcOffsetSeconds": server.utc_offset_seconds,
+                    "clientUtcOffsetSeconds": client_utc_offset_seconds,
+                }),
+            )
+            .ok();
+    }
+
+    Ok(ServerInfoResult {
+        server,
+        client_utc_offset_seconds,
+        timezone_mismatch,
+    })
 }
 
+/// The backend's plan for `sql`, spelled for its dialect. `analyze` also
+/// runs the statement for actual timings, so it is only allowed for a
+/// single read-only query; `json` asks for a JSON plan, returned as
+/// structured values rather than text so it can be drawn as a tree.
+/// Backends without a planner, or without the requested option, are
+/// rejected.
 #[tauri::command]
-pub fn delete_exploration(id: String, db: State<'_, Database>) -> Result<(), AppError> {
-    db.delete_exploration(&id)
+pub fn explain_query(
+    connection_id: String,
+    sql: String,
+    analyze: Option<bool>,
+    json: Option<bool>,
+    conn_manager: State<'_, ConnectionManager>,
+) -> Result<QueryResult, AppError> {
+    let adapter = conn_manager.get(&connection_id)?;
+    let (analyze, json) = (analyze.unwrap_or(false), json.unwrap_or(false));
+    if analyze {
+        ensure_analyzable(adapter.kind(), &sql)?;
+    }
+    let explain_sql = crate::dialect::explain(adapter.kind(), &sql, analyze, json).ok_or_else(|| {
+        let what = match (analyze, json) {
+            (false, false) => "EXPLAIN",
+            (true, false) => "EXPLAIN ANALYZE",
+            (false, true) => "JSON plans",
+            (true, true) => "EXPLAIN ANALYZE with JSON output",
+        };
+        AppError::QueryError(format!("{what} is not supported for {:?}", adapter.kind()))
+    })?;
+    let _permit = conn_manager.acquire_query_slot(&connection_id)?;
+    let mut result = adapter.execute_query(&explain_sql)?;
+    if json {
+        // MySQL hands the plan back as text.
+        for cell in result.rows.iter_mut().flatten() {
+            if let serde_json::Value::String(text) = cell {
+                if let Ok(plan) = serde_json::from_str::<serde_json::Value>(text) {
+                    *cell = plan;
+                }
+            }
+        }
+    }
+    result.environment = conn_manager.environment(&connection_id);
+    Ok(result)
 }
 
+/// Structured Postgres plan with per-node cost, and with `analyze` also
+/// timing and shared-buffer hit/read counts. ANALYZE runs the statement, so
+/// it is only allowed for a single read-only query.
 #[tauri::command]
-pub fn list_messages(
-    exploration_id: String,
-    db: State<'_, Database>,
-) -> Result<Vec<ExplorationMessage>, AppError> {
-    db.list_messages(&exploration_id)
+pub fn explain_query_plan(
+    connection_id: String,
+    sql: String,
+    analyze: Option<bool>,
+    conn_manager: State<'_, ConnectionManager>,
+) -> Result<crate::plan::QueryPlan, AppError> {
+    let adapter = conn_manager.get(&connection_id)?;
+    if adapter.kind() != DatabaseKind::PostgreSQL {
+        return Err(AppError::QueryError(format!(
+            "Structured plans are not supported for {:?}",
+            adapter.kind()
+        )));
+    }
+    let analyze = analyze.unwrap_or(false);
+    if analyze {
+        ensure_analyzable(adapter.kind(), &sql)?;
+    }
+    let options = if analyze {
+        "FORMAT JSON, ANALYZE, BUFFERS"
+    } else {
+        "FORMAT JSON"
+    };
+    let explain_sql = format!("EXPLAIN ({options}) {sql}");
+    let _permit = conn_manager.acquire_query_slot(&connection_id)?;
+    let result = adapter.execute_query(&explain_sql)?;
+    let value = result
+        .rows
+        .first()
+        .and_then(|row| row.first())
+        .ok_or_else(|| AppError::QueryError("EXPLAIN returned no plan".to_string()))?;
+    crate::plan::parse_json_plan(value)
 }
 
-#[tauri::command]
-pub fn add_message(
-    exploration_id: String,
-    role: String,
-    content: String,
-    metadata: Option<String>,
-    db: State<'_, Database>,
-) -> Result<ExplorationMessage, AppError> {
-    db.add_message(&exploration_id, &role, &content, metadata.as_deref())
+/// EXPLAIN ANALYZE executes the statement it plans, so refuse anything but
+/// a single read-only query rather than run a write as a side effect.
+fn ensure_analyzable(kind: DatabaseKind, sql: &str) -> Result<(), AppError> {
+    if crate::dialect::is_read_only_query(kind, sql) {
+        Ok(())
+    } else {
+        Err(AppError::QueryError(
+            "EXPLAIN ANALYZE runs the statement, so it is only allowed for a single read-only query"
+                .to_string(),
+        ))
+    }
 }
 
+/// Run this connection's later queries as `role`, for checking what
+/// row-level security policies let that role see.
 #[tauri::command]
-pub fn get_message_token_count(
-    exploration_id: String,
-    db: State<'_, Database>,
-) -> Result<u64, AppError> {
-    db.get_exploration_token_count(&exploration_id)
+pub fn set_session_role(
+    connection_id: String,
+    role: String,
+    conn_manager: State<'_, ConnectionManager>,
+) -> Result<(), AppError> {
+    let role = role.trim();
+    if role.is_empty() {
+        return Err(AppError::QueryError("Role name is empty".to_string()));
+    }
+    conn_manager.get(&connection_id)?.set_session_role(Some(role))
 }
 
+/// Go back to running queries as the connection's login role.
 #[tauri::command]
-pub fn add_message_tokens(
-    exploration_id: String,
-    count: i64,
-    db: State<'_, Database>,
+pub fn reset_session_role(
+    connection_id: String,
+    conn_manager: State<'_, ConnectionManager>,
 ) -> Result<(), AppError> {
-    db.add_exploration_tokens(&exploration_id, count)
+    conn_manager.get(&connection_id)?.set_session_role(None)
 }
 
+/// Advisory static analysis of a query (SELECT *, unbounded scans of large
+/// tables, leading-wildcard LIKE, cross joins). Nothing is executed; the
+/// schema comes from the cache when there is one.
 #[tauri::command]
-pub fn get_message_turn_count(
-    exploration_id: String,
+pub fn lint_query(
+    connection_id: String,
+    sql: String,
     db: State<'_, Database>,
-) -> Result<u64, AppError> {
-    db.get_exploration_turn_count(&exploration_id)
+    conn_manager: State<'_, ConnectionManager>,
+) -> Result<Vec<crate::lint::LintDiagnostic>, AppError> {
+    let adapter = conn_manager.get(&connection_id)?;
+    if matches!(adapter.kind(), DatabaseKind::Redis | DatabaseKind::Elasticsearch) {
+        return Err(AppError::QueryError(format!(
+            "SQL linting is not supported for {:?}",
+            adapter.kind()
+        )));
+    }
+    let schema = match load_cached_schema(&db, &connection_id)? {
+        Some(schema) => schema,
+        None => adapter.get_schema().unwrap_or_default(),
+    };
+    Ok(crate::lint::lint_sql(adapter.kind(), &sql, &schema))
 }
 
 #[tauri::command]
-pub fn increment_message_turn(
-    exploration_id: String,
+pub fn write_file(path: String, contents: String) -> Result<(), AppError> {
+    fs::write(&path, contents).map_err(|e| {
+        AppError::DatabaseError(format!("Failed to write file {}: {}", path, e))
+    })
+}
+
+/// Setting holding the LIMIT added to SELECTs that have none. Auto-limit
+/// is off unless it holds a positive number.
+const AUTO_LIMIT_KEY: &str = "auto_limit";
+
+/// The LIMIT to add for the `auto_limit` setting `setting`. A caller that
+/// sets `max_rows` has chosen its own cap, which is enforced while reading
+/// so `truncated` stays accurate, and gets no LIMIT.
+fn auto_limit(setting: Option<&str>, max_rows: Option<usize>) -> Option<usize> {
+    if max_rows.is_some() {
+        return None;
+    }
+    setting?.trim().parse().ok().filter(|&limit: &usize| limit > 0)
+}
+
+/// Rows `execute_query` returns when the caller doesn't set `max_rows`.
+/// Larger results are truncated (see `QueryResult::truncated`); exports
+/// stream without a cap.
+const DEFAULT_MAX_ROWS: usize = 10_000;
+
+/// Runs on a blocking worker rather than the main thread, so `cancel_query`
+/// and the rest of the UI are served while the query is in flight.
+#[tauri::command]
+pub async fn execute_query(
+    connection_id: String,
+    sql: String,
+    max_rows: Option<usize>,
+    binary_encoding: Option<BinaryEncoding>,
+    app: AppHandle,
+) -> Result<QueryResult, AppError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        run_query(
+            &connection_id,
+            &sql,
+            max_rows,
+            binary_encoding,
+            &app.state::<Database>(),
+            &app.state::<ConnectionManager>(),
+        )
+    })
+    .await
+    .map_err(|e| AppError::QueryError(e.to_string()))?
+}
+
+fn run_query(
+    connection_id: &str,
+    sql: &str,
+    max_rows: Option<usize>,
+    binary_encoding: Option<BinaryEncoding>,
+    db: &Database,
+    conn_manager: &ConnectionManager,
+) -> Result<QueryResult, AppError> {
+    let adapter = conn_manager.get(connection_id)?;
+    let _permit = conn_manager.acquire_query_slot(connection_id)?;
+    let limit = auto_limit(db.get_setting(AUTO_LIMIT_KEY)?.as_deref(), max_rows);
+    let limited_sql =
+        limit.and_then(|limit| crate::dialect::with_auto_limit(adapter.kind(), sql, limit));
+    let start = std::time::Instant::now();
+    let outcome = adapter.execute_query_encoded(
+        limited_sql.as_deref().unwrap_or(sql),
+        Some(max_rows.unwrap_or(DEFAULT_MAX_ROWS)),
+        binary_encoding.unwrap_or_default(),
+    );
+    // History is a convenience; failing to record it shouldn't fail the query.
+    let _ = db.add_query_history(
+        connection_id,
+        sql,
+        start.elapsed().as_millis() as u64,
+        outcome.as_ref().ok().map(|r| r.row_count),
+        outcome.as_ref().err().map(|e| e.to_string()).as_deref(),
+    );
+    let mut result = outcome?;
+    forget_schema_after(db, adapter.kind(), connection_id, sql);
+    result.applied_limit = limited_sql.and(limit);
+    result.environment = conn_manager.environment(connection_id);
+    Ok(result)
+}
+
+/// One page of a query's rows from `execute_query_paginated`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaginatedResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<serde_json::Value>>,
+    /// Zero-based page index.
+    pub page: usize,
+    pub page_size: usize,
+    /// Whether rows exist past this page.
+    pub has_more: bool,
+    pub execution_time_ms: u64,
+    /// Environment label of the connection, as on `QueryResult`.
+    #[serde(default)]
+    pub environment: Option<String>,
+}
+
+/// Return page `page` (zero-based) of `sql`'s rows without the caller
+/// editing LIMIT/OFFSET into the SQL. Only read-only queries are accepted;
+/// on Redis the matching keys are paged instead.
+#[tauri::command]
+pub fn execute_query_paginated(
+    connection_id: String,
+    sql: String,
+    page: usize,
+    page_size: usize,
     db: State<'_, Database>,
+    conn_manager: State<'_, ConnectionManager>,
+) -> Result<PaginatedResult, AppError> {
+    if page_size == 0 {
+        return Err(AppError::QueryError("Page size must be at least 1".to_string()));
+    }
+    let adapter = conn_manager.get(&connection_id)?;
+    let _permit = conn_manager.acquire_query_slot(&connection_id)?;
+    // One extra row tells whether another page follows.
+    let offset = page.saturating_mul(page_size);
+    let mut result = adapter.execute_query_page(&sql, offset, page_size + 1)?;
+    forget_schema_after(&db, adapter.kind(), &connection_id, &sql);
+    let has_more = result.rows.len() > page_size;
+    result.rows.truncate(page_size);
+    Ok(PaginatedResult {
+        columns: result.columns,
+        rows: result.rows,
+        page,
+        page_size,
+        has_more,
+        execution_time_ms: result.execution_time_ms,
+        environment: conn_manager.environment(&connection_id),
+    })
+}
+
+/// Stop the queries running on `connection_id`; their `execute_query` calls
+/// return a "cancelled" error. Doesn't take a query slot, so it gets through
+/// even when the connection is saturated. Like `execute_query`, it stays
+/// off the main thread while the backend is asked to cancel.
+#[tauri::command]
+pub async fn cancel_query(connection_id: String, app: AppHandle) -> Result<(), AppError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        app.state::<ConnectionManager>().get(&connection_id)?.cancel_query()
+    })
+    .await
+    .map_err(|e| AppError::QueryError(e.to_string()))?
+}
+
+/// Open a transaction on `connection_id`. Statements from every command
+/// run inside it until `commit_transaction` or `rollback_transaction`.
+#[tauri::command]
+pub fn begin_transaction(
+    connection_id: String,
+    conn_manager: State<'_, ConnectionManager>,
 ) -> Result<(), AppError> {
-    db.increment_exploration_turn(&exploration_id)
+    conn_manager.get(&connection_id)?.begin_transaction()
 }
 
 #[tauri::command]
-pub fn reset_message_history(
-    exploration_id: String,
-    db: State<'_, Database>,
+pub fn commit_transaction(
+    connection_id: String,
+    conn_manager: State<'_, ConnectionManager>,
 ) -> Result<(), AppError> {
-    db.reset_exploration_history(&exploration_id)
+    conn_manager.get(&connection_id)?.commit_transaction()
 }
 
 #[tauri::command]
-pub fn list_saved_queries(db: State<'_, Database>) -> Result<Vec<SavedQuery>, AppError> {
-    db.list_saved_queries()
+pub fn rollback_transaction(
+    connection_id: String,
+    conn_manager: State<'_, ConnectionManager>,
+) -> Result<(), AppError> {
+    conn_manager.get(&connection_id)?.rollback_transaction()
 }
 
+/// Attach another SQLite file to the connection as `alias`, so its tables
+/// can be joined as `alias.table`. Returns the attached aliases.
 #[tauri::command]
-pub fn save_query(
-    name: String,
-    description: String,
-    sql: String,
+pub fn attach_database(
+    connection_id: String,
+    file_path: String,
+    alias: String,
+    conn_manager: State<'_, ConnectionManager>,
+) -> Result<Vec<String>, AppError> {
+    let alias = alias.trim();
+    if alias.is_empty() {
+        return Err(AppError::QueryError("Alias cannot be empty".to_string()));
+    }
+    conn_manager.get(&connection_id)?.attach_database(&file_path, alias)
+}
+
+#[tauri::command]
+pub fn detach_database(
+    connection_id: String,
+    alias: String,
+    conn_manager: State<'_, ConnectionManager>,
+) -> Result<Vec<String>, AppError> {
+    conn_manager.get(&connection_id)?.detach_database(alias.trim())
+}
+
+/// Run `sql` with `params` bound to its placeholders (`$1`, `$2`, ... on
+/// Postgres, `?` on MySQL and SQLite), so values from the UI never have to
+/// be spliced into the SQL text.
+#[tauri::command]
+pub fn execute_query_with_params(
     connection_id: String,
+    sql: String,
+    params: Vec<serde_json::Value>,
     db: State<'_, Database>,
-) -> Result<SavedQuery, AppError> {
-    db.save_query(&name, &description, &sql, &connection_id)
+    conn_manager: State<'_, ConnectionManager>,
+) -> Result<QueryResult, AppError> {
+    let adapter = conn_manager.get(&connection_id)?;
+    let _permit = conn_manager.acquire_query_slot(&connection_id)?;
+    let mut result = adapter.execute_query_params(&sql, &params)?;
+    forget_schema_after(&db, adapter.kind(), &connection_id, &sql);
+    result.environment = conn_manager.environment(&connection_id);
+    Ok(result)
 }
 
+/// Run a saved query, found by id or name, with `params` bound to its
+/// placeholders (see `bind_placeholders`). It runs on `connection_id`, or
+/// else on the connection it was saved against.
 #[tauri::command]
-pub fn delete_saved_query(id: String, db: State<'_, Database>) -> Result<(), AppError> {
-    db.delete_saved_query(&id)
+pub fn execute_saved_query(
+    query_ref: String,
+    params: Option<serde_json::Map<String, serde_json::Value>>,
+    connection_id: Option<String>,
+    db: State<'_, Database>,
+    conn_manager: State<'_, ConnectionManager>,
+) -> Result<QueryResult, AppError> {
+    let saved = db.find_saved_query(&query_ref)?;
+    let connection_id = connection_id.unwrap_or(saved.connection_id);
+    let adapter = conn_manager.get(&connection_id)?;
+    let params = params.unwrap_or_default();
+    let (sql, values) = bind_placeholders(adapter.kind(), &saved.sql, &params)?;
+
+    let _permit = conn_manager.acquire_query_slot(&connection_id)?;
+    let mut result = if values.is_empty() {
+        adapter.execute_query(&sql)?
+    } else {
+        adapter.execute_query_params(&sql, &values)?
+    };
+    forget_schema_after(&db, adapter.kind(), &connection_id, &sql);
+    result.environment = conn_manager.environment(&connection_id);
+    Ok(result)
 }
 
+/// The parameters a saved query (by id or name) takes, in order of first
+/// use: `:name` placeholders by name and `$N` ones by number. These are
+/// the keys `execute_saved_query` expects in its `params`.
 #[tauri::command]
-pub fn list_saved_charts(db: State<'_, Database>) -> Result<Vec<SavedChart>, AppError> {
-    db.list_saved_charts()
+pub fn list_saved_query_params(id: String, db: State<'_, Database>) -> Result<Vec<String>, AppError> {
+    let saved = db.find_saved_query(&id)?;
+    Ok(placeholder_params(&saved.sql))
+}
+
+/// A parameter placeholder in saved query SQL. Saved queries name their
+/// inputs `:name` (`WHERE region = :region`); `$1`-style positional
+/// placeholders are accepted too. Values are always bound, never spliced
+/// into the SQL text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SqlPlaceholder {
+    /// `:name`
+    Named(String),
+    /// `$1`, `$2`, ...
+    Positional(usize),
+}
+
+impl std::fmt::Display for SqlPlaceholder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Named(name) => write!(f, ":{name}"),
+            Self::Positional(n) => write!(f, "${n}"),
+        }
+    }
+}
+
+/// The placeholders in `sql` with their byte ranges, in order. String
+/// literals, quoted identifiers, comments and `::` casts are skipped.
+fn scan_placeholders(sql: &str) -> Vec<(std::ops::Range<usize>, SqlPlaceholder)> {
+    let bytes = sql.as_bytes();
+    let is_ident = |b: u8| b.is_ascii_alphanumeric() || b == b'_';
+    let run_end = |from: usize, accept: &dyn Fn(u8) -> bool| {
+        from + bytes[from..].iter().take_while(|b| accept(**b)).count()
+    };
+
+    let mut found = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            quote @ (b'\'' | b'"' | b'`') => {
+                i += 1;
+                while i < bytes.len() {
+                    if bytes[i] == quote && bytes.get(i + 1) == Some(&quote) {
+                        i += 2;
+                    } else if bytes[i] == quote {
+                        break;
+                    } else {
+                        i += 1;
+                    }
+                }
+                i += 1;
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                i = run_end(i, &|b| b != b'\n');
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = sql[i + 2..].find("*/").map_or(bytes.len(), |end| i + 2 + end + 2);
+            }
+            b':' if bytes.get(i + 1) == Some(&b':') => i += 2,
+            b':' | b'$' if i > 0 && is_ident(bytes[i - 1]) => i += 1,
+            b':' => {
+                let end = run_end(i + 1, &is_ident);
+                if end > i + 1 && !bytes[i + 1].is_ascii_digit() {
+                    found.push((i..end, SqlPlaceholder::Named(sql[i + 1..end].to_string())));
+                }
+                i = end.max(i + 1);
+            }
+            b'$' => {
+                let end = run_end(i + 1, &|b: u8| b.is_ascii_digit());
+                if let Ok(n) = sql[i + 1..end].parse() {
+                    found.push((i..end, SqlPlaceholder::Positional(n)));
+                }
+                i = end.max(i + 1);
+            }
+            _ => i += 1,
+        }
+    }
+    found
+}
+
+/// The distinct parameter keys of `sql`'s placeholders, in order.
+fn placeholder_params(sql: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for (_, placeholder) in scan_placeholders(sql) {
+        let name = match placeholder {
+            SqlPlaceholder::Named(name) => name,
+            SqlPlaceholder::Positional(n) => n.to_string(),
+        };
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+/// Rewrite `sql`'s placeholders into the backend's bind syntax and collect
+/// their values from `params` in order, so the values are bound rather
+/// than spliced into the SQL. `:name` takes the `name` entry; `$N` takes
+/// `N` or `paramN`, as the slash command parser names positional values.
+/// Every placeholder without a value is named in the error.
+fn bind_placeholders(
+    kind: DatabaseKind,
+    sql: &str,
+    params: &serde_json::Map<String, serde_json::Value>,
+) -> Result<(String, Vec<serde_json::Value>), AppError> {
+    let mut bound = String::with_capacity(sql.len());
+    let mut values = Vec::new();
+    let mut missing: Vec<String> = Vec::new();
+    let mut copied = 0;
+    for (range, placeholder) in scan_placeholders(sql) {
+        bound.push_str(&sql[copied..range.start]);
+        copied = range.end;
+        let value = match &placeholder {
+            SqlPlaceholder::Named(name) => params.get(name),
+            SqlPlaceholder::Positional(n) => params
+                .get(&n.to_string())
+                .or_else(|| params.get(&format!("param{n}"))),
+        };
+        match value {
+            Some(value) => {
+                values.push(value.clone());
+                bound.push_str(&crate::dialect::placeholder(kind, values.len()));
+            }
+            None => {
+                let name = placeholder.to_string();
+                if !missing.contains(&name) {
+                    missing.push(name);
+                }
+            }
+        }
+    }
+    bound.push_str(&sql[copied..]);
+
+    if !missing.is_empty() {
+        return Err(AppError::QueryError(format!(
+            "No value given for query parameter{} {}",
+            if missing.len() == 1 { "" } else { "s" },
+            missing.join(", ")
+        )));
+    }
+    Ok((bound, values))
 }
 
+/// Backup path of the corrupt app database replaced at startup, so the UI
+/// can tell the user even if it missed the `db-recovered` event.
 #[tauri::command]
-pub fn save_saved_chart(
+pub fn get_db_recovery(db: State<'_, Database>) -> Option<String> {
+    db.recovered_backup().map(|p| p.display().to_string())
+}
+
+#[tauri::command]
+pub fn list_projects(db: State<'_, Database>) -> Result<Vec<Project>, AppError> {
+    db.list_projects()
+}
+
+#[tauri::command]
+pub fn create_project(
     name: String,
     description: String,
-    chart_type: String,
-    x_key: String,
-    y_key: String,
-    connection_id: Option<String>,
-    sql: Option<String>,
-    data: serde_json::Value,
     db: State<'_, Database>,
-) -> Result<SavedChart, AppError> {
-    db.save_saved_chart(
-        &name,
-        &description,
-        &chart_type,
-        &x_key,
-        &y_key,
-        connection_id.as_deref(),
-        sql.as_deref(),
-        &data,
+) -> Result<Project, AppError> {
+    db.create_project(&name, &description)
+}
+
+#[tauri::command]
+pub fn update_project(
+    id: String,
+    name: Option<String>,
+    description: Option<String>,
+    db: State<'_, Database>,
+) -> Result<Project, AppError> {
+    db.update_project(&id, name.as_deref(), description.as_deref())
+}
+
+#[tauri::command]
+pub fn link_connection_to_project(
+    project_id: String,
+    connection_id: String,
+    db: State<'_, Database>,
+) -> Result<(), AppError> {
+    db.link_connection_to_project(&project_id, &connection_id)
+}
+
+#[tauri::command]
+pub fn unlink_connection_from_project(
+    project_id: String,
+    connection_id: String,
+    db: State<'_, Database>,
+) -> Result<(), AppError> {
+    db.unlink_connection_from_project(&project_id, &connection_id)
+}
+
+#[tauri::command]
+pub fn list_project_connections(
+    project_id: String,
+    db: State<'_, Database>,
+) -> Result<Vec<DatabaseConnection>, AppError> {
+    db.list_project_connections(&project_id)
+}
+
+#[tauri::command]
+pub fn list_explorations(
+    project_id: String,
+    db: State<'_, Database>,
+) -> Result<Vec<Exploration>, AppError> {
+    db.list_explorations(&project_id)
+}
+
+#[tauri::command]
+pub fn create_exploration(
+    project_id: String,
+    title: String,
+    db: State<'_, Database>,
+) -> Result<Exploration, AppError> {
+    db.create_exploration(&project_id, &title)
+}
+
+/// Branch an exploration: a copy in the same project (and so with the same
+/// connections) holding the messages up to `up_to_message_id`.
+#[tauri::command]
+pub fn fork_exploration(
+    exploration_id: String,
+    up_to_message_id: String,
+    db: State<'_, Database>,
+) -> Result<Exploration, AppError> {
+    db.fork_exploration(&exploration_id, &up_to_message_id)
+}
+
+#[tauri::command]
+pub fn update_exploration(
+    id: String,
+    title: String,
+    db: State<'_, Database>,
+) -> Result<Exploration, AppError> {
+    db.update_exploration(&id, &title)
+}
+
+/// Move an exploration to the trash; see `restore_exploration` and
+/// `purge_exploration`.
+#[tauri::command]
+pub fn delete_exploration(id: String, db: State<'_, Database>) -> Result<(), AppError> {
+    db.delete_exploration(&id)
+}
+
+#[tauri::command]
+pub fn list_trashed_explorations(
+    project_id: String,
+    db: State<'_, Database>,
+) -> Result<Vec<Exploration>, AppError> {
+    db.list_trashed_explorations(&project_id)
+}
+
+#[tauri::command]
+pub fn restore_exploration(id: String, db: State<'_, Database>) -> Result<(), AppError> {
+    db.restore_exploration(&id)
+}
+
+/// Permanently delete an exploration and its messages.
+#[tauri::command]
+pub fn purge_exploration(id: String, db: State<'_, Database>) -> Result<(), AppError> {
+    db.purge_exploration(&id)
+}
+
+/// Messages `list_messages` returns per page when the caller doesn't say.
+const DEFAULT_MESSAGE_PAGE_SIZE: usize = 50;
+
+/// One page of an exploration's messages, oldest first. Without a cursor
+/// this is the latest `limit` messages; pass the first message's id as
+/// `before` to load older ones, or the last one's as `after` for newer ones.
+#[tauri::command]
+pub fn list_messages(
+    exploration_id: String,
+    limit: Option<usize>,
+    before: Option<String>,
+    after: Option<String>,
+    db: State<'_, Database>,
+) -> Result<Vec<ExplorationMessage>, AppError> {
+    db.list_messages_page(
+        &exploration_id,
+        limit.unwrap_or(DEFAULT_MESSAGE_PAGE_SIZE),
+        before.as_deref(),
+        after.as_deref(),
     )
 }
 
-#[tauri::command]
-pub fn delete_saved_chart(id: String, db: State<'_, Database>) -> Result<(), AppError> {
-    db.delete_saved_chart(&id)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExplorationQuery {
+    pub sql: String,
+    pub message_id: String,
+    /// `created_at` of the message that most recently ran this SQL.
+    pub executed_at: String,
+}
+
+/// The `sql` argument of an `execute_query` tool call, from its JSON-encoded
+/// arguments.
+fn sql_from_tool_arguments(arguments: &str) -> Option<String> {
+    let v = serde_json::from_str::<serde_json::Value>(arguments).ok()?;
+    v.get("sql").and_then(|s| s.as_str()).map(|s| s.to_string())
+}
+
+/// The SQL of a persisted `execute_query` tool call. Message metadata stores
+/// tool calls either in the OpenAI shape (`function.name` plus a JSON string
+/// of arguments) or as `tool_name` with an `input_args` object.
+fn stored_tool_call_sql(call: &serde_json::Value) -> Option<String> {
+    if let Some(function) = call.get("function") {
+        if function.get("name").and_then(|n| n.as_str()) != Some("execute_query") {
+            return None;
+        }
+        return sql_from_tool_arguments(function.get("arguments")?.as_str()?);
+    }
+
+    let name = call.get("tool_name").or_else(|| call.get("name")).and_then(|n| n.as_str());
+    if name != Some("execute_query") {
+        return None;
+    }
+    match call.get("input_args").or_else(|| call.get("arguments"))? {
+        serde_json::Value::String(arguments) => sql_from_tool_arguments(arguments),
+        args => args.get("sql").and_then(|s| s.as_str()).map(|s| s.to_string()),
+    }
+}
+
+/// Distinct SQL statements run through `execute_query` tool calls in an
+/// exploration, most recent first, for one-click re-runs.
+#[tauri::command]
+pub fn list_exploration_queries(
+    exploration_id: String,
+    db: State<'_, Database>,
+) -> Result<Vec<ExplorationQuery>, AppError> {
+    let messages = db.list_messages(&exploration_id)?;
+    let mut seen = std::collections::HashSet::new();
+    let mut queries = Vec::new();
+
+    for message in messages.iter().rev() {
+        let Some(metadata) = message
+            .metadata
+            .as_deref()
+            .and_then(|m| serde_json::from_str::<serde_json::Value>(m).ok())
+        else {
+            continue;
+        };
+        let Some(calls) = metadata.get("tool_calls").and_then(|c| c.as_array()) else {
+            continue;
+        };
+        // Later calls within a message ran later.
+        for sql in calls.iter().rev().filter_map(stored_tool_call_sql) {
+            let sql = sql.trim().to_string();
+            if sql.is_empty() || !seen.insert(sql.clone()) {
+                continue;
+            }
+            queries.push(ExplorationQuery {
+                sql,
+                message_id: message.id.clone(),
+                executed_at: message.created_at.clone(),
+            });
+        }
+    }
+
+    Ok(queries)
+}
+
+#[tauri::command]
+pub fn add_message(
+    exploration_id: String,
+    role: String,
+    content: String,
+    metadata: Option<String>,
+    db: State<'_, Database>,
+) -> Result<ExplorationMessage, AppError> {
+    db.add_message(&exploration_id, &role, &content, metadata.as_deref())
+}
+
+#[tauri::command]
+pub fn get_message_token_count(
+    exploration_id: String,
+    db: State<'_, Database>,
+) -> Result<u64, AppError> {
+    db.get_exploration_token_count(&exploration_id)
+}
+
+#[tauri::command]
+pub fn add_message_tokens(
+    exploration_id: String,
+    count: i64,
+    db: State<'_, Database>,
+) -> Result<(), AppError> {
+    db.add_exploration_tokens(&exploration_id, count)
+}
+
+#[tauri::command]
+pub fn get_message_turn_count(
+    exploration_id: String,
+    db: State<'_, Database>,
+) -> Result<u64, AppError> {
+    db.get_exploration_turn_count(&exploration_id)
+}
+
+#[tauri::command]
+pub fn increment_message_turn(
+    exploration_id: String,
+    db: State<'_, Database>,
+) -> Result<(), AppError> {
+    db.increment_exploration_turn(&exploration_id)
+}
+
+#[tauri::command]
+pub fn get_message_counters(
+    exploration_id: String,
+    db: State<'_, Database>,
+) -> Result<ExplorationCounters, AppError> {
+    db.get_exploration_counters(&exploration_id)
+}
+
+#[tauri::command]
+pub fn reset_message_history(
+    exploration_id: String,
+    clear_messages: Option<bool>,
+    db: State<'_, Database>,
+) -> Result<(), AppError> {
+    db.reset_exploration_history(&exploration_id, clear_messages.unwrap_or(false))
+}
+
+#[tauri::command]
+pub fn list_saved_queries(db: State<'_, Database>) -> Result<Vec<SavedQuery>, AppError> {
+    db.list_saved_queries()
+}
+
+#[tauri::command]
+pub fn list_saved_queries_by_tag(
+    tag: String,
+    db: State<'_, Database>,
+) -> Result<Vec<SavedQuery>, AppError> {
+    db.list_saved_queries_by_tag(&tag)
+}
+
+#[tauri::command]
+pub fn list_saved_query_tags(db: State<'_, Database>) -> Result<Vec<SavedQueryTag>, AppError> {
+    db.list_saved_query_tags()
+}
+
+#[tauri::command]
+pub fn save_query(
+    name: String,
+    description: String,
+    sql: String,
+    connection_id: String,
+    tags: Option<Vec<String>>,
+    db: State<'_, Database>,
+) -> Result<SavedQuery, AppError> {
+    db.save_query(&name, &description, &sql, &connection_id, &tags.unwrap_or_default())
+}
+
+/// Edit a saved query; `tags`, when given, replaces its tags.
+#[tauri::command]
+pub fn update_saved_query(
+    id: String,
+    name: String,
+    description: String,
+    sql: String,
+    tags: Option<Vec<String>>,
+    db: State<'_, Database>,
+) -> Result<SavedQuery, AppError> {
+    db.update_saved_query(&id, &name, &description, &sql, tags.as_deref())
+}
+
+#[tauri::command]
+pub fn delete_saved_query(id: String, db: State<'_, Database>) -> Result<(), AppError> {
+    db.delete_saved_query(&id)
+}
+
+/// Queries run on `connection_id`, newest first.
+#[tauri::command]
+pub fn list_query_history(
+    connection_id: String,
+    limit: Option<usize>,
+    db: State<'_, Database>,
+) -> Result<Vec<QueryHistoryEntry>, AppError> {
+    db.list_query_history(&connection_id, limit.unwrap_or(100))
+}
+
+#[tauri::command]
+pub fn clear_query_history(connection_id: String, db: State<'_, Database>) -> Result<(), AppError> {
+    db.clear_query_history(&connection_id)
+}
+
+#[tauri::command]
+pub fn list_saved_charts(db: State<'_, Database>) -> Result<Vec<SavedChart>, AppError> {
+    db.list_saved_charts()
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn save_saved_chart(
+    name: String,
+    description: String,
+    chart_type: String,
+    x_key: String,
+    y_key: String,
+    connection_id: Option<String>,
+    sql: Option<String>,
+    data: serde_json::Value,
+    db: State<'_, Database>,
+) -> Result<SavedChart, AppError> {
+    db.save_saved_chart(
+        &name,
+        &description,
+        &chart_type,
+        &x_key,
+        &y_key,
+        connection_id.as_deref(),
+        sql.as_deref(),
+        &data,
+    )
+}
+
+/// Replace an existing chart's definition, keeping its id, `created_at`
+/// and dashboard placements.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn update_saved_chart(
+    id: String,
+    name: String,
+    description: String,
+    chart_type: String,
+    x_key: String,
+    y_key: String,
+    connection_id: Option<String>,
+    sql: Option<String>,
+    data: serde_json::Value,
+    db: State<'_, Database>,
+) -> Result<SavedChart, AppError> {
+    db.update_saved_chart(
+        &id,
+        &name,
+        &description,
+        &chart_type,
+        &x_key,
+        &y_key,
+        connection_id.as_deref(),
+        sql.as_deref(),
+        &data,
+    )
+}
+
+#[tauri::command]
+pub fn delete_saved_chart(id: String, db: State<'_, Database>) -> Result<(), AppError> {
+    db.delete_saved_chart(&id)
+}
+
+#[tauri::command]
+pub fn rename_saved_chart(id: String, name: String, db: State<'_, Database>) -> Result<(), AppError> {
+    db.rename_saved_chart(&id, &name)
+}
+
+#[tauri::command]
+pub fn list_dashboards(db: State<'_, Database>) -> Result<Vec<Dashboard>, AppError> {
+    db.list_dashboards()
+}
+
+#[tauri::command]
+pub fn create_dashboard(
+    name: String,
+    description: Option<String>,
+    db: State<'_, Database>,
+) -> Result<Dashboard, AppError> {
+    db.create_dashboard(&name, description.as_deref().unwrap_or(""))
+}
+
+#[tauri::command]
+pub fn add_chart_to_dashboard(
+    dashboard_id: String,
+    chart_id: String,
+    db: State<'_, Database>,
+) -> Result<(), AppError> {
+    db.add_chart_to_dashboard(&dashboard_id, &chart_id)
+}
+
+#[tauri::command]
+pub fn list_dashboard_charts(
+    dashboard_id: String,
+    db: State<'_, Database>,
+) -> Result<Vec<SavedChart>, AppError> {
+    db.list_dashboard_charts(&dashboard_id)
+}
+
+#[tauri::command]
+pub fn list_snippets(
+    connection_id: Option<String>,
+    db: State<'_, Database>,
+) -> Result<Vec<Snippet>, AppError> {
+    db.list_snippets(connection_id.as_deref())
+}
+
+fn is_snippet_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-'
+}
+
+/// Create a snippet, or update it when `id` is given. Names are limited to
+/// letters, digits, `_` and `-` so `@name` can be found in SQL text.
+#[tauri::command]
+pub fn save_snippet(
+    id: Option<String>,
+    name: String,
+    body: String,
+    connection_id: Option<String>,
+    tags: Option<Vec<String>>,
+    db: State<'_, Database>,
+) -> Result<Snippet, AppError> {
+    let name = name.trim();
+    if name.is_empty() || !name.chars().all(is_snippet_name_char) {
+        return Err(AppError::QueryError(format!(
+            "Invalid snippet name {name:?}: use letters, digits, '_' or '-'"
+        )));
+    }
+    db.save_snippet(
+        id.as_deref(),
+        name,
+        &body,
+        connection_id.as_deref(),
+        &tags.unwrap_or_default(),
+    )
+}
+
+#[tauri::command]
+pub fn delete_snippet(id: String, db: State<'_, Database>) -> Result<(), AppError> {
+    db.delete_snippet(&id)
+}
+
+/// Replace each `@name` in `sql` with the body of the snippet of that name,
+/// preferring the connection's own snippet over a global one. Unknown names
+/// (e.g. MySQL `@variables`) and anything inside quotes, comments or
+/// dollar-quoted strings are left as is.
+#[tauri::command]
+pub fn expand_snippets(
+    sql: String,
+    connection_id: Option<String>,
+    db: State<'_, Database>,
+) -> Result<String, AppError> {
+    let mut bodies = std::collections::HashMap::new();
+    // The connection's own snippet is listed before a global one of the
+    // same name.
+    for snippet in db.list_snippets(connection_id.as_deref())? {
+        bodies.entry(snippet.name).or_insert(snippet.body);
+    }
+    Ok(expand_snippet_refs(&sql, &bodies))
+}
+
+/// `sql` with every `@name` found in `bodies` replaced, outside quoted
+/// strings and identifiers, `--` and `/* */` comments and `$tag$` strings.
+fn expand_snippet_refs(sql: &str, bodies: &std::collections::HashMap<String, String>) -> String {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut out = String::with_capacity(sql.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let rest = &chars[i..];
+        // Where the quoted part or comment starting at `i` ends.
+        let skip_to = match c {
+            '\'' | '"' | '`' => Some(
+                (i + 1..chars.len())
+                    .find(|&j| chars[j] == c)
+                    .map_or(chars.len(), |j| j + 1),
+            ),
+            '-' if rest.starts_with(&['-', '-']) => Some(
+                (i..chars.len())
+                    .find(|&j| chars[j] == '\n')
+                    .unwrap_or(chars.len()),
+            ),
+            '/' if rest.starts_with(&['/', '*']) => Some(
+                (i + 2..chars.len())
+                    .find(|&j| chars[j..].starts_with(&['*', '/']))
+                    .map_or(chars.len(), |j| j + 2),
+            ),
+            '$' if i == 0 || !is_snippet_name_char(chars[i - 1]) => {
+                dollar_quote_tag(rest).map(|tag| {
+                    (i + tag.len()..chars.len())
+                        .find(|&j| chars[j..].starts_with(&tag))
+                        .map_or(chars.len(), |j| j + tag.len())
+                })
+            }
+            _ => None,
+        };
+        if let Some(end) = skip_to {
+            out.extend(&chars[i..end]);
+            i = end;
+            continue;
+        }
+
+        if c == '@' && (i == 0 || !is_snippet_name_char(chars[i - 1])) {
+            let end = (i + 1..chars.len())
+                .find(|&j| !is_snippet_name_char(chars[j]))
+                .unwrap_or(chars.len());
+            let name: String = chars[i + 1..end].iter().collect();
+            if let Some(body) = bodies.get(&name) {
+                out.push_str(body);
+                i = end;
+                continue;
+            }
+        }
+        out.push(c);
+        i += 1;
+    }
+    out
+}
+
+/// The opening `$tag$` (or `$$`) of a Postgres dollar-quoted string at the
+/// start of `chars`. Positional parameters such as `$1` don't qualify.
+fn dollar_quote_tag(chars: &[char]) -> Option<Vec<char>> {
+    let close = chars.iter().skip(1).position(|&c| c == '$')? + 1;
+    let tag = &chars[1..close];
+    let valid = tag.first().is_none_or(|c| c.is_alphabetic() || *c == '_')
+        && tag.iter().all(|c| c.is_alphanumeric() || *c == '_');
+    valid.then(|| chars[..=close].to_vec())
+}
+
+/// Results `search_workspace` returns when the caller doesn't say.
+const DEFAULT_WORKSPACE_SEARCH_LIMIT: usize = 50;
+
+/// Full-text search over saved queries (name, description and SQL),
+/// connection notes and exploration messages. `scope` restricts the search
+/// to one kind of item.
+#[tauri::command]
+pub fn search_workspace(
+    query: String,
+    scope: Option<WorkspaceItemKind>,
+    limit: Option<usize>,
+    db: State<'_, Database>,
+) -> Result<Vec<WorkspaceSearchResult>, AppError> {
+    db.search_workspace(&query, scope, limit.unwrap_or(DEFAULT_WORKSPACE_SEARCH_LIMIT))
+}
+
+#[tauri::command]
+pub fn list_connection_notes(db: State<'_, Database>) -> Result<Vec<ConnectionNote>, AppError> {
+    db.list_connection_notes()
+}
+
+#[tauri::command]
+pub fn set_connection_note(
+    connection_id: String,
+    note: String,
+    db: State<'_, Database>,
+) -> Result<ConnectionNote, AppError> {
+    db.set_connection_note(&connection_id, &note)
+}
+
+#[tauri::command]
+pub fn get_database_stats(
+    connection_id: String,
+    db: State<'_, Database>,
+    conn_manager: State<'_, ConnectionManager>,
+) -> Result<DatabaseStats, AppError> {
+    // Try real adapter first
+    if let Ok(adapter) = conn_manager.get(&connection_id) {
+        let stats = adapter.get_stats()?;
+        let params = conn_manager.params(&connection_id).ok();
+        return Ok(DatabaseStats {
+            connection_id: connection_id.clone(),
+            table_count: stats.table_count,
+            total_row_count: stats.total_row_count,
+            disk_usage_bytes: stats.disk_usage_bytes,
+            connected: true,
+            kind: Some(adapter.kind()),
+            host: params.as_ref().map(|p| p.host.clone()),
+            port: params.as_ref().map(|p| p.port),
+            database: params.map(|p| p.database),
+        });
+    }
+    // Fall back to app DB metadata
+    db.get_database_stats(&connection_id)
+}
+
+/// Import CSV text, creating `table_name` on the given connection. With
+/// `dry_run` the CSV is parsed, typed and validated exactly as for a real
+/// import, but nothing is written; the report lists every problem found.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn import_csv(
+    csv_content: String,
+    table_name: String,
+    connection_id: Option<String>,
+    primary_key: Option<Vec<String>>,
+    dry_run: Option<bool>,
+    delimiter: Option<char>,
+    quote: Option<char>,
+    db: State<'_, Database>,
+    conn_manager: State<'_, ConnectionManager>,
+) -> Result<CsvImportResult, AppError> {
+    let dry_run = dry_run.unwrap_or(false);
+    let parsed = parse_csv(&csv_content, CsvFormat::new(delimiter, quote)?)?;
+    let adapter = connection_id
+        .as_deref()
+        .and_then(|id| conn_manager.get(id).ok());
+    let primary_key = primary_key.filter(|k| !k.is_empty());
+
+    let mut errors = parsed.issues.clone();
+    if let Some(ref key) = primary_key {
+        if let Err(e) = validate_csv_primary_key(&parsed, key) {
+            errors.push(e.to_string());
+        }
+        if let Some(adapter) = &adapter {
+            if let Err(e) = check_existing_primary_key(adapter.as_ref(), &table_name, key) {
+                errors.push(e.to_string());
+            }
+        }
+    }
+    let create_sql = adapter
+        .as_ref()
+        .map(|a| csv_create_table_sql(a.as_ref(), &table_name, &parsed, primary_key.as_deref()));
+
+    // If a connection is active, create the table and insert rows
+    if !dry_run {
+        if let (Some(adapter), Some(id)) = (&adapter, &connection_id) {
+            create_csv_table(adapter, &table_name, &parsed, primary_key.as_deref())?;
+            let _ = db.delete_schema_cache(id);
+        }
+    }
+
+    let row_count = parsed.rows.len();
+    Ok(CsvImportResult {
+        report: CsvImportReport {
+            dry_run,
+            columns: parsed.columns.clone(),
+            row_count,
+            errors,
+            create_sql,
+        },
+        result: QueryResult {
+            columns_binary: vec![false; parsed.columns.len()],
+            column_types: Vec::new(),
+            columns: parsed.columns.into_iter().map(|c| c.name).collect(),
+            rows: parsed.rows,
+            row_count,
+            execution_time_ms: 0,
+            truncated: false,
+            applied_limit: None,
+            environment: None,
+            warning: None,
+        },
+    })
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SqlScriptResult {
+    pub statements: Vec<StatementOutcome>,
+    /// False when a statement failed and the script was rolled back.
+    pub committed: bool,
+    /// Set when a rollback could not undo everything: MySQL commits DDL
+    /// implicitly, so schema changes made before the failure remain.
+    #[serde(default)]
+    pub warning: Option<String>,
+}
+
+/// Split `script` into statements and run them in order in one
+/// transaction, stopping and rolling back at the first failure. The
+/// script's own `BEGIN`/`COMMIT` lines, as in a `.dump`, are dropped since
+/// the whole script already runs in a transaction.
+fn run_sql_script(
+    connection_id: &str,
+    script: &str,
+    db: &Database,
+    conn_manager: &ConnectionManager,
+) -> Result<SqlScriptResult, AppError> {
+    let adapter = conn_manager.get(connection_id)?;
+    let statements: Vec<String> = crate::dialect::split_sql_statements(adapter.kind(), script)
+        .into_iter()
+        .filter(|statement| !crate::dialect::is_transaction_control(statement))
+        .collect();
+    if statements.is_empty() {
+        return Err(AppError::QueryError("SQL script contains no statements".to_string()));
+    }
+
+    let _permit = conn_manager.acquire_query_slot(connection_id)?;
+    let outcomes = adapter.execute_transaction(&statements)?;
+    let committed = outcomes.len() == statements.len() && outcomes.iter().all(|o| o.success);
+    // DDL run before a failure stays applied on MySQL.
+    let kept_ddl: Vec<String> = outcomes
+        .iter()
+        .filter(|o| {
+            adapter.kind() == DatabaseKind::MySQL
+                && o.success
+                && crate::dialect::is_schema_change(adapter.kind(), &o.sql)
+        })
+        .map(|o| (o.index + 1).to_string())
+        .collect();
+    if committed || !kept_ddl.is_empty() {
+        // Scripts tend to change the schema, and at least the row counts.
+        let _ = db.delete_schema_cache(connection_id);
+    }
+    let warning = (!committed && !kept_ddl.is_empty()).then(|| {
+        format!(
+            "MySQL cannot roll back DDL: statement(s) {} stayed applied although the rest \
+             of the script was rolled back",
+            kept_ddl.join(", ")
+        )
+    });
+
+    Ok(SqlScriptResult {
+        statements: outcomes,
+        committed,
+        warning,
+    })
+}
+
+/// Seed a connection (typically a fresh SQLite scratch database) from a SQL
+/// dump. The script is split into statements and run in one transaction.
+#[tauri::command]
+pub fn init_connection_from_sql(
+    connection_id: String,
+    sql_script: String,
+    db: State<'_, Database>,
+    conn_manager: State<'_, ConnectionManager>,
+) -> Result<SqlScriptResult, AppError> {
+    run_sql_script(&connection_id, &sql_script, &db, &conn_manager)
+}
+
+/// Run a multi-statement script, such as a migration file, in one
+/// transaction. Semicolons inside literals, comments and dollar-quoted
+/// bodies don't split statements. On failure the script is rolled back and
+/// the last outcome carries the failing statement's index and error; on
+/// MySQL, DDL that ran before the failure is reported in `warning`.
+#[tauri::command]
+pub fn execute_script(
+    connection_id: String,
+    script: String,
+    db: State<'_, Database>,
+    conn_manager: State<'_, ConnectionManager>,
+) -> Result<SqlScriptResult, AppError> {
+    run_sql_script(&connection_id, &script, &db, &conn_manager)
+}
+
+#[tauri::command]
+pub fn preview_csv(csv_content: String, options: Option<CsvOptions>) -> Result<CsvPreview, AppError> {
+    let options = options.unwrap_or_default();
+    let parsed = parse_csv(&csv_content, CsvFormat::new(options.delimiter, options.quote)?)?;
+    let total_rows = parsed.rows.len();
+    let limit = options.preview_rows.unwrap_or(DEFAULT_CSV_PREVIEW_ROWS);
+
+    Ok(CsvPreview {
+        columns: parsed.columns,
+        rows: parsed.rows.into_iter().take(limit).collect(),
+        total_rows,
+    })
+}
+
+// --- CSV parsing ---
+
+const DEFAULT_CSV_PREVIEW_ROWS: usize = 20;
+
+/// Give up on an import that is still inserting after this long.
+const CSV_IMPORT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(120);
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CsvOptions {
+    /// How many parsed rows `preview_csv` returns.
+    pub preview_rows: Option<usize>,
+    /// Field separator; a comma unless set.
+    #[serde(default)]
+    pub delimiter: Option<char>,
+    /// Quote character; `"` unless set.
+    #[serde(default)]
+    pub quote: Option<char>,
+}
+
+/// How fields are separated and quoted in a CSV file.
+#[derive(Debug, Clone, Copy)]
+struct CsvFormat {
+    delimiter: u8,
+    quote: u8,
+}
+
+impl CsvFormat {
+    /// Comma and double quote unless overridden, e.g. `;` for European
+    /// exports or a tab for TSV.
+    fn new(delimiter: Option<char>, quote: Option<char>) -> Result<Self, AppError> {
+        let byte = |c: char, what: &str| {
+            u8::try_from(c)
+                .ok()
+                .filter(|b| b.is_ascii() && *b != b'\n' && *b != b'\r')
+                .ok_or_else(|| {
+                    AppError::CsvParseError(format!("Unsupported CSV {what} character {c:?}"))
+                })
+        };
+        let format = Self {
+            delimiter: byte(delimiter.unwrap_or(','), "delimiter")?,
+            quote: byte(quote.unwrap_or('"'), "quote")?,
+        };
+        if format.delimiter == format.quote {
+            return Err(AppError::CsvParseError(
+                "CSV delimiter and quote character must differ".into(),
+            ));
+        }
+        Ok(format)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CsvColumn {
+    pub name: String,
+    /// SQL type the import will create the column with.
+    pub data_type: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CsvPreview {
+    pub columns: Vec<CsvColumn>,
+    pub rows: Vec<Vec<serde_json::Value>>,
+    pub total_rows: usize,
+}
+
+/// Validation outcome of an import; for a dry run, what the import would do.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CsvImportReport {
+    pub dry_run: bool,
+    pub columns: Vec<CsvColumn>,
+    pub row_count: usize,
+    pub errors: Vec<String>,
+    /// The CREATE TABLE statement for the target connection, when one is active.
+    pub create_sql: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CsvImportResult {
+    #[serde(flatten)]
+    pub result: QueryResult,
+    pub report: CsvImportReport,
+}
+
+/// Stop listing ragged rows individually after this many.
+const MAX_CSV_ISSUES: usize = 100;
+
+struct ParsedCsv {
+    columns: Vec<CsvColumn>,
+    rows: Vec<Vec<serde_json::Value>>,
+    /// Non-fatal problems: ragged rows, blank or duplicate header names.
+    issues: Vec<String>,
+}
+
+/// Which fields of the raw CSV record `raw` open with a quote. Mirrors the
+/// reader's rule that a quote only counts at the very start of a field.
+fn quoted_csv_fields(raw: &[u8], format: CsvFormat) -> Vec<bool> {
+    let mut quoted = Vec::new();
+    let mut field_start = true;
+    let mut in_quotes = false;
+    let mut bytes = raw.iter().peekable();
+    while let Some(&b) = bytes.next() {
+        if in_quotes {
+            if b == format.quote && bytes.next_if_eq(&&format.quote).is_none() {
+                in_quotes = false;
+            }
+            continue;
+        }
+        if field_start {
+            field_start = false;
+            quoted.push(b == format.quote);
+            if b == format.quote {
+                in_quotes = true;
+                continue;
+            }
+        }
+        match b {
+            b'\r' | b'\n' => break,
+            b if b == format.delimiter => field_start = true,
+            _ => {}
+        }
+    }
+    if field_start {
+        quoted.push(false);
+    }
+    quoted
+}
+
+/// Split CSV text into records following RFC 4180: fields may be quoted,
+/// quoted fields may contain delimiters, newlines and doubled-quote escapes.
+/// Unquoted fields are trimmed; blank lines are skipped.
+fn split_csv_records(content: &str, format: CsvFormat) -> Result<Vec<Vec<String>>, AppError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(format.delimiter)
+        .quote(format.quote)
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(content.as_bytes());
+
+    let mut parsed = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| AppError::CsvParseError(e.to_string()))?;
+        let start = record.position().map_or(0, |p| p.byte() as usize);
+        parsed.push((start, record));
+    }
+
+    let mut records = Vec::with_capacity(parsed.len());
+    for (i, (start, record)) in parsed.iter().enumerate() {
+        let end = parsed.get(i + 1).map_or(content.len(), |(next, _)| *next);
+        let quoted = quoted_csv_fields(&content.as_bytes()[*start..end], format);
+        let fields: Vec<String> = record
+            .iter()
+            .enumerate()
+            .map(|(i, field)| match quoted.get(i) {
+                Some(true) => field.to_string(),
+                _ => field.trim().to_string(),
+            })
+            .collect();
+        if fields.len() == 1 && fields[0].is_empty() {
+            continue;
+        }
+        records.push(fields);
+    }
+    Ok(records)
+}
+
+/// Pick the narrowest SQL type that fits every non-empty value in a column.
+fn infer_csv_type<'a>(values: impl Iterator<Item = &'a str>) -> &'static str {
+    let mut seen_any = false;
+    let (mut all_int, mut all_num, mut all_bool) = (true, true, true);
+    for v in values.filter(|v| !v.is_empty()) {
+        seen_any = true;
+        all_int &= v.parse::<i64>().is_ok();
+        all_num &= v.parse::<f64>().is_ok();
+        all_bool &= v.eq_ignore_ascii_case("true") || v.eq_ignore_ascii_case("false");
+    }
+
+    match (seen_any, all_int, all_num, all_bool) {
+        (false, ..) => "TEXT",
+        (true, true, _, _) => "INTEGER",
+        (true, _, true, _) => "REAL",
+        (true, _, _, true) => "BOOLEAN",
+        _ => "TEXT",
+    }
+}
+
+fn csv_cell_to_json(value: &str, data_type: &str) -> serde_json::Value {
+    if value.is_empty() {
+        return serde_json::Value::Null;
+    }
+    match data_type {
+        "INTEGER" => value.parse::<i64>().map(serde_json::Value::from).unwrap_or_default(),
+        "REAL" => value
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+            .map(serde_json::Value::Number)
+            .unwrap_or_default(),
+        "BOOLEAN" => serde_json::Value::Bool(value.eq_ignore_ascii_case("true")),
+        _ => serde_json::Value::String(value.to_string()),
+    }
+}
+
+/// Parse CSV text into typed rows. The first record is the header; each
+/// column's type is inferred from all of its values, not just the first row.
+fn parse_csv(content: &str, format: CsvFormat) -> Result<ParsedCsv, AppError> {
+    let mut records = split_csv_records(content, format)?.into_iter();
+    let header = records.next().ok_or_else(|| {
+        AppError::CsvParseError("CSV is empty, no header row found".into())
+    })?;
+
+    if header.iter().all(|h| h.is_empty()) {
+        return Err(AppError::CsvParseError("No columns found in CSV header".into()));
+    }
+
+    let mut issues = Vec::new();
+    let mut seen_names = std::collections::HashSet::new();
+    for (i, name) in header.iter().enumerate() {
+        if name.is_empty() {
+            issues.push(format!("Header column {} has no name", i + 1));
+        } else if !seen_names.insert(name.as_str()) {
+            issues.push(format!("Header column {} is a duplicate", name));
+        }
+    }
+
+    let mut ragged = 0usize;
+    let raw_rows: Vec<Vec<String>> = records
+        .enumerate()
+        .map(|(line, mut r)| {
+            if r.len() != header.len() {
+                ragged += 1;
+                if ragged <= MAX_CSV_ISSUES {
+                    issues.push(format!(
+                        "Row {} has {} fields, expected {}",
+                        line + 1,
+                        r.len(),
+                        header.len()
+                    ));
+                }
+            }
+            r.resize(header.len(), String::new());
+            r
+        })
+        .collect();
+    if ragged > MAX_CSV_ISSUES {
+        issues.push(format!("{} rows in total have the wrong number of fields", ragged));
+    }
+
+    let columns: Vec<CsvColumn> = header
+        .iter()
+        .enumerate()
+        .map(|(i, name)| CsvColumn {
+            name: name.clone(),
+            data_type: infer_csv_type(raw_rows.iter().map(|r| r[i].as_str())).to_string(),
+        })
+        .collect();
+
+    let rows = raw_rows
+        .iter()
+        .map(|r| {
+            r.iter()
+                .zip(columns.iter())
+                .map(|(v, c)| csv_cell_to_json(v, &c.data_type))
+                .collect()
+        })
+        .collect();
+
+    Ok(ParsedCsv { columns, rows, issues })
+}
+
+/// Check that `key` names header columns and that the key values are
+/// present and unique in every row, so the `PRIMARY KEY` can't reject rows
+/// halfway through an import.
+fn validate_csv_primary_key(parsed: &ParsedCsv, key: &[String]) -> Result<(), AppError> {
+    let indexes: Vec<usize> = key
+        .iter()
+        .map(|k| {
+            parsed
+                .columns
+                .iter()
+                .position(|c| &c.name == k)
+                .ok_or_else(|| AppError::CsvParseError(format!("Primary key column {k} is not in the CSV header")))
+        })
+        .collect::<Result<_, _>>()?;
+
+    let mut seen = std::collections::HashSet::new();
+    for (line, row) in parsed.rows.iter().enumerate() {
+        let values: Vec<&serde_json::Value> = indexes.iter().map(|&i| &row[i]).collect();
+        if values.iter().any(|v| v.is_null()) {
+            return Err(AppError::CsvParseError(format!(
+                "Row {} has an empty primary key value",
+                line + 1
+            )));
+        }
+        let encoded = serde_json::to_string(&values).unwrap_or_default();
+        if !seen.insert(encoded.clone()) {
+            return Err(AppError::CsvParseError(format!(
+                "Duplicate primary key {} in row {}",
+                encoded,
+                line + 1
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// `CREATE TABLE IF NOT EXISTS` keeps an existing table as it is, so when
+/// `table_name` is already there its primary key must be the one asked for;
+/// otherwise rows would land in a table keyed differently than the user chose.
+fn check_existing_primary_key(
+    adapter: &dyn crate::adapter::DatabaseAdapter,
+    table_name: &str,
+    key: &[String],
+) -> Result<(), AppError> {
+    let schema = adapter.get_schema_with(&SchemaOptions::only(table_name))?;
+    let Some(existing) = schema.iter().find(|t| t.name == table_name) else {
+        return Ok(());
+    };
+    let mut existing_key: Vec<&str> = existing
+        .columns
+        .iter()
+        .filter(|c| c.primary_key)
+        .map(|c| c.name.as_str())
+        .collect();
+    let mut wanted: Vec<&str> = key.iter().map(String::as_str).collect();
+    existing_key.sort_unstable();
+    wanted.sort_unstable();
+    if existing_key == wanted {
+        return Ok(());
+    }
+    let existing_desc = if existing_key.is_empty() {
+        "no primary key".to_string()
+    } else {
+        format!("primary key ({})", existing_key.join(", "))
+    };
+    Err(AppError::QueryError(format!(
+        "Table {table_name} already exists with {existing_desc}, not ({})",
+        key.join(", ")
+    )))
+}
+
+/// The CREATE TABLE statement an import of `parsed` would run.
+fn csv_create_table_sql(
+    adapter: &dyn crate::adapter::DatabaseAdapter,
+    table_name: &str,
+    parsed: &ParsedCsv,
+    primary_key: Option<&[String]>,
+) -> String {
+    let mut col_defs: Vec<String> = parsed
+        .columns
+        .iter()
+        .map(|c| format!("{} {}", adapter.quote_ident(&c.name), c.data_type))
+        .collect();
+
+    if let Some(key) = primary_key.filter(|k| !k.is_empty()) {
+        let key_list = key
+            .iter()
+            .map(|k| adapter.quote_ident(k))
+            .collect::<Vec<_>>()
+            .join(", ");
+        col_defs.push(format!("PRIMARY KEY ({})", key_list));
+    }
+
+    format!(
+        "CREATE TABLE IF NOT EXISTS {} ({})",
+        adapter.quote_table(table_name),
+        col_defs.join(", ")
+    )
+}
+
+/// Create `table_name` from a parsed CSV and insert its rows, optionally
+/// declaring `primary_key` on the new table. The import runs in one
+/// transaction, so a failed batch leaves none of the rows behind. When the
+/// user already has a transaction open it runs under a savepoint instead,
+/// leaving that transaction open.
+fn create_csv_table(
+    adapter: &std::sync::Arc<dyn crate::adapter::DatabaseAdapter>,
+    table_name: &str,
+    parsed: &ParsedCsv,
+    primary_key: Option<&[String]>,
+) -> Result<(), AppError> {
+    if let Some(key) = primary_key.filter(|k| !k.is_empty()) {
+        validate_csv_primary_key(parsed, key)?;
+        check_existing_primary_key(adapter.as_ref(), table_name, key)?;
+    }
+    let create_sql = csv_create_table_sql(adapter.as_ref(), table_name, parsed, primary_key);
+    // MySQL commits implicitly on DDL, which would end the transaction
+    // before the first insert.
+    let ddl_in_transaction = adapter.kind() != DatabaseKind::MySQL;
+    if !ddl_in_transaction {
+        if adapter.in_transaction() {
+            return Err(AppError::QueryError(
+                "Commit or roll back the open transaction before importing: creating the \
+                 table would commit it implicitly on MySQL"
+                    .to_string(),
+            ));
+        }
+        adapter.execute_statement(&create_sql)?;
+    }
+
+    run_atomically(adapter, || {
+        if ddl_in_transaction {
+            adapter.execute_statement(&create_sql)?;
+        }
+        insert_csv_rows(adapter, table_name, parsed)
+    })
+}
+
+/// Run `work` so that an error undoes everything it did: in a transaction
+/// of its own, or under a savepoint when the user already has one open on
+/// the connection, which is then left open.
+fn run_atomically<T>(
+    adapter: &std::sync::Arc<dyn crate::adapter::DatabaseAdapter>,
+    work: impl FnOnce() -> Result<T, AppError>,
+) -> Result<T, AppError> {
+    let user_transaction = adapter.in_transaction();
+    if user_transaction {
+        adapter.execute_statement("SAVEPOINT arc_atomic")?;
+    } else {
+        adapter.begin_transaction()?;
+    }
+    match (work(), user_transaction) {
+        (Ok(value), false) => adapter.commit_transaction().map(|_| value),
+        (Ok(value), true) => adapter
+            .execute_statement("RELEASE SAVEPOINT arc_atomic")
+            .map(|_| value),
+        (Err(e), false) => {
+            let _ = adapter.rollback_transaction();
+            Err(e)
+        }
+        (Err(e), true) => {
+            let _ = adapter.execute_statement("ROLLBACK TO SAVEPOINT arc_atomic");
+            Err(e)
+        }
+    }
+}
+
+fn insert_csv_rows(
+    adapter: &std::sync::Arc<dyn crate::adapter::DatabaseAdapter>,
+    table_name: &str,
+    parsed: &ParsedCsv,
+) -> Result<(), AppError> {
+    const CHUNK_SIZE: usize = 1000;
+
+    let names: Vec<String> = parsed.columns.iter().map(|c| c.name.clone()).collect();
+    let import_start = std::time::Instant::now();
+    for chunk in parsed.rows.chunks(CHUNK_SIZE) {
+        if import_start.elapsed() > CSV_IMPORT_TIMEOUT {
+            return Err(AppError::QueryError(format!(
+                "CSV import timed out after {} seconds",
+                CSV_IMPORT_TIMEOUT.as_secs()
+            )));
+        }
+        insert_bound_rows(adapter, table_name, &names, chunk)?;
+    }
+    Ok(())
+}
+
+/// Insert `rows` into `table` in multi-row batches with every value bound
+/// as a parameter, so text from a file can't break out of its literal.
+/// Returns the number of rows inserted.
+fn insert_bound_rows(
+    adapter: &std::sync::Arc<dyn crate::adapter::DatabaseAdapter>,
+    table: &str,
+    columns: &[String],
+    rows: &[Vec<serde_json::Value>],
+) -> Result<u64, AppError> {
+    const BATCH_SIZE: usize = 50;
+
+    let col_list = columns
+        .iter()
+        .map(|c| adapter.quote_ident(c))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let mut inserted = 0u64;
+    for chunk in rows.chunks(BATCH_SIZE) {
+        let mut params = Vec::with_capacity(chunk.len() * columns.len());
+        let value_groups: Vec<String> = chunk
+            .iter()
+            .map(|row| {
+                let placeholders: Vec<String> = row
+                    .iter()
+                    .map(|value| {
+                        params.push(value.clone());
+                        crate::dialect::placeholder(adapter.kind(), params.len())
+                    })
+                    .collect();
+                format!("({})", placeholders.join(", "))
+            })
+            .collect();
+
+        let insert_sql = format!(
+            "INSERT INTO {} ({}) VALUES {}",
+            adapter.quote_table(table),
+            col_list,
+            value_groups.join(", ")
+        );
+        inserted += adapter.execute_statement_params(&insert_sql, &params)?;
+    }
+    Ok(inserted)
+}
+
+// --- Postgres notifications ---
+
+/// Payload of the `pg-notify` event.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PgNotifyEvent {
+    pub connection_id: String,
+    #[serde(flatten)]
+    pub notification: crate::adapter::PgNotification,
+}
+
+/// Active LISTEN connections, keyed by (connection ID, channel).
+#[derive(Default)]
+pub struct PgListeners(std::sync::Mutex<std::collections::HashMap<(String, String), crate::adapter::PgListener>>);
+
+impl PgListeners {
+    /// Stop every listener opened for `connection_id`.
+    fn remove_connection(&self, connection_id: &str) {
+        if let Ok(mut listeners) = self.0.lock() {
+            listeners.retain(|(id, _), _| id != connection_id);
+        }
+    }
+}
+
+/// LISTEN on `channel` over a dedicated connection and forward each
+/// notification as a `pg-notify` event, to `window` if given or to every
+/// window otherwise. Subscribing again to the same channel replaces the
+/// previous listener (e.g. one whose connection dropped).
+#[tauri::command]
+pub fn subscribe_notifications(
+    connection_id: String,
+    channel: String,
+    window: Option<String>,
+    app_handle: AppHandle,
+    listeners: State<'_, PgListeners>,
+    conn_manager: State<'_, ConnectionManager>,
+) -> Result<(), AppError> {
+    // CockroachDB speaks the protocol but doesn't implement LISTEN.
+    if conn_manager.get(&connection_id)?.kind() != DatabaseKind::PostgreSQL {
+        return Err(AppError::QueryError(
+            "Notifications are only supported on PostgreSQL connections".to_string(),
+        ));
+    }
+
+    let params = conn_manager.params(&connection_id)?;
+    let id = connection_id.clone();
+    let listener = crate::adapter::PgListener::start(&params, &channel, conn_manager.runtime(), move |notification| {
+        let event = PgNotifyEvent {
+            connection_id: id.clone(),
+            notification,
+        };
+        let _ = match window.as_deref() {
+            Some(label) => app_handle.emit_to(label, "pg-notify", event),
+            None => app_handle.emit("pg-notify", event),
+        };
+    })?;
+
+    let mut active = listeners
+        .0
+        .lock()
+        .map_err(|_| AppError::QueryError("Listener registry lock poisoned".to_string()))?;
+    active.insert((connection_id, channel), listener);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn unsubscribe_notifications(
+    connection_id: String,
+    channel: String,
+    listeners: State<'_, PgListeners>,
+) -> Result<(), AppError> {
+    let mut active = listeners
+        .0
+        .lock()
+        .map_err(|_| AppError::QueryError("Listener registry lock poisoned".to_string()))?;
+    active
+        .remove(&(connection_id, channel.clone()))
+        .map(|_| ())
+        .ok_or_else(|| AppError::NotFound(format!("No subscription for channel {channel}")))
+}
+
+// --- File export ---
+
+/// Emit `export-progress` after every this many rows.
+const EXPORT_PROGRESS_EVERY: u64 = 10_000;
+
+/// Exports in flight, keyed by export ID, with their cancellation flags.
+#[derive(Default)]
+pub struct ActiveExports(
+    std::sync::Mutex<std::collections::HashMap<String, std::sync::Arc<std::sync::atomic::AtomicBool>>>,
+);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExportFormat {
+    Csv,
+    /// A single JSON array of row objects.
+    Json,
+    /// One JSON object per line.
+    JsonLines,
+}
+
+impl ExportFormat {
+    fn parse(format: &str) -> Result<Self, AppError> {
+        match format.to_ascii_lowercase().as_str() {
+            "csv" => Ok(Self::Csv),
+            "json" | "array" => Ok(Self::Json),
+            "jsonl" | "ndjson" => Ok(Self::JsonLines),
+            other => Err(AppError::QueryError(format!("Unsupported export format: {other}"))),
+        }
+    }
+}
+
+/// Payload of `export-progress` and `export-complete`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportProgress {
+    pub export_id: String,
+    pub path: String,
+    pub rows_written: u64,
+    /// Set on `export-complete`.
+    pub done: bool,
+    pub cancelled: bool,
+    pub error: Option<String>,
+}
+
+/// A result value as CSV text: NULL is an empty field, strings are
+/// written unquoted and everything else as JSON.
+fn csv_text(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Null => String::new(),
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Quote a CSV field when it contains a delimiter, quote or line break.
+fn csv_field(value: &serde_json::Value) -> String {
+    let text = csv_text(value);
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text
+    }
+}
+
+/// Object keys for `columns` in JSON exports. A repeated column name, as
+/// from a join selecting `id` twice, gets a `_2`, `_3`, ... suffix instead
+/// of overwriting the earlier value.
+fn unique_keys(columns: &[String]) -> Vec<String> {
+    let mut keys: Vec<String> = Vec::with_capacity(columns.len());
+    for column in columns {
+        let mut key = column.clone();
+        let mut n = 1;
+        while keys.contains(&key) || (n > 1 && columns.contains(&key)) {
+            n += 1;
+            key = format!("{column}_{n}");
+        }
+        keys.push(key);
+    }
+    keys
+}
+
+/// Writes streamed rows to a file in the chosen format.
+struct FileExportSink {
+    writer: std::io::BufWriter<fs::File>,
+    format: ExportFormat,
+    columns: Vec<String>,
+    rows_written: u64,
+    cancel: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    progress: Box<dyn Fn(u64)>,
+}
+
+impl FileExportSink {
+    fn new(
+        file: fs::File,
+        format: ExportFormat,
+        cancel: std::sync::Arc<std::sync::atomic::AtomicBool>,
+        progress: Box<dyn Fn(u64)>,
+    ) -> Self {
+        Self {
+            writer: std::io::BufWriter::new(file),
+            format,
+            columns: Vec::new(),
+            rows_written: 0,
+            cancel,
+            progress,
+        }
+    }
+
+    fn write(&mut self, text: &str) -> Result<(), AppError> {
+        use std::io::Write;
+        self.writer
+            .write_all(text.as_bytes())
+            .map_err(|e| AppError::DatabaseError(format!("Failed to write export: {e}")))
+    }
+
+    fn finish(mut self) -> Result<u64, AppError> {
+        use std::io::Write;
+        if self.format == ExportFormat::Json {
+            self.write(if self.rows_written == 0 { "]\n" } else { "\n]\n" })?;
+        }
+        self.writer
+            .flush()
+            .map_err(|e| AppError::DatabaseError(format!("Failed to write export: {e}")))?;
+        Ok(self.rows_written)
+    }
+}
+
+impl crate::adapter::RowSink for FileExportSink {
+    fn columns(&mut self, columns: &[String]) -> Result<(), AppError> {
+        self.columns = unique_keys(columns);
+        match self.format {
+            ExportFormat::Csv => {
+                let header = columns
+                    .iter()
+                    .map(|c| csv_field(&serde_json::Value::String(c.clone())))
+                    .collect::<Vec<_>>()
+                    .join(",");
+                self.write(&format!("{header}\n"))
+            }
+            ExportFormat::Json => self.write("["),
+            ExportFormat::JsonLines => Ok(()),
+        }
+    }
+
+    fn row(&mut self, row: Vec<serde_json::Value>) -> Result<(), AppError> {
+        if self.cancel.load(std::sync::atomic::Ordering::Relaxed) {
+            return Err(AppError::QueryError("Export cancelled".to_string()));
+        }
+        let line = match self.format {
+            ExportFormat::Csv => row.iter().map(csv_field).collect::<Vec<_>>().join(","),
+            ExportFormat::Json | ExportFormat::JsonLines => {
+                serde_json::Value::Object(self.columns.iter().cloned().zip(row).collect()).to_string()
+            }
+        };
+        match self.format {
+            ExportFormat::Json if self.rows_written > 0 => self.write(&format!(",\n{line}"))?,
+            ExportFormat::Json => self.write(&format!("\n{line}"))?,
+            _ => self.write(&format!("{line}\n"))?,
+        }
+        self.rows_written += 1;
+        if self.rows_written.is_multiple_of(EXPORT_PROGRESS_EVERY) {
+            (self.progress)(self.rows_written);
+        }
+        Ok(())
+    }
+}
+
+/// The file extensions accepted for each export format.
+fn export_extensions(format: ExportFormat) -> &'static [&'static str] {
+    match format {
+        ExportFormat::Csv => &["csv"],
+        ExportFormat::Json => &["json"],
+        ExportFormat::JsonLines => &["jsonl", "ndjson", "json"],
+    }
+}
+
+/// Check an export target before anything is written: an absolute path
+/// with one of `extensions`, in an existing directory outside the app's
+/// own data directory, so an export can't overwrite `arc.db`, its key or
+/// arbitrary dotfiles.
+fn export_target(
+    app_handle: &AppHandle,
+    path: &str,
+    extensions: &[&str],
+) -> Result<std::path::PathBuf, AppError> {
+    let target = Path::new(path);
+    let rejected =
+        |reason: &str| AppError::QueryError(format!("Cannot export to {path}: {reason}"));
+    if !target.is_absolute()
+        || target
+            .components()
+            .any(|c| matches!(c, std::path::Component::ParentDir))
+    {
+        return Err(rejected("the path must be absolute"));
+    }
+    let extension = target
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase);
+    if !extension.is_some_and(|e| extensions.contains(&e.as_str())) {
+        return Err(rejected(&format!("the file must end in .{}", extensions.join(" or ."))));
+    }
+    let (Some(parent), Some(name)) = (target.parent(), target.file_name()) else {
+        return Err(rejected("the path has no file name"));
+    };
+    let parent = parent
+        .canonicalize()
+        .map_err(|_| rejected("the directory does not exist"))?;
+    if let Ok(app_dir) = app_handle.path().app_data_dir() {
+        if app_dir.canonicalize().is_ok_and(|app_dir| parent.starts_with(app_dir)) {
+            return Err(rejected("the app's data directory is off limits"));
+        }
+    }
+    Ok(parent.join(name))
+}
+
+/// Stream `sql`'s rows through `sink` into the file at `path` and finish
+/// it, removing the partial file if the query, a write or a cancel stops
+/// it. Returns the rows written either way, and the outcome.
+fn stream_export(
+    adapter: &dyn crate::adapter::DatabaseAdapter,
+    sql: &str,
+    encoding: BinaryEncoding,
+    mut sink: FileExportSink,
+    path: &Path,
+) -> (u64, Result<(), AppError>) {
+    let result = adapter.stream_query(sql, encoding, &mut sink);
+    let rows_written = sink.rows_written;
+    let result = result.and_then(|_| sink.finish()).map(|_| ());
+    if result.is_err() {
+        let _ = fs::remove_file(path);
+    }
+    (rows_written, result)
+}
+
+/// Create the export file at `path` after checking it with `export_target`.
+fn create_export_file(
+    app_handle: &AppHandle,
+    path: &str,
+    format: ExportFormat,
+) -> Result<(std::path::PathBuf, fs::File), AppError> {
+    let target = export_target(app_handle, path, export_extensions(format))?;
+    let file = fs::File::create(&target)
+        .map_err(|e| AppError::DatabaseError(format!("Failed to create file {}: {}", path, e)))?;
+    Ok((target, file))
+}
+
+/// Stream a query straight into a CSV, JSON or JSON Lines file without
+/// holding the result in memory. Runs in the background and returns the
+/// export ID at once; progress arrives as `export-progress` events and the
+/// outcome as `export-complete`. A failed or cancelled export removes the
+/// partial file. The path is checked as by `export_target`.
+#[tauri::command]
+pub fn export_query_to_file(
+    connection_id: String,
+    sql: String,
+    path: String,
+    format: String,
+    app_handle: AppHandle,
+    exports: State<'_, ActiveExports>,
+    conn_manager: State<'_, ConnectionManager>,
+) -> Result<String, AppError> {
+    let format = ExportFormat::parse(&format)?;
+    let adapter = conn_manager.get(&connection_id)?;
+    let permit = conn_manager.acquire_query_slot(&connection_id)?;
+    let (target, file) = create_export_file(&app_handle, &path, format)?;
+
+    let export_id = uuid::Uuid::new_v4().to_string();
+    let cancel = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+    exports
+        .0
+        .lock()
+        .map_err(|_| AppError::QueryError("Export registry lock poisoned".to_string()))?
+        .insert(export_id.clone(), cancel.clone());
+
+    let id = export_id.clone();
+    std::thread::spawn(move || {
+        // The export counts against the connection's concurrency cap
+        // until it finishes.
+        let _permit = permit;
+        let progress_handle = app_handle.clone();
+        let (progress_id, progress_path) = (id.clone(), path.clone());
+        let sink = FileExportSink::new(
+            file,
+            format,
+            cancel.clone(),
+            Box::new(move |rows_written| {
+                let _ = progress_handle.emit(
+                    "export-progress",
+                    ExportProgress {
+                        export_id: progress_id.clone(),
+                        path: progress_path.clone(),
+                        rows_written,
+                        done: false,
+                        cancelled: false,
+                        error: None,
+                    },
+                );
+            }),
+        );
+
+        let (rows_written, result) =
+            stream_export(adapter.as_ref(), &sql, BinaryEncoding::default(), sink, &target);
+        let cancelled = cancel.load(std::sync::atomic::Ordering::Relaxed);
+
+        if let Some(exports) = app_handle.try_state::<ActiveExports>() {
+            if let Ok(mut active) = exports.0.lock() {
+                active.remove(&id);
+            }
+        }
+        let _ = app_handle.emit(
+            "export-complete",
+            ExportProgress {
+                export_id: id,
+                path,
+                rows_written,
+                done: true,
+                cancelled,
+                error: result.err().filter(|_| !cancelled).map(|e| e.to_string()),
+            },
+        );
+    });
+
+    Ok(export_id)
+}
+
+/// Ask a running export to stop. It stops at the next row and removes the
+/// partial file.
+#[tauri::command]
+pub fn cancel_export(export_id: String, exports: State<'_, ActiveExports>) -> Result<(), AppError> {
+    let active = exports
+        .0
+        .lock()
+        .map_err(|_| AppError::QueryError("Export registry lock poisoned".to_string()))?;
+    let cancel = active
+        .get(&export_id)
+        .ok_or_else(|| AppError::NotFound(format!("Export {export_id} not running")))?;
+    cancel.store(true, std::sync::atomic::Ordering::Relaxed);
+    Ok(())
+}
+
+/// Run an export to completion on the calling thread through the same
+/// streaming writer as `export_query_to_file`. Returns the rows written.
+fn export_now(
+    app_handle: &AppHandle,
+    conn_manager: &ConnectionManager,
+    connection_id: &str,
+    sql: &str,
+    path: &str,
+    format: ExportFormat,
+    encoding: BinaryEncoding,
+) -> Result<usize, AppError> {
+    let adapter = conn_manager.get(connection_id)?;
+    let _permit = conn_manager.acquire_query_slot(connection_id)?;
+    let (target, file) = create_export_file(app_handle, path, format)?;
+    let cancel = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let sink = FileExportSink::new(file, format, cancel, Box::new(|_| {}));
+    let (rows_written, result) = stream_export(adapter.as_ref(), sql, encoding, sink, &target);
+    result.map(|_| rows_written as usize)
+}
+
+/// Run `sql` and write the whole result to `file_path` as CSV with a
+/// header row. NULLs are empty fields and binary values follow
+/// `binary_encoding`. Returns the number of rows written.
+#[tauri::command]
+pub fn export_query_csv(
+    connection_id: String,
+    sql: String,
+    file_path: String,
+    binary_encoding: Option<BinaryEncoding>,
+    app_handle: AppHandle,
+    conn_manager: State<'_, ConnectionManager>,
+) -> Result<usize, AppError> {
+    export_now(
+        &app_handle,
+        &conn_manager,
+        &connection_id,
+        &sql,
+        &file_path,
+        ExportFormat::Csv,
+        binary_encoding.unwrap_or_default(),
+    )
+}
+
+/// Run `sql` and write the result to `file_path` as JSON: with `format`
+/// "array" one array of row objects keyed by column name, with "ndjson"
+/// one object per line. Values keep their JSON types; repeated column
+/// names get a numeric suffix. Returns the number of rows written.
+#[tauri::command]
+pub fn export_query_json(
+    connection_id: String,
+    sql: String,
+    file_path: String,
+    format: String,
+    app_handle: AppHandle,
+    conn_manager: State<'_, ConnectionManager>,
+) -> Result<usize, AppError> {
+    let format = match ExportFormat::parse(&format)? {
+        ExportFormat::Csv => {
+            return Err(AppError::QueryError(
+                "JSON export format must be \"array\" or \"ndjson\"".to_string(),
+            ))
+        }
+        format => format,
+    };
+    export_now(
+        &app_handle,
+        &conn_manager,
+        &connection_id,
+        &sql,
+        &file_path,
+        format,
+        BinaryEncoding::default(),
+    )
+}
+
+/// Most rows an Arrow or Parquet result may hold. Columnar output is built
+/// in memory, so a larger result is refused rather than cut short.
+const MAX_COLUMNAR_ROWS: usize = 1_000_000;
+
+/// Run `sql` for columnar output: binary values base64-encoded so they
+/// convert back to bytes, and at most `MAX_COLUMNAR_ROWS` rows.
+fn columnar_result(
+    conn_manager: &ConnectionManager,
+    connection_id: &str,
+    sql: &str,
+) -> Result<QueryResult, AppError> {
+    let adapter = conn_manager.get(connection_id)?;
+    let _permit = conn_manager.acquire_query_slot(connection_id)?;
+    let result =
+        adapter.execute_query_encoded(sql, Some(MAX_COLUMNAR_ROWS), BinaryEncoding::Base64)?;
+    if result.truncated {
+        return Err(AppError::QueryError(format!(
+            "The result has more than {MAX_COLUMNAR_ROWS} rows; narrow the query or export it \
+             as CSV or JSON"
+        )));
+    }
+    Ok(result)
+}
+
+/// Run a query and return its rows as an Arrow IPC stream, for handing to
+/// downstream data tools. Column types follow the types the backend reports
+/// for the result, falling back to ones inferred from the values.
+#[tauri::command]
+pub fn execute_query_arrow(
+    connection_id: String,
+    sql: String,
+    conn_manager: State<'_, ConnectionManager>,
+) -> Result<tauri::ipc::Response, AppError> {
+    let result = columnar_result(&conn_manager, &connection_id, &sql)?;
+    Ok(tauri::ipc::Response::new(crate::columnar::to_ipc_bytes(&result)?))
+}
+
+/// Run a query and write its rows to `path` as Parquet. The path is checked
+/// as by `export_target` and must end in `.parquet`; it is only created
+/// once the query has succeeded. Returns the number of rows written; a
+/// failed write removes the partial file.
+#[tauri::command]
+pub fn export_query_parquet(
+    connection_id: String,
+    sql: String,
+    path: String,
+    app_handle: AppHandle,
+    conn_manager: State<'_, ConnectionManager>,
+) -> Result<usize, AppError> {
+    let target = export_target(&app_handle, &path, &["parquet"])?;
+    let result = columnar_result(&conn_manager, &connection_id, &sql)?;
+    let file = fs::File::create(&target)
+        .map_err(|e| AppError::DatabaseError(format!("Failed to create file {}: {}", path, e)))?;
+    if let Err(e) = crate::columnar::write_parquet(&result, file) {
+        let _ = fs::remove_file(&target);
+        return Err(e);
+    }
+    Ok(result.row_count)
+}
+
+/// Refuse an operation that changes data (`action`, e.g. "Deleting rows")
+/// on a read-only connection or one labelled production.
+fn ensure_writable(
+    conn_manager: &ConnectionManager,
+    connection_id: &str,
+    action: &str,
+) -> Result<(), AppError> {
+    let params = conn_manager.params(connection_id)?;
+    if params.read_only {
+        return Err(AppError::QueryError(format!(
+            "{action} is not allowed on a read-only connection"
+        )));
+    }
+    if params
+        .environment
+        .as_deref()
+        .is_some_and(|env| env.eq_ignore_ascii_case("production"))
+    {
+        return Err(AppError::QueryError(format!(
+            "{action} is not allowed on a production connection"
+        )));
+    }
+    Ok(())
+}
+
+// --- Guarded deletes ---
+
+/// How long a delete confirmation token stays valid.
+const DELETE_CONFIRM_TTL: std::time::Duration = std::time::Duration::from_secs(300);
+
+/// Equality condition for `delete_rows`; a null value matches `IS NULL`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeleteCondition {
+    pub column: String,
+    pub value: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeleteRowsResult {
+    /// Rows matching the conditions when the count was taken.
+    pub matched: u64,
+    /// Token to pass back to actually run the delete; absent once executed.
+    pub confirm_token: Option<String>,
+    /// Rows removed; only set when the delete ran.
+    pub deleted: Option<u64>,
+}
+
+struct PendingDelete {
+    connection_id: String,
+    table: String,
+    where_clause: String,
+    params: Vec<serde_json::Value>,
+    matched: u64,
+    created: std::time::Instant,
+}
+
+/// Deletes that have been counted and are waiting for confirmation, keyed by
+/// confirm token.
+#[derive(Default)]
+pub struct PendingDeletes(std::sync::Mutex<std::collections::HashMap<String, PendingDelete>>);
+
+/// The WHERE clause for `conditions`, with every value bound as a
+/// parameter, and the parameters in placeholder order.
+fn build_delete_where(
+    adapter: &dyn crate::adapter::DatabaseAdapter,
+    conditions: &[DeleteCondition],
+) -> Result<(String, Vec<serde_json::Value>), AppError> {
+    if conditions.is_empty() {
+        return Err(AppError::QueryError(
+            "delete_rows needs at least one condition".to_string(),
+        ));
+    }
+    let mut params = Vec::new();
+    let clause = conditions
+        .iter()
+        .map(|c| {
+            if c.value.is_null() {
+                format!("{} IS NULL", adapter.quote_ident(&c.column))
+            } else {
+                params.push(c.value.clone());
+                format!(
+                    "{} = {}",
+                    adapter.quote_ident(&c.column),
+                    crate::dialect::placeholder(adapter.kind(), params.len())
+                )
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" AND ");
+    Ok((clause, params))
+}
+
+fn count_matching_rows(
+    adapter: &std::sync::Arc<dyn crate::adapter::DatabaseAdapter>,
+    table: &str,
+    where_clause: &str,
+    params: &[serde_json::Value],
+) -> Result<u64, AppError> {
+    let result = adapter.execute_query_params(
+        &format!("SELECT COUNT(*) FROM {} WHERE {}", adapter.quote_table(table), where_clause),
+        params,
+    )?;
+    Ok(result
+        .rows
+        .first()
+        .and_then(|r| r.first())
+        .and_then(|v| v.as_u64().or_else(|| v.as_str().and_then(|s| s.parse().ok())))
+        .unwrap_or(0))
+}
+
+/// Two-step delete. Without `confirm_token` the matching rows are counted and
+/// a token is returned; calling again with that token (and the same table and
+/// conditions) performs the delete. The recount and the delete share one
+/// transaction; if the matching count changed in between, nothing is deleted
+/// and a fresh token is issued. Read-only and production connections are
+/// refused.
+#[tauri::command]
+pub fn delete_rows(
+    connection_id: String,
+    table_name: String,
+    conditions: Vec<DeleteCondition>,
+    confirm_token: Option<String>,
+    pending: State<'_, PendingDeletes>,
+    conn_manager: State<'_, ConnectionManager>,
+) -> Result<DeleteRowsResult, AppError> {
+    let adapter = conn_manager.get(&connection_id)?;
+    let _permit = conn_manager.acquire_query_slot(&connection_id)?;
+    if matches!(adapter.kind(), DatabaseKind::Redis | DatabaseKind::Elasticsearch) {
+        return Err(AppError::QueryError(
+            "delete_rows is only supported for SQL databases".to_string(),
+        ));
+    }
+    ensure_writable(&conn_manager, &connection_id, "Deleting rows")?;
+    let (where_clause, params) = build_delete_where(adapter.as_ref(), &conditions)?;
+
+    let matched = match confirm_token {
+        Some(token) => {
+            // Take the token and let go of the lock before touching the
+            // database, so a slow delete doesn't hold up other confirmations.
+            let confirmed = {
+                let mut pending = pending.0.lock().unwrap();
+                pending.retain(|_, p| p.created.elapsed() < DELETE_CONFIRM_TTL);
+                pending.remove(&token).ok_or_else(|| {
+                    AppError::NotFound("Delete confirmation expired or unknown".to_string())
+                })?
+            };
+            if confirmed.connection_id != connection_id
+                || confirmed.table != table_name
+                || confirmed.where_clause != where_clause
+                || confirmed.params != params
+            {
+                return Err(AppError::QueryError(
+                    "Confirmation token does not match this delete".to_string(),
+                ));
+            }
+
+            let outcome = run_atomically(&adapter, || {
+                let matched = count_matching_rows(&adapter, &table_name, &where_clause, &params)?;
+                if matched != confirmed.matched {
+                    return Ok((matched, None));
+                }
+                let deleted = adapter.execute_statement_params(
+                    &format!(
+                        "DELETE FROM {} WHERE {}",
+                        adapter.quote_table(&table_name),
+                        where_clause
+                    ),
+                    &params,
+                )?;
+                Ok((matched, Some(deleted)))
+            })?;
+            match outcome {
+                (matched, Some(deleted)) => {
+                    return Ok(DeleteRowsResult {
+                        matched,
+                        confirm_token: None,
+                        deleted: Some(deleted),
+                    })
+                }
+                // Rows changed since the user confirmed; re-confirm.
+                (matched, None) => matched,
+            }
+        }
+        None => count_matching_rows(&adapter, &table_name, &where_clause, &params)?,
+    };
+
+    let token = uuid::Uuid::new_v4().to_string();
+    let mut pending = pending.0.lock().unwrap();
+    pending.retain(|_, p| p.created.elapsed() < DELETE_CONFIRM_TTL);
+    pending.insert(
+        token.clone(),
+        PendingDelete {
+            connection_id,
+            table: table_name,
+            where_clause,
+            params,
+            matched,
+            created: std::time::Instant::now(),
+        },
+    );
+    Ok(DeleteRowsResult {
+        matched,
+        confirm_token: Some(token),
+        deleted: None,
+    })
+}
+
+// --- Mock data ---
+
+/// Upper bound on rows generated by a single `generate_mock_data` call.
+const MAX_MOCK_ROWS: u32 = 10_000;
+
+const MOCK_WORDS: &[&str] = &[
+    "alpha", "bravo", "cedar", "delta", "ember", "falcon", "granite", "harbor",
+    "iris", "juniper", "kestrel", "lumen", "maple", "nova", "onyx", "prairie",
+];
+
+const MOCK_FIRST_NAMES: &[&str] = &[
+    "Ada", "Grace", "Alan", "Linus", "Margaret", "Dennis", "Barbara", "Ken",
+    "Frances", "Edsger", "Radia", "John",
+];
+
+const MOCK_LAST_NAMES: &[&str] = &[
+    "Lovelace", "Hopper", "Turing", "Torvalds", "Hamilton", "Ritchie", "Liskov",
+    "Thompson", "Allen", "Dijkstra", "Perlman", "Backus",
+];
+
+/// Tiny xorshift generator; mock data only needs variety, not quality.
+struct MockRng(u64);
+
+impl MockRng {
+    fn new() -> Self {
+        let seed = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0x2545_f491_4f6c_dd1d);
+        Self(seed | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+
+    fn range(&mut self, min: u64, max: u64) -> u64 {
+        min + self.next() % (max - min + 1)
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.next() as usize % items.len()]
+    }
+}
+
+fn is_integer_type(ty: &str) -> bool {
+    (ty.contains("int") || ty.contains("serial")) && !ty.contains("interval") && !ty.contains("point")
+}
+
+/// Parse a length limit such as `varchar(40)` from a column type.
+fn column_length_limit(data_type: &str) -> Option<usize> {
+    let start = data_type.find('(')?;
+    let end = data_type[start..].find(')')? + start;
+    data_type[start + 1..end].trim().parse().ok()
+}
+
+/// A value for `col` in mock row `row`. Integer keys count up from
+/// `key_base`, the column's current maximum, so they don't collide with
+/// rows already in the table.
+fn mock_value(col: &ColumnInfo, row: u32, key_base: u64, rng: &mut MockRng) -> serde_json::Value {
+    if col.nullable && !col.primary_key && rng.range(0, 9) == 0 {
+        return serde_json::Value::Null;
+    }
+
+    let ty = col.data_type.to_lowercase();
+    let name = col.name.to_lowercase();
+
+    if ty.contains("bool") {
+        return serde_json::Value::Bool(rng.range(0, 1) == 1);
+    }
+    if is_integer_type(&ty) {
+        // Keys need to be unique across the batch; everything else is random.
+        let n = if col.primary_key { key_base + row as u64 + 1 } else { rng.range(1, 10_000) };
+        return serde_json::Value::from(n);
+    }
+    if ["numeric", "decimal", "real", "double", "float", "money"].iter().any(|t| ty.contains(t)) {
+        let cents = rng.range(0, 1_000_000) as f64;
+        return serde_json::json!(cents / 100.0);
+    }
+    if ty.contains("uuid") {
+        return serde_json::Value::String(uuid::Uuid::new_v4().to_string());
+    }
+    if ty.contains("json") {
+        return serde_json::json!({ "seed": rng.range(1, 1000) }).to_string().into();
+    }
+    if ty.contains("timestamp") || ty.contains("datetime") || ty.contains("date") || ty.starts_with("time") {
+        let base = chrono::NaiveDate::from_ymd_opt(2020, 1, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        let at = base + chrono::Duration::seconds(rng.range(0, 5 * 365 * 86_400) as i64);
+        let formatted = if ty.contains("timestamp") || ty.contains("datetime") {
+            at.format("%Y-%m-%d %H:%M:%S").to_string()
+        } else if ty.contains("date") {
+            at.format("%Y-%m-%d").to_string()
+        } else {
+            at.format("%H:%M:%S").to_string()
+        };
+        return serde_json::Value::String(formatted);
+    }
+
+    let mut text = if name.contains("email") {
+        format!(
+            "{}.{}{}@example.com",
+            rng.pick(MOCK_FIRST_NAMES).to_lowercase(),
+            rng.pick(MOCK_LAST_NAMES).to_lowercase(),
+            row + 1
+        )
+    } else if name.contains("first") && name.contains("name") {
+        rng.pick(MOCK_FIRST_NAMES).to_string()
+    } else if name.contains("last") && name.contains("name") {
+        rng.pick(MOCK_LAST_NAMES).to_string()
+    } else if name.contains("name") {
+        format!("{} {}", rng.pick(MOCK_FIRST_NAMES), rng.pick(MOCK_LAST_NAMES))
+    } else {
+        format!("{} {} {}", rng.pick(MOCK_WORDS), rng.pick(MOCK_WORDS), row + 1)
+    };
+    if col.primary_key && !text.ends_with(&(row + 1).to_string()) {
+        text = format!("{}-{}", text, row + 1);
+    }
+    if let Some(limit) = column_length_limit(&ty) {
+        text = text.chars().take(limit).collect();
+    }
+    serde_json::Value::String(text)
+}
+
+/// The largest value in integer column `column` of `table`, or 0 when the
+/// table is empty.
+fn max_integer_value(
+    adapter: &dyn crate::adapter::DatabaseAdapter,
+    table: &str,
+    column: &str,
+) -> Result<u64, AppError> {
+    let result = adapter.execute_query(&format!(
+        "SELECT MAX({}) FROM {}",
+        adapter.quote_ident(column),
+        adapter.quote_table(table)
+    ))?;
+    Ok(result
+        .rows
+        .first()
+        .and_then(|r| r.first())
+        .and_then(|v| v.as_u64().or_else(|| v.as_str().and_then(|s| s.parse().ok())))
+        .unwrap_or(0))
+}
+
+#[tauri::command]
+pub fn generate_mock_data(
+    connection_id: String,
+    table_name: String,
+    count: u32,
+    conn_manager: State<'_, ConnectionManager>,
+) -> Result<u64, AppError> {
+    let adapter = conn_manager.get(&connection_id)?;
+    let _permit = conn_manager.acquire_query_slot(&connection_id)?;
+    if matches!(adapter.kind(), DatabaseKind::Redis | DatabaseKind::Elasticsearch) {
+        return Err(AppError::QueryError(
+            "Mock data generation is only supported for SQL databases".to_string(),
+        ));
+    }
+
+    ensure_writable(&conn_manager, &connection_id, "Generating mock data")?;
+
+    let count = count.min(MAX_MOCK_ROWS);
+    if count == 0 {
+        return Ok(0);
+    }
+
+    let table = adapter.get_table_metadata(&table_name)?.schema;
+    let generated = adapter.generated_columns(&table.name)?;
+    let columns: Vec<&ColumnInfo> = table
+        .columns
+        .iter()
+        .filter(|c| !generated.contains(&c.name))
+        .collect();
+    if columns.is_empty() {
+        return Err(AppError::QueryError(format!(
+            "Table {table_name} has no columns that can be populated"
+        )));
+    }
+
+    let key_bases = columns
+        .iter()
+        .map(|c| {
+            if c.primary_key && is_integer_type(&c.data_type.to_lowercase()) {
+                max_integer_value(adapter.as_ref(), &table.name, &c.name)
+            } else {
+                Ok(0)
+            }
+        })
+        .collect::<Result<Vec<u64>, AppError>>()?;
+
+    let mut rng = MockRng::new();
+    let rows: Vec<Vec<serde_json::Value>> = (0..count)
+        .map(|row| {
+            columns
+                .iter()
+                .zip(&key_bases)
+                .map(|(c, &base)| mock_value(c, row, base, &mut rng))
+                .collect()
+        })
+        .collect();
+    let names: Vec<String> = columns.iter().map(|c| c.name.clone()).collect();
+
+    insert_bound_rows(&adapter, &table.name, &names, &rows)
+}
+
+// --- Result diffing ---
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnChange {
+    pub column: String,
+    pub before: serde_json::Value,
+    pub after: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangedRow {
+    pub key: serde_json::Map<String, serde_json::Value>,
+    pub changes: Vec<ColumnChange>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResultDiff {
+    pub key_columns: Vec<String>,
+    /// Columns present in both results; only these are compared.
+    pub compared_columns: Vec<String>,
+    pub columns_only_in_a: Vec<String>,
+    pub columns_only_in_b: Vec<String>,
+    /// Rows of `b` with no counterpart in `a`, keyed by column name.
+    pub added: Vec<serde_json::Map<String, serde_json::Value>>,
+    /// Rows of `a` with no counterpart in `b`, keyed by column name.
+    pub removed: Vec<serde_json::Map<String, serde_json::Value>>,
+    pub changed: Vec<ChangedRow>,
+    pub unchanged_count: usize,
+}
+
+fn row_to_map(
+    columns: &[String],
+    row: &[serde_json::Value],
+) -> serde_json::Map<String, serde_json::Value> {
+    columns
+        .iter()
+        .zip(row.iter())
+        .map(|(c, v)| (c.clone(), v.clone()))
+        .collect()
+}
+
+/// Align the rows of two results by `key_columns` and report which rows were
+/// added, removed or changed. With no key columns, every shared column is part
+/// of the key, so rows can only be added or removed. Duplicate keys are paired
+/// up in the order they appear.
+#[tauri::command]
+pub fn diff_results(
+    a: QueryResult,
+    b: QueryResult,
+    key_columns: Vec<String>,
+) -> Result<ResultDiff, AppError> {
+    let compared_columns: Vec<String> = a
+        .columns
+        .iter()
+        .filter(|c| b.columns.contains(c))
+        .cloned()
+        .collect();
+    let columns_only_in_a: Vec<String> = a
+        .columns
+        .iter()
+        .filter(|c| !b.columns.contains(c))
+        .cloned()
+        .collect();
+    let columns_only_in_b: Vec<String> = b
+        .columns
+        .iter()
+        .filter(|c| !a.columns.contains(c))
+        .cloned()
+        .collect();
+
+    let key_columns = if key_columns.is_empty() {
+        compared_columns.clone()
+    } else {
+        key_columns
+    };
+    if let Some(missing) = key_columns.iter().find(|k| !compared_columns.contains(k)) {
+        return Err(AppError::QueryError(format!(
+            "Key column {missing} is not present in both results"
+        )));
+    }
+
+    let index_of = |columns: &[String], name: &str| columns.iter().position(|c| c == name).unwrap();
+    let a_key_idx: Vec<usize> = key_columns.iter().map(|k| index_of(&a.columns, k)).collect();
+    let b_key_idx: Vec<usize> = key_columns.iter().map(|k| index_of(&b.columns, k)).collect();
+    let key_of = |row: &[serde_json::Value], idx: &[usize]| {
+        serde_json::Value::Array(
+            idx.iter()
+                .map(|&i| row.get(i).cloned().unwrap_or(serde_json::Value::Null))
+                .collect(),
+        )
+        .to_string()
+    };
+
+    let mut b_by_key: std::collections::HashMap<String, std::collections::VecDeque<usize>> =
+        std::collections::HashMap::new();
+    for (i, row) in b.rows.iter().enumerate() {
+        b_by_key.entry(key_of(row, &b_key_idx)).or_default().push_back(i);
+    }
+
+    let mut matched_b = vec![false; b.rows.len()];
+    let mut removed = Vec::new();
+    let mut changed = Vec::new();
+    let mut unchanged_count = 0;
+
+    for a_row in &a.rows {
+        let key = key_of(a_row, &a_key_idx);
+        let Some(b_idx) = b_by_key.get_mut(&key).and_then(|q| q.pop_front()) else {
+            removed.push(row_to_map(&a.columns, a_row));
+            continue;
+        };
+        matched_b[b_idx] = true;
+        let b_row = &b.rows[b_idx];
+
+        let changes: Vec<ColumnChange> = compared_columns
+            .iter()
+            .filter_map(|col| {
+                let before = a_row.get(index_of(&a.columns, col)).cloned().unwrap_or_default();
+                let after = b_row.get(index_of(&b.columns, col)).cloned().unwrap_or_default();
+                (before != after).then(|| ColumnChange {
+                    column: col.clone(),
+                    before,
+                    after,
+                })
+            })
+            .collect();
+
+        if changes.is_empty() {
+            unchanged_count += 1;
+        } else {
+            let key = key_columns
+                .iter()
+                .zip(a_key_idx.iter())
+                .map(|(k, &i)| (k.clone(), a_row.get(i).cloned().unwrap_or_default()))
+                .collect();
+            changed.push(ChangedRow { key, changes });
+        }
+    }
+
+    let added = b
+        .rows
+        .iter()
+        .zip(matched_b)
+        .filter(|(_, matched)| !matched)
+        .map(|(row, _)| row_to_map(&b.columns, row))
+        .collect();
+
+    Ok(ResultDiff {
+        key_columns,
+        compared_columns,
+        columns_only_in_a,
+        columns_only_in_b,
+        added,
+        removed,
+        changed,
+        unchanged_count,
+    })
+}
+
+// --- Result coercion ---
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CoerceTarget {
+    Number,
+    /// ISO 8601: `YYYY-MM-DD` for plain dates, RFC 3339 in UTC otherwise.
+    Date,
+    String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoercedResult {
+    #[serde(flatten)]
+    pub result: QueryResult,
+    /// Values that could not be converted and were left as they were.
+    pub failed_count: usize,
+}
+
+fn coerce_number(value: &serde_json::Value) -> Option<serde_json::Value> {
+    match value {
+        serde_json::Value::Number(_) => Some(value.clone()),
+        serde_json::Value::Bool(b) => Some(serde_json::Value::from(*b as i64)),
+        serde_json::Value::String(s) => {
+            let s = s.trim();
+            if let Ok(n) = s.parse::<i64>() {
+                return Some(serde_json::Value::from(n));
+            }
+            s.parse::<f64>()
+                .ok()
+                .and_then(serde_json::Number::from_f64)
+                .map(serde_json::Value::Number)
+        }
+        _ => None,
+    }
+}
+
+fn coerce_date(value: &serde_json::Value) -> Option<serde_json::Value> {
+    use chrono::{DateTime, NaiveDate, NaiveDateTime, SecondsFormat, Utc};
+
+    let to_json = |dt: DateTime<Utc>| {
+        serde_json::Value::String(dt.to_rfc3339_opts(SecondsFormat::AutoSi, true))
+    };
+    match value {
+        // Epoch timestamps; anything past ~5000 AD in seconds is taken as
+        // milliseconds.
+        serde_json::Value::Number(n) => {
+            let n = n.as_f64()?;
+            let millis = if n.abs() >= 1e11 { n } else { n * 1000.0 };
+            DateTime::from_timestamp_millis(millis as i64).map(to_json)
+        }
+        serde_json::Value::String(s) => {
+            let s = s.trim();
+            if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+                return Some(to_json(dt.with_timezone(&Utc)));
+            }
+            for format in [
+                "%Y-%m-%d %H:%M:%S%.f",
+                "%Y-%m-%dT%H:%M:%S%.f",
+                "%Y-%m-%d %H:%M",
+            ] {
+                if let Ok(dt) = NaiveDateTime::parse_from_str(s, format) {
+                    return Some(to_json(dt.and_utc()));
+                }
+            }
+            NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                .ok()
+                .map(|d| serde_json::Value::String(d.format("%Y-%m-%d").to_string()))
+        }
+        _ => None,
+    }
+}
+
+fn coerce_string(value: &serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::String(_) => value.clone(),
+        other => serde_json::Value::String(other.to_string()),
+    }
+}
+
+/// Convert one column of a result to `to`, so charts get real numbers or
+/// dates from columns that came back as text. NULLs are left alone and not
+/// counted as failures.
+#[tauri::command]
+pub fn coerce_result_column(
+    mut result: QueryResult,
+    column: String,
+    to: CoerceTarget,
+) -> Result<CoercedResult, AppError> {
+    let idx = result
+        .columns
+        .iter()
+        .position(|c| *c == column)
+        .ok_or_else(|| AppError::QueryError(format!("Column {column} is not in the result")))?;
+
+    let mut failed_count = 0;
+    for value in result.rows.iter_mut().filter_map(|row| row.get_mut(idx)) {
+        if value.is_null() {
+            continue;
+        }
+        let coerced = match to {
+            CoerceTarget::Number => coerce_number(value),
+            CoerceTarget::Date => coerce_date(value),
+            CoerceTarget::String => Some(coerce_string(value)),
+        };
+        match coerced {
+            Some(coerced) => *value = coerced,
+            None => failed_count += 1,
+        }
+    }
+
+    Ok(CoercedResult {
+        result,
+        failed_count,
+    })
 }
 
-#[tauri::command]
-pub fn list_connection_notes(db: State<'_, Database>) -> Result<Vec<ConnectionNote>, AppError> {
-    db.list_connection_notes()
-}
+// --- Schema migration ---
 
-#[tauri::command]
-pub fn set_connection_note(
-    connection_id: String,
-    note: String,
-    db: State<'_, Database>,
-) -> Result<ConnectionNote, AppError> {
-    db.set_connection_note(&connection_id, &note)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationStep {
+    pub sql: String,
+    /// Set for steps that lose data or may fail (dropped tables and columns,
+    /// narrowed types, new NOT NULL constraints), with a description of what
+    /// would be lost or checked.
+    pub warning: Option<String>,
 }
 
-#[tauri::command]
-pub fn get_database_stats(
-    connection_id: String,
-    db: State<'_, Database>,
-    conn_manager: State<'_, ConnectionManager>,
-) -> Result<DatabaseStats, AppError> {
-    // Try real adapter first
-    if let Ok(adapter) = conn_manager.get(&connection_id) {
-        let stats = adapter.get_stats()?;
-        return Ok(DatabaseStats {
-            connection_id: connection_id.clone(),
-            table_count: stats.table_count,
-            total_row_count: stats.total_row_count,
-            disk_usage_bytes: stats.disk_usage_bytes,
-            connected: true,
-        });
+impl MigrationStep {
+    fn safe(sql: String) -> Self {
+        Self { sql, warning: None }
+    }
+
+    fn destructive(sql: String, warning: String) -> Self {
+        Self {
+            sql,
+            warning: Some(warning),
+        }
     }
-    // Fall back to app DB metadata
-    db.get_database_stats(&connection_id)
 }
 
-#[tauri::command]
-pub fn import_csv(
-    csv_content: String,
-    table_name: String,
-    connection_id: Option<String>,
-    conn_manager: State<'_, ConnectionManager>,
-) -> Result<QueryResult, AppError> {
-    let mut lines = csv_content.lines();
-    let header_line = lines.next().ok_or_else(|| {
-        AppError::CsvParseError("CSV is empty, no header row found".into())
-    })?;
+fn column_definition(adapter: &dyn crate::adapter::DatabaseAdapter, column: &ColumnInfo) -> String {
+    let mut def = format!("{} {}", adapter.quote_ident(&column.name), column.data_type);
+    if !column.nullable {
+        def.push_str(" NOT NULL");
+    }
+    def
+}
 
-    let columns: Vec<String> = header_line
-        .split(',')
-        .map(|s| s.trim().trim_matches('"').to_string())
+/// The column definitions of a `CREATE TABLE` for `columns`, followed by
+/// the `PRIMARY KEY` clause when any column is part of the key.
+fn create_table_definitions(
+    adapter: &dyn crate::adapter::DatabaseAdapter,
+    columns: &[ColumnInfo],
+) -> Vec<String> {
+    let mut defs: Vec<String> = columns.iter().map(|c| column_definition(adapter, c)).collect();
+    let key: Vec<String> = columns
+        .iter()
+        .filter(|c| c.primary_key)
+        .map(|c| adapter.quote_ident(&c.name))
         .collect();
+    if !key.is_empty() {
+        defs.push(format!("PRIMARY KEY ({})", key.join(", ")));
+    }
+    defs
+}
 
-    if columns.is_empty() {
-        return Err(AppError::CsvParseError("No columns found in CSV header".into()));
+/// Statements that change the type or nullability of `table.column` to
+/// match `to`, in the dialect of `adapter`. `ColumnInfo` carries no
+/// defaults, so MySQL's `MODIFY COLUMN`, which restates the whole column,
+/// is always flagged.
+fn alter_column_steps(
+    adapter: &dyn crate::adapter::DatabaseAdapter,
+    table: &TableSchema,
+    from: &ColumnInfo,
+    to: &ColumnInfo,
+) -> Vec<MigrationStep> {
+    let table_name = adapter.quote_listed_table(table);
+    let column_name = adapter.quote_ident(&to.name);
+    let type_changed = !from.data_type.eq_ignore_ascii_case(&to.data_type);
+    if !type_changed && from.nullable == to.nullable {
+        return Vec::new();
     }
+    let type_warning = format!(
+        "Changing {}.{} from {} to {} may fail or truncate values in {} rows",
+        table.name, to.name, from.data_type, to.data_type, table.row_count
+    );
+    let not_null_warning = format!(
+        "Making {}.{} NOT NULL fails if any of its {} rows hold NULL",
+        table.name, to.name, table.row_count
+    );
+    let adds_not_null = from.nullable && !to.nullable;
 
-    let mut rows: Vec<Vec<serde_json::Value>> = Vec::new();
-    for line in lines {
-        let trimmed = line.trim();
-        if trimmed.is_empty() {
-            continue;
+    match adapter.kind() {
+        DatabaseKind::MySQL => {
+            let sql = format!(
+                "ALTER TABLE {} MODIFY COLUMN {}",
+                table_name,
+                column_definition(adapter, to)
+            );
+            let mut warnings = Vec::new();
+            if type_changed {
+                warnings.push(type_warning);
+            }
+            if adds_not_null {
+                warnings.push(not_null_warning);
+            }
+            warnings.push(format!(
+                "MODIFY COLUMN drops any DEFAULT, AUTO_INCREMENT or comment on {}.{}; \
+                 add them back to the statement before running it",
+                table.name, to.name
+            ));
+            vec![MigrationStep::destructive(sql, warnings.join(". "))]
         }
-        let values: Vec<serde_json::Value> = trimmed
-            .split(',')
-            .map(|s| {
-                let s = s.trim().trim_matches('"');
-                if let Ok(n) = s.parse::<i64>() {
-                    serde_json::Value::from(n)
-                } else if let Ok(f) = s.parse::<f64>() {
-                    serde_json::json!(f)
-                } else if s == "true" {
-                    serde_json::Value::Bool(true)
-                } else if s == "false" {
-                    serde_json::Value::Bool(false)
+        DatabaseKind::SQLite => vec![MigrationStep::destructive(
+            format!(
+                "-- SQLite cannot alter {}.{} in place; rebuild the table as {}",
+                table.name,
+                to.name,
+                column_definition(adapter, to)
+            ),
+            format!("{}.{} needs a manual table rebuild", table.name, to.name),
+        )],
+        _ => {
+            let mut steps = Vec::new();
+            if type_changed {
+                steps.push(MigrationStep::destructive(
+                    format!(
+                        "ALTER TABLE {} ALTER COLUMN {} TYPE {}",
+                        table_name, column_name, to.data_type
+                    ),
+                    type_warning,
+                ));
+            }
+            if from.nullable != to.nullable {
+                let change = if to.nullable { "DROP" } else { "SET" };
+                let sql = format!(
+                    "ALTER TABLE {} ALTER COLUMN {} {} NOT NULL",
+                    table_name, column_name, change
+                );
+                steps.push(if adds_not_null {
+                    MigrationStep::destructive(sql, not_null_warning)
                 } else {
-                    serde_json::Value::String(s.to_string())
-                }
-            })
-            .collect();
-        rows.push(values);
+                    MigrationStep::safe(sql)
+                });
+            }
+            steps
+        }
     }
+}
 
-    let row_count = rows.len();
+/// Statements that make `from` look like `to`, spelled for `adapter`'s
+/// dialect: new tables, then added, altered and dropped columns, then
+/// dropped tables. The changes come from `schema_diff`, so types are
+/// compared as text and schemas from different backends will report every
+/// column as changed.
+fn migration_steps(
+    adapter: &dyn crate::adapter::DatabaseAdapter,
+    from: &[TableSchema],
+    to: &[TableSchema],
+) -> Vec<MigrationStep> {
+    fn table<'s>(tables: &'s [TableSchema], name: &str) -> Option<&'s TableSchema> {
+        tables.iter().find(|t| t.name == name)
+    }
+    let diff = schema_diff(from, to, folds_identifier_case(adapter.kind()));
+    let mut steps = Vec::new();
+    let mut alters = Vec::new();
+    let mut column_drops = Vec::new();
+
+    for target in diff.tables_only_in_b.iter().filter_map(|name| table(to, name)) {
+        steps.push(MigrationStep::safe(format!(
+            "CREATE TABLE {} ({})",
+            adapter.quote_listed_table(target),
+            create_table_definitions(adapter, &target.columns).join(", ")
+        )));
+    }
+    for changes in &diff.changed_tables {
+        let Some(current) = table(from, &changes.table) else {
+            continue;
+        };
+        let table_name = adapter.quote_listed_table(current);
+        for column in &changes.added_columns {
+            steps.push(MigrationStep::safe(format!(
+                "ALTER TABLE {} ADD COLUMN {}",
+                table_name,
+                column_definition(adapter, column)
+            )));
+        }
+        for column in &changes.changed_columns {
+            alters.extend(alter_column_steps(adapter, current, &column.a, &column.b));
+        }
+        for column in &changes.removed_columns {
+            column_drops.push(MigrationStep::destructive(
+                format!(
+                    "ALTER TABLE {} DROP COLUMN {}",
+                    table_name,
+                    adapter.quote_ident(&column.name)
+                ),
+                format!(
+                    "Drops {}.{} and its values in {} rows",
+                    current.name, column.name, current.row_count
+                ),
+            ));
+        }
+    }
+    let table_drops = diff
+        .tables_only_in_a
+        .iter()
+        .filter_map(|name| table(from, name))
+        .map(|dropped| {
+            MigrationStep::destructive(
+                format!("DROP TABLE {}", adapter.quote_listed_table(dropped)),
+                format!("Drops {} and its {} rows", dropped.name, dropped.row_count),
+            )
+        });
 
-    // If a connection is active, create the table and insert rows
-    if let Some(ref conn_id) = connection_id {
-        if let Ok(adapter) = conn_manager.get(conn_id) {
-            let import_start = std::time::Instant::now();
-            let import_timeout = std::time::Duration::from_secs(120);
+    steps
+        .into_iter()
+        .chain(alters)
+        .chain(column_drops)
+        .chain(table_drops)
+        .collect()
+}
 
-            // Infer column types from first row
-            let col_defs: Vec<String> = columns
-                .iter()
-                .enumerate()
-                .map(|(i, name)| {
-                    let sample = rows
-                        .first()
-                        .and_then(|r| r.get(i))
-                        .unwrap_or(&serde_json::Value::Null);
-                    let type_str = match sample {
-                        serde_json::Value::Number(n) => {
-                            if n.is_f64() { "REAL" } else { "INTEGER" }
-                        }
-                        serde_json::Value::Bool(_) => "BOOLEAN",
-                        _ => "TEXT",
-                    };
-                    format!("\"{}\" {}", name, type_str)
-                })
-                .collect();
+/// The DDL that would make `from_connection`'s schema match
+/// `to_connection`'s. Nothing is executed; destructive steps carry a warning.
+#[tauri::command]
+pub fn generate_migration_sql(
+    from_connection: String,
+    to_connection: String,
+    conn_manager: State<'_, ConnectionManager>,
+) -> Result<Vec<MigrationStep>, AppError> {
+    let from_adapter = conn_manager.get(&from_connection)?;
+    let to_adapter = conn_manager.get(&to_connection)?;
+    let from = from_adapter.get_schema()?;
+    let to = to_adapter.get_schema()?;
+    Ok(migration_steps(from_adapter.as_ref(), &from, &to))
+}
 
-            let create_sql = format!(
-                "CREATE TABLE IF NOT EXISTS \"{}\" ({})",
-                table_name,
-                col_defs.join(", ")
-            );
-            adapter.execute_statement(&create_sql)?;
+// --- Schema diffing ---
 
-            // Insert rows in batches of 50 using multi-row VALUES
-            let col_list = columns
-                .iter()
-                .map(|c| format!("\"{}\"", c))
-                .collect::<Vec<_>>()
-                .join(", ");
-
-            const BATCH_SIZE: usize = 50;
-            for chunk in rows.chunks(BATCH_SIZE) {
-                if import_start.elapsed() > import_timeout {
-                    return Err(AppError::QueryError(
-                        "CSV import timed out after 120 seconds".to_string(),
-                    ));
-                }
+/// A column present in both schemas whose type, nullability or primary key
+/// membership differs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangedColumn {
+    pub name: String,
+    pub a: ColumnInfo,
+    pub b: ColumnInfo,
+}
 
-                let value_groups: Vec<String> = chunk
-                    .iter()
-                    .map(|row| {
-                        let placeholders: Vec<String> = row
-                            .iter()
-                            .map(|v| match v {
-                                serde_json::Value::Null => "NULL".to_string(),
-                                serde_json::Value::Number(n) => n.to_string(),
-                                serde_json::Value::Bool(b) => b.to_string(),
-                                serde_json::Value::String(s) => {
-                                    format!("'{}'", s.replace('\'', "''"))
-                                }
-                                _ => format!("'{}'", v),
-                            })
-                            .collect();
-                        format!("({})", placeholders.join(", "))
-                    })
-                    .collect();
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableDiff {
+    pub table: String,
+    /// Columns of the table in `b` with no counterpart in `a`.
+    pub added_columns: Vec<ColumnInfo>,
+    /// Columns of the table in `a` with no counterpart in `b`.
+    pub removed_columns: Vec<ColumnInfo>,
+    pub changed_columns: Vec<ChangedColumn>,
+}
 
-                let insert_sql = format!(
-                    "INSERT INTO \"{}\" ({}) VALUES {}",
-                    table_name,
-                    col_list,
-                    value_groups.join(", ")
-                );
-                adapter.execute_statement(&insert_sql)?;
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaDiff {
+    pub tables_only_in_a: Vec<String>,
+    pub tables_only_in_b: Vec<String>,
+    /// Tables in both schemas whose columns differ.
+    pub changed_tables: Vec<TableDiff>,
+    pub unchanged_count: usize,
+}
+
+/// Whether `kind` treats identifiers differing only in case as the same
+/// name. Postgres reports names exactly as created, so `"Users"` and
+/// `users` there are two tables.
+fn folds_identifier_case(kind: DatabaseKind) -> bool {
+    !matches!(kind, DatabaseKind::PostgreSQL | DatabaseKind::CockroachDB)
+}
+
+/// Compare two schemas table by table. Names match exactly, or ignoring
+/// case with `fold_case`; types are compared as text, so schemas from
+/// different backends will report most columns as changed.
+fn schema_diff(a: &[TableSchema], b: &[TableSchema], fold_case: bool) -> SchemaDiff {
+    let same = |x: &str, y: &str| if fold_case { x.eq_ignore_ascii_case(y) } else { x == y };
+    let find =
+        |tables: &[TableSchema], name: &str| tables.iter().position(|t| same(&t.name, name));
+    let find_column =
+        |table: &TableSchema, name: &str| table.columns.iter().position(|c| same(&c.name, name));
+
+    let mut diff = SchemaDiff {
+        tables_only_in_a: Vec::new(),
+        tables_only_in_b: Vec::new(),
+        changed_tables: Vec::new(),
+        unchanged_count: 0,
+    };
+    for table_a in a {
+        let Some(table_b) = find(b, &table_a.name).map(|i| &b[i]) else {
+            diff.tables_only_in_a.push(table_a.name.clone());
+            continue;
+        };
+        let mut table_diff = TableDiff {
+            table: table_a.name.clone(),
+            added_columns: Vec::new(),
+            removed_columns: Vec::new(),
+            changed_columns: Vec::new(),
+        };
+        for column_a in &table_a.columns {
+            match find_column(table_b, &column_a.name).map(|i| &table_b.columns[i]) {
+                None => table_diff.removed_columns.push(column_a.clone()),
+                Some(column_b)
+                    if !column_a.data_type.eq_ignore_ascii_case(&column_b.data_type)
+                        || column_a.nullable != column_b.nullable
+                        || column_a.primary_key != column_b.primary_key =>
+                {
+                    table_diff.changed_columns.push(ChangedColumn {
+                        name: column_a.name.clone(),
+                        a: column_a.clone(),
+                        b: column_b.clone(),
+                    });
+                }
+                Some(_) => {}
             }
         }
+        table_diff.added_columns = table_b
+            .columns
+            .iter()
+            .filter(|c| find_column(table_a, &c.name).is_none())
+            .cloned()
+            .collect();
+
+        if table_diff.added_columns.is_empty()
+            && table_diff.removed_columns.is_empty()
+            && table_diff.changed_columns.is_empty()
+        {
+            diff.unchanged_count += 1;
+        } else {
+            diff.changed_tables.push(table_diff);
+        }
     }
+    diff.tables_only_in_b = b
+        .iter()
+        .filter(|t| find(a, &t.name).is_none())
+        .map(|t| t.name.clone())
+        .collect();
+    diff
+}
 
-    Ok(QueryResult {
-        columns,
-        rows,
-        row_count,
-        execution_time_ms: 0,
-    })
+/// Structural differences between the schemas of two connections, e.g.
+/// staging and production: tables on only one side and, for shared tables,
+/// added, removed and changed columns.
+#[tauri::command]
+pub fn diff_schema(
+    connection_id_a: String,
+    connection_id_b: String,
+    conn_manager: State<'_, ConnectionManager>,
+) -> Result<SchemaDiff, AppError> {
+    let adapter_a = conn_manager.get(&connection_id_a)?;
+    let adapter_b = conn_manager.get(&connection_id_b)?;
+    for adapter in [&adapter_a, &adapter_b] {
+        if matches!(adapter.kind(), DatabaseKind::Redis | DatabaseKind::Elasticsearch) {
+            return Err(AppError::QueryError(format!(
+                "Schema diffing is not supported for {:?}",
+                adapter.kind()
+            )));
+        }
+    }
+    let fold_case =
+        folds_identifier_case(adapter_a.kind()) && folds_identifier_case(adapter_b.kind());
+    Ok(schema_diff(&adapter_a.get_schema()?, &adapter_b.get_schema()?, fold_case))
 }
 
 // --- Query Scanner ---
@@ -1023,87 +5074,262 @@ const SCANNABLE_EXTENSIONS: &[&str] = &[
     "rs", "ts", "tsx", "js", "jsx", "py", "go", "java", "rb",
 ];
 
-const SQL_KEYWORDS: &[&str] = &[
-    "SELECT", "INSERT", "UPDATE", "DELETE", "CREATE", "DROP", "ALTER",
+/// Directories skipped by default on top of ignore-file rules.
+const IGNORED_DIRS: &[&str] = &["node_modules", "target", "vendor", "dist", "build"];
+
+const DEFAULT_MAX_SCAN_RESULTS: usize = 500;
+
+/// What `scan_queries` walks and how much it reports.
+struct ScanOptions {
+    ignore_dirs: Vec<String>,
+    extensions: Vec<String>,
+    max_results: usize,
+}
+
+/// Objects a DDL keyword must name right away.
+const SCHEMA_OBJECTS: &[&str] = &[
+    "TABLE", "INDEX", "VIEW", "SCHEMA", "DATABASE", "SEQUENCE", "TRIGGER", "FUNCTION",
+    "PROCEDURE", "TYPE", "EXTENSION", "MATERIALIZED",
+];
+
+/// Statement keywords, the tokens one of which must follow them for the
+/// text to count as SQL rather than prose or an identifier, and how many
+/// words later that token may appear.
+const SQL_STATEMENTS: &[(&str, &[&str], usize)] = &[
+    ("SELECT", &["FROM"], usize::MAX),
+    ("INSERT", &["INTO"], 2),
+    ("UPDATE", &["SET"], usize::MAX),
+    ("DELETE", &["FROM"], 2),
+    ("CREATE", SCHEMA_OBJECTS, 4),
+    ("DROP", SCHEMA_OBJECTS, 2),
+    ("ALTER", SCHEMA_OBJECTS, 1),
 ];
 
-fn classify_query(line: &str) -> Option<&'static str> {
-    let upper = line.trim().to_uppercase();
-    SQL_KEYWORDS
+const LINE_COMMENT_MARKERS: &[&str] = &["//", "#", "--", "/*", "*"];
+
+/// The keyword opening a statement on `line` and its byte offset. The
+/// keyword must be a whole word at the start of the line or right after a
+/// quote or `(`, and the line must not be a comment.
+fn classify_query(line: &str) -> Option<(&'static str, usize)> {
+    let trimmed = line.trim_start();
+    if LINE_COMMENT_MARKERS.iter().any(|m| trimmed.starts_with(m)) {
+        return None;
+    }
+    let upper = line.to_ascii_uppercase();
+    let is_word = |b: u8| b.is_ascii_alphanumeric() || b == b'_';
+    SQL_STATEMENTS
         .iter()
-        .find(|&&keyword| upper.contains(keyword))
-        .copied()
+        .flat_map(|&(keyword, _, _)| upper.match_indices(keyword).map(move |(i, _)| (keyword, i)))
+        .filter(|&(keyword, start)| {
+            let end = start + keyword.len();
+            let before = upper[..start].trim_end();
+            !upper.as_bytes().get(end).copied().is_some_and(is_word)
+                && (before.is_empty() || before.ends_with(['"', '\'', '`', '(']))
+        })
+        .min_by_key(|&(_, start)| start)
 }
 
-fn has_scannable_extension(path: &Path) -> bool {
-    path.extension()
-        .and_then(|ext| ext.to_str())
-        .map(|ext| SCANNABLE_EXTENSIONS.contains(&ext))
-        .unwrap_or(false)
+/// Whether `statement`, which opens with `keyword`, has the shape of SQL:
+/// one of the keyword's expected tokens follows close enough.
+fn has_statement_shape(keyword: &str, statement: &str) -> bool {
+    let Some(&(_, followers, within)) = SQL_STATEMENTS.iter().find(|(k, _, _)| *k == keyword)
+    else {
+        return false;
+    };
+    statement
+        .to_ascii_uppercase()
+        .split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .filter(|word| !word.is_empty())
+        .skip(1)
+        .take(within)
+        .any(|word| followers.contains(&word))
 }
 
-fn walk_directory(dir: &Path, results: &mut Vec<ScanResult>, max_results: usize) -> Result<(), AppError> {
-    if results.len() >= max_results {
-        return Ok(());
-    }
+/// Most lines one statement may span before the scanner gives up on finding
+/// its end and reports the starting line alone.
+const MAX_STATEMENT_LINES: usize = 50;
 
-    let entries = fs::read_dir(dir).map_err(|e| {
-        AppError::ScanError(format!("Cannot read directory {}: {}", dir.display(), e))
-    })?;
+/// Longest snippet stored for one statement.
+const MAX_SNIPPET_CHARS: usize = 2000;
 
-    for entry in entries {
-        if results.len() >= max_results {
-            break;
-        }
+/// The string delimiter opened just before the statement starting at byte
+/// `start` of `line`, when the statement sits inside a string literal.
+fn opening_delimiter(line: &str, start: usize) -> Option<&'static str> {
+    let before = line[..start].trim_end();
+    ["\"\"\"", "'''", "`", "\"", "'"]
+        .into_iter()
+        .find(|d| before.ends_with(d))
+}
+
+/// The multi-line string delimiter that `previous` ends by opening, as in
+/// `query = """` or `db.Query(` followed by a backtick, for a statement
+/// starting on the next line.
+fn trailing_opener(previous: &str) -> Option<&'static str> {
+    let previous = previous.trim_end();
+    ["\"\"\"", "'''", "`"].into_iter().find(|d| {
+        previous
+            .strip_suffix(d)
+            .is_some_and(|before| before.trim_end().ends_with(['=', '(', ',']))
+    })
+}
+
+/// How many of `lines` the statement starting on the first one spans: up to
+/// the first `;`, or the line closing the string literal it opened in unless
+/// that line goes on to concatenate another string with `+`. The literal
+/// may open on the same line or, for a statement at the start of its line,
+/// at the end of `previous`.
+fn statement_span(lines: &[&str], previous: Option<&str>, keyword: &str, start: usize) -> usize {
+    let first = lines[0];
+    let opener = match opening_delimiter(first, start) {
+        Some(delimiter) => Some(delimiter),
+        None if first[..start].trim().is_empty() => previous.and_then(trailing_opener),
+        None => None,
+    };
+    let Some(delimiter) = opener else {
+        return 1;
+    };
+    let ends = |text: &str| {
+        text.contains(';') || (text.contains(delimiter) && !text.trim_end().ends_with('+'))
+    };
+    if ends(&first[start + keyword.len()..]) {
+        return 1;
+    }
+    lines
+        .iter()
+        .enumerate()
+        .take(MAX_STATEMENT_LINES)
+        .skip(1)
+        .find(|(_, line)| ends(line))
+        .map_or(1, |(i, _)| i + 1)
+}
+
+/// The statement's lines, trimmed and capped at `MAX_SNIPPET_CHARS`.
+fn statement_snippet(lines: &[&str]) -> String {
+    let snippet = lines.iter().map(|l| l.trim()).collect::<Vec<_>>().join("\n");
+    match snippet.char_indices().nth(MAX_SNIPPET_CHARS) {
+        Some((cut, _)) => format!("{}...", &snippet[..cut]),
+        None => snippet,
+    }
+}
 
-        let entry = match entry {
-            Ok(e) => e,
-            Err(_) => continue,
+/// Every statement found in one file's `content`, at most `max_results`.
+fn scan_file(path: &Path, content: &str, max_results: usize) -> Vec<ScanResult> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut results = Vec::new();
+    let mut i = 0;
+    while i < lines.len() && results.len() < max_results {
+        let Some((query_type, start)) = classify_query(lines[i]) else {
+            i += 1;
+            continue;
         };
+        let previous = i.checked_sub(1).map(|p| lines[p]);
+        let span = statement_span(&lines[i..], previous, query_type, start);
+        let statement = std::iter::once(&lines[i][start..])
+            .chain(lines[i + 1..i + span].iter().copied())
+            .collect::<Vec<_>>()
+            .join("\n");
+        if !has_statement_shape(query_type, &statement) {
+            i += 1;
+            continue;
+        }
+        results.push(ScanResult {
+            file_path: path.display().to_string(),
+            line_number: i + 1,
+            query_snippet: statement_snippet(&lines[i..i + span]),
+            query_type: query_type.to_string(),
+        });
+        i += span;
+    }
+    results
+}
 
-        let path = entry.path();
+fn has_scannable_extension(path: &Path, extensions: &[String]) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| extensions.iter().any(|e| e == ext))
+        .unwrap_or(false)
+}
 
-        if path.is_dir() {
-            let dir_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
-            if dir_name.starts_with('.') || dir_name == "node_modules" || dir_name == "target" || dir_name == "vendor" || dir_name == "dist" || dir_name == "build" {
-                continue;
+/// Walk `dir` across threads, honoring `.gitignore` and `.ignore` rules and
+/// skipping hidden entries, and scan every file with one of the configured
+/// extensions. Results come back sorted by file and line; once
+/// `max_results` is reached the walk stops, so which files made it in then
+/// depends on the order threads reached them.
+fn walk_directory(dir: &Path, options: &ScanOptions) -> Vec<ScanResult> {
+    let ignore_dirs = options.ignore_dirs.clone();
+    let walker = ignore::WalkBuilder::new(dir)
+        .require_git(false)
+        .filter_entry(move |entry| {
+            let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+            let ignored = entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| ignore_dirs.iter().any(|d| d == name));
+            entry.depth() == 0 || !(is_dir && ignored)
+        })
+        .build_parallel();
+
+    let found = std::sync::Mutex::new(Vec::new());
+    // Result slots handed out so far; never exceeds `max_results`.
+    let claimed = std::sync::atomic::AtomicUsize::new(0);
+    let (found_ref, claimed_ref) = (&found, &claimed);
+    let max_results = options.max_results;
+    walker.run(|| {
+        Box::new(move |entry| {
+            use std::sync::atomic::Ordering;
+            if claimed_ref.load(Ordering::Relaxed) >= max_results {
+                return ignore::WalkState::Quit;
             }
-            walk_directory(&path, results, max_results)?;
-        } else if has_scannable_extension(&path) {
-            let content = match fs::read_to_string(&path) {
-                Ok(c) => c,
-                Err(_) => continue,
+            let Ok(entry) = entry else {
+                return ignore::WalkState::Continue;
             };
-
-            for (line_idx, line) in content.lines().enumerate() {
-                if results.len() >= max_results {
-                    break;
-                }
-
-                if let Some(query_type) = classify_query(line) {
-                    let snippet = line.trim().to_string();
-                    let truncated = if snippet.len() > 200 {
-                        format!("{}...", &snippet[..200])
-                    } else {
-                        snippet
-                    };
-
-                    results.push(ScanResult {
-                        file_path: path.display().to_string(),
-                        line_number: line_idx + 1,
-                        query_snippet: truncated,
-                        query_type: query_type.to_string(),
-                    });
-                }
+            let path = entry.path();
+            if !entry.file_type().is_some_and(|t| t.is_file())
+                || !has_scannable_extension(path, &options.extensions)
+            {
+                return ignore::WalkState::Continue;
             }
-        }
-    }
+            let Ok(content) = fs::read_to_string(path) else {
+                return ignore::WalkState::Continue;
+            };
+            let mut file_results = scan_file(path, &content, max_results);
+            if file_results.is_empty() {
+                return ignore::WalkState::Continue;
+            }
+            let wanted = file_results.len();
+            let Ok(before) = claimed_ref.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| {
+                (n < max_results).then(|| (n + wanted).min(max_results))
+            }) else {
+                return ignore::WalkState::Quit;
+            };
+            file_results.truncate(max_results - before);
+            found_ref
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .extend(file_results);
+            ignore::WalkState::Continue
+        })
+    });
 
-    Ok(())
+    let mut results = found.into_inner().unwrap_or_else(|e| e.into_inner());
+    results.sort_by(|a, b| {
+        a.file_path
+            .cmp(&b.file_path)
+            .then(a.line_number.cmp(&b.line_number))
+    });
+    results
 }
 
+/// Scan a directory for embedded SQL. `ignore_dirs` and `extensions`
+/// replace the built-in directory skip list and file extensions; results
+/// stop at `max_results`, 500 by default.
 #[tauri::command]
-pub fn scan_queries(directory_path: String) -> Result<Vec<ScanResult>, AppError> {
+pub fn scan_queries(
+    directory_path: String,
+    ignore_dirs: Option<Vec<String>>,
+    extensions: Option<Vec<String>>,
+    max_results: Option<usize>,
+) -> Result<Vec<ScanResult>, AppError> {
     let path = Path::new(&directory_path);
     if !path.exists() {
         return Err(AppError::ScanError(format!(
@@ -1118,10 +5344,16 @@ pub fn scan_queries(directory_path: String) -> Result<Vec<ScanResult>, AppError>
         )));
     }
 
-    let mut results = Vec::new();
-    let max_results = 500;
-    walk_directory(path, &mut results, max_results)?;
-    Ok(results)
+    let defaults =
+        |list: &[&str]| -> Vec<String> { list.iter().map(|s| s.to_string()).collect() };
+    let options = ScanOptions {
+        ignore_dirs: ignore_dirs.unwrap_or_else(|| defaults(IGNORED_DIRS)),
+        extensions: extensions
+            .map(|exts| exts.iter().map(|e| e.trim_start_matches('.').to_string()).collect())
+            .unwrap_or_else(|| defaults(SCANNABLE_EXTENSIONS)),
+        max_results: max_results.unwrap_or(DEFAULT_MAX_SCAN_RESULTS),
+    };
+    Ok(walk_directory(path, &options))
 }
 
 // --- Table Links ---
@@ -1149,6 +5381,102 @@ pub fn remove_table_link(id: String, db: State<'_, Database>) -> Result<(), AppE
     db.remove_table_link(&id)
 }
 
+/// Where a relationship edge comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RelationshipSource {
+    ForeignKey,
+    Link,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelationshipNode {
+    pub table: String,
+    pub columns: Vec<ColumnInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelationshipEdge {
+    pub from_table: String,
+    pub from_column: String,
+    pub to_table: String,
+    pub to_column: String,
+    pub source: RelationshipSource,
+    /// The constraint name for a foreign key, the label for a link.
+    pub label: String,
+    /// The table link describing this edge, if any; set on a foreign key
+    /// too when the user also linked the same columns by hand.
+    pub link_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelationshipGraph {
+    pub nodes: Vec<RelationshipNode>,
+    pub edges: Vec<RelationshipEdge>,
+}
+
+/// Every table on `connection_id` with the relationships between them, for
+/// the ER view: declared foreign keys plus the user's table links. A link
+/// between the same columns as a foreign key is folded into it.
+#[tauri::command]
+pub fn get_relationship_graph(
+    connection_id: String,
+    db: State<'_, Database>,
+    conn_manager: State<'_, ConnectionManager>,
+) -> Result<RelationshipGraph, AppError> {
+    let adapter = conn_manager.get(&connection_id)?;
+    let tables = adapter.list_table_names()?;
+    let mut metadata = adapter.get_tables_metadata(&tables)?;
+
+    let mut nodes = Vec::new();
+    let mut edges = Vec::new();
+    for table in tables {
+        let Some(meta) = metadata.remove(&table) else {
+            continue;
+        };
+        edges.extend(meta.foreign_keys.into_iter().map(|fk| RelationshipEdge {
+            from_table: table.clone(),
+            from_column: fk.from_column,
+            to_table: fk.to_table,
+            to_column: fk.to_column,
+            source: RelationshipSource::ForeignKey,
+            label: fk.name,
+            link_id: None,
+        }));
+        nodes.push(RelationshipNode {
+            table,
+            columns: meta.schema.columns,
+        });
+    }
+
+    for link in db.list_table_links()? {
+        if link.connection_id != connection_id {
+            continue;
+        }
+        let duplicate = edges.iter_mut().find(|e| {
+            e.source == RelationshipSource::ForeignKey
+                && e.from_table == link.source_table
+                && e.from_column == link.source_column
+                && e.to_table == link.target_table
+                && e.to_column == link.target_column
+        });
+        match duplicate {
+            Some(edge) => edge.link_id = Some(link.id),
+            None => edges.push(RelationshipEdge {
+                from_table: link.source_table,
+                from_column: link.source_column,
+                to_table: link.target_table,
+                to_column: link.target_column,
+                source: RelationshipSource::Link,
+                label: link.label,
+                link_id: Some(link.id),
+            }),
+        }
+    }
+
+    Ok(RelationshipGraph { nodes, edges })
+}
+
 // --- Chat Completion ---
 // This is the local tool-dispatch engine. It examines the last user message
 // and decides which Glove tool to invoke. In a production setup this would
@@ -1164,8 +5492,93 @@ fn quick_schema_lookup(adapter: &std::sync::Arc<dyn crate::adapter::DatabaseAdap
     rx.recv_timeout(std::time::Duration::from_secs(5)).ok().flatten()
 }
 
-fn contains_any(text: &str, keywords: &[&str]) -> bool {
-    keywords.iter().any(|kw| text.contains(kw))
+fn contains_any(text: &str, keywords: &[&str]) -> bool {
+    keywords.iter().any(|kw| text.contains(kw))
+}
+
+/// A plain reply with no tool call.
+fn text_response(content: &str) -> ChatCompletionResponse {
+    ChatCompletionResponse {
+        content: Some(content.to_string()),
+        tool_calls: None,
+        finish_reason: "stop".to_string(),
+        requires_approval: false,
+    }
+}
+
+/// Whether a statement modifies schema or deletes data and so needs the
+/// user's confirmation before it runs.
+fn is_destructive_sql(sql: &str) -> bool {
+    let upper = sql.trim_start().to_uppercase();
+    ["DELETE", "DROP", "TRUNCATE", "ALTER"]
+        .iter()
+        .any(|kw| upper.starts_with(kw))
+}
+
+/// `text` as a destructive statement, if it parses as exactly one.
+/// Requests such as "Delete user 5" start with the same keyword but are
+/// not SQL.
+fn destructive_statement(kind: Option<DatabaseKind>, text: &str) -> Option<String> {
+    let sql = text.trim();
+    let dialect = kind.map_or_else(
+        || Box::new(sqlparser::dialect::GenericDialect {}) as Box<dyn sqlparser::dialect::Dialect>,
+        crate::lint::parser_dialect,
+    );
+    let statements = sqlparser::parser::Parser::parse_sql(dialect.as_ref(), sql).ok()?;
+    (statements.len() == 1 && is_destructive_sql(sql)).then(|| sql.to_string())
+}
+
+/// SQL of the most recent `execute_query` tool call in the conversation.
+fn recent_query_sql(messages: &[ChatMessage]) -> Option<String> {
+    messages.iter().rev().find_map(|m| {
+        m.tool_calls.as_ref()?.iter().rev().find_map(|call| {
+            (call.function.name == "execute_query")
+                .then(|| sql_from_tool_arguments(&call.function.arguments))
+                .flatten()
+        })
+    })
+}
+
+/// The most recent query result returned to the conversation by a tool.
+fn recent_query_result(messages: &[ChatMessage]) -> Option<QueryResult> {
+    messages
+        .iter()
+        .rev()
+        .filter(|m| m.role == "tool")
+        .find_map(|m| serde_json::from_str::<QueryResult>(m.content.as_deref()?).ok())
+}
+
+/// A bar chart of `result`: the first column labels the bars and the first
+/// other column holding only numbers gives their height.
+fn chart_from_result(result: &QueryResult) -> Option<serde_json::Value> {
+    let x_key = result.columns.first()?;
+    let y_index = (1..result.columns.len()).find(|&i| {
+        result
+            .rows
+            .iter()
+            .filter_map(|row| row.get(i))
+            .all(|v| v.is_number() || v.is_null())
+    })?;
+    let data: Vec<serde_json::Value> = result
+        .rows
+        .iter()
+        .map(|row| {
+            let point: serde_json::Map<String, serde_json::Value> = result
+                .columns
+                .iter()
+                .cloned()
+                .zip(row.iter().cloned())
+                .collect();
+            serde_json::Value::Object(point)
+        })
+        .collect();
+    Some(serde_json::json!({
+        "chartType": "bar",
+        "title": "Query Results",
+        "data": data,
+        "xKey": x_key,
+        "yKey": result.columns[y_index]
+    }))
 }
 
 fn tool_call_response(name: &str, args: &str) -> ChatCompletionResponse {
@@ -1180,19 +5593,27 @@ fn tool_call_response(name: &str, args: &str) -> ChatCompletionResponse {
             },
         }]),
         finish_reason: "tool_calls".to_string(),
+        requires_approval: false,
     }
 }
 
-/// Extract the active connection ID from conversation context.
-/// Looks for the most recently mentioned connectionId in tool results,
-/// falling back to "conn-1".
-fn extract_connection_id(messages: &[ChatMessage]) -> String {
+/// Work out which connection the conversation is about: the most recently
+/// mentioned connectionId in tool calls and results, then the exploration's
+/// selected connection, then the `default_connection_id` setting if that
+/// connection is active, then the only active connection if exactly one is
+/// open. `None` means the user has to pick one.
+fn extract_connection_id(
+    messages: &[ChatMessage],
+    selected: Option<&str>,
+    default_id: Option<&str>,
+    active_ids: &[String],
+) -> Option<String> {
     for msg in messages.iter().rev() {
         if let Some(ref content) = msg.content {
             // Check for connectionId in tool result JSON
             if let Ok(v) = serde_json::from_str::<serde_json::Value>(content) {
                 if let Some(id) = v.get("connectionId").and_then(|v| v.as_str()) {
-                    return id.to_string();
+                    return Some(id.to_string());
                 }
             }
         }
@@ -1201,13 +5622,39 @@ fn extract_connection_id(messages: &[ChatMessage]) -> String {
             for call in calls {
                 if let Ok(v) = serde_json::from_str::<serde_json::Value>(&call.function.arguments) {
                     if let Some(id) = v.get("connectionId").and_then(|v| v.as_str()) {
-                        return id.to_string();
+                        return Some(id.to_string());
                     }
                 }
             }
         }
     }
-    "conn-1".to_string()
+    if let Some(id) = selected {
+        return Some(id.to_string());
+    }
+    if let Some(id) = default_id.filter(|id| active_ids.iter().any(|a| a == id)) {
+        return Some(id.to_string());
+    }
+    match active_ids {
+        [only] => Some(only.clone()),
+        _ => None,
+    }
+}
+
+/// Asks the user to choose a connection when none can be inferred.
+fn select_connection_response(active_ids: &[String]) -> ChatCompletionResponse {
+    let reason = if active_ids.is_empty() {
+        "No connection selected and none are connected. Connect a database to continue."
+    } else {
+        "No connection selected. Pick which connection to use."
+    };
+    tool_call_response(
+        "select_connection",
+        &serde_json::json!({
+            "reason": reason,
+            "activeConnections": active_ids
+        })
+        .to_string(),
+    )
 }
 
 /// Try to extract a SQL query from user text. If the message looks like
@@ -1262,11 +5709,406 @@ fn parse_saved_query_slash(text: &str) -> Option<(String, serde_json::Value)> {
     Some((query_ref.to_string(), serde_json::Value::Object(params)))
 }
 
+/// Check a tool's argument schema: an object schema whose `properties`, if
+/// any, is an object and whose `required` names only declared properties.
+fn validate_tool_parameters(parameters: &serde_json::Value) -> Result<(), String> {
+    let schema = parameters
+        .as_object()
+        .ok_or("parameters must be a JSON Schema object")?;
+    if schema.get("type").and_then(|t| t.as_str()) != Some("object") {
+        return Err("parameters must have \"type\": \"object\"".to_string());
+    }
+    let properties = match schema.get("properties") {
+        None => None,
+        Some(serde_json::Value::Object(props)) => Some(props),
+        Some(_) => return Err("parameters.properties must be an object".to_string()),
+    };
+    if let Some((name, _)) = properties
+        .into_iter()
+        .flatten()
+        .find(|(_, prop)| !prop.is_object())
+    {
+        return Err(format!(
+            "parameters.properties.{name} must be a schema object"
+        ));
+    }
+    match schema.get("required") {
+        None => Ok(()),
+        Some(serde_json::Value::Array(required)) => {
+            for field in required {
+                let field = field
+                    .as_str()
+                    .ok_or("parameters.required must list property names")?;
+                if !properties.is_some_and(|p| p.contains_key(field)) {
+                    return Err(format!(
+                        "parameters.required names {field}, which is not in parameters.properties"
+                    ));
+                }
+            }
+            Ok(())
+        }
+        Some(_) => Err("parameters.required must be an array".to_string()),
+    }
+}
+
+/// Parse and validate the request's tool list, naming the offending entry
+/// on failure rather than letting a bad spec be silently ignored.
+fn parse_tool_specs(tools: &[serde_json::Value]) -> Result<Vec<ToolSpec>, AppError> {
+    let invalid = |i: usize, reason: String| {
+        AppError::QueryError(format!("Invalid tool at index {i}: {reason}"))
+    };
+    let mut specs: Vec<ToolSpec> = Vec::with_capacity(tools.len());
+    for (i, raw) in tools.iter().enumerate() {
+        let spec: ToolSpec =
+            serde_json::from_value(raw.clone()).map_err(|e| invalid(i, e.to_string()))?;
+        if spec.tool_type != "function" {
+            return Err(invalid(
+                i,
+                format!(
+                    "unsupported type {:?}, expected \"function\"",
+                    spec.tool_type
+                ),
+            ));
+        }
+        let name = &spec.function.name;
+        let valid_name = !name.is_empty()
+            && name.len() <= 64
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        if !valid_name {
+            return Err(invalid(
+                i,
+                format!("name {name:?} must be 1-64 letters, digits, '_' or '-'"),
+            ));
+        }
+        if specs.iter().any(|s| s.function.name == *name) {
+            return Err(invalid(i, format!("duplicate tool name {name}")));
+        }
+        if let Some(parameters) = &spec.function.parameters {
+            validate_tool_parameters(parameters).map_err(|reason| invalid(i, reason))?;
+        }
+        specs.push(spec);
+    }
+    Ok(specs)
+}
+
+/// Tool calls that run SQL against a connection. In plan-only mode these are
+/// returned for review instead of being auto-executed by the frontend.
+const EXECUTING_TOOLS: &[&str] = &["execute_query", "execute_saved_query"];
+
+/// Setting naming the connection chat falls back to when the conversation
+/// and exploration don't pick one.
+const DEFAULT_CONNECTION_SETTING: &str = "default_connection_id";
+
 #[tauri::command]
 pub fn chat_completion(
     request: ChatCompletionRequest,
+    plan_only: Option<bool>,
+    db: State<'_, Database>,
+    conn_manager: State<'_, ConnectionManager>,
+) -> Result<ChatCompletionResponse, AppError> {
+    let tools = request.tools.as_deref().map(parse_tool_specs).transpose()?;
+    let default_id = db.get_setting(DEFAULT_CONNECTION_SETTING)?;
+    let mut response =
+        dispatch_chat(&request, tools.as_deref(), default_id.as_deref(), &conn_manager)?;
+
+    if plan_only.unwrap_or(false) {
+        hold_sql_for_approval(&mut response);
+    }
+
+    Ok(response)
+}
+
+/// Mark a response whose tool calls would run SQL as needing approval.
+fn hold_sql_for_approval(response: &mut ChatCompletionResponse) {
+    let executes_sql = response
+        .tool_calls
+        .as_ref()
+        .map(|calls| {
+            calls
+                .iter()
+                .any(|c| EXECUTING_TOOLS.contains(&c.function.name.as_str()))
+        })
+        .unwrap_or(false);
+    if executes_sql {
+        response.requires_approval = true;
+        response.finish_reason = "requires_approval".to_string();
+    }
+}
+
+/// Settings naming the OpenAI-compatible endpoint used for streamed chat.
+/// Without an API key the local keyword dispatcher answers instead.
+const LLM_BASE_URL_SETTING: &str = "llm_base_url";
+const LLM_MODEL_SETTING: &str = "llm_model";
+const DEFAULT_LLM_BASE_URL: &str = "https://api.openai.com/v1";
+const DEFAULT_LLM_MODEL: &str = "gpt-4o-mini";
+/// Limits on the streamed chat request. The read timeout applies between
+/// chunks, so a long answer that keeps streaming is never cut off.
+const LLM_CONNECT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+const LLM_READ_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Streamed chat completions in flight, keyed by stream ID.
+#[derive(Default)]
+pub struct ChatStreams(
+    std::sync::Mutex<std::collections::HashMap<String, tauri::async_runtime::JoinHandle<()>>>,
+);
+
+/// Payload of `chat-token`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatTokenEvent {
+    pub stream_id: String,
+    pub token: String,
+}
+
+/// Payload of `chat-done`. `response` is absent when the stream failed or
+/// was cancelled.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatDoneEvent {
+    pub stream_id: String,
+    pub response: Option<ChatCompletionResponse>,
+    pub cancelled: bool,
+    pub error: Option<String>,
+}
+
+/// Start a streamed chat completion. Content deltas arrive as `chat-token`
+/// events and the assembled response as a final `chat-done`; the command
+/// itself returns as soon as the request is under way.
+#[tauri::command]
+pub fn chat_completion_stream(
+    request: ChatCompletionRequest,
+    stream_id: String,
+    plan_only: Option<bool>,
+    app_handle: AppHandle,
+    db: State<'_, Database>,
     conn_manager: State<'_, ConnectionManager>,
+    streams: State<'_, ChatStreams>,
+) -> Result<(), AppError> {
+    let tools = request.tools.as_deref().map(parse_tool_specs).transpose()?;
+    let plan_only = plan_only.unwrap_or(false);
+
+    let Some(api_key) = db.get_secret_setting(LLM_API_KEY_SETTING)? else {
+        let default_id = db.get_setting(DEFAULT_CONNECTION_SETTING)?;
+        let mut response =
+            dispatch_chat(&request, tools.as_deref(), default_id.as_deref(), &conn_manager)?;
+        if plan_only {
+            hold_sql_for_approval(&mut response);
+        }
+        if let Some(token) = response.content.clone() {
+            let _ = app_handle.emit(
+                "chat-token",
+                ChatTokenEvent { stream_id: stream_id.clone(), token },
+            );
+        }
+        let _ = app_handle.emit(
+            "chat-done",
+            ChatDoneEvent { stream_id, response: Some(response), cancelled: false, error: None },
+        );
+        return Ok(());
+    };
+    let base_url = db
+        .get_setting(LLM_BASE_URL_SETTING)?
+        .filter(|u| !u.is_empty())
+        .unwrap_or_else(|| DEFAULT_LLM_BASE_URL.to_string());
+    let model = db
+        .get_setting(LLM_MODEL_SETTING)?
+        .filter(|m| !m.is_empty())
+        .unwrap_or_else(|| DEFAULT_LLM_MODEL.to_string());
+
+    let mut body = serde_json::json!({
+        "model": model,
+        "messages": request.messages,
+        "stream": true,
+    });
+    if let Some(tools) = &request.tools {
+        body["tools"] = serde_json::Value::Array(tools.clone());
+    }
+
+    let mut active = streams
+        .0
+        .lock()
+        .map_err(|_| AppError::QueryError("Chat stream registry lock poisoned".to_string()))?;
+    if active.contains_key(&stream_id) {
+        return Err(AppError::QueryError(format!("Chat stream {stream_id} already running")));
+    }
+    // The registry lock is held until the handle is stored, so the task's
+    // own removal below cannot run first.
+    let id = stream_id.clone();
+    let task = tauri::async_runtime::spawn(async move {
+        let result = stream_llm_chat(&base_url, &api_key, &body, |token| {
+            let _ = app_handle.emit(
+                "chat-token",
+                ChatTokenEvent { stream_id: id.clone(), token: token.to_string() },
+            );
+        })
+        .await;
+        // Whoever takes the stream out of the registry sends `chat-done`; if
+        // `cancel_chat` got there first it has already reported the stream.
+        let still_registered = app_handle
+            .state::<ChatStreams>()
+            .0
+            .lock()
+            .is_ok_and(|mut active| active.remove(&id).is_some());
+        if !still_registered {
+            return;
+        }
+        let (response, error) = match result {
+            Ok(mut response) => {
+                if plan_only {
+                    hold_sql_for_approval(&mut response);
+                }
+                (Some(response), None)
+            }
+            Err(e) => (None, Some(e.to_string())),
+        };
+        let _ = app_handle.emit(
+            "chat-done",
+            ChatDoneEvent { stream_id: id, response, cancelled: false, error },
+        );
+    });
+    active.insert(stream_id, task);
+    Ok(())
+}
+
+/// Abort a streamed chat completion, dropping its HTTP response.
+#[tauri::command]
+pub fn cancel_chat(
+    stream_id: String,
+    app_handle: AppHandle,
+    streams: State<'_, ChatStreams>,
+) -> Result<(), AppError> {
+    let task = streams
+        .0
+        .lock()
+        .map_err(|_| AppError::QueryError("Chat stream registry lock poisoned".to_string()))?
+        .remove(&stream_id)
+        .ok_or_else(|| AppError::NotFound(format!("Chat stream {stream_id} not running")))?;
+    task.abort();
+    let _ = app_handle.emit(
+        "chat-done",
+        ChatDoneEvent { stream_id, response: None, cancelled: true, error: None },
+    );
+    Ok(())
+}
+
+/// Add one streamed tool call fragment to `tool_calls`. A fragment either
+/// continues a call already started or starts the next one; any other index
+/// comes from a malformed stream and is dropped, so the server can't make
+/// us allocate an arbitrarily long list.
+fn merge_tool_call_delta(tool_calls: &mut Vec<ToolCallInfo>, call: &serde_json::Value) {
+    let index = call["index"].as_u64().unwrap_or(0);
+    let Some(index) = usize::try_from(index).ok().filter(|&i| i <= tool_calls.len()) else {
+        return;
+    };
+    if index == tool_calls.len() {
+        tool_calls.push(ToolCallInfo {
+            id: String::new(),
+            call_type: "function".to_string(),
+            function: FunctionCallInfo {
+                name: String::new(),
+                arguments: String::new(),
+            },
+        });
+    }
+    let entry = &mut tool_calls[index];
+    if let Some(id) = call["id"].as_str() {
+        entry.id = id.to_string();
+    }
+    if let Some(name) = call["function"]["name"].as_str() {
+        entry.function.name.push_str(name);
+    }
+    if let Some(arguments) = call["function"]["arguments"].as_str() {
+        entry.function.arguments.push_str(arguments);
+    }
+}
+
+/// POST a streaming chat completion and decode its server-sent events,
+/// passing each content delta to `on_token`. Tool call fragments are
+/// stitched together by index into the final response.
+async fn stream_llm_chat(
+    base_url: &str,
+    api_key: &str,
+    body: &serde_json::Value,
+    mut on_token: impl FnMut(&str),
+) -> Result<ChatCompletionResponse, AppError> {
+    use futures_util::StreamExt;
+
+    let request_error =
+        |e: reqwest::Error| AppError::QueryError(format!("Chat request failed: {e}"));
+    let client = reqwest::Client::builder()
+        .connect_timeout(LLM_CONNECT_TIMEOUT)
+        .read_timeout(LLM_READ_TIMEOUT)
+        .build()
+        .map_err(request_error)?;
+    let response = client
+        .post(format!("{}/chat/completions", base_url.trim_end_matches('/')))
+        .bearer_auth(api_key)
+        .json(body)
+        .send()
+        .await
+        .map_err(request_error)?;
+    let status = response.status();
+    if !status.is_success() {
+        let text = response.text().await.unwrap_or_default();
+        return Err(AppError::QueryError(format!("Chat request failed ({status}): {text}")));
+    }
+
+    let mut content = String::new();
+    let mut tool_calls: Vec<ToolCallInfo> = Vec::new();
+    let mut finish_reason = "stop".to_string();
+    // Raw bytes, so a multi-byte character split across chunks survives.
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut chunks = response.bytes_stream();
+    'stream: while let Some(chunk) = chunks.next().await {
+        buffer.extend_from_slice(&chunk.map_err(request_error)?);
+        while let Some(end) = buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let Some(data) = line.trim().strip_prefix("data:").map(str::trim) else {
+                continue;
+            };
+            if data == "[DONE]" {
+                break 'stream;
+            }
+            let Ok(event) = serde_json::from_str::<serde_json::Value>(data) else {
+                continue;
+            };
+            let Some(choice) = event["choices"].get(0) else {
+                continue;
+            };
+            if let Some(reason) = choice["finish_reason"].as_str() {
+                finish_reason = reason.to_string();
+            }
+            let delta = &choice["delta"];
+            if let Some(token) = delta["content"].as_str() {
+                content.push_str(token);
+                on_token(token);
+            }
+            for call in delta["tool_calls"].as_array().into_iter().flatten() {
+                merge_tool_call_delta(&mut tool_calls, call);
+            }
+        }
+    }
+
+    Ok(ChatCompletionResponse {
+        content: (!content.is_empty()).then_some(content),
+        tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
+        finish_reason,
+        requires_approval: false,
+    })
+}
+
+/// Route the conversation to a tool call by keyword. When the request
+/// declares tools, only those are ever called; with no tool list every
+/// built-in tool is available.
+fn dispatch_chat(
+    request: &ChatCompletionRequest,
+    tools: Option<&[ToolSpec]>,
+    default_connection_id: Option<&str>,
+    conn_manager: &ConnectionManager,
 ) -> Result<ChatCompletionResponse, AppError> {
+    let offers = |tool: &str| tools.is_none_or(|t| t.iter().any(|s| s.function.name == tool));
+
     let last_user_raw = request
         .messages
         .iter()
@@ -1276,7 +6118,9 @@ pub fn chat_completion(
         .map(|s| s.to_string())
         .unwrap_or_default();
 
-    if let Some((query_ref, params)) = parse_saved_query_slash(&last_user_raw) {
+    if let Some((query_ref, params)) =
+        parse_saved_query_slash(&last_user_raw).filter(|_| offers("execute_saved_query"))
+    {
         return Ok(tool_call_response(
             "execute_saved_query",
             &serde_json::json!({
@@ -1296,16 +6140,18 @@ pub fn chat_completion(
         .map(|s| s.to_lowercase())
         .unwrap_or_default();
 
-    let conn_id = extract_connection_id(&request.messages);
+    let active_ids = conn_manager.active_ids();
+    let conn_id = extract_connection_id(
+        &request.messages,
+        request.connection_id.as_deref(),
+        default_connection_id,
+        &active_ids,
+    );
+    let adapter = conn_id.as_deref().and_then(|id| conn_manager.get(id).ok());
 
     // Check if the user typed raw SQL
-    if let Some(sql) = extract_sql(&last_user_msg) {
-        let upper = sql.trim().to_uppercase();
-        if upper.starts_with("DELETE")
-            || upper.starts_with("DROP")
-            || upper.starts_with("TRUNCATE")
-            || upper.starts_with("ALTER")
-        {
+    if let Some(sql) = extract_sql(&last_user_msg).filter(|_| offers("execute_query")) {
+        if is_destructive_sql(&sql) {
             return Ok(tool_call_response(
                 "confirm_action",
                 &serde_json::json!({
@@ -1315,6 +6161,9 @@ pub fn chat_completion(
                 .to_string(),
             ));
         }
+        let Some(conn_id) = &conn_id else {
+            return Ok(select_connection_response(&active_ids));
+        };
         return Ok(tool_call_response(
             "execute_query",
             &serde_json::json!({
@@ -1326,7 +6175,10 @@ pub fn chat_completion(
     }
 
     // Schema / structure requests
-    if contains_any(&last_user_msg, &["schema", "tables", "structure", "columns", "describe"]) {
+    if offers("get_schema") && contains_any(&last_user_msg, &["schema", "tables", "structure", "columns", "describe"]) {
+        let Some(conn_id) = &conn_id else {
+            return Ok(select_connection_response(&active_ids));
+        };
         return Ok(tool_call_response(
             "get_schema",
             &serde_json::json!({ "connectionId": conn_id }).to_string(),
@@ -1334,18 +6186,19 @@ pub fn chat_completion(
     }
 
     // Query requests — try to generate SQL from intent
-    if contains_any(&last_user_msg, &["query", "show me", "find", "count", "how many", "list all", "get all"]) {
+    if offers("execute_query") && contains_any(&last_user_msg, &["query", "show me", "find", "count", "how many", "list all", "get all"]) {
+        let Some(conn_id) = &conn_id else {
+            return Ok(select_connection_response(&active_ids));
+        };
         // Attempt to infer which table the user wants
-        let table_hint = if let Ok(adapter) = conn_manager.get(&conn_id) {
-            quick_schema_lookup(&adapter).and_then(|schema| {
+        let table_hint = adapter.as_ref().and_then(|adapter| {
+            quick_schema_lookup(adapter).and_then(|schema| {
                 schema
                     .iter()
                     .find(|t| last_user_msg.contains(&t.name.to_lowercase()))
                     .map(|t| t.name.clone())
             })
-        } else {
-            None
-        };
+        });
 
         let table = table_hint.unwrap_or_else(|| "users".to_string());
         let sql = if contains_any(&last_user_msg, &["count", "how many"]) {
@@ -1365,17 +6218,15 @@ pub fn chat_completion(
     }
 
     // Insert / form collection
-    if contains_any(&last_user_msg, &["insert", "add new", "create row", "add row"]) {
-        let table_hint = if let Ok(adapter) = conn_manager.get(&conn_id) {
-            quick_schema_lookup(&adapter).and_then(|schema| {
+    if offers("collect_form") && contains_any(&last_user_msg, &["insert", "add new", "create row", "add row"]) {
+        let table_hint = adapter.as_ref().and_then(|adapter| {
+            quick_schema_lookup(adapter).and_then(|schema| {
                 schema
                     .iter()
                     .find(|t| last_user_msg.contains(&t.name.to_lowercase()))
                     .map(|t| (t.name.clone(), t.columns.iter().filter(|c| !c.primary_key).map(|c| c.name.clone()).collect::<Vec<_>>()))
             })
-        } else {
-            None
-        };
+        });
 
         let (table, columns) = table_hint
             .unwrap_or_else(|| ("users".to_string(), vec!["email".to_string(), "name".to_string(), "active".to_string()]));
@@ -1392,60 +6243,53 @@ pub fn chat_completion(
     }
 
     // Destructive operations
-    if contains_any(&last_user_msg, &["delete", "drop", "truncate", "remove row"]) {
+    if offers("confirm_action") && contains_any(&last_user_msg, &["delete", "drop", "truncate", "remove row"]) {
+        // Only confirm a statement the user actually wrote or ran; never
+        // guess at what to delete.
+        let kind = adapter.as_ref().map(|a| a.kind());
+        let statement = destructive_statement(kind, &last_user_raw).or_else(|| {
+            recent_query_sql(&request.messages).and_then(|sql| destructive_statement(kind, &sql))
+        });
+        let Some(sql) = statement else {
+            return Ok(text_response(
+                "What should be deleted? Give me the exact statement, e.g. \
+                 'DELETE FROM orders WHERE id = 42', and I'll ask you to confirm it.",
+            ));
+        };
         return Ok(tool_call_response(
             "confirm_action",
             &serde_json::json!({
-                "sql": "DELETE FROM users WHERE id = 1",
-                "description": "Delete a user record"
+                "sql": sql,
+                "description": "This will modify your database. Please confirm."
             })
             .to_string(),
         ));
     }
 
     // Chart / visualization
-    if contains_any(&last_user_msg, &["chart", "visualize", "graph", "plot"]) {
-        // If we have a recent query result, we could chart it.
-        // For now, prompt user to run a query first, or show sample.
-        return Ok(tool_call_response(
-            "show_chart",
-            &serde_json::json!({
-                "chartType": "bar",
-                "title": "Query Results",
-                "data": [
-                    {"name": "Jan", "value": 100},
-                    {"name": "Feb", "value": 200},
-                    {"name": "Mar", "value": 350},
-                    {"name": "Apr", "value": 280}
-                ],
-                "xKey": "name",
-                "yKey": "value"
-            })
-            .to_string(),
-        ));
+    if offers("show_chart") && contains_any(&last_user_msg, &["chart", "visualize", "graph", "plot"]) {
+        let Some(result) = recent_query_result(&request.messages) else {
+            return Ok(text_response(
+                "There's no query result to chart yet. Run a query first, then ask me to chart it.",
+            ));
+        };
+        let Some(chart) = chart_from_result(&result) else {
+            return Ok(text_response(
+                "The last result has no numeric column to plot. \
+                 Try a query with a label column followed by a number.",
+            ));
+        };
+        return Ok(tool_call_response("show_chart", &chart.to_string()));
     }
 
     // Save query
-    if contains_any(&last_user_msg, &["save", "bookmark"]) {
+    if offers("save_query") && contains_any(&last_user_msg, &["save", "bookmark"]) {
+        let Some(conn_id) = &conn_id else {
+            return Ok(select_connection_response(&active_ids));
+        };
         // Look for the most recent SQL in conversation
-        let recent_sql = request
-            .messages
-            .iter()
-            .rev()
-            .filter_map(|m| {
-                if let Some(ref calls) = m.tool_calls {
-                    for call in calls {
-                        if call.function.name == "execute_query" {
-                            if let Ok(v) = serde_json::from_str::<serde_json::Value>(&call.function.arguments) {
-                                return v.get("sql").and_then(|s| s.as_str()).map(|s| s.to_string());
-                            }
-                        }
-                    }
-                }
-                None
-            })
-            .next()
-            .unwrap_or_else(|| "SELECT 1".to_string());
+        let recent_sql =
+            recent_query_sql(&request.messages).unwrap_or_else(|| "SELECT 1".to_string());
 
         return Ok(tool_call_response(
             "save_query",
@@ -1460,7 +6304,7 @@ pub fn chat_completion(
     }
 
     // CSV import
-    if contains_any(&last_user_msg, &["import", "csv", "upload"]) {
+    if offers("import_csv") && contains_any(&last_user_msg, &["import", "csv", "upload"]) {
         return Ok(tool_call_response(
             "import_csv",
             r#"{"tableName":"imported_data"}"#,
@@ -1468,7 +6312,10 @@ pub fn chat_completion(
     }
 
     // Stats / overview
-    if contains_any(&last_user_msg, &["stats", "statistics", "overview", "database info"]) {
+    if offers("get_database_stats") && contains_any(&last_user_msg, &["stats", "statistics", "overview", "database info"]) {
+        let Some(conn_id) = &conn_id else {
+            return Ok(select_connection_response(&active_ids));
+        };
         return Ok(tool_call_response(
             "get_database_stats",
             &serde_json::json!({ "connectionId": conn_id }).to_string(),
@@ -1476,7 +6323,7 @@ pub fn chat_completion(
     }
 
     // Scan codebase
-    if contains_any(&last_user_msg, &["scan", "codebase", "find sql"]) {
+    if offers("scan_queries") && contains_any(&last_user_msg, &["scan", "codebase", "find sql"]) {
         return Ok(tool_call_response(
             "scan_queries",
             r#"{"directoryPath":"."}"#,
@@ -1484,7 +6331,7 @@ pub fn chat_completion(
     }
 
     // Connection setup
-    if contains_any(&last_user_msg, &["connect", "setup", "new connection", "add database"]) {
+    if offers("setup_connection") && contains_any(&last_user_msg, &["connect", "setup", "new connection", "add database"]) {
         return Ok(tool_call_response(
             "setup_connection",
             r#"{"existingConnections":[]}"#,
@@ -1492,16 +6339,18 @@ pub fn chat_completion(
     }
 
     // Connection status
-    if contains_any(&last_user_msg, &["status", "am i connected", "connection info"]) {
-        if let Ok(adapter) = conn_manager.get(&conn_id) {
+    if offers("show_connection_status") && contains_any(&last_user_msg, &["status", "am i connected", "connection info"]) {
+        if let (Some(conn_id), Some(adapter)) = (&conn_id, &adapter) {
             let kind = format!("{:?}", adapter.kind());
+            let params = conn_manager.params(conn_id).ok();
             return Ok(tool_call_response(
                 "show_connection_status",
                 &serde_json::json!({
                     "name": conn_id,
                     "dbType": kind,
-                    "host": "localhost",
-                    "port": 5432,
+                    "host": params.as_ref().map(|p| p.host.as_str()).unwrap_or("-"),
+                    "port": params.as_ref().map(|p| p.port).unwrap_or(0),
+                    "database": params.as_ref().map(|p| p.database.as_str()),
                     "connected": true
                 })
                 .to_string(),
@@ -1521,17 +6370,15 @@ pub fn chat_completion(
     }
 
     // Filter builder
-    if contains_any(&last_user_msg, &["filter", "where clause", "build filter"]) {
-        let columns = if let Ok(adapter) = conn_manager.get(&conn_id) {
-            quick_schema_lookup(&adapter).and_then(|schema| {
+    if offers("build_filter") && contains_any(&last_user_msg, &["filter", "where clause", "build filter"]) {
+        let columns = adapter.as_ref().and_then(|adapter| {
+            quick_schema_lookup(adapter).and_then(|schema| {
                 let table = schema.iter()
                     .find(|t| last_user_msg.contains(&t.name.to_lowercase()))
                     .or(schema.first());
                 table.map(|t| (t.name.clone(), t.columns.iter().map(|c| serde_json::json!({"name": c.name, "type": c.data_type})).collect::<Vec<_>>()))
             })
-        } else {
-            None
-        };
+        });
 
         let (table, cols) = columns.unwrap_or_else(|| (
             "users".to_string(),
@@ -1553,8 +6400,7 @@ pub fn chat_completion(
     }
 
     // Default response — context-aware help message
-    let active_conns = conn_manager.active_ids();
-    let status = if active_conns.is_empty() {
+    let status = if active_ids.is_empty() {
         "No databases are currently connected. Go to Connections to connect one."
     } else {
         "Your database is connected and ready."
@@ -1574,15 +6420,424 @@ pub fn chat_completion(
         )),
         tool_calls: None,
         finish_reason: "stop".to_string(),
+        requires_approval: false,
     })
 }
 
+/// A setting's value. Secret settings such as the LLM API key only report
+/// whether they are set.
 #[tauri::command]
 pub fn get_setting(key: String, db: State<'_, Database>) -> Result<Option<String>, AppError> {
-    db.get_setting(&key)
+    let value = db.get_setting(&key)?;
+    if SECRET_SETTINGS.contains(&key.as_str()) {
+        return Ok(value
+            .filter(|v| !v.is_empty())
+            .map(|_| REDACTED_SETTING_VALUE.to_string()));
+    }
+    Ok(value)
 }
 
 #[tauri::command]
 pub fn set_setting(key: String, value: String, db: State<'_, Database>) -> Result<(), AppError> {
     db.set_setting(&key, &value)
 }
+
+/// Key endings that mark a setting as holding a credential.
+const SENSITIVE_SETTING_MARKERS: &[&str] = &["api_key", "apikey", "password", "secret", "token"];
+
+const REDACTED_SETTING_VALUE: &str = "********";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettingEntry {
+    pub key: String,
+    pub value: String,
+    pub redacted: bool,
+}
+
+/// Whether the last part of `key` (after any `prefix:` or `prefix.`
+/// namespace) names a credential, e.g. `github_token` or `llm_api_key`.
+/// Keys that merely mention one, like `max_tokens` or `token_count`, don't.
+fn is_sensitive_setting(key: &str) -> bool {
+    let key = key.to_lowercase();
+    let name = key.rsplit([':', '.']).next().unwrap_or(&key);
+    SENSITIVE_SETTING_MARKERS.iter().any(|m| name.ends_with(m))
+}
+
+/// List settings, optionally restricted to keys starting with `prefix`.
+/// Values of credential-like keys are redacted unless `include_sensitive`
+/// is set; secret settings are always redacted.
+#[tauri::command]
+pub fn list_settings(
+    prefix: Option<String>,
+    include_sensitive: Option<bool>,
+    db: State<'_, Database>,
+) -> Result<Vec<SettingEntry>, AppError> {
+    let include_sensitive = include_sensitive.unwrap_or(false);
+    let settings = db.list_settings(prefix.as_deref().unwrap_or(""))?;
+
+    Ok(settings
+        .into_iter()
+        .map(|(key, value)| {
+            let redacted = SECRET_SETTINGS.contains(&key.as_str())
+                || (!include_sensitive && is_sensitive_setting(&key));
+            SettingEntry {
+                value: if redacted { REDACTED_SETTING_VALUE.to_string() } else { value },
+                key,
+                redacted,
+            }
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub fn delete_setting(key: String, db: State<'_, Database>) -> Result<(), AppError> {
+    db.delete_setting(&key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scan(content: &str) -> Vec<ScanResult> {
+        scan_file(Path::new("src/db.py"), content, DEFAULT_MAX_SCAN_RESULTS)
+    }
+
+    fn table(name: &str, columns: &[(&str, &str)]) -> TableSchema {
+        TableSchema {
+            name: name.to_string(),
+            schema: None,
+            columns: columns
+                .iter()
+                .map(|(name, data_type)| ColumnInfo {
+                    name: name.to_string(),
+                    data_type: data_type.to_string(),
+                    nullable: true,
+                    primary_key: false,
+                })
+                .collect(),
+            row_count: 0,
+            is_estimate: false,
+        }
+    }
+
+    #[test]
+    fn snippet_expansion_skips_strings_and_comments() {
+        let bodies = std::collections::HashMap::from([
+            ("recent".to_string(), "created_at > now() - interval '1 day'".to_string()),
+        ]);
+        let expand = |sql: &str| expand_snippet_refs(sql, &bodies);
+
+        assert_eq!(
+            expand("SELECT * FROM t WHERE @recent"),
+            "SELECT * FROM t WHERE created_at > now() - interval '1 day'"
+        );
+        for untouched in [
+            "SELECT '@recent'",
+            "SELECT 1 -- @recent\n",
+            "SELECT 1 /* @recent */",
+            "SELECT $$ @recent $$",
+            "SELECT $body$ @recent $body$",
+            "SELECT @unknown, user@recent",
+        ] {
+            assert_eq!(expand(untouched), untouched);
+        }
+        assert_eq!(expand("SELECT $1 WHERE @recent").matches("interval").count(), 1);
+    }
+
+    fn csv_records(content: &str) -> Vec<Vec<String>> {
+        split_csv_records(content, CsvFormat::new(None, None).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn csv_keeps_quoted_commas_in_one_field() {
+        let parsed = parse_csv(
+            "id,name,note\r\n1,\"Doe, John\",\"said \"\"hi\"\"\"\r\n2,\"Roe,\nJane\",x\r\n",
+            CsvFormat::new(None, None).unwrap(),
+        )
+        .unwrap();
+        assert!(parsed.issues.is_empty());
+        assert_eq!(parsed.rows.len(), 2);
+        assert_eq!(parsed.rows[0][1], serde_json::json!("Doe, John"));
+        assert_eq!(parsed.rows[0][2], serde_json::json!("said \"hi\""));
+        assert_eq!(parsed.rows[1][1], serde_json::json!("Roe,\nJane"));
+        assert_eq!(parsed.rows[1][2], serde_json::json!("x"));
+    }
+
+    #[test]
+    fn csv_trailing_crlf_adds_no_row() {
+        assert_eq!(csv_records("a,b\r\n1,2\r\n"), [["a", "b"], ["1", "2"]]);
+        assert_eq!(csv_records("a,b\r\n1,2\r\n\r\n"), [["a", "b"], ["1", "2"]]);
+    }
+
+    #[test]
+    fn csv_import_rejects_an_existing_table_with_another_primary_key() {
+        let path = std::env::temp_dir().join(format!("arc-csv-pk-{}.db", uuid::Uuid::new_v4()));
+        rusqlite::Connection::open(&path)
+            .unwrap()
+            .execute_batch("CREATE TABLE people (id INTEGER PRIMARY KEY, email TEXT)")
+            .unwrap();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let manager = ConnectionManager::new(runtime.handle().clone());
+        let params = ConnectParams {
+            kind: DatabaseKind::SQLite,
+            host: String::new(),
+            port: 0,
+            database: path.to_string_lossy().into_owned(),
+            username: String::new(),
+            password: String::new(),
+            use_ssl: false,
+            quote_all_identifiers: false,
+            max_concurrent_queries: 1,
+            pool_size: 1,
+            connect_timeout_secs: None,
+            query_timeout_secs: None,
+            environment: None,
+            ssl_mode: None,
+            ca_cert_path: None,
+            client_cert_path: None,
+            client_key_path: None,
+            read_only: false,
+        };
+        manager.connect("csv", &params).unwrap();
+        let adapter = manager.get("csv").unwrap();
+        let format = CsvFormat::new(None, None).unwrap();
+        let parsed = parse_csv("id,email\n1,a@x.io\n2,b@x.io\n", format).unwrap();
+
+        let email = ["email".to_string()];
+        let err = create_csv_table(&adapter, "people", &parsed, Some(&email)).unwrap_err();
+        assert!(err.to_string().contains("already exists with primary key (id)"), "{err}");
+        let count = adapter.execute_query("SELECT COUNT(*) FROM people").unwrap();
+        assert_eq!(count.rows[0][0], serde_json::json!(0));
+
+        create_csv_table(&adapter, "people", &parsed, Some(&["id".to_string()])).unwrap();
+        let count = adapter.execute_query("SELECT COUNT(*) FROM people").unwrap();
+        assert_eq!(count.rows[0][0], serde_json::json!(2));
+
+        manager.disconnect("csv").unwrap();
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn csv_trims_only_unquoted_fields() {
+        assert_eq!(
+            csv_records("name , note\n  x  ,\"  padded  \"\n"),
+            [["name", "note"], ["x", "  padded  "]]
+        );
+    }
+
+    fn user_message(text: &str) -> ChatMessage {
+        ChatMessage {
+            role: "user".to_string(),
+            content: Some(text.to_string()),
+            tool_calls: None,
+            tool_call_id: None,
+        }
+    }
+
+    #[test]
+    fn chat_connection_falls_back_by_active_count() {
+        let messages = [user_message("show me the tables")];
+        let ids = |ids: &[&str]| -> Vec<String> { ids.iter().map(|s| s.to_string()).collect() };
+
+        assert_eq!(extract_connection_id(&messages, None, None, &[]), None);
+        assert_eq!(
+            extract_connection_id(&messages, None, None, &ids(&["a"])).as_deref(),
+            Some("a")
+        );
+        assert_eq!(extract_connection_id(&messages, None, None, &ids(&["a", "b"])), None);
+        // The exploration's connection and an active default both settle it.
+        assert_eq!(
+            extract_connection_id(&messages, Some("b"), None, &ids(&["a", "b"])).as_deref(),
+            Some("b")
+        );
+        assert_eq!(
+            extract_connection_id(&messages, None, Some("a"), &ids(&["a", "b"])).as_deref(),
+            Some("a")
+        );
+        assert_eq!(extract_connection_id(&messages, None, Some("gone"), &ids(&["a", "b"])), None);
+
+        let arguments = |active: &[&str]| -> serde_json::Value {
+            let response = select_connection_response(&ids(active));
+            let call = &response.tool_calls.unwrap()[0];
+            assert_eq!(call.function.name, "select_connection");
+            serde_json::from_str(&call.function.arguments).unwrap()
+        };
+        let several = arguments(&["a", "b"]);
+        assert_eq!(several["activeConnections"], serde_json::json!(["a", "b"]));
+        assert!(several.get("connectionId").is_none());
+        assert!(several["reason"].as_str().unwrap().contains("Pick which connection"));
+
+        let none = arguments(&[]);
+        assert_eq!(none["activeConnections"], serde_json::json!([]));
+        assert!(none.get("connectionId").is_none());
+        assert!(none["reason"].as_str().unwrap().contains("none are connected"));
+    }
+
+    #[test]
+    fn valid_tool_specs_parse() {
+        let tools = [serde_json::json!({
+            "type": "function",
+            "function": {
+                "name": "execute_query",
+                "description": "Run SQL",
+                "parameters": {
+                    "type": "object",
+                    "properties": { "sql": { "type": "string" } },
+                    "required": ["sql"]
+                }
+            }
+        })];
+        let specs = parse_tool_specs(&tools).unwrap();
+        assert_eq!(specs.len(), 1);
+        assert_eq!(specs[0].function.name, "execute_query");
+    }
+
+    #[test]
+    fn invalid_tool_specs_name_the_bad_entry() {
+        let error = |tools: serde_json::Value| match parse_tool_specs(tools.as_array().unwrap()) {
+            Err(AppError::QueryError(message)) => message,
+            other => panic!("expected a validation error, got {other:?}"),
+        };
+        let ok = serde_json::json!({ "type": "function", "function": { "name": "a" } });
+
+        assert!(error(serde_json::json!([ok, { "type": "retrieval", "function": { "name": "b" } }]))
+            .starts_with("Invalid tool at index 1"));
+        let badly_named =
+            serde_json::json!({ "type": "function", "function": { "name": "bad name" } });
+        assert!(error(serde_json::json!([badly_named])).contains("bad name"));
+        assert!(error(serde_json::json!([ok, ok])).contains("duplicate tool name a"));
+        assert!(error(serde_json::json!([{
+            "type": "function",
+            "function": {
+                "name": "q",
+                "parameters": { "type": "object", "properties": {}, "required": ["sql"] }
+            }
+        }]))
+        .contains("required names sql"));
+        assert!(error(serde_json::json!([{ "type": "function" }]))
+            .starts_with("Invalid tool at index 0"));
+    }
+
+    #[test]
+    fn auto_limit_is_opt_in_and_yields_to_max_rows() {
+        assert_eq!(auto_limit(None, None), None);
+        assert_eq!(auto_limit(Some("off"), None), None);
+        assert_eq!(auto_limit(Some("false"), None), None);
+        assert_eq!(auto_limit(Some("0"), None), None);
+        assert_eq!(auto_limit(Some(" 500 "), None), Some(500));
+        // An explicit cap above the auto limit is honored, not cut to it.
+        assert_eq!(auto_limit(Some("1000"), Some(50_000)), None);
+    }
+
+    #[test]
+    fn tool_call_fragments_only_extend_the_next_index() {
+        let deltas = serde_json::json!([
+            {"index": 0, "id": "c1", "function": {"name": "run_", "arguments": "{\"a\""}},
+            {"index": 0, "function": {"name": "query", "arguments": ":1}"}},
+            {"index": 1_000_000_000_000_000u64, "id": "huge"},
+            {"index": 2, "id": "skipped"},
+            {"index": 1, "id": "c2"},
+        ]);
+        let mut calls = Vec::new();
+        for delta in deltas.as_array().unwrap() {
+            merge_tool_call_delta(&mut calls, delta);
+        }
+
+        let ids: Vec<&str> = calls.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, ["c1", "c2"]);
+        assert_eq!(calls[0].function.name, "run_query");
+        assert_eq!(calls[0].function.arguments, "{\"a\":1}");
+    }
+
+    #[test]
+    fn only_credential_settings_are_sensitive() {
+        let secrets = ["llm_api_key", "github_token", "smtp_password", "oauth:secret", "apiKey"];
+        for key in secrets {
+            assert!(is_sensitive_setting(key), "{key}");
+        }
+        let plain = ["max_tokens", "token_count", "llm_model", "ui:token_usage", "password_hint"];
+        for key in plain {
+            assert!(!is_sensitive_setting(key), "{key}");
+        }
+    }
+
+    #[test]
+    fn zero_timeouts_are_rejected() {
+        assert!(check_timeouts(None, None).is_ok());
+        assert!(check_timeouts(Some(5), Some(120)).is_ok());
+        assert!(check_timeouts(Some(0), None).is_err());
+        assert!(check_timeouts(None, Some(0)).is_err());
+    }
+
+    #[test]
+    fn schema_diff_keeps_tables_differing_only_in_case_apart() {
+        let a = [table("Users", &[("id", "integer")])];
+        let b = [table("users", &[("id", "integer")])];
+
+        let exact = schema_diff(&a, &b, false);
+        assert_eq!(exact.tables_only_in_a, ["Users"]);
+        assert_eq!(exact.tables_only_in_b, ["users"]);
+
+        let folded = schema_diff(&a, &b, true);
+        assert!(folded.tables_only_in_a.is_empty());
+        assert_eq!(folded.unchanged_count, 1);
+    }
+
+    #[test]
+    fn schema_diff_reports_added_removed_and_changed_columns() {
+        let a = [table("t", &[("id", "integer"), ("old", "text"), ("n", "integer")])];
+        let b = [table("t", &[("id", "integer"), ("new", "text"), ("n", "bigint")])];
+        let diff = schema_diff(&a, &b, false);
+        let changes = &diff.changed_tables[0];
+        assert_eq!(changes.added_columns[0].name, "new");
+        assert_eq!(changes.removed_columns[0].name, "old");
+        assert_eq!(changes.changed_columns[0].name, "n");
+    }
+
+    #[test]
+    fn scanner_ignores_identifiers_and_comments() {
+        assert!(scan("const selected = 5;").is_empty());
+        assert!(scan("// SELECT this option to enable it").is_empty());
+        assert!(scan("Select the rows you want, then press Delete.").is_empty());
+    }
+
+    #[test]
+    fn scanner_follows_statements_opened_on_the_previous_line() {
+        let content = "rows = db.execute(\"\"\"\n    SELECT id, name\n    FROM users\n\"\"\")\n";
+        let results = scan(content);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].line_number, 2);
+        assert_eq!(results[0].query_type, "SELECT");
+        assert_eq!(results[0].query_snippet, "SELECT id, name\nFROM users\n\"\"\")");
+    }
+
+    #[test]
+    fn scanner_captures_go_raw_strings() {
+        let content = "rows, err := db.Query(`SELECT id, total\n\tFROM orders\n\tWHERE paid`)\n";
+        let results = scan_file(Path::new("store.go"), content, DEFAULT_MAX_SCAN_RESULTS);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].line_number, 1);
+        assert_eq!(
+            results[0].query_snippet,
+            "rows, err := db.Query(`SELECT id, total\nFROM orders\nWHERE paid`)"
+        );
+    }
+
+    #[test]
+    fn json_export_keeps_repeated_columns() {
+        let columns: Vec<String> = ["id", "id", "id_2"].iter().map(|c| c.to_string()).collect();
+        assert_eq!(unique_keys(&columns), vec!["id", "id_3", "id_2"]);
+    }
+
+    #[test]
+    fn destructive_statement_needs_real_sql() {
+        let kind = Some(DatabaseKind::PostgreSQL);
+        assert_eq!(
+            destructive_statement(kind, " DELETE FROM orders WHERE id = 42 ").as_deref(),
+            Some("DELETE FROM orders WHERE id = 42")
+        );
+        assert_eq!(destructive_statement(kind, "Delete user 5"), None);
+        assert_eq!(destructive_statement(None, "drop the old tables please"), None);
+        assert_eq!(destructive_statement(kind, "SELECT * FROM orders"), None);
+    }
+}
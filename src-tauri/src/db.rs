@@ -3,13 +3,31 @@ use std::sync::Mutex;
 use rusqlite::{params, Connection};
 use tauri::{AppHandle, Manager};
 
+use crate::adapter::{DatabaseKind, SslMode, DEFAULT_MAX_CONCURRENT_QUERIES};
 use crate::commands::{
-    AppError, ConnectionNote, DatabaseConnection, DatabaseStats, Exploration, ExplorationMessage,
-    Project, SavedChart, SavedQuery, TableLink,
+    AppError, ConnectionNote, ConnectionOverrides, Dashboard, DatabaseConnection, DatabaseStats,
+    Exploration, ExplorationCounters, ExplorationMessage, Project, QueryHistoryEntry, SavedChart,
+    SavedQuery, SavedQueryTag, Snippet, TableLink, WorkspaceItemKind, WorkspaceSearchResult,
 };
+use crate::secrets::SecretBox;
+
+/// History entries kept per connection; older ones are pruned on insert.
+const QUERY_HISTORY_LIMIT: i64 = 500;
+
+/// API key for the OpenAI-compatible endpoint used for streamed chat.
+pub const LLM_API_KEY_SETTING: &str = "llm_api_key";
+
+/// Settings holding credentials. They are encrypted like connection
+/// passwords and read back only through `get_secret_setting`.
+pub const SECRET_SETTINGS: &[&str] = &[LLM_API_KEY_SETTING];
 
 pub struct Database {
     conn: Mutex<Connection>,
+    /// Where the previous, unreadable `arc.db` was moved when startup had to
+    /// recreate it.
+    recovered_backup: Option<std::path::PathBuf>,
+    /// Encrypts connection passwords at rest.
+    secrets: SecretBox,
 }
 
 impl Database {
@@ -17,133 +35,41 @@ impl Database {
         let app_dir = app_handle.path().app_data_dir()?;
         std::fs::create_dir_all(&app_dir)?;
         let db_path = app_dir.join("arc.db");
-        let connection = Connection::open(db_path)?;
+        let (connection, recovered_backup) = open_or_recover(&db_path)?;
+        let secrets = SecretBox::open(&app_dir.join("arc.key"))?;
 
         connection.execute_batch(
             "PRAGMA journal_mode=WAL;
              PRAGMA foreign_keys=ON;",
         )?;
 
-        connection.execute_batch(
-            "CREATE TABLE IF NOT EXISTS projects (
-                id TEXT PRIMARY KEY,
-                name TEXT NOT NULL,
-                description TEXT NOT NULL DEFAULT '',
-                created_at TEXT NOT NULL
-            );
-
-            CREATE TABLE IF NOT EXISTS connections (
-                id TEXT PRIMARY KEY,
-                name TEXT NOT NULL,
-                db_type TEXT NOT NULL,
-                host TEXT NOT NULL,
-                port INTEGER NOT NULL,
-                database_name TEXT NOT NULL,
-                username TEXT NOT NULL,
-                connected INTEGER NOT NULL DEFAULT 0,
-                password TEXT NOT NULL DEFAULT '',
-                use_ssl INTEGER NOT NULL DEFAULT 0
-            );
-
-            CREATE TABLE IF NOT EXISTS project_connections (
-                project_id TEXT NOT NULL,
-                connection_id TEXT NOT NULL,
-                PRIMARY KEY (project_id, connection_id),
-                FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE,
-                FOREIGN KEY (connection_id) REFERENCES connections(id) ON DELETE CASCADE
-            );
-
-            CREATE TABLE IF NOT EXISTS explorations (
-                id TEXT PRIMARY KEY,
-                project_id TEXT NOT NULL,
-                title TEXT NOT NULL,
-                created_at TEXT NOT NULL,
-                FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
-            );
-
-            CREATE TABLE IF NOT EXISTS exploration_messages (
-                id TEXT PRIMARY KEY,
-                exploration_id TEXT NOT NULL,
-                role TEXT NOT NULL,
-                content TEXT NOT NULL,
-                metadata TEXT,
-                created_at TEXT NOT NULL,
-                FOREIGN KEY (exploration_id) REFERENCES explorations(id) ON DELETE CASCADE
-            );
-
-            CREATE TABLE IF NOT EXISTS exploration_store_state (
-                exploration_id TEXT PRIMARY KEY,
-                token_count INTEGER NOT NULL DEFAULT 0,
-                turn_count INTEGER NOT NULL DEFAULT 0,
-                updated_at TEXT NOT NULL,
-                FOREIGN KEY (exploration_id) REFERENCES explorations(id) ON DELETE CASCADE
-            );
-
-            CREATE TABLE IF NOT EXISTS saved_queries (
-                id TEXT PRIMARY KEY,
-                name TEXT NOT NULL,
-                description TEXT NOT NULL DEFAULT '',
-                sql_text TEXT NOT NULL,
-                connection_id TEXT NOT NULL,
-                created_at TEXT NOT NULL
-            );
-
-            CREATE TABLE IF NOT EXISTS saved_charts (
-                id TEXT PRIMARY KEY,
-                name TEXT NOT NULL,
-                description TEXT NOT NULL DEFAULT '',
-                chart_type TEXT NOT NULL,
-                x_key TEXT NOT NULL,
-                y_key TEXT NOT NULL,
-                connection_id TEXT,
-                sql_text TEXT,
-                data_json TEXT NOT NULL,
-                created_at TEXT NOT NULL,
-                FOREIGN KEY (connection_id) REFERENCES connections(id) ON DELETE SET NULL
-            );
-
-            CREATE TABLE IF NOT EXISTS connection_notes (
-                connection_id TEXT PRIMARY KEY,
-                note TEXT NOT NULL,
-                updated_at TEXT NOT NULL,
-                FOREIGN KEY (connection_id) REFERENCES connections(id) ON DELETE CASCADE
-            );
-
-            CREATE TABLE IF NOT EXISTS table_links (
-                id TEXT PRIMARY KEY,
-                source_table TEXT NOT NULL,
-                source_column TEXT NOT NULL,
-                target_table TEXT NOT NULL,
-                target_column TEXT NOT NULL,
-                label TEXT NOT NULL DEFAULT '',
-                connection_id TEXT NOT NULL
-            );
-
-            CREATE TABLE IF NOT EXISTS settings (
-                key TEXT PRIMARY KEY,
-                value TEXT NOT NULL
-            );",
-        )?;
-
-        // Migrate: add password and use_ssl columns if missing
-        connection
-            .execute("ALTER TABLE connections ADD COLUMN password TEXT NOT NULL DEFAULT ''", [])
-            .ok();
-        connection
-            .execute("ALTER TABLE connections ADD COLUMN use_ssl INTEGER NOT NULL DEFAULT 0", [])
-            .ok();
+        run_migrations(&connection)?;
 
         // Reset all connection statuses on startup — the ConnectionManager
         // starts empty so no adapters are actually live yet.
-        connection
-            .execute("UPDATE connections SET connected = 0", [])
-            .ok();
+        connection.execute("UPDATE connections SET connected = 0", [])?;
+
+        secure_stored_passwords(&connection, &secrets)?;
 
         Ok(Database {
             conn: Mutex::new(connection),
+            recovered_backup,
+            secrets,
         })
     }
 
+    /// A connection's saved password. One that no longer decrypts, e.g.
+    /// because the key was replaced, reads as unsaved so the user is asked
+    /// for it again.
+    fn stored_password(&self, stored: &str) -> String {
+        self.secrets.decrypt(stored).unwrap_or_default()
+    }
+
+    /// The backup path of a corrupt database replaced at startup, if any.
+    pub fn recovered_backup(&self) -> Option<&std::path::Path> {
+        self.recovered_backup.as_deref()
+    }
+
 
     // --- Projects ---
 
@@ -316,7 +242,10 @@ impl Database {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn
             .prepare(
-                "SELECT c.id, c.name, c.db_type, c.host, c.port, c.database_name, c.username, c.connected, c.password, c.use_ssl
+                "SELECT c.id, c.name, c.db_type, c.host, c.port, c.database_name, c.username, c.connected, c.password, c.use_ssl, c.quote_all_identifiers, c.max_concurrent_queries,
+                        c.connect_timeout_secs, c.query_timeout_secs, c.environment,
+                        c.ssl_mode, c.ca_cert_path, c.client_cert_path, c.client_key_path,
+                        c.read_only
                  FROM connections c
                  INNER JOIN project_connections pc ON c.id = pc.connection_id
                  WHERE pc.project_id = ?1
@@ -335,8 +264,18 @@ impl Database {
                     database: row.get(5)?,
                     username: row.get(6)?,
                     connected: row.get::<_, i32>(7)? != 0,
-                    password: row.get(8)?,
+                    password: self.stored_password(&row.get::<_, String>(8)?),
                     use_ssl: row.get::<_, i32>(9)? != 0,
+                    quote_all_identifiers: row.get::<_, i32>(10)? != 0,
+                    max_concurrent_queries: row.get::<_, i64>(11)?.max(1) as u32,
+                    connect_timeout_secs: row.get(12)?,
+                    query_timeout_secs: row.get(13)?,
+                    environment: row.get(14)?,
+                    ssl_mode: ssl_mode_from_row(row, 15)?,
+                    ca_cert_path: row.get(16)?,
+                    client_cert_path: row.get(17)?,
+                    client_key_path: row.get(18)?,
+                    read_only: row.get::<_, i32>(19)? != 0,
                 })
             })
             .map_err(|e| AppError::DatabaseError(e.to_string()))?
@@ -351,7 +290,7 @@ impl Database {
     pub fn list_connections(&self) -> Result<Vec<DatabaseConnection>, AppError> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn
-            .prepare("SELECT id, name, db_type, host, port, database_name, username, connected, password, use_ssl FROM connections ORDER BY name")
+            .prepare("SELECT id, name, db_type, host, port, database_name, username, connected, password, use_ssl, quote_all_identifiers, max_concurrent_queries, connect_timeout_secs, query_timeout_secs, environment, ssl_mode, ca_cert_path, client_cert_path, client_key_path, read_only FROM connections ORDER BY name")
             .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
         let results = stmt
@@ -365,8 +304,18 @@ impl Database {
                     database: row.get(5)?,
                     username: row.get(6)?,
                     connected: row.get::<_, i32>(7)? != 0,
-                    password: row.get(8)?,
+                    password: self.stored_password(&row.get::<_, String>(8)?),
                     use_ssl: row.get::<_, i32>(9)? != 0,
+                    quote_all_identifiers: row.get::<_, i32>(10)? != 0,
+                    max_concurrent_queries: row.get::<_, i64>(11)?.max(1) as u32,
+                    connect_timeout_secs: row.get(12)?,
+                    query_timeout_secs: row.get(13)?,
+                    environment: row.get(14)?,
+                    ssl_mode: ssl_mode_from_row(row, 15)?,
+                    ca_cert_path: row.get(16)?,
+                    client_cert_path: row.get(17)?,
+                    client_key_path: row.get(18)?,
+                    read_only: row.get::<_, i32>(19)? != 0,
                 })
             })
             .map_err(|e| AppError::DatabaseError(e.to_string()))?
@@ -385,18 +334,8 @@ impl Database {
         database: &str,
         username: &str,
     ) -> Result<DatabaseConnection, AppError> {
-        let conn = self.conn.lock().unwrap();
-        let id = uuid::Uuid::new_v4().to_string();
-
-        conn.execute(
-            "INSERT INTO connections (id, name, db_type, host, port, database_name, username, connected, password, use_ssl)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 0, '', 0)",
-            params![&id, name, db_type, host, port as i32, database, username],
-        )
-        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
-
-        Ok(DatabaseConnection {
-            id,
+        self.create_connection(DatabaseConnection {
+            id: String::new(),
             name: name.to_string(),
             db_type: db_type.to_string(),
             host: host.to_string(),
@@ -406,9 +345,36 @@ impl Database {
             connected: false,
             password: String::new(),
             use_ssl: false,
+            quote_all_identifiers: false,
+            max_concurrent_queries: DEFAULT_MAX_CONCURRENT_QUERIES,
+            connect_timeout_secs: None,
+            query_timeout_secs: None,
+            environment: None,
+            ssl_mode: None,
+            ca_cert_path: None,
+            client_cert_path: None,
+            client_key_path: None,
+            read_only: false,
         })
     }
 
+    /// Store a new connection built from `template`, under a fresh id. The
+    /// password is never stored here; it is saved on first connect.
+    pub fn create_connection(
+        &self,
+        template: DatabaseConnection,
+    ) -> Result<DatabaseConnection, AppError> {
+        let conn = self.conn.lock().unwrap();
+        let connection = DatabaseConnection {
+            id: uuid::Uuid::new_v4().to_string(),
+            connected: false,
+            password: String::new(),
+            ..template
+        };
+        insert_connection(&conn, &connection)?;
+        Ok(connection)
+    }
+
     pub fn set_connection_status(&self, id: &str, connected: bool) -> Result<(), AppError> {
         let conn = self.conn.lock().unwrap();
         conn.execute(
@@ -425,6 +391,7 @@ impl Database {
         password: &str,
         use_ssl: bool,
     ) -> Result<(), AppError> {
+        let password = self.secrets.encrypt(password)?;
         let conn = self.conn.lock().unwrap();
         conn.execute(
             "UPDATE connections SET password = ?1, use_ssl = ?2 WHERE id = ?3",
@@ -434,6 +401,245 @@ impl Database {
         Ok(())
     }
 
+    /// Copy a connection under a new id and name, applying `overrides`. The
+    /// password is never copied, so the clone prompts for it on first
+    /// connect.
+    pub fn clone_connection(
+        &self,
+        source_id: &str,
+        new_name: &str,
+        overrides: &ConnectionOverrides,
+        copy_note: bool,
+    ) -> Result<DatabaseConnection, AppError> {
+        let conn = self.conn.lock().unwrap();
+        let source = conn
+            .query_row(
+                "SELECT db_type, host, port, database_name, username, use_ssl, quote_all_identifiers,
+                        max_concurrent_queries, connect_timeout_secs, query_timeout_secs, environment,
+                        ssl_mode, ca_cert_path, client_cert_path, client_key_path, read_only
+                 FROM connections WHERE id = ?1",
+                params![source_id],
+                |row| {
+                    Ok(DatabaseConnection {
+                        id: String::new(),
+                        name: String::new(),
+                        db_type: row.get(0)?,
+                        host: row.get(1)?,
+                        port: row.get::<_, i32>(2)? as u16,
+                        database: row.get(3)?,
+                        username: row.get(4)?,
+                        connected: false,
+                        password: String::new(),
+                        use_ssl: row.get::<_, i32>(5)? != 0,
+                        quote_all_identifiers: row.get::<_, i32>(6)? != 0,
+                        max_concurrent_queries: row.get::<_, i64>(7)?.max(1) as u32,
+                        connect_timeout_secs: row.get(8)?,
+                        query_timeout_secs: row.get(9)?,
+                        environment: row.get(10)?,
+                        ssl_mode: ssl_mode_from_row(row, 11)?,
+                        ca_cert_path: row.get(12)?,
+                        client_cert_path: row.get(13)?,
+                        client_key_path: row.get(14)?,
+                        read_only: row.get::<_, i32>(15)? != 0,
+                    })
+                },
+            )
+            .map_err(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => {
+                    AppError::NotFound(format!("Connection {source_id} not found"))
+                }
+                e => AppError::DatabaseError(e.to_string()),
+            })?;
+
+        let clone = DatabaseConnection {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: new_name.to_string(),
+            host: overrides.host.clone().unwrap_or(source.host),
+            port: overrides.port.unwrap_or(source.port),
+            database: overrides.database.clone().unwrap_or(source.database),
+            username: overrides.username.clone().unwrap_or(source.username),
+            use_ssl: overrides.use_ssl.unwrap_or(source.use_ssl),
+            ..source
+        };
+
+        insert_connection(&conn, &clone)?;
+
+        if copy_note {
+            conn.execute(
+                "INSERT INTO connection_notes (connection_id, note, updated_at)
+                 SELECT ?1, note, ?2 FROM connection_notes WHERE connection_id = ?3",
+                params![&clone.id, chrono::Utc::now().to_rfc3339(), source_id],
+            )
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        }
+
+        Ok(clone)
+    }
+
+    /// Change where a connection points; `None` fields are left as they are.
+    /// A saved password is cleared when the host, port or username actually
+    /// changes, so it is never sent to a server it wasn't saved for.
+    pub fn update_connection(
+        &self,
+        id: &str,
+        name: Option<&str>,
+        host: Option<&str>,
+        port: Option<u16>,
+        database: Option<&str>,
+        username: Option<&str>,
+    ) -> Result<DatabaseConnection, AppError> {
+        let affected = self
+            .conn
+            .lock()
+            .unwrap()
+            .execute(
+                "UPDATE connections SET name = COALESCE(?1, name), host = COALESCE(?2, host),
+                        port = COALESCE(?3, port), database_name = COALESCE(?4, database_name),
+                        username = COALESCE(?5, username),
+                        password = CASE
+                            WHEN COALESCE(?2, host) != host OR COALESCE(?3, port) != port
+                                 OR COALESCE(?5, username) != username THEN ''
+                            ELSE password
+                        END
+                 WHERE id = ?6",
+                params![name, host, port, database, username, id],
+            )
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        if affected == 0 {
+            return Err(AppError::NotFound(format!("Connection {id} not found")));
+        }
+        self.list_connections()?
+            .into_iter()
+            .find(|c| c.id == id)
+            .ok_or_else(|| AppError::NotFound(format!("Connection {id} not found")))
+    }
+
+    pub fn set_connection_quoting(&self, id: &str, quote_all_identifiers: bool) -> Result<(), AppError> {
+        let conn = self.conn.lock().unwrap();
+        let affected = conn
+            .execute(
+                "UPDATE connections SET quote_all_identifiers = ?1 WHERE id = ?2",
+                params![quote_all_identifiers as i32, id],
+            )
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        if affected == 0 {
+            return Err(AppError::NotFound(format!("Connection {id} not found")));
+        }
+        Ok(())
+    }
+
+    pub fn set_connection_read_only(&self, id: &str, read_only: bool) -> Result<(), AppError> {
+        let conn = self.conn.lock().unwrap();
+        let affected = conn
+            .execute(
+                "UPDATE connections SET read_only = ?1 WHERE id = ?2",
+                params![read_only as i32, id],
+            )
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        if affected == 0 {
+            return Err(AppError::NotFound(format!("Connection {id} not found")));
+        }
+        Ok(())
+    }
+
+    pub fn set_connection_environment(
+        &self,
+        id: &str,
+        environment: Option<&str>,
+    ) -> Result<(), AppError> {
+        let conn = self.conn.lock().unwrap();
+        let affected = conn
+            .execute(
+                "UPDATE connections SET environment = ?1 WHERE id = ?2",
+                params![environment, id],
+            )
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        if affected == 0 {
+            return Err(AppError::NotFound(format!("Connection {id} not found")));
+        }
+        Ok(())
+    }
+
+    /// Store the TLS mode and CA bundle used for the connection; `None`
+    /// clears them.
+    pub fn set_connection_tls(
+        &self,
+        id: &str,
+        ssl_mode: Option<SslMode>,
+        ca_cert_path: Option<&str>,
+    ) -> Result<(), AppError> {
+        let conn = self.conn.lock().unwrap();
+        let affected = conn
+            .execute(
+                "UPDATE connections SET ssl_mode = ?1, ca_cert_path = ?2 WHERE id = ?3",
+                params![ssl_mode.map(SslMode::as_str), ca_cert_path, id],
+            )
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        if affected == 0 {
+            return Err(AppError::NotFound(format!("Connection {id} not found")));
+        }
+        Ok(())
+    }
+
+    /// Store the client certificate and key files used for mutual TLS;
+    /// `None` clears them.
+    pub fn set_connection_client_cert(
+        &self,
+        id: &str,
+        client_cert_path: Option<&str>,
+        client_key_path: Option<&str>,
+    ) -> Result<(), AppError> {
+        let conn = self.conn.lock().unwrap();
+        let affected = conn
+            .execute(
+                "UPDATE connections SET client_cert_path = ?1, client_key_path = ?2 WHERE id = ?3",
+                params![client_cert_path, client_key_path, id],
+            )
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        if affected == 0 {
+            return Err(AppError::NotFound(format!("Connection {id} not found")));
+        }
+        Ok(())
+    }
+
+    /// Store per-connection timeouts; `None` falls back to the defaults.
+    pub fn set_connection_timeouts(
+        &self,
+        id: &str,
+        connect_timeout_secs: Option<u64>,
+        query_timeout_secs: Option<u64>,
+    ) -> Result<(), AppError> {
+        let conn = self.conn.lock().unwrap();
+        let affected = conn
+            .execute(
+                "UPDATE connections SET connect_timeout_secs = ?1, query_timeout_secs = ?2 WHERE id = ?3",
+                params![connect_timeout_secs, query_timeout_secs, id],
+            )
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        if affected == 0 {
+            return Err(AppError::NotFound(format!("Connection {id} not found")));
+        }
+        Ok(())
+    }
+
+    pub fn set_connection_concurrency(
+        &self,
+        id: &str,
+        max_concurrent_queries: u32,
+    ) -> Result<(), AppError> {
+        let conn = self.conn.lock().unwrap();
+        let affected = conn
+            .execute(
+                "UPDATE connections SET max_concurrent_queries = ?1 WHERE id = ?2",
+                params![max_concurrent_queries, id],
+            )
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        if affected == 0 {
+            return Err(AppError::NotFound(format!("Connection {id} not found")));
+        }
+        Ok(())
+    }
+
     pub fn remove_connection(&self, id: &str) -> Result<(), AppError> {
         let conn = self.conn.lock().unwrap();
         let affected = conn
@@ -448,16 +654,43 @@ impl Database {
 
     // --- Explorations ---
 
+    /// A project's explorations, leaving out those in the trash.
     pub fn list_explorations(&self, project_id: &str) -> Result<Vec<Exploration>, AppError> {
+        self.query_explorations(
+            "SELECT e.id, e.project_id, e.title, e.created_at,
+                    (SELECT COUNT(*) FROM exploration_messages WHERE exploration_id = e.id) as msg_count,
+                    e.deleted_at
+             FROM explorations e
+             WHERE e.project_id = ?1 AND e.deleted_at IS NULL
+             ORDER BY e.created_at",
+            project_id,
+        )
+    }
+
+    /// A project's deleted explorations, most recently deleted first.
+    pub fn list_trashed_explorations(
+        &self,
+        project_id: &str,
+    ) -> Result<Vec<Exploration>, AppError> {
+        self.query_explorations(
+            "SELECT e.id, e.project_id, e.title, e.created_at,
+                    (SELECT COUNT(*) FROM exploration_messages WHERE exploration_id = e.id) as msg_count,
+                    e.deleted_at
+             FROM explorations e
+             WHERE e.project_id = ?1 AND e.deleted_at IS NOT NULL
+             ORDER BY e.deleted_at DESC",
+            project_id,
+        )
+    }
+
+    fn query_explorations(
+        &self,
+        sql: &str,
+        project_id: &str,
+    ) -> Result<Vec<Exploration>, AppError> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn
-            .prepare(
-                "SELECT e.id, e.project_id, e.title, e.created_at,
-                        (SELECT COUNT(*) FROM exploration_messages WHERE exploration_id = e.id) as msg_count
-                 FROM explorations e
-                 WHERE e.project_id = ?1
-                 ORDER BY e.created_at",
-            )
+            .prepare(sql)
             .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
         let results = stmt
@@ -468,6 +701,7 @@ impl Database {
                     title: row.get(2)?,
                     created_at: row.get(3)?,
                     message_count: row.get::<_, i32>(4)? as u32,
+                    deleted_at: row.get(5)?,
                 })
             })
             .map_err(|e| AppError::DatabaseError(e.to_string()))?
@@ -483,22 +717,7 @@ impl Database {
         title: &str,
     ) -> Result<Exploration, AppError> {
         let conn = self.conn.lock().unwrap();
-        let id = uuid::Uuid::new_v4().to_string();
-        let created_at = chrono::Utc::now().to_rfc3339();
-
-        conn.execute(
-            "INSERT INTO explorations (id, project_id, title, created_at) VALUES (?1, ?2, ?3, ?4)",
-            params![&id, project_id, title, &created_at],
-        )
-        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
-
-        Ok(Exploration {
-            id,
-            project_id: project_id.to_string(),
-            title: title.to_string(),
-            created_at,
-            message_count: 0,
-        })
+        insert_exploration(&conn, project_id, title)
     }
 
     pub fn update_exploration(&self, id: &str, title: &str) -> Result<Exploration, AppError> {
@@ -524,7 +743,7 @@ impl Database {
             .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
         conn.query_row(
-            "SELECT id, project_id, title, created_at FROM explorations WHERE id = ?1",
+            "SELECT id, project_id, title, created_at, deleted_at FROM explorations WHERE id = ?1",
             params![id],
             |row| {
                 Ok(Exploration {
@@ -533,13 +752,153 @@ impl Database {
                     title: row.get(2)?,
                     created_at: row.get(3)?,
                     message_count: msg_count as u32,
+                    deleted_at: row.get(4)?,
                 })
             },
         )
         .map_err(|e| AppError::DatabaseError(e.to_string()))
     }
 
+    /// Start a new exploration in the same project from the messages of
+    /// `exploration_id` up to and including `up_to_message_id`. The turn
+    /// count is the number of copied user messages; the token count is the
+    /// source's, scaled by the share of message text that was copied.
+    pub fn fork_exploration(
+        &self,
+        exploration_id: &str,
+        up_to_message_id: &str,
+    ) -> Result<Exploration, AppError> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn
+            .transaction()
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let (project_id, title, source_tokens): (String, String, i64) = tx
+            .query_row(
+                "SELECT project_id, title, token_count FROM explorations WHERE id = ?1",
+                params![exploration_id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .map_err(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => {
+                    AppError::NotFound(format!("Exploration {exploration_id} not found"))
+                }
+                e => AppError::DatabaseError(e.to_string()),
+            })?;
+
+        let messages: Vec<(String, String, String, Option<String>, String)> = {
+            let mut stmt = tx
+                .prepare(
+                    "SELECT id, role, content, metadata, created_at
+                     FROM exploration_messages
+                     WHERE exploration_id = ?1
+                     ORDER BY created_at",
+                )
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            let rows = stmt
+                .query_map(params![exploration_id], |row| {
+                    Ok((
+                        row.get(0)?,
+                        row.get(1)?,
+                        row.get(2)?,
+                        row.get(3)?,
+                        row.get(4)?,
+                    ))
+                })
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?
+                .filter_map(|r| r.ok())
+                .collect();
+            rows
+        };
+        let cut = messages
+            .iter()
+            .position(|(id, ..)| id == up_to_message_id)
+            .ok_or_else(|| {
+                AppError::NotFound(format!(
+                    "Message {up_to_message_id} not found in exploration {exploration_id}"
+                ))
+            })?;
+
+        let total_len: usize = messages
+            .iter()
+            .map(|(_, _, content, ..)| content.len())
+            .sum();
+        let copied = &messages[..=cut];
+        let copied_len: usize = copied.iter().map(|(_, _, content, ..)| content.len()).sum();
+        let token_count = if total_len == 0 {
+            0
+        } else {
+            (source_tokens.max(0) as f64 * copied_len as f64 / total_len as f64).round() as i64
+        };
+        let turn_count = copied.iter().filter(|(_, role, ..)| role == "user").count() as i64;
+
+        let mut fork = insert_exploration(&tx, &project_id, &format!("{title} (fork)"))?;
+        tx.execute(
+            "UPDATE explorations SET token_count = ?1, turn_count = ?2 WHERE id = ?3",
+            params![token_count, turn_count, &fork.id],
+        )
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        // Original timestamps are kept so the copies sort the same way.
+        for (_, role, content, metadata, message_created_at) in copied {
+            tx.execute(
+                "INSERT INTO exploration_messages (id, exploration_id, role, content, metadata, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    uuid::Uuid::new_v4().to_string(),
+                    &fork.id,
+                    role,
+                    content,
+                    metadata,
+                    message_created_at
+                ],
+            )
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        }
+
+        tx.commit()
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        fork.message_count = copied.len() as u32;
+        Ok(fork)
+    }
+
+    /// Move an exploration to the trash. Its messages are kept until it is
+    /// purged.
     pub fn delete_exploration(&self, id: &str) -> Result<(), AppError> {
+        let conn = self.conn.lock().unwrap();
+        let affected = conn
+            .execute(
+                "UPDATE explorations SET deleted_at = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+                params![chrono::Utc::now().to_rfc3339(), id],
+            )
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        if affected == 0 {
+            return Err(AppError::NotFound(format!("Exploration {id} not found")));
+        }
+        Ok(())
+    }
+
+    pub fn restore_exploration(&self, id: &str) -> Result<(), AppError> {
+        let conn = self.conn.lock().unwrap();
+        let affected = conn
+            .execute(
+                "UPDATE explorations SET deleted_at = NULL
+                 WHERE id = ?1 AND deleted_at IS NOT NULL",
+                params![id],
+            )
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        if affected == 0 {
+            return Err(AppError::NotFound(format!("Exploration {id} not found in the trash")));
+        }
+        Ok(())
+    }
+
+    /// Delete an exploration and its messages for good, whether or not it is
+    /// in the trash.
+    pub fn purge_exploration(&self, id: &str) -> Result<(), AppError> {
         let conn = self.conn.lock().unwrap();
         let affected = conn
             .execute("DELETE FROM explorations WHERE id = ?1", params![id])
@@ -582,28 +941,90 @@ impl Database {
         Ok(results)
     }
 
-    pub fn add_message(
+    /// Up to `limit` messages of an exploration, oldest first. With `before`
+    /// (a message id) they are the ones just before that message, with
+    /// `after` the ones just after it; with neither, the most recent.
+    /// Messages sharing a timestamp are ordered by insertion.
+    pub fn list_messages_page(
         &self,
         exploration_id: &str,
-        role: &str,
-        content: &str,
-        metadata: Option<&str>,
-    ) -> Result<ExplorationMessage, AppError> {
+        limit: usize,
+        before: Option<&str>,
+        after: Option<&str>,
+    ) -> Result<Vec<ExplorationMessage>, AppError> {
         let conn = self.conn.lock().unwrap();
-        let id = uuid::Uuid::new_v4().to_string();
-        let created_at = chrono::Utc::now().to_rfc3339();
+        let cursor = "(SELECT created_at, rowid FROM exploration_messages WHERE id = ?2)";
+        let (filter, order, cursor_id) = match (before, after) {
+            (Some(id), _) => (format!("AND (created_at, rowid) < {cursor}"), "DESC", Some(id)),
+            (None, Some(id)) => (format!("AND (created_at, rowid) > {cursor}"), "ASC", Some(id)),
+            (None, None) => (String::new(), "DESC", None),
+        };
+        if let Some(id) = cursor_id {
+            let exists = conn
+                .query_row(
+                    "SELECT 1 FROM exploration_messages WHERE id = ?1 AND exploration_id = ?2",
+                    params![id, exploration_id],
+                    |_| Ok(()),
+                )
+                .is_ok();
+            if !exists {
+                return Err(AppError::NotFound(format!("Message {id} not found")));
+            }
+        }
 
-        conn.execute(
-            "INSERT INTO exploration_messages (id, exploration_id, role, content, metadata, created_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-            params![&id, exploration_id, role, content, metadata, &created_at],
-        )
-        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT id, exploration_id, role, content, metadata, created_at
+                 FROM exploration_messages
+                 WHERE exploration_id = ?1 {filter}
+                 ORDER BY created_at {order}, rowid {order}
+                 LIMIT ?3"
+            ))
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
-        Ok(ExplorationMessage {
-            id,
-            exploration_id: exploration_id.to_string(),
-            role: role.to_string(),
+        let mut results: Vec<ExplorationMessage> = stmt
+            .query_map(params![exploration_id, cursor_id, limit as i64], |row| {
+                Ok(ExplorationMessage {
+                    id: row.get(0)?,
+                    exploration_id: row.get(1)?,
+                    role: row.get(2)?,
+                    content: row.get(3)?,
+                    metadata: row.get(4)?,
+                    created_at: row.get(5)?,
+                })
+            })
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?
+            .filter_map(|r| r.ok())
+            .collect();
+        if order == "DESC" {
+            results.reverse();
+        }
+
+        Ok(results)
+    }
+
+    pub fn add_message(
+        &self,
+        exploration_id: &str,
+        role: &str,
+        content: &str,
+        metadata: Option<&str>,
+    ) -> Result<ExplorationMessage, AppError> {
+        let conn = self.conn.lock().unwrap();
+        let id = uuid::Uuid::new_v4().to_string();
+        let created_at = chrono::Utc::now().to_rfc3339();
+
+        conn.execute(
+            "INSERT INTO exploration_messages (id, exploration_id, role, content, metadata, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![&id, exploration_id, role, content, metadata, &created_at],
+        )
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(ExplorationMessage {
+            id,
+            exploration_id: exploration_id.to_string(),
+            role: role.to_string(),
             content: content.to_string(),
             metadata: metadata.map(|s| s.to_string()),
             created_at,
@@ -611,83 +1032,93 @@ impl Database {
     }
 
     pub fn get_exploration_token_count(&self, exploration_id: &str) -> Result<u64, AppError> {
+        Ok(self.get_exploration_counters(exploration_id)?.token_count)
+    }
+
+    pub fn add_exploration_tokens(&self, exploration_id: &str, count: i64) -> Result<(), AppError> {
         let conn = self.conn.lock().unwrap();
-        let result = conn.query_row(
-            "SELECT token_count FROM exploration_store_state WHERE exploration_id = ?1",
-            params![exploration_id],
-            |row| row.get::<_, i64>(0),
-        );
+        let affected = conn
+            .execute(
+                "UPDATE explorations SET token_count = MAX(0, token_count + ?1) WHERE id = ?2",
+                params![count, exploration_id],
+            )
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
-        match result {
-            Ok(v) => Ok(v.max(0) as u64),
-            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(0),
-            Err(e) => Err(AppError::DatabaseError(e.to_string())),
+        if affected == 0 {
+            return Err(AppError::NotFound(format!("Exploration {exploration_id} not found")));
         }
+        Ok(())
     }
 
-    pub fn add_exploration_tokens(&self, exploration_id: &str, count: i64) -> Result<(), AppError> {
+    pub fn get_exploration_turn_count(&self, exploration_id: &str) -> Result<u64, AppError> {
+        Ok(self.get_exploration_counters(exploration_id)?.turn_count)
+    }
+
+    pub fn increment_exploration_turn(&self, exploration_id: &str) -> Result<(), AppError> {
         let conn = self.conn.lock().unwrap();
-        let updated_at = chrono::Utc::now().to_rfc3339();
-        conn.execute(
-            "INSERT INTO exploration_store_state (exploration_id, token_count, turn_count, updated_at)
-             VALUES (?1, ?2, 0, ?3)
-             ON CONFLICT(exploration_id) DO UPDATE SET
-               token_count = MAX(0, exploration_store_state.token_count + excluded.token_count),
-               updated_at = excluded.updated_at",
-            params![exploration_id, count, &updated_at],
-        )
-        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        let affected = conn
+            .execute(
+                "UPDATE explorations SET turn_count = turn_count + 1 WHERE id = ?1",
+                params![exploration_id],
+            )
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        if affected == 0 {
+            return Err(AppError::NotFound(format!("Exploration {exploration_id} not found")));
+        }
         Ok(())
     }
 
-    pub fn get_exploration_turn_count(&self, exploration_id: &str) -> Result<u64, AppError> {
+    /// Read both counters in a single statement so callers never observe a
+    /// token count from one update paired with a turn count from another.
+    pub fn get_exploration_counters(
+        &self,
+        exploration_id: &str,
+    ) -> Result<ExplorationCounters, AppError> {
         let conn = self.conn.lock().unwrap();
         let result = conn.query_row(
-            "SELECT turn_count FROM exploration_store_state WHERE exploration_id = ?1",
+            "SELECT token_count, turn_count FROM explorations WHERE id = ?1",
             params![exploration_id],
-            |row| row.get::<_, i64>(0),
+            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)),
         );
 
         match result {
-            Ok(v) => Ok(v.max(0) as u64),
-            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(0),
+            Ok((tokens, turns)) => Ok(ExplorationCounters {
+                token_count: tokens.max(0) as u64,
+                turn_count: turns.max(0) as u64,
+            }),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(ExplorationCounters {
+                token_count: 0,
+                turn_count: 0,
+            }),
             Err(e) => Err(AppError::DatabaseError(e.to_string())),
         }
     }
 
-    pub fn increment_exploration_turn(&self, exploration_id: &str) -> Result<(), AppError> {
-        let conn = self.conn.lock().unwrap();
-        let updated_at = chrono::Utc::now().to_rfc3339();
-        conn.execute(
-            "INSERT INTO exploration_store_state (exploration_id, token_count, turn_count, updated_at)
-             VALUES (?1, 0, 1, ?2)
-             ON CONFLICT(exploration_id) DO UPDATE SET
-               turn_count = exploration_store_state.turn_count + 1,
-               updated_at = excluded.updated_at",
-            params![exploration_id, &updated_at],
-        )
-        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
-        Ok(())
-    }
-
-    pub fn reset_exploration_history(&self, exploration_id: &str) -> Result<(), AppError> {
+    pub fn reset_exploration_history(
+        &self,
+        exploration_id: &str,
+        clear_messages: bool,
+    ) -> Result<(), AppError> {
         let mut conn = self.conn.lock().unwrap();
         let tx = conn
             .transaction()
             .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
-        let updated_at = chrono::Utc::now().to_rfc3339();
         tx.execute(
-            "INSERT INTO exploration_store_state (exploration_id, token_count, turn_count, updated_at)
-             VALUES (?1, 0, 0, ?2)
-             ON CONFLICT(exploration_id) DO UPDATE SET
-               token_count = 0,
-               turn_count = 0,
-               updated_at = excluded.updated_at",
-            params![exploration_id, &updated_at],
+            "UPDATE explorations SET token_count = 0, turn_count = 0 WHERE id = ?1",
+            params![exploration_id],
         )
         .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
+        if clear_messages {
+            tx.execute(
+                "DELETE FROM exploration_messages WHERE exploration_id = ?1",
+                params![exploration_id],
+            )
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        }
+
         tx.commit()
             .map_err(|e| AppError::DatabaseError(e.to_string()))?;
         Ok(())
@@ -699,23 +1130,35 @@ impl Database {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn
             .prepare(
-                "SELECT id, name, description, sql_text, connection_id, created_at
+                "SELECT id, name, description, sql_text, connection_id, created_at, updated_at, tags
                  FROM saved_queries
                  ORDER BY created_at",
             )
             .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
         let results = stmt
-            .query_map([], |row| {
-                Ok(SavedQuery {
-                    id: row.get(0)?,
-                    name: row.get(1)?,
-                    description: row.get(2)?,
-                    sql: row.get(3)?,
-                    connection_id: row.get(4)?,
-                    created_at: row.get(5)?,
-                })
-            })
+            .query_map([], saved_query_from_row)
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(results)
+    }
+
+    /// Saved queries carrying `tag`, matched exactly.
+    pub fn list_saved_queries_by_tag(&self, tag: &str) -> Result<Vec<SavedQuery>, AppError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, name, description, sql_text, connection_id, created_at, updated_at, tags
+                 FROM saved_queries
+                 WHERE instr(',' || tags || ',', ',' || ?1 || ',') > 0
+                 ORDER BY created_at",
+            )
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let results = stmt
+            .query_map(params![tag.trim()], saved_query_from_row)
             .map_err(|e| AppError::DatabaseError(e.to_string()))?
             .filter_map(|r| r.ok())
             .collect();
@@ -723,21 +1166,48 @@ impl Database {
         Ok(results)
     }
 
+    /// Every tag in use on a saved query and how many queries carry it, by
+    /// tag.
+    pub fn list_saved_query_tags(&self) -> Result<Vec<SavedQueryTag>, AppError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare("SELECT tags FROM saved_queries WHERE tags <> ''")
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let mut counts = std::collections::BTreeMap::<String, u32>::new();
+        let rows = stmt
+            .query_map([], |row| row.get::<_, String>(0))
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        for tags in rows.filter_map(|r| r.ok()) {
+            for tag in split_tags(&tags) {
+                *counts.entry(tag).or_default() += 1;
+            }
+        }
+
+        Ok(counts
+            .into_iter()
+            .map(|(tag, count)| SavedQueryTag { tag, count })
+            .collect())
+    }
+
     pub fn save_query(
         &self,
         name: &str,
         description: &str,
         sql: &str,
         connection_id: &str,
+        tags: &[String],
     ) -> Result<SavedQuery, AppError> {
         let conn = self.conn.lock().unwrap();
         let id = uuid::Uuid::new_v4().to_string();
         let created_at = chrono::Utc::now().to_rfc3339();
+        let tags = join_tags(tags);
 
         conn.execute(
-            "INSERT INTO saved_queries (id, name, description, sql_text, connection_id, created_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-            params![&id, name, description, sql, connection_id, &created_at],
+            "INSERT INTO saved_queries
+               (id, name, description, sql_text, connection_id, created_at, updated_at, tags)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6, ?7)",
+            params![&id, name, description, sql, connection_id, &created_at, &tags],
         )
         .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
@@ -747,51 +1217,451 @@ impl Database {
             description: description.to_string(),
             sql: sql.to_string(),
             connection_id: connection_id.to_string(),
+            updated_at: Some(created_at.clone()),
             created_at,
+            tags: split_tags(&tags),
         })
     }
 
+    /// A saved query by id or, failing that, by name. Names match without
+    /// regard to case, and with dashes standing for spaces as typed in a
+    /// `/daily-revenue` slash command.
+    pub fn find_saved_query(&self, query_ref: &str) -> Result<SavedQuery, AppError> {
+        let conn = self.conn.lock().unwrap();
+        let found = conn.query_row(
+            "SELECT id, name, description, sql_text, connection_id, created_at, updated_at, tags
+             FROM saved_queries
+             WHERE id = ?1
+                OR lower(name) = lower(?1)
+                OR replace(lower(name), ' ', '-') = lower(?1)
+             ORDER BY id = ?1 DESC, lower(name) = lower(?1) DESC, created_at DESC
+             LIMIT 1",
+            params![query_ref],
+            saved_query_from_row,
+        );
+        match found {
+            Ok(query) => Ok(query),
+            Err(rusqlite::Error::QueryReturnedNoRows) => {
+                Err(AppError::NotFound(format!("Saved query {query_ref} not found")))
+            }
+            Err(e) => Err(AppError::DatabaseError(e.to_string())),
+        }
+    }
+
+    /// Edit a saved query in place, keeping its id and `created_at`. Tags
+    /// are left alone when `tags` is `None`.
+    pub fn update_saved_query(
+        &self,
+        id: &str,
+        name: &str,
+        description: &str,
+        sql: &str,
+        tags: Option<&[String]>,
+    ) -> Result<SavedQuery, AppError> {
+        let conn = self.conn.lock().unwrap();
+        let updated_at = chrono::Utc::now().to_rfc3339();
+        let affected = conn
+            .execute(
+                "UPDATE saved_queries
+                 SET name = ?1, description = ?2, sql_text = ?3, updated_at = ?4,
+                     tags = COALESCE(?6, tags)
+                 WHERE id = ?5",
+                params![name, description, sql, &updated_at, id, tags.map(join_tags)],
+            )
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        if affected == 0 {
+            return Err(AppError::NotFound(format!("Query {id} not found")));
+        }
+
+        conn.query_row(
+            "SELECT id, name, description, sql_text, connection_id, created_at, updated_at, tags
+             FROM saved_queries WHERE id = ?1",
+            params![id],
+            saved_query_from_row,
+        )
+        .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+
     pub fn delete_saved_query(&self, id: &str) -> Result<(), AppError> {
         let conn = self.conn.lock().unwrap();
         let affected = conn
             .execute("DELETE FROM saved_queries WHERE id = ?1", params![id])
             .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
-        if affected == 0 {
-            return Err(AppError::NotFound(format!("Query {id} not found")));
-        }
-        Ok(())
+        if affected == 0 {
+            return Err(AppError::NotFound(format!("Query {id} not found")));
+        }
+        Ok(())
+    }
+
+    // --- Query History ---
+
+    /// Record a query run on `connection_id`, dropping the oldest entries
+    /// beyond `QUERY_HISTORY_LIMIT`.
+    pub fn add_query_history(
+        &self,
+        connection_id: &str,
+        sql: &str,
+        duration_ms: u64,
+        row_count: Option<usize>,
+        error: Option<&str>,
+    ) -> Result<QueryHistoryEntry, AppError> {
+        let conn = self.conn.lock().unwrap();
+        let id = uuid::Uuid::new_v4().to_string();
+        let executed_at = chrono::Utc::now().to_rfc3339();
+
+        conn.execute(
+            "INSERT INTO query_history
+               (id, connection_id, sql_text, executed_at, duration_ms, row_count, success, error)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                &id,
+                connection_id,
+                sql,
+                &executed_at,
+                duration_ms as i64,
+                row_count.map(|n| n as i64),
+                error.is_none(),
+                error
+            ],
+        )
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        conn.execute(
+            "DELETE FROM query_history WHERE connection_id = ?1 AND id NOT IN (
+               SELECT id FROM query_history WHERE connection_id = ?1
+               ORDER BY executed_at DESC LIMIT ?2)",
+            params![connection_id, QUERY_HISTORY_LIMIT],
+        )
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(QueryHistoryEntry {
+            id,
+            connection_id: connection_id.to_string(),
+            sql: sql.to_string(),
+            executed_at,
+            duration_ms,
+            row_count,
+            success: error.is_none(),
+            error: error.map(|e| e.to_string()),
+        })
+    }
+
+    /// The most recent `limit` queries run on `connection_id`, newest first.
+    pub fn list_query_history(
+        &self,
+        connection_id: &str,
+        limit: usize,
+    ) -> Result<Vec<QueryHistoryEntry>, AppError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, connection_id, sql_text, executed_at, duration_ms, row_count, success, error
+                 FROM query_history
+                 WHERE connection_id = ?1
+                 ORDER BY executed_at DESC
+                 LIMIT ?2",
+            )
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let results = stmt
+            .query_map(params![connection_id, limit as i64], |row| {
+                Ok(QueryHistoryEntry {
+                    id: row.get(0)?,
+                    connection_id: row.get(1)?,
+                    sql: row.get(2)?,
+                    executed_at: row.get(3)?,
+                    duration_ms: row.get::<_, i64>(4)? as u64,
+                    row_count: row.get::<_, Option<i64>>(5)?.map(|n| n as usize),
+                    success: row.get(6)?,
+                    error: row.get(7)?,
+                })
+            })
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(results)
+    }
+
+    pub fn clear_query_history(&self, connection_id: &str) -> Result<(), AppError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM query_history WHERE connection_id = ?1",
+            params![connection_id],
+        )
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    // --- Saved Charts ---
+
+    pub fn rename_saved_chart(&self, id: &str, name: &str) -> Result<(), AppError> {
+        let conn = self.conn.lock().unwrap();
+        let affected = conn
+            .execute(
+                "UPDATE saved_charts SET name = ?1, updated_at = ?2 WHERE id = ?3",
+                params![name, chrono::Utc::now().to_rfc3339(), id],
+            )
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        if affected == 0 {
+            return Err(AppError::NotFound(format!("Chart {id} not found")));
+        }
+        Ok(())
+    }
+
+    pub fn list_saved_charts(&self) -> Result<Vec<SavedChart>, AppError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, name, description, chart_type, x_key, y_key, connection_id, sql_text, data_json, created_at, updated_at
+                 FROM saved_charts
+                 ORDER BY created_at DESC",
+            )
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let results = stmt
+            .query_map([], saved_chart_from_row)
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(results)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn save_saved_chart(
+        &self,
+        name: &str,
+        description: &str,
+        chart_type: &str,
+        x_key: &str,
+        y_key: &str,
+        connection_id: Option<&str>,
+        sql: Option<&str>,
+        data: &serde_json::Value,
+    ) -> Result<SavedChart, AppError> {
+        let conn = self.conn.lock().unwrap();
+        let id = uuid::Uuid::new_v4().to_string();
+        let created_at = chrono::Utc::now().to_rfc3339();
+        let data_json = serde_json::to_string(data)
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        conn.execute(
+            "INSERT INTO saved_charts (id, name, description, chart_type, x_key, y_key, connection_id, sql_text, data_json, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?10)",
+            params![
+                &id,
+                name,
+                description,
+                chart_type,
+                x_key,
+                y_key,
+                connection_id,
+                sql,
+                &data_json,
+                &created_at
+            ],
+        )
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(SavedChart {
+            id,
+            name: name.to_string(),
+            description: description.to_string(),
+            chart_type: chart_type.to_string(),
+            x_key: x_key.to_string(),
+            y_key: y_key.to_string(),
+            connection_id: connection_id.map(|s| s.to_string()),
+            sql: sql.map(|s| s.to_string()),
+            data: data.clone(),
+            updated_at: Some(created_at.clone()),
+            created_at,
+        })
+    }
+
+    /// Overwrite the chart with `id` rather than inserting a new one.
+    #[allow(clippy::too_many_arguments)]
+    pub fn update_saved_chart(
+        &self,
+        id: &str,
+        name: &str,
+        description: &str,
+        chart_type: &str,
+        x_key: &str,
+        y_key: &str,
+        connection_id: Option<&str>,
+        sql: Option<&str>,
+        data: &serde_json::Value,
+    ) -> Result<SavedChart, AppError> {
+        let conn = self.conn.lock().unwrap();
+        let updated_at = chrono::Utc::now().to_rfc3339();
+        let data_json = serde_json::to_string(data)
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let affected = conn
+            .execute(
+                "UPDATE saved_charts SET name = ?1, description = ?2, chart_type = ?3, x_key = ?4,
+                   y_key = ?5, connection_id = ?6, sql_text = ?7, data_json = ?8, updated_at = ?9
+                 WHERE id = ?10",
+                params![
+                    name,
+                    description,
+                    chart_type,
+                    x_key,
+                    y_key,
+                    connection_id,
+                    sql,
+                    &data_json,
+                    &updated_at,
+                    id
+                ],
+            )
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        if affected == 0 {
+            return Err(AppError::NotFound(format!("Chart {id} not found")));
+        }
+
+        conn.query_row(
+            "SELECT id, name, description, chart_type, x_key, y_key, connection_id, sql_text, data_json, created_at, updated_at
+             FROM saved_charts WHERE id = ?1",
+            params![id],
+            saved_chart_from_row,
+        )
+        .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+
+    pub fn delete_saved_chart(&self, id: &str) -> Result<(), AppError> {
+        let conn = self.conn.lock().unwrap();
+        let affected = conn
+            .execute("DELETE FROM saved_charts WHERE id = ?1", params![id])
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        if affected == 0 {
+            return Err(AppError::NotFound(format!("Chart {id} not found")));
+        }
+        Ok(())
+    }
+
+    // --- Dashboards ---
+
+    pub fn list_dashboards(&self) -> Result<Vec<Dashboard>, AppError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare("SELECT id, name, description, created_at FROM dashboards ORDER BY name")
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let results = stmt
+            .query_map([], |row| {
+                Ok(Dashboard {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    description: row.get(2)?,
+                    created_at: row.get(3)?,
+                })
+            })
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(results)
+    }
+
+    pub fn create_dashboard(&self, name: &str, description: &str) -> Result<Dashboard, AppError> {
+        let conn = self.conn.lock().unwrap();
+        let id = uuid::Uuid::new_v4().to_string();
+        let created_at = chrono::Utc::now().to_rfc3339();
+
+        conn.execute(
+            "INSERT INTO dashboards (id, name, description, created_at) VALUES (?1, ?2, ?3, ?4)",
+            params![&id, name, description, &created_at],
+        )
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(Dashboard {
+            id,
+            name: name.to_string(),
+            description: description.to_string(),
+            created_at,
+        })
+    }
+
+    /// Append a chart to the end of a dashboard. Adding a chart that is
+    /// already on the dashboard leaves its position unchanged.
+    pub fn add_chart_to_dashboard(&self, dashboard_id: &str, chart_id: &str) -> Result<(), AppError> {
+        let conn = self.conn.lock().unwrap();
+        for (table, id, label) in [
+            ("dashboards", dashboard_id, "Dashboard"),
+            ("saved_charts", chart_id, "Chart"),
+        ] {
+            let exists: bool = conn
+                .query_row(
+                    &format!("SELECT EXISTS(SELECT 1 FROM {table} WHERE id = ?1)"),
+                    params![id],
+                    |row| row.get(0),
+                )
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            if !exists {
+                return Err(AppError::NotFound(format!("{label} {id} not found")));
+            }
+        }
+
+        conn.execute(
+            "INSERT OR IGNORE INTO saved_chart_dashboard (dashboard_id, chart_id, position)
+             SELECT ?1, ?2, COALESCE(MAX(position) + 1, 0) FROM saved_chart_dashboard WHERE dashboard_id = ?1",
+            params![dashboard_id, chart_id],
+        )
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    pub fn list_dashboard_charts(&self, dashboard_id: &str) -> Result<Vec<SavedChart>, AppError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare(
+                "SELECT c.id, c.name, c.description, c.chart_type, c.x_key, c.y_key, c.connection_id, c.sql_text, c.data_json, c.created_at, c.updated_at
+                 FROM saved_charts c
+                 INNER JOIN saved_chart_dashboard d ON d.chart_id = c.id
+                 WHERE d.dashboard_id = ?1
+                 ORDER BY d.position",
+            )
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let results = stmt
+            .query_map(params![dashboard_id], saved_chart_from_row)
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(results)
     }
 
-    // --- Saved Charts ---
+    // --- Snippets ---
 
-    pub fn list_saved_charts(&self) -> Result<Vec<SavedChart>, AppError> {
+    /// Snippets usable on `connection_id`: the global ones plus that
+    /// connection's own, which comes first where both use a name. With no
+    /// connection, only the global ones.
+    pub fn list_snippets(&self, connection_id: Option<&str>) -> Result<Vec<Snippet>, AppError> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn
             .prepare(
-                "SELECT id, name, description, chart_type, x_key, y_key, connection_id, sql_text, data_json, created_at
-                 FROM saved_charts
-                 ORDER BY created_at DESC",
+                "SELECT id, name, body, connection_id, tags_json, created_at
+                 FROM snippets
+                 WHERE connection_id IS NULL OR connection_id = ?1
+                 ORDER BY name, connection_id IS NULL",
             )
             .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
         let results = stmt
-            .query_map([], |row| {
-                let data_json: String = row.get(8)?;
-                let data = serde_json::from_str::<serde_json::Value>(&data_json)
-                    .unwrap_or(serde_json::Value::Array(vec![]));
-
-                Ok(SavedChart {
+            .query_map(params![connection_id], |row| {
+                let tags_json: String = row.get(4)?;
+                Ok(Snippet {
                     id: row.get(0)?,
                     name: row.get(1)?,
-                    description: row.get(2)?,
-                    chart_type: row.get(3)?,
-                    x_key: row.get(4)?,
-                    y_key: row.get(5)?,
-                    connection_id: row.get(6)?,
-                    sql: row.get(7)?,
-                    data,
-                    created_at: row.get(9)?,
+                    body: row.get(2)?,
+                    connection_id: row.get(3)?,
+                    tags: serde_json::from_str(&tags_json).unwrap_or_default(),
+                    created_at: row.get(5)?,
                 })
             })
             .map_err(|e| AppError::DatabaseError(e.to_string()))?
@@ -801,63 +1671,81 @@ impl Database {
         Ok(results)
     }
 
-    pub fn save_saved_chart(
+    /// Create a snippet, or replace the one with `id`.
+    pub fn save_snippet(
         &self,
+        id: Option<&str>,
         name: &str,
-        description: &str,
-        chart_type: &str,
-        x_key: &str,
-        y_key: &str,
+        body: &str,
         connection_id: Option<&str>,
-        sql: Option<&str>,
-        data: &serde_json::Value,
-    ) -> Result<SavedChart, AppError> {
+        tags: &[String],
+    ) -> Result<Snippet, AppError> {
         let conn = self.conn.lock().unwrap();
-        let id = uuid::Uuid::new_v4().to_string();
-        let created_at = chrono::Utc::now().to_rfc3339();
-        let data_json = serde_json::to_string(data)
-            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
-
-        conn.execute(
-            "INSERT INTO saved_charts (id, name, description, chart_type, x_key, y_key, connection_id, sql_text, data_json, created_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
-            params![
-                &id,
-                name,
-                description,
-                chart_type,
-                x_key,
-                y_key,
-                connection_id,
-                sql,
-                &data_json,
-                &created_at
-            ],
-        )
-        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
-
-        Ok(SavedChart {
+        let tags_json = serde_json::to_string(tags)
+            .map_err(|e| AppError::DatabaseError(format!("Failed to serialize tags: {e}")))?;
+        let write_error = |e: rusqlite::Error| match e {
+            rusqlite::Error::SqliteFailure(ref err, _)
+                if err.extended_code == rusqlite::ffi::SQLITE_CONSTRAINT_UNIQUE =>
+            {
+                AppError::QueryError(format!(
+                    "A snippet named {name} already exists for this connection"
+                ))
+            }
+            _ => AppError::DatabaseError(e.to_string()),
+        };
+
+        let (id, created_at) = match id {
+            Some(id) => {
+                let affected = conn
+                    .execute(
+                        "UPDATE snippets SET name = ?1, body = ?2, connection_id = ?3, tags_json = ?4
+                         WHERE id = ?5",
+                        params![name, body, connection_id, &tags_json, id],
+                    )
+                    .map_err(write_error)?;
+                if affected == 0 {
+                    return Err(AppError::NotFound(format!("Snippet {id} not found")));
+                }
+                let created_at: String = conn
+                    .query_row(
+                        "SELECT created_at FROM snippets WHERE id = ?1",
+                        params![id],
+                        |row| row.get(0),
+                    )
+                    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+                (id.to_string(), created_at)
+            }
+            None => {
+                let id = uuid::Uuid::new_v4().to_string();
+                let created_at = chrono::Utc::now().to_rfc3339();
+                conn.execute(
+                    "INSERT INTO snippets (id, name, body, connection_id, tags_json, created_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![&id, name, body, connection_id, &tags_json, &created_at],
+                )
+                .map_err(write_error)?;
+                (id, created_at)
+            }
+        };
+
+        Ok(Snippet {
             id,
             name: name.to_string(),
-            description: description.to_string(),
-            chart_type: chart_type.to_string(),
-            x_key: x_key.to_string(),
-            y_key: y_key.to_string(),
-            connection_id: connection_id.map(|s| s.to_string()),
-            sql: sql.map(|s| s.to_string()),
-            data: data.clone(),
+            body: body.to_string(),
+            connection_id: connection_id.map(|c| c.to_string()),
+            tags: tags.to_vec(),
             created_at,
         })
     }
 
-    pub fn delete_saved_chart(&self, id: &str) -> Result<(), AppError> {
+    pub fn delete_snippet(&self, id: &str) -> Result<(), AppError> {
         let conn = self.conn.lock().unwrap();
         let affected = conn
-            .execute("DELETE FROM saved_charts WHERE id = ?1", params![id])
+            .execute("DELETE FROM snippets WHERE id = ?1", params![id])
             .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
         if affected == 0 {
-            return Err(AppError::NotFound(format!("Chart {id} not found")));
+            return Err(AppError::NotFound(format!("Snippet {id} not found")));
         }
         Ok(())
     }
@@ -1009,12 +1897,16 @@ impl Database {
 
         let connection = conn
             .query_row(
-                "SELECT id, connected FROM connections WHERE id = ?1",
+                "SELECT id, connected, db_type, host, port, database_name FROM connections WHERE id = ?1",
                 params![connection_id],
                 |row| {
                     Ok((
                         row.get::<_, String>(0)?,
                         row.get::<_, i32>(1)? != 0,
+                        row.get::<_, String>(2)?,
+                        row.get::<_, String>(3)?,
+                        row.get::<_, i32>(4)? as u16,
+                        row.get::<_, String>(5)?,
                     ))
                 },
             )
@@ -1029,9 +1921,78 @@ impl Database {
             total_row_count: 0,
             disk_usage_bytes: 0,
             connected: connection.1,
+            kind: DatabaseKind::from_str_loose(&connection.2),
+            host: Some(connection.3),
+            port: Some(connection.4),
+            database: Some(connection.5),
         })
     }
 
+    // --- Workspace search ---
+
+    /// Saved queries, connection notes and exploration messages matching
+    /// every word of `query` (as a prefix), best match first, optionally only
+    /// those of `kind`.
+    pub fn search_workspace(
+        &self,
+        query: &str,
+        kind: Option<WorkspaceItemKind>,
+        limit: usize,
+    ) -> Result<Vec<WorkspaceSearchResult>, AppError> {
+        // Quote each word so FTS5 operators and punctuation match literally.
+        let terms: Vec<String> = query
+            .split_whitespace()
+            .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+            .collect();
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare(
+                "SELECT s.kind, s.entity_id,
+                        CASE s.kind
+                          WHEN 'saved_query' THEN s.title
+                          WHEN 'connection_note' THEN c.name
+                          ELSE e.title
+                        END,
+                        m.exploration_id,
+                        snippet(workspace_search, -1, '**', '**', '…', 16)
+                 FROM workspace_search s
+                 LEFT JOIN connections c
+                   ON s.kind = 'connection_note' AND c.id = s.entity_id
+                 LEFT JOIN exploration_messages m
+                   ON s.kind = 'message' AND m.id = s.entity_id
+                 LEFT JOIN explorations e ON e.id = m.exploration_id
+                 WHERE workspace_search MATCH ?1 AND (?2 IS NULL OR s.kind = ?2)
+                   AND e.deleted_at IS NULL
+                 ORDER BY rank
+                 LIMIT ?3",
+            )
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let results = stmt
+            .query_map(
+                params![terms.join(" "), kind.map(WorkspaceItemKind::as_str), limit as i64],
+                |row| {
+                    let kind = WorkspaceItemKind::parse(&row.get::<_, String>(0)?);
+                    Ok(kind.map(|kind| WorkspaceSearchResult {
+                        kind,
+                        id: row.get(1).unwrap_or_default(),
+                        title: row.get(2).unwrap_or_default(),
+                        parent_id: row.get(3).unwrap_or_default(),
+                        snippet: row.get(4).unwrap_or_default(),
+                    }))
+                },
+            )
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?
+            .filter_map(|r| r.ok().flatten())
+            .collect();
+
+        Ok(results)
+    }
+
     // --- Settings ---
 
     pub fn get_setting(&self, key: &str) -> Result<Option<String>, AppError> {
@@ -1049,6 +2010,11 @@ impl Database {
     }
 
     pub fn set_setting(&self, key: &str, value: &str) -> Result<(), AppError> {
+        let value = if SECRET_SETTINGS.contains(&key) {
+            self.secrets.encrypt(value)?
+        } else {
+            value.to_string()
+        };
         let conn = self.conn.lock().unwrap();
         conn.execute(
             "INSERT INTO settings (key, value) VALUES (?1, ?2) ON CONFLICT(key) DO UPDATE SET value = excluded.value",
@@ -1057,4 +2023,918 @@ impl Database {
         .map_err(|e| AppError::DatabaseError(e.to_string()))?;
         Ok(())
     }
+
+    /// The decrypted value of one of `SECRET_SETTINGS`. A value that no
+    /// longer decrypts reads as unset, like a saved password.
+    pub fn get_secret_setting(&self, key: &str) -> Result<Option<String>, AppError> {
+        Ok(self
+            .get_setting(key)?
+            .map(|stored| self.secrets.decrypt(&stored).unwrap_or_default())
+            .filter(|value| !value.is_empty()))
+    }
+
+    /// All settings whose key starts with `prefix` (every setting when empty),
+    /// ordered by key.
+    pub fn list_settings(&self, prefix: &str) -> Result<Vec<(String, String)>, AppError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare(
+                "SELECT key, value FROM settings
+                 WHERE substr(key, 1, length(?1)) = ?1
+                 ORDER BY key",
+            )
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let results = stmt
+            .query_map(params![prefix], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(results)
+    }
+
+    pub fn delete_setting(&self, key: &str) -> Result<(), AppError> {
+        let conn = self.conn.lock().unwrap();
+        let affected = conn
+            .execute("DELETE FROM settings WHERE key = ?1", params![key])
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        if affected == 0 {
+            return Err(AppError::NotFound(format!("Setting {key} not found")));
+        }
+        Ok(())
+    }
+
+    // --- Schema cache ---
+
+    /// The cached schema JSON for a connection and when it was cached
+    /// (RFC 3339).
+    pub fn get_schema_cache(
+        &self,
+        connection_id: &str,
+    ) -> Result<Option<(String, String)>, AppError> {
+        let conn = self.conn.lock().unwrap();
+        let result = conn.query_row(
+            "SELECT schema_json, cached_at FROM schema_cache WHERE connection_id = ?1",
+            params![connection_id],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+        );
+        match result {
+            Ok(cached) => Ok(Some(cached)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(AppError::DatabaseError(e.to_string())),
+        }
+    }
+
+    pub fn save_schema_cache(
+        &self,
+        connection_id: &str,
+        schema_json: &str,
+    ) -> Result<(), AppError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO schema_cache (connection_id, schema_json, cached_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(connection_id) DO UPDATE SET
+               schema_json = excluded.schema_json, cached_at = excluded.cached_at",
+            params![connection_id, schema_json, chrono::Utc::now().to_rfc3339()],
+        )
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Forget a connection's cached schema. Not an error when none is cached.
+    pub fn delete_schema_cache(&self, connection_id: &str) -> Result<(), AppError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM schema_cache WHERE connection_id = ?1",
+            params![connection_id],
+        )
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+}
+
+/// Schema migrations, applied in order by `run_migrations`; entry `i` is
+/// version `i + 1`. Append new migrations, never edit applied ones.
+const MIGRATIONS: &[&str] = &[
+    // 1: the schema as it was before versioned migrations.
+    "CREATE TABLE IF NOT EXISTS projects (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            description TEXT NOT NULL DEFAULT '',
+            created_at TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS connections (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            db_type TEXT NOT NULL,
+            host TEXT NOT NULL,
+            port INTEGER NOT NULL,
+            database_name TEXT NOT NULL,
+            username TEXT NOT NULL,
+            connected INTEGER NOT NULL DEFAULT 0
+        );
+
+        CREATE TABLE IF NOT EXISTS project_connections (
+            project_id TEXT NOT NULL,
+            connection_id TEXT NOT NULL,
+            PRIMARY KEY (project_id, connection_id),
+            FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE,
+            FOREIGN KEY (connection_id) REFERENCES connections(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS explorations (
+            id TEXT PRIMARY KEY,
+            project_id TEXT NOT NULL,
+            title TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS exploration_messages (
+            id TEXT PRIMARY KEY,
+            exploration_id TEXT NOT NULL,
+            role TEXT NOT NULL,
+            content TEXT NOT NULL,
+            metadata TEXT,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (exploration_id) REFERENCES explorations(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS exploration_store_state (
+            exploration_id TEXT PRIMARY KEY,
+            token_count INTEGER NOT NULL DEFAULT 0,
+            turn_count INTEGER NOT NULL DEFAULT 0,
+            updated_at TEXT NOT NULL,
+            FOREIGN KEY (exploration_id) REFERENCES explorations(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS saved_queries (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            description TEXT NOT NULL DEFAULT '',
+            sql_text TEXT NOT NULL,
+            connection_id TEXT NOT NULL,
+            created_at TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS saved_charts (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            description TEXT NOT NULL DEFAULT '',
+            chart_type TEXT NOT NULL,
+            x_key TEXT NOT NULL,
+            y_key TEXT NOT NULL,
+            connection_id TEXT,
+            sql_text TEXT,
+            data_json TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (connection_id) REFERENCES connections(id) ON DELETE SET NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS connection_notes (
+            connection_id TEXT PRIMARY KEY,
+            note TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            FOREIGN KEY (connection_id) REFERENCES connections(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS table_links (
+            id TEXT PRIMARY KEY,
+            source_table TEXT NOT NULL,
+            source_column TEXT NOT NULL,
+            target_table TEXT NOT NULL,
+            target_column TEXT NOT NULL,
+            label TEXT NOT NULL DEFAULT '',
+            connection_id TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );",
+    // 2
+    "ALTER TABLE connections ADD COLUMN password TEXT NOT NULL DEFAULT '';
+     ALTER TABLE connections ADD COLUMN use_ssl INTEGER NOT NULL DEFAULT 0;",
+    // 3: exploration counters move from exploration_store_state onto
+    // explorations so they can be updated in place.
+    "ALTER TABLE explorations ADD COLUMN token_count INTEGER NOT NULL DEFAULT 0;
+     ALTER TABLE explorations ADD COLUMN turn_count INTEGER NOT NULL DEFAULT 0;
+     UPDATE explorations SET
+       token_count = COALESCE((SELECT token_count FROM exploration_store_state s
+                               WHERE s.exploration_id = explorations.id), 0),
+       turn_count = COALESCE((SELECT turn_count FROM exploration_store_state s
+                              WHERE s.exploration_id = explorations.id), 0);",
+    // 4
+    "ALTER TABLE connections ADD COLUMN quote_all_identifiers INTEGER NOT NULL DEFAULT 0;",
+    // 5
+    "CREATE TABLE IF NOT EXISTS dashboards (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            description TEXT NOT NULL DEFAULT '',
+            created_at TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS saved_chart_dashboard (
+            dashboard_id TEXT NOT NULL,
+            chart_id TEXT NOT NULL,
+            position INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (dashboard_id, chart_id),
+            FOREIGN KEY (dashboard_id) REFERENCES dashboards(id) ON DELETE CASCADE,
+            FOREIGN KEY (chart_id) REFERENCES saved_charts(id) ON DELETE CASCADE
+        );",
+    // 6
+    "ALTER TABLE connections ADD COLUMN max_concurrent_queries INTEGER NOT NULL DEFAULT 4;",
+    // 7
+    "CREATE TABLE IF NOT EXISTS snippets (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            body TEXT NOT NULL,
+            connection_id TEXT,
+            tags_json TEXT NOT NULL DEFAULT '[]',
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (connection_id) REFERENCES connections(id) ON DELETE CASCADE
+        );",
+    // 8
+    "ALTER TABLE connections ADD COLUMN connect_timeout_secs INTEGER;
+     ALTER TABLE connections ADD COLUMN query_timeout_secs INTEGER;",
+    // 9
+    "ALTER TABLE connections ADD COLUMN environment TEXT;",
+    // 10
+    "ALTER TABLE connections ADD COLUMN ssl_mode TEXT;
+     ALTER TABLE connections ADD COLUMN ca_cert_path TEXT;
+     ALTER TABLE connections ADD COLUMN client_cert_path TEXT;
+     ALTER TABLE connections ADD COLUMN client_key_path TEXT;",
+    // 11
+    "CREATE TABLE IF NOT EXISTS query_history (
+            id TEXT PRIMARY KEY,
+            connection_id TEXT NOT NULL,
+            sql_text TEXT NOT NULL,
+            executed_at TEXT NOT NULL,
+            duration_ms INTEGER NOT NULL,
+            row_count INTEGER,
+            success INTEGER NOT NULL,
+            error TEXT,
+            FOREIGN KEY (connection_id) REFERENCES connections(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_query_history_connection
+            ON query_history (connection_id, executed_at);",
+    // 12
+    "ALTER TABLE saved_queries ADD COLUMN updated_at TEXT;
+     ALTER TABLE saved_charts ADD COLUMN updated_at TEXT;",
+    // 13
+    "ALTER TABLE connections ADD COLUMN read_only INTEGER NOT NULL DEFAULT 0;",
+    // 14: schema caches used to live in settings, with no timestamp; they
+    // are rebuilt on the next `get_schema`.
+    "CREATE TABLE IF NOT EXISTS schema_cache (
+            connection_id TEXT PRIMARY KEY,
+            schema_json TEXT NOT NULL,
+            cached_at TEXT NOT NULL,
+            FOREIGN KEY (connection_id) REFERENCES connections(id) ON DELETE CASCADE
+        );
+
+        DELETE FROM settings WHERE substr(key, 1, 13) = 'schema_cache:';",
+    // 15
+    "CREATE INDEX IF NOT EXISTS idx_exploration_messages_created
+            ON exploration_messages (exploration_id, created_at);",
+    // 16: the FTS5 index behind `search_workspace`, kept in step with saved
+    // queries, connection notes and exploration messages by triggers, and
+    // filled with the rows written before it existed.
+    "CREATE VIRTUAL TABLE IF NOT EXISTS workspace_search USING fts5(
+            kind UNINDEXED,
+            entity_id UNINDEXED,
+            title,
+            body
+        );
+
+        CREATE TRIGGER IF NOT EXISTS workspace_search_query_insert
+        AFTER INSERT ON saved_queries BEGIN
+            INSERT INTO workspace_search (kind, entity_id, title, body)
+            VALUES ('saved_query', new.id, new.name, new.description || char(10) || new.sql_text);
+        END;
+        CREATE TRIGGER IF NOT EXISTS workspace_search_query_update
+        AFTER UPDATE ON saved_queries BEGIN
+            DELETE FROM workspace_search WHERE kind = 'saved_query' AND entity_id = old.id;
+            INSERT INTO workspace_search (kind, entity_id, title, body)
+            VALUES ('saved_query', new.id, new.name, new.description || char(10) || new.sql_text);
+        END;
+        CREATE TRIGGER IF NOT EXISTS workspace_search_query_delete
+        AFTER DELETE ON saved_queries BEGIN
+            DELETE FROM workspace_search WHERE kind = 'saved_query' AND entity_id = old.id;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS workspace_search_note_insert
+        AFTER INSERT ON connection_notes BEGIN
+            INSERT INTO workspace_search (kind, entity_id, title, body)
+            VALUES ('connection_note', new.connection_id, '', new.note);
+        END;
+        CREATE TRIGGER IF NOT EXISTS workspace_search_note_update
+        AFTER UPDATE ON connection_notes BEGIN
+            DELETE FROM workspace_search
+            WHERE kind = 'connection_note' AND entity_id = old.connection_id;
+            INSERT INTO workspace_search (kind, entity_id, title, body)
+            VALUES ('connection_note', new.connection_id, '', new.note);
+        END;
+        CREATE TRIGGER IF NOT EXISTS workspace_search_note_delete
+        AFTER DELETE ON connection_notes BEGIN
+            DELETE FROM workspace_search
+            WHERE kind = 'connection_note' AND entity_id = old.connection_id;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS workspace_search_message_insert
+        AFTER INSERT ON exploration_messages BEGIN
+            INSERT INTO workspace_search (kind, entity_id, title, body)
+            VALUES ('message', new.id, '', new.content);
+        END;
+        CREATE TRIGGER IF NOT EXISTS workspace_search_message_update
+        AFTER UPDATE ON exploration_messages BEGIN
+            DELETE FROM workspace_search WHERE kind = 'message' AND entity_id = old.id;
+            INSERT INTO workspace_search (kind, entity_id, title, body)
+            VALUES ('message', new.id, '', new.content);
+        END;
+        CREATE TRIGGER IF NOT EXISTS workspace_search_message_delete
+        AFTER DELETE ON exploration_messages BEGIN
+            DELETE FROM workspace_search WHERE kind = 'message' AND entity_id = old.id;
+        END;
+
+        DELETE FROM workspace_search;
+        INSERT INTO workspace_search (kind, entity_id, title, body)
+        SELECT 'saved_query', id, name, description || char(10) || sql_text FROM saved_queries;
+        INSERT INTO workspace_search (kind, entity_id, title, body)
+        SELECT 'connection_note', connection_id, '', note FROM connection_notes;
+        INSERT INTO workspace_search (kind, entity_id, title, body)
+        SELECT 'message', id, '', content FROM exploration_messages;",
+    // 17
+    "ALTER TABLE explorations ADD COLUMN deleted_at TEXT;",
+    // 18
+    "ALTER TABLE saved_queries ADD COLUMN tags TEXT NOT NULL DEFAULT '';",
+    // 19: one snippet per name globally and per connection. Duplicates
+    // saved before this are renamed rather than dropped.
+    "UPDATE snippets SET name = name || '-' || rowid
+        WHERE rowid NOT IN (SELECT MAX(rowid) FROM snippets GROUP BY connection_id, name);
+     CREATE UNIQUE INDEX IF NOT EXISTS idx_snippets_global_name
+        ON snippets (name) WHERE connection_id IS NULL;
+     CREATE UNIQUE INDEX IF NOT EXISTS idx_snippets_connection_name
+        ON snippets (connection_id, name) WHERE connection_id IS NOT NULL;",
+];
+
+/// Apply the `MIGRATIONS` newer than the version recorded in
+/// `schema_migrations`, each in its own transaction, and record them. A
+/// failure aborts startup with the failing version.
+fn run_migrations(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    let versioned = table_exists(conn, "schema_migrations")?;
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+            version INTEGER PRIMARY KEY,
+            applied_at TEXT NOT NULL
+        );",
+    )?;
+    if !versioned && table_exists(conn, "connections")? {
+        // A database from before versioned migrations has the baseline
+        // schema, plus the password and use_ssl columns if the version that
+        // made it was recent enough.
+        let legacy_version = if has_column(conn, "connections", "password")? { 2 } else { 1 };
+        for version in 1..=legacy_version {
+            record_migration(conn, version)?;
+        }
+    }
+    let current: usize = conn.query_row(
+        "SELECT COALESCE(MAX(version), 0) FROM schema_migrations",
+        [],
+        |row| row.get::<_, i64>(0),
+    )? as usize;
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(current) {
+        let version = index + 1;
+        let failed = |e: rusqlite::Error| format!("Migration {version} failed: {e}");
+        let tx = conn.unchecked_transaction().map_err(failed)?;
+        tx.execute_batch(migration).map_err(failed)?;
+        record_migration(&tx, version).map_err(failed)?;
+        tx.commit().map_err(failed)?;
+    }
+    Ok(())
+}
+
+fn record_migration(conn: &Connection, version: usize) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO schema_migrations (version, applied_at) VALUES (?1, ?2)",
+        params![version as i64, chrono::Utc::now().to_rfc3339()],
+    )?;
+    Ok(())
+}
+
+fn table_exists(conn: &Connection, table: &str) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1)",
+        params![table],
+        |row| row.get(0),
+    )
+}
+
+fn has_column(conn: &Connection, table: &str, column: &str) -> rusqlite::Result<bool> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info(\"{}\")", table.replace('"', "\"\"")))?;
+    let columns = stmt.query_map([], |row| row.get::<_, String>(1))?;
+    for name in columns {
+        if name?.eq_ignore_ascii_case(column) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Startup pass over saved passwords: encrypt any saved in plaintext by
+/// versions before credentials were encrypted at rest, and clear any that
+/// no longer decrypt because the key was lost or replaced. Plaintext
+/// `SECRET_SETTINGS` are encrypted too.
+fn secure_stored_passwords(
+    conn: &Connection,
+    secrets: &SecretBox,
+) -> Result<(), Box<dyn std::error::Error>> {
+    for key in SECRET_SETTINGS {
+        let stored: Option<String> = conn
+            .query_row("SELECT value FROM settings WHERE key = ?1", params![key], |row| {
+                row.get(0)
+            })
+            .ok();
+        if let Some(value) = stored.filter(|v| !v.is_empty() && !SecretBox::is_encrypted(v)) {
+            conn.execute(
+                "UPDATE settings SET value = ?1 WHERE key = ?2",
+                params![secrets.encrypt(&value)?, key],
+            )?;
+        }
+    }
+    let saved: Vec<(String, String)> = conn
+        .prepare("SELECT id, password FROM connections WHERE password != ''")?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<Vec<(String, String)>>>()?;
+    for (id, password) in saved {
+        let replacement = if !SecretBox::is_encrypted(&password) {
+            secrets.encrypt(&password)?
+        } else if secrets.decrypt(&password).is_err() {
+            String::new()
+        } else {
+            continue;
+        };
+        conn.execute(
+            "UPDATE connections SET password = ?1 WHERE id = ?2",
+            params![replacement, id],
+        )?;
+    }
+    Ok(())
+}
+
+/// How long startup waits on a database another process has locked before
+/// giving up. A locked file is never treated as corrupt.
+const STARTUP_BUSY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Open `path` and confirm it passes `PRAGMA integrity_check`.
+fn open_healthy(path: &std::path::Path) -> rusqlite::Result<Connection> {
+    let connection = Connection::open(path)?;
+    connection.busy_timeout(STARTUP_BUSY_TIMEOUT)?;
+    let status: String = connection.query_row("PRAGMA integrity_check", [], |row| row.get(0))?;
+    if status != "ok" {
+        return Err(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CORRUPT),
+            Some(status),
+        ));
+    }
+    Ok(connection)
+}
+
+/// Open the app database, replacing it with a fresh one when it is corrupt.
+/// The bad file (and its WAL/SHM sidecars) is renamed aside first, never
+/// deleted; its new path is returned alongside the connection.
+fn open_or_recover(
+    path: &std::path::Path,
+) -> Result<(Connection, Option<std::path::PathBuf>), Box<dyn std::error::Error>> {
+    let err = match open_healthy(path) {
+        Ok(connection) => return Ok((connection, None)),
+        Err(e) => e,
+    };
+    let corrupt = matches!(
+        err.sqlite_error_code(),
+        Some(rusqlite::ErrorCode::DatabaseCorrupt | rusqlite::ErrorCode::NotADatabase)
+    );
+    if !corrupt {
+        return Err(err.into());
+    }
+
+    let stamp = chrono::Utc::now().format("%Y%m%d%H%M%S");
+    let backup = path.with_extension(format!("db.corrupt-{stamp}"));
+    std::fs::rename(path, &backup)?;
+    for suffix in ["-wal", "-shm"] {
+        let mut sidecar = path.as_os_str().to_owned();
+        sidecar.push(suffix);
+        let sidecar = std::path::PathBuf::from(sidecar);
+        if sidecar.exists() {
+            let mut moved = backup.as_os_str().to_owned();
+            moved.push(suffix);
+            std::fs::rename(&sidecar, moved)?;
+        }
+    }
+    eprintln!(
+        "App database was unreadable ({err}); moved it to {} and started fresh",
+        backup.display()
+    );
+
+    Ok((open_healthy(path)?, Some(backup)))
+}
+
+/// Insert a connection row. The password column starts empty.
+fn insert_connection(conn: &Connection, c: &DatabaseConnection) -> Result<(), AppError> {
+    conn.execute(
+        "INSERT INTO connections (id, name, db_type, host, port, database_name, username, connected, password, use_ssl, quote_all_identifiers, max_concurrent_queries, connect_timeout_secs, query_timeout_secs, environment, ssl_mode, ca_cert_path, client_cert_path, client_key_path, read_only)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 0, '', ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)",
+        params![
+            &c.id,
+            &c.name,
+            &c.db_type,
+            &c.host,
+            c.port as i32,
+            &c.database,
+            &c.username,
+            c.use_ssl as i32,
+            c.quote_all_identifiers as i32,
+            c.max_concurrent_queries,
+            c.connect_timeout_secs,
+            c.query_timeout_secs,
+            &c.environment,
+            c.ssl_mode.map(SslMode::as_str),
+            &c.ca_cert_path,
+            &c.client_cert_path,
+            &c.client_key_path,
+            c.read_only as i32,
+        ],
+    )
+    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    Ok(())
+}
+
+/// Read an `ssl_mode` column; unrecognized values count as unset.
+/// Insert an empty exploration. Takes a `Connection` so a transaction can
+/// create one alongside other writes.
+fn insert_exploration(
+    conn: &Connection,
+    project_id: &str,
+    title: &str,
+) -> Result<Exploration, AppError> {
+    let id = uuid::Uuid::new_v4().to_string();
+    let created_at = chrono::Utc::now().to_rfc3339();
+
+    conn.execute(
+        "INSERT INTO explorations (id, project_id, title, created_at) VALUES (?1, ?2, ?3, ?4)",
+        params![&id, project_id, title, &created_at],
+    )
+    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+    Ok(Exploration {
+        id,
+        project_id: project_id.to_string(),
+        title: title.to_string(),
+        created_at,
+        message_count: 0,
+        deleted_at: None,
+    })
+}
+
+fn ssl_mode_from_row(row: &rusqlite::Row<'_>, idx: usize) -> rusqlite::Result<Option<SslMode>> {
+    Ok(row
+        .get::<_, Option<String>>(idx)?
+        .as_deref()
+        .and_then(SslMode::from_str_loose))
+}
+
+/// Map a `saved_queries` row selected in the usual column order, with the
+/// tags last.
+fn saved_query_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<SavedQuery> {
+    Ok(SavedQuery {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        description: row.get(2)?,
+        sql: row.get(3)?,
+        connection_id: row.get(4)?,
+        created_at: row.get(5)?,
+        updated_at: row.get(6)?,
+        tags: split_tags(&row.get::<_, String>(7)?),
+    })
+}
+
+/// Saved query tags as stored: trimmed, comma-free, without duplicates,
+/// joined by commas.
+fn join_tags(tags: &[String]) -> String {
+    let mut joined: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.replace(',', " ").trim().to_string();
+        if !tag.is_empty() && !joined.contains(&tag) {
+            joined.push(tag);
+        }
+    }
+    joined.join(",")
+}
+
+fn split_tags(tags: &str) -> Vec<String> {
+    tags.split(',')
+        .filter(|t| !t.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Map a `saved_charts` row selected in the usual column order.
+fn saved_chart_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<SavedChart> {
+    let data_json: String = row.get(8)?;
+    let data = serde_json::from_str::<serde_json::Value>(&data_json)
+        .unwrap_or(serde_json::Value::Array(vec![]));
+
+    Ok(SavedChart {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        description: row.get(2)?,
+        chart_type: row.get(3)?,
+        x_key: row.get(4)?,
+        y_key: row.get(5)?,
+        connection_id: row.get(6)?,
+        sql: row.get(7)?,
+        data,
+        created_at: row.get(9)?,
+        updated_at: row.get(10)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn applied_versions(conn: &Connection) -> Vec<i64> {
+        conn.prepare("SELECT version FROM schema_migrations ORDER BY version")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap()
+    }
+
+    fn all_versions() -> Vec<i64> {
+        (1..=MIGRATIONS.len() as i64).collect()
+    }
+
+    fn database() -> Database {
+        let conn = Connection::open_in_memory().unwrap();
+        run_migrations(&conn).unwrap();
+        Database {
+            conn: Mutex::new(conn),
+            recovered_backup: None,
+            secrets: SecretBox::ephemeral(),
+        }
+    }
+
+    fn with_connections(db: &Database, ids: &[&str]) {
+        let conn = db.conn.lock().unwrap();
+        for id in ids {
+            conn.execute(
+                "INSERT INTO connections (id, name, db_type, host, port, database_name, username)
+                 VALUES (?1, ?1, 'sqlite', '', 0, ':memory:', '')",
+                params![id],
+            )
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn snippets_are_scoped_to_their_connection() {
+        let db = database();
+        with_connections(&db, &["c1", "c2"]);
+        db.save_snippet(None, "recent", "global body", None, &[]).unwrap();
+        db.save_snippet(None, "recent", "own body", Some("c1"), &[]).unwrap();
+        db.save_snippet(None, "other", "other body", Some("c2"), &[]).unwrap();
+
+        let names = |snippets: Vec<Snippet>| -> Vec<(String, String)> {
+            snippets.into_iter().map(|s| (s.name, s.body)).collect()
+        };
+        assert_eq!(
+            names(db.list_snippets(None).unwrap()),
+            [("recent".to_string(), "global body".to_string())]
+        );
+        // The connection's own snippet comes before the global one.
+        assert_eq!(
+            names(db.list_snippets(Some("c1")).unwrap()),
+            [
+                ("recent".to_string(), "own body".to_string()),
+                ("recent".to_string(), "global body".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn snippet_names_are_unique_per_connection() {
+        let db = database();
+        with_connections(&db, &["c1"]);
+        db.save_snippet(None, "join", "a", None, &[]).unwrap();
+        assert!(matches!(
+            db.save_snippet(None, "join", "b", None, &[]),
+            Err(AppError::QueryError(_))
+        ));
+        db.save_snippet(None, "join", "c", Some("c1"), &[]).unwrap();
+        assert!(matches!(
+            db.save_snippet(None, "join", "d", Some("c1"), &[]),
+            Err(AppError::QueryError(_))
+        ));
+    }
+
+    #[test]
+    fn cloned_connections_take_overrides_but_not_the_password() {
+        let db = database();
+        let source = db
+            .add_connection("prod", "PostgreSQL", "db.internal", 5432, "app", "admin")
+            .unwrap();
+        db.save_connection_credentials(&source.id, "hunter2", true).unwrap();
+        db.set_connection_note(&source.id, "read replica lags").unwrap();
+
+        let overrides = ConnectionOverrides {
+            database: Some("analytics".to_string()),
+            ..Default::default()
+        };
+        let clone = db.clone_connection(&source.id, "analytics", &overrides, true).unwrap();
+        assert_ne!(clone.id, source.id);
+        assert_eq!(clone.name, "analytics");
+        assert_eq!(clone.database, "analytics");
+        assert_eq!((clone.host.as_str(), clone.port), ("db.internal", 5432));
+        assert!(clone.use_ssl);
+
+        let stored = db
+            .list_connections()
+            .unwrap()
+            .into_iter()
+            .find(|c| c.id == clone.id)
+            .unwrap();
+        assert!(stored.password.is_empty());
+        let notes = db.list_connection_notes().unwrap();
+        assert!(notes.iter().any(|n| n.connection_id == clone.id && n.note == "read replica lags"));
+
+        assert!(matches!(
+            db.clone_connection("missing", "x", &ConnectionOverrides::default(), false),
+            Err(AppError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn concurrent_counter_updates_all_land() {
+        let db = database();
+        let project = db.create_project("p", "").unwrap();
+        let exploration = db.create_exploration(&project.id, "e").unwrap();
+        std::thread::scope(|s| {
+            for _ in 0..8 {
+                s.spawn(|| {
+                    for _ in 0..25 {
+                        db.add_exploration_tokens(&exploration.id, 3).unwrap();
+                        db.increment_exploration_turn(&exploration.id).unwrap();
+                    }
+                });
+            }
+        });
+        let counters = db.get_exploration_counters(&exploration.id).unwrap();
+        assert_eq!(counters.token_count, 8 * 25 * 3);
+        assert_eq!(counters.turn_count, 8 * 25);
+
+        db.add_message(&exploration.id, "user", "hi", None).unwrap();
+        db.reset_exploration_history(&exploration.id, true).unwrap();
+        let counters = db.get_exploration_counters(&exploration.id).unwrap();
+        assert_eq!((counters.token_count, counters.turn_count), (0, 0));
+        assert!(db.list_messages(&exploration.id).unwrap().is_empty());
+    }
+
+    #[test]
+    fn fork_copies_messages_up_to_the_cut_with_scaled_counters() {
+        let db = database();
+        let project = db.create_project("p", "").unwrap();
+        let source = db.create_exploration(&project.id, "e").unwrap();
+        let mut ids = Vec::new();
+        for (role, content) in [
+            ("user", "aaaa"),
+            ("assistant", "bbbb"),
+            ("user", "cccc"),
+            ("assistant", "dddd"),
+        ] {
+            ids.push(db.add_message(&source.id, role, content, None).unwrap().id);
+        }
+        db.add_exploration_tokens(&source.id, 100).unwrap();
+
+        let fork = db.fork_exploration(&source.id, &ids[1]).unwrap();
+        assert_eq!((fork.title.as_str(), fork.message_count), ("e (fork)", 2));
+        let counters = db.get_exploration_counters(&fork.id).unwrap();
+        assert_eq!((counters.token_count, counters.turn_count), (50, 1));
+        let copied: Vec<String> = db
+            .list_messages(&fork.id)
+            .unwrap()
+            .into_iter()
+            .map(|m| m.content)
+            .collect();
+        assert_eq!(copied, ["aaaa", "bbbb"]);
+        assert!(matches!(
+            db.fork_exploration(&source.id, "missing"),
+            Err(AppError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn connection_environment_is_stored_with_the_connection() {
+        let db = database();
+        with_connections(&db, &["c1"]);
+        db.set_connection_environment("c1", Some("production")).unwrap();
+        let environment = |db: &Database| {
+            db.list_connections().unwrap().into_iter().find(|c| c.id == "c1").unwrap().environment
+        };
+        assert_eq!(environment(&db).as_deref(), Some("production"));
+        db.set_connection_environment("c1", None).unwrap();
+        assert_eq!(environment(&db), None);
+    }
+
+    #[test]
+    fn secret_settings_are_encrypted_at_rest() {
+        let db = database();
+        db.set_setting(LLM_API_KEY_SETTING, "sk-test").unwrap();
+        let stored = db.get_setting(LLM_API_KEY_SETTING).unwrap().unwrap();
+        assert!(SecretBox::is_encrypted(&stored));
+        assert_eq!(
+            db.get_secret_setting(LLM_API_KEY_SETTING).unwrap().as_deref(),
+            Some("sk-test")
+        );
+        db.set_setting("llm_model", "gpt-4o").unwrap();
+        assert_eq!(db.get_setting("llm_model").unwrap().as_deref(), Some("gpt-4o"));
+    }
+
+    #[test]
+    fn corrupt_database_is_moved_aside_and_replaced() {
+        let dir = std::env::temp_dir().join(format!("arc-recover-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("arc.db");
+        let garbage = vec![0x5a_u8; 8192];
+        std::fs::write(&path, &garbage).unwrap();
+
+        let (conn, backup) = open_or_recover(&path).unwrap();
+        let backup = backup.expect("the corrupt file should have been moved aside");
+        assert_eq!(std::fs::read(&backup).unwrap(), garbage);
+        run_migrations(&conn).unwrap();
+        assert_eq!(applied_versions(&conn), all_versions());
+
+        // A healthy file is opened as it is.
+        drop(conn);
+        let (_, backup) = open_or_recover(&path).unwrap();
+        assert!(backup.is_none());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn fresh_install_applies_every_migration() {
+        let conn = Connection::open_in_memory().unwrap();
+        run_migrations(&conn).unwrap();
+        assert_eq!(applied_versions(&conn), all_versions());
+        assert!(has_column(&conn, "connections", "read_only").unwrap());
+        assert!(has_column(&conn, "saved_queries", "tags").unwrap());
+
+        // A second start finds nothing left to apply.
+        run_migrations(&conn).unwrap();
+        assert_eq!(applied_versions(&conn), all_versions());
+    }
+
+    #[test]
+    fn legacy_database_is_upgraded_in_place() {
+        let conn = Connection::open_in_memory().unwrap();
+        // What `Database::new` created before versioned migrations.
+        conn.execute_batch(MIGRATIONS[0]).unwrap();
+        conn.execute_batch(MIGRATIONS[1]).unwrap();
+        conn.execute_batch(
+            "INSERT INTO projects (id, name, created_at) VALUES ('p', 'Project', 'now');
+             INSERT INTO explorations (id, project_id, title, created_at)
+             VALUES ('e', 'p', 'Exploration', 'now');
+             INSERT INTO exploration_store_state
+                 (exploration_id, token_count, turn_count, updated_at)
+             VALUES ('e', 120, 3, 'now');",
+        )
+        .unwrap();
+
+        run_migrations(&conn).unwrap();
+        assert_eq!(applied_versions(&conn), all_versions());
+        let counters: (i64, i64) = conn
+            .query_row(
+                "SELECT token_count, turn_count FROM explorations WHERE id = 'e'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(counters, (120, 3));
+    }
+
+    #[test]
+    fn legacy_database_without_credentials_columns_gains_them() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(MIGRATIONS[0]).unwrap();
+
+        run_migrations(&conn).unwrap();
+        assert_eq!(applied_versions(&conn), all_versions());
+        assert!(has_column(&conn, "connections", "password").unwrap());
+        assert!(has_column(&conn, "connections", "use_ssl").unwrap());
+    }
 }
@@ -0,0 +1,722 @@
+// Arc SQL dialect helpers
+//
+// Small, backend-aware helpers for commands that generate SQL text
+// (identifier quoting and the like). Adapters execute SQL; this module only
+// decides how it should be spelled for a given `DatabaseKind`.
+
+use std::ops::ControlFlow;
+
+use sqlparser::ast::{Query, SetExpr, Statement, Visit, Visitor};
+use sqlparser::parser::Parser;
+use sqlparser::tokenizer::{Token, Tokenizer};
+
+use crate::adapter::DatabaseKind;
+
+/// Spell an identifier for the given backend. Plain lowercase names
+/// (`[a-z_][a-z0-9_]*`) that aren't reserved words are left bare so
+/// case-insensitive lookups keep working; anything else, or everything when
+/// `force` is set, is quoted with embedded quote characters escaped. MySQL
+/// uses backticks; everything else uses ANSI double quotes.
+pub fn quote_ident(kind: DatabaseKind, name: &str, force: bool) -> String {
+    if !force && !needs_quoting(kind, name) {
+        return name.to_string();
+    }
+    match kind {
+        DatabaseKind::MySQL => format!("`{}`", name.replace('`', "``")),
+        _ => format!("\"{}\"", name.replace('"', "\"\"")),
+    }
+}
+
+/// `sql` with `LIMIT limit` appended, when it is a single top-level query
+/// (SELECT, UNION, WITH ... SELECT) without a LIMIT or FETCH of its own.
+/// A trailing semicolon or comment is cut first so it can't end the
+/// statement early. Anything else, including SQL that doesn't parse,
+/// yields `None`.
+pub fn with_auto_limit(kind: DatabaseKind, sql: &str, limit: usize) -> Option<String> {
+    let query = single_query(kind, sql)?;
+    if query.limit.is_some() || query.fetch.is_some() || !query.locks.is_empty() {
+        return None;
+    }
+    Some(format!("{} LIMIT {limit}", statement_body(kind, sql)?))
+}
+
+/// `sql` wrapped as a subselect returning `limit` rows after skipping
+/// `offset`, when it is a single read-only query (see
+/// `is_read_only_query`); `None` for anything else, so a write, including
+/// one hidden in a CTE, is never run to page through it.
+pub fn paginate(kind: DatabaseKind, sql: &str, offset: usize, limit: usize) -> Option<String> {
+    if !is_read_only_query(kind, sql) {
+        return None;
+    }
+    // The line breaks keep a trailing `--` comment from swallowing the
+    // closing parenthesis.
+    Some(format!(
+        "SELECT * FROM (\n{}\n) _arc_sub LIMIT {limit} OFFSET {offset}",
+        statement_body(kind, sql)?
+    ))
+}
+
+/// How to ask the backend for `sql`'s plan: plain `EXPLAIN` by default,
+/// `analyze` to also run it and report actual timings, `json` for a
+/// machine-readable plan where the backend has one. SQLite only offers
+/// `EXPLAIN QUERY PLAN`, since its plain EXPLAIN lists VM bytecode. `None`
+/// when the backend has no planner or can't honor the options.
+pub fn explain(kind: DatabaseKind, sql: &str, analyze: bool, json: bool) -> Option<String> {
+    let prefix = match kind {
+        DatabaseKind::PostgreSQL => match (analyze, json) {
+            (false, false) => "EXPLAIN".to_string(),
+            (true, false) => "EXPLAIN ANALYZE".to_string(),
+            (false, true) => "EXPLAIN (FORMAT JSON)".to_string(),
+            (true, true) => "EXPLAIN (ANALYZE, FORMAT JSON)".to_string(),
+        },
+        // CockroachDB has EXPLAIN ANALYZE but no JSON output.
+        DatabaseKind::CockroachDB if json => return None,
+        DatabaseKind::CockroachDB if analyze => "EXPLAIN ANALYZE".to_string(),
+        DatabaseKind::CockroachDB => "EXPLAIN".to_string(),
+        // MySQL's EXPLAIN ANALYZE only prints a tree.
+        DatabaseKind::MySQL => match (analyze, json) {
+            (true, true) => return None,
+            (true, false) => "EXPLAIN ANALYZE".to_string(),
+            (false, true) => "EXPLAIN FORMAT=JSON".to_string(),
+            (false, false) => "EXPLAIN".to_string(),
+        },
+        DatabaseKind::SQLite if analyze || json => return None,
+        DatabaseKind::SQLite => "EXPLAIN QUERY PLAN".to_string(),
+        DatabaseKind::Redis | DatabaseKind::Elasticsearch => return None,
+    };
+    Some(format!("{prefix} {sql}"))
+}
+
+/// Whether `sql` is exactly one query that only reads, at any depth: no
+/// data-modifying CTE (`WITH d AS (UPDATE ... RETURNING ...)`), no
+/// `SELECT ... INTO` and no row locks. Commands that would otherwise run a
+/// statement as a side effect (EXPLAIN ANALYZE, paging) check this first.
+pub fn is_read_only_query(kind: DatabaseKind, sql: &str) -> bool {
+    single_query(kind, sql).is_some_and(|query| query_only_reads(&query))
+}
+
+fn query_only_reads(query: &Query) -> bool {
+    struct FindWrites;
+
+    impl Visitor for FindWrites {
+        type Break = ();
+
+        fn pre_visit_query(&mut self, query: &Query) -> ControlFlow<()> {
+            if !query.locks.is_empty() || set_expr_writes(&query.body) {
+                return ControlFlow::Break(());
+            }
+            ControlFlow::Continue(())
+        }
+
+        // The top-level query isn't a statement, so any statement found
+        // is nested inside it (an INSERT or UPDATE in a CTE).
+        fn pre_visit_statement(&mut self, _statement: &Statement) -> ControlFlow<()> {
+            ControlFlow::Break(())
+        }
+    }
+
+    fn set_expr_writes(body: &SetExpr) -> bool {
+        match body {
+            SetExpr::Select(select) => select.into.is_some(),
+            SetExpr::SetOperation { left, right, .. } => {
+                set_expr_writes(left) || set_expr_writes(right)
+            }
+            SetExpr::Insert(_) | SetExpr::Update(_) => true,
+            // Nested queries get their own `pre_visit_query`.
+            SetExpr::Query(_) | SetExpr::Values(_) | SetExpr::Table(_) => false,
+        }
+    }
+
+    query.visit(&mut FindWrites).is_continue()
+}
+
+/// The query `sql` consists of, if it is exactly one SQL query on a
+/// backend that speaks SQL.
+fn single_query(kind: DatabaseKind, sql: &str) -> Option<Box<sqlparser::ast::Query>> {
+    if !matches!(
+        kind,
+        DatabaseKind::PostgreSQL
+            | DatabaseKind::CockroachDB
+            | DatabaseKind::MySQL
+            | DatabaseKind::SQLite
+    ) {
+        return None;
+    }
+    let mut statements =
+        Parser::parse_sql(crate::lint::parser_dialect(kind).as_ref(), sql).ok()?;
+    if statements.len() != 1 {
+        return None;
+    }
+    match statements.pop()? {
+        Statement::Query(query) => Some(query),
+        _ => None,
+    }
+}
+
+/// `sql` without its trailing semicolon, comments and whitespace, so text
+/// can be appended to the statement.
+fn statement_body(kind: DatabaseKind, sql: &str) -> Option<&str> {
+    let tokens = Tokenizer::new(crate::lint::parser_dialect(kind).as_ref(), sql)
+        .tokenize_with_location()
+        .ok()?;
+    let last = tokens.iter().rposition(|t| {
+        !matches!(
+            t.token,
+            Token::Whitespace(_) | Token::SemiColon | Token::EOF
+        )
+    })?;
+    let end = match tokens.get(last + 1) {
+        Some(next) => byte_offset(sql, next.span.start.line, next.span.start.column),
+        None => sql.len(),
+    };
+    Some(&sql[..end])
+}
+
+/// Byte offset of a tokenizer location (1-based line and character column).
+fn byte_offset(sql: &str, line: u64, column: u64) -> usize {
+    let line_start: usize = sql
+        .split_inclusive('\n')
+        .take(line.saturating_sub(1) as usize)
+        .map(str::len)
+        .sum();
+    line_start
+        + sql[line_start..]
+            .chars()
+            .take(column.saturating_sub(1) as usize)
+            .map(char::len_utf8)
+            .sum::<usize>()
+}
+
+/// Whether any statement in `sql` is DDL (CREATE, ALTER or DROP) and so
+/// may have changed the schema. Leading comments are skipped; SQL that
+/// doesn't tokenize is assumed not to be DDL.
+pub fn is_schema_change(kind: DatabaseKind, sql: &str) -> bool {
+    let Ok(tokens) = Tokenizer::new(crate::lint::parser_dialect(kind).as_ref(), sql).tokenize()
+    else {
+        return false;
+    };
+    let mut at_statement_start = true;
+    for token in tokens {
+        match token {
+            Token::Whitespace(_) => {}
+            Token::SemiColon => at_statement_start = true,
+            Token::Word(word) if at_statement_start => {
+                if ["CREATE", "ALTER", "DROP"]
+                    .iter()
+                    .any(|ddl| word.value.eq_ignore_ascii_case(ddl))
+                {
+                    return true;
+                }
+                at_statement_start = false;
+            }
+            _ => at_statement_start = false,
+        }
+    }
+    false
+}
+
+/// The bind placeholder for the `index`th (1-based) parameter of a query:
+/// `$1`, `$2`, ... for Postgres-family backends, `?` elsewhere.
+pub fn placeholder(kind: DatabaseKind, index: usize) -> String {
+    match kind {
+        DatabaseKind::PostgreSQL | DatabaseKind::CockroachDB => format!("${index}"),
+        _ => "?".to_string(),
+    }
+}
+
+fn needs_quoting(kind: DatabaseKind, name: &str) -> bool {
+    let mut chars = name.chars();
+    let plain = match chars.next() {
+        Some(first) => {
+            (first.is_ascii_lowercase() || first == '_')
+                && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        }
+        None => false,
+    };
+    !plain || is_reserved_word(kind, name)
+}
+
+/// Whether `word` is reserved in the backend's SQL dialect and so can't be
+/// used as a bare identifier.
+pub fn is_reserved_word(kind: DatabaseKind, word: &str) -> bool {
+    let upper = word.to_ascii_uppercase();
+    reserved_words(kind).binary_search(&upper.as_str()).is_ok()
+}
+
+/// Reserved words per dialect, sorted for binary search.
+fn reserved_words(kind: DatabaseKind) -> &'static [&'static str] {
+    match kind {
+        DatabaseKind::MySQL => MYSQL_RESERVED_WORDS,
+        DatabaseKind::SQLite => SQLITE_RESERVED_WORDS,
+        _ => POSTGRES_RESERVED_WORDS,
+    }
+}
+
+/// Postgres reserved key words (also used for CockroachDB and as the ANSI
+/// fallback), including those reserved only as function or type names.
+const POSTGRES_RESERVED_WORDS: &[&str] = &[
+    "ALL", "ANALYSE", "ANALYZE", "AND", "ANY", "ARRAY", "AS", "ASC", "ASYMMETRIC",
+    "AUTHORIZATION", "BINARY", "BOTH", "CASE", "CAST", "CHECK", "COLLATE", "COLLATION",
+    "COLUMN", "CONCURRENTLY", "CONSTRAINT", "CREATE", "CROSS", "CURRENT_CATALOG",
+    "CURRENT_DATE", "CURRENT_ROLE", "CURRENT_SCHEMA", "CURRENT_TIME", "CURRENT_TIMESTAMP",
+    "CURRENT_USER", "DEFAULT", "DEFERRABLE", "DESC", "DISTINCT", "DO", "ELSE", "END",
+    "EXCEPT", "FALSE", "FETCH", "FOR", "FOREIGN", "FREEZE", "FROM", "FULL", "GRANT", "GROUP",
+    "HAVING", "ILIKE", "IN", "INITIALLY", "INNER", "INTERSECT", "INTO", "IS", "ISNULL",
+    "JOIN", "LATERAL", "LEADING", "LEFT", "LIKE", "LIMIT", "LOCALTIME", "LOCALTIMESTAMP",
+    "NATURAL", "NOT", "NOTNULL", "NULL", "OFFSET", "ON", "ONLY", "OR", "ORDER", "OUTER",
+    "OVERLAPS", "PLACING", "PRIMARY", "REFERENCES", "RETURNING", "RIGHT", "SELECT",
+    "SESSION_USER", "SIMILAR", "SOME", "SYMMETRIC", "SYSTEM_USER", "TABLE", "TABLESAMPLE",
+    "THEN", "TO", "TRAILING", "TRUE", "UNION", "UNIQUE", "USER", "USING", "VARIADIC",
+    "VERBOSE", "WHEN", "WHERE", "WINDOW", "WITH",
+];
+
+/// MySQL 8.0 reserved words.
+const MYSQL_RESERVED_WORDS: &[&str] = &[
+    "ACCESSIBLE", "ADD", "ALL", "ALTER", "ANALYZE", "AND", "AS", "ASC", "ASENSITIVE",
+    "BEFORE", "BETWEEN", "BIGINT", "BINARY", "BLOB", "BOTH", "BY", "CALL", "CASCADE", "CASE",
+    "CHANGE", "CHAR", "CHARACTER", "CHECK", "COLLATE", "COLUMN", "CONDITION", "CONSTRAINT",
+    "CONTINUE", "CONVERT", "CREATE", "CROSS", "CUBE", "CUME_DIST", "CURRENT_DATE",
+    "CURRENT_TIME", "CURRENT_TIMESTAMP", "CURRENT_USER", "CURSOR", "DATABASE", "DATABASES",
+    "DAY_HOUR", "DAY_MICROSECOND", "DAY_MINUTE", "DAY_SECOND", "DEC", "DECIMAL", "DECLARE",
+    "DEFAULT", "DELAYED", "DELETE", "DENSE_RANK", "DESC", "DESCRIBE", "DETERMINISTIC",
+    "DISTINCT", "DISTINCTROW", "DIV", "DOUBLE", "DROP", "DUAL", "EACH", "ELSE", "ELSEIF",
+    "EMPTY", "ENCLOSED", "ESCAPED", "EXCEPT", "EXISTS", "EXIT", "EXPLAIN", "FALSE", "FETCH",
+    "FIRST_VALUE", "FLOAT", "FLOAT4", "FLOAT8", "FOR", "FORCE", "FOREIGN", "FROM",
+    "FULLTEXT", "FUNCTION", "GENERATED", "GET", "GRANT", "GROUP", "GROUPING", "GROUPS",
+    "HAVING", "HIGH_PRIORITY", "HOUR_MICROSECOND", "HOUR_MINUTE", "HOUR_SECOND", "IF",
+    "IGNORE", "IN", "INDEX", "INFILE", "INNER", "INOUT", "INSENSITIVE", "INSERT", "INT",
+    "INT1", "INT2", "INT3", "INT4", "INT8", "INTEGER", "INTERSECT", "INTERVAL", "INTO",
+    "IO_AFTER_GTIDS", "IO_BEFORE_GTIDS", "IS", "ITERATE", "JOIN", "JSON_TABLE", "KEY", "KEYS",
+    "KILL", "LAG", "LAST_VALUE", "LATERAL", "LEAD", "LEADING", "LEAVE", "LEFT", "LIKE",
+    "LIMIT", "LINEAR", "LINES", "LOAD", "LOCALTIME", "LOCALTIMESTAMP", "LOCK", "LONG",
+    "LONGBLOB", "LONGTEXT", "LOOP", "LOW_PRIORITY", "MASTER_BIND",
+    "MASTER_SSL_VERIFY_SERVER_CERT", "MATCH", "MAXVALUE", "MEDIUMBLOB", "MEDIUMINT",
+    "MEDIUMTEXT", "MIDDLEINT", "MINUTE_MICROSECOND", "MINUTE_SECOND", "MOD", "MODIFIES",
+    "NATURAL", "NOT", "NO_WRITE_TO_BINLOG", "NTH_VALUE", "NTILE", "NULL", "NUMERIC", "OF",
+    "ON", "OPTIMIZE", "OPTIMIZER_COSTS", "OPTION", "OPTIONALLY", "OR", "ORDER", "OUT",
+    "OUTER", "OUTFILE", "OVER", "PARTITION", "PERCENT_RANK", "PRECISION", "PRIMARY",
+    "PROCEDURE", "PURGE", "RANGE", "RANK", "READ", "READS", "READ_WRITE", "REAL",
+    "RECURSIVE", "REFERENCES", "REGEXP", "RELEASE", "RENAME", "REPEAT", "REPLACE",
+    "REQUIRE", "RESIGNAL", "RESTRICT", "RETURN", "REVOKE", "RIGHT", "RLIKE", "ROW",
+    "ROWS", "ROW_NUMBER", "SCHEMA", "SCHEMAS", "SECOND_MICROSECOND", "SELECT", "SENSITIVE",
+    "SEPARATOR", "SET", "SHOW", "SIGNAL", "SMALLINT", "SPATIAL", "SPECIFIC", "SQL",
+    "SQLEXCEPTION", "SQLSTATE", "SQLWARNING", "SQL_BIG_RESULT", "SQL_CALC_FOUND_ROWS",
+    "SQL_SMALL_RESULT", "SSL", "STARTING", "STORED", "STRAIGHT_JOIN", "SYSTEM", "TABLE",
+    "TERMINATED", "THEN", "TINYBLOB", "TINYINT", "TINYTEXT", "TO", "TRAILING", "TRIGGER",
+    "TRUE", "UNDO", "UNION", "UNIQUE", "UNLOCK", "UNSIGNED", "UPDATE", "USAGE", "USE",
+    "USING", "UTC_DATE", "UTC_TIME", "UTC_TIMESTAMP", "VALUES", "VARBINARY", "VARCHAR",
+    "VARCHARACTER", "VARYING", "VIRTUAL", "WHEN", "WHERE", "WHILE", "WINDOW", "WITH",
+    "WRITE", "XOR", "YEAR_MONTH", "ZEROFILL",
+];
+
+/// SQLite keywords. SQLite accepts many of these as bare identifiers in some
+/// positions, but not reliably, so all of them are treated as reserved.
+const SQLITE_RESERVED_WORDS: &[&str] = &[
+    "ABORT", "ACTION", "ADD", "AFTER", "ALL", "ALTER", "ALWAYS", "ANALYZE", "AND", "AS",
+    "ASC", "ATTACH", "AUTOINCREMENT", "BEFORE", "BEGIN", "BETWEEN", "BY", "CASCADE", "CASE",
+    "CAST", "CHECK", "COLLATE", "COLUMN", "COMMIT", "CONFLICT", "CONSTRAINT", "CREATE",
+    "CROSS", "CURRENT", "CURRENT_DATE", "CURRENT_TIME", "CURRENT_TIMESTAMP", "DATABASE",
+    "DEFAULT", "DEFERRABLE", "DEFERRED", "DELETE", "DESC", "DETACH", "DISTINCT", "DO",
+    "DROP", "EACH", "ELSE", "END", "ESCAPE", "EXCEPT", "EXCLUDE", "EXCLUSIVE", "EXISTS",
+    "EXPLAIN", "FAIL", "FILTER", "FIRST", "FOLLOWING", "FOR", "FOREIGN", "FROM", "FULL",
+    "GENERATED", "GLOB", "GROUP", "GROUPS", "HAVING", "IF", "IGNORE", "IMMEDIATE", "IN",
+    "INDEX", "INDEXED", "INITIALLY", "INNER", "INSERT", "INSTEAD", "INTERSECT", "INTO", "IS",
+    "ISNULL", "JOIN", "KEY", "LAST", "LEFT", "LIKE", "LIMIT", "MATCH", "MATERIALIZED",
+    "NATURAL", "NO", "NOT", "NOTHING", "NOTNULL", "NULL", "NULLS", "OF", "OFFSET", "ON",
+    "OR", "ORDER", "OTHERS", "OUTER", "OVER", "PARTITION", "PLAN", "PRAGMA", "PRECEDING",
+    "PRIMARY", "QUERY", "RAISE", "RANGE", "RECURSIVE", "REFERENCES", "REGEXP", "REINDEX",
+    "RELEASE", "RENAME", "REPLACE", "RESTRICT", "RETURNING", "RIGHT", "ROLLBACK", "ROW",
+    "ROWS", "SAVEPOINT", "SELECT", "SET", "TABLE", "TEMP", "TEMPORARY", "THEN", "TIES", "TO",
+    "TRANSACTION", "TRIGGER", "UNBOUNDED", "UNION", "UNIQUE", "UPDATE", "USING", "VACUUM",
+    "VALUES", "VIEW", "VIRTUAL", "WHEN", "WHERE", "WINDOW", "WITH", "WITHOUT",
+];
+
+/// Built-in MySQL functions. `information_schema.routines` only covers stored
+/// routines, so the built-ins have to come from here.
+pub const MYSQL_BUILTIN_FUNCTIONS: &[&str] = &[
+    // Aggregates
+    "AVG", "BIT_AND", "BIT_OR", "BIT_XOR", "COUNT", "GROUP_CONCAT", "JSON_ARRAYAGG",
+    "JSON_OBJECTAGG", "MAX", "MIN", "STD", "STDDEV", "STDDEV_POP", "STDDEV_SAMP", "SUM",
+    "VAR_POP", "VAR_SAMP", "VARIANCE",
+    // Window
+    "CUME_DIST", "DENSE_RANK", "FIRST_VALUE", "LAG", "LAST_VALUE", "LEAD", "NTH_VALUE", "NTILE",
+    "PERCENT_RANK", "RANK", "ROW_NUMBER",
+    // Control flow / comparison
+    "COALESCE", "GREATEST", "IF", "IFNULL", "INTERVAL", "ISNULL", "LEAST", "NULLIF",
+    // String
+    "ASCII", "BIN", "BIT_LENGTH", "CHAR", "CHAR_LENGTH", "CHARACTER_LENGTH", "CONCAT",
+    "CONCAT_WS", "ELT", "FIELD", "FIND_IN_SET", "FORMAT", "FROM_BASE64", "HEX", "INSERT",
+    "INSTR", "LCASE", "LEFT", "LENGTH", "LIKE", "LOCATE", "LOWER", "LPAD", "LTRIM", "MID",
+    "OCT", "ORD", "POSITION", "QUOTE", "REGEXP_INSTR", "REGEXP_LIKE", "REGEXP_REPLACE",
+    "REGEXP_SUBSTR", "REPEAT", "REPLACE", "REVERSE", "RIGHT", "RPAD", "RTRIM", "SOUNDEX",
+    "SPACE", "STRCMP", "SUBSTR", "SUBSTRING", "SUBSTRING_INDEX", "TO_BASE64", "TRIM", "UCASE",
+    "UNHEX", "UPPER",
+    // Numeric
+    "ABS", "ACOS", "ASIN", "ATAN", "ATAN2", "CEIL", "CEILING", "CONV", "COS", "COT", "CRC32",
+    "DEGREES", "DIV", "EXP", "FLOOR", "LN", "LOG", "LOG10", "LOG2", "MOD", "PI", "POW",
+    "POWER", "RADIANS", "RAND", "ROUND", "SIGN", "SIN", "SQRT", "TAN", "TRUNCATE",
+    // Date and time
+    "ADDDATE", "ADDTIME", "CONVERT_TZ", "CURDATE", "CURRENT_DATE", "CURRENT_TIME",
+    "CURRENT_TIMESTAMP", "CURTIME", "DATE", "DATE_ADD", "DATE_FORMAT", "DATE_SUB", "DATEDIFF",
+    "DAY", "DAYNAME", "DAYOFMONTH", "DAYOFWEEK", "DAYOFYEAR", "EXTRACT", "FROM_DAYS",
+    "FROM_UNIXTIME", "HOUR", "LAST_DAY", "LOCALTIME", "LOCALTIMESTAMP", "MAKEDATE",
+    "MAKETIME", "MICROSECOND", "MINUTE", "MONTH", "MONTHNAME", "NOW", "PERIOD_ADD",
+    "PERIOD_DIFF", "QUARTER", "SEC_TO_TIME", "SECOND", "STR_TO_DATE", "SUBDATE", "SUBTIME",
+    "SYSDATE", "TIME", "TIME_FORMAT", "TIME_TO_SEC", "TIMEDIFF", "TIMESTAMP", "TIMESTAMPADD",
+    "TIMESTAMPDIFF", "TO_DAYS", "TO_SECONDS", "UNIX_TIMESTAMP", "UTC_DATE", "UTC_TIME",
+    "UTC_TIMESTAMP", "WEEK", "WEEKDAY", "WEEKOFYEAR", "YEAR", "YEARWEEK",
+    // JSON
+    "JSON_ARRAY", "JSON_ARRAY_APPEND", "JSON_CONTAINS", "JSON_CONTAINS_PATH", "JSON_DEPTH",
+    "JSON_EXTRACT", "JSON_INSERT", "JSON_KEYS", "JSON_LENGTH", "JSON_MERGE_PATCH",
+    "JSON_MERGE_PRESERVE", "JSON_OBJECT", "JSON_OVERLAPS", "JSON_PRETTY", "JSON_QUOTE",
+    "JSON_REMOVE", "JSON_REPLACE", "JSON_SEARCH", "JSON_SET", "JSON_TABLE", "JSON_TYPE",
+    "JSON_UNQUOTE", "JSON_VALID", "JSON_VALUE",
+    // Casting, hashing and information
+    "BENCHMARK", "CAST", "CONNECTION_ID", "CONVERT", "CURRENT_USER", "DATABASE",
+    "FOUND_ROWS", "LAST_INSERT_ID", "MD5", "ROW_COUNT", "SCHEMA", "SHA1", "SHA2",
+    "SESSION_USER", "SYSTEM_USER", "USER", "UUID", "UUID_SHORT", "VERSION",
+];
+
+/// Core SQLite scalar, aggregate, window, date and JSON functions.
+pub const SQLITE_BUILTIN_FUNCTIONS: &[&str] = &[
+    // Scalar
+    "abs", "changes", "char", "coalesce", "format", "glob", "hex", "ifnull", "iif", "instr",
+    "last_insert_rowid", "length", "like", "likelihood", "likely", "lower", "ltrim", "max",
+    "min", "nullif", "octet_length", "printf", "quote", "random", "randomblob", "replace",
+    "round", "rtrim", "sign", "soundex", "sqlite_version", "substr", "substring",
+    "total_changes", "trim", "typeof", "unhex", "unicode", "unlikely", "upper", "zeroblob",
+    // Aggregate
+    "avg", "count", "group_concat", "string_agg", "sum", "total",
+    // Window
+    "cume_dist", "dense_rank", "first_value", "lag", "last_value", "lead", "nth_value",
+    "ntile", "percent_rank", "rank", "row_number",
+    // Date and time
+    "date", "datetime", "julianday", "strftime", "time", "timediff", "unixepoch",
+    // JSON
+    "json", "json_array", "json_array_length", "json_each", "json_extract", "json_group_array",
+    "json_group_object", "json_insert", "json_object", "json_patch", "json_quote",
+    "json_remove", "json_replace", "json_set", "json_tree", "json_type", "json_valid",
+    // Math (SQLITE_ENABLE_MATH_FUNCTIONS)
+    "acos", "asin", "atan", "atan2", "ceil", "ceiling", "cos", "degrees", "exp", "floor", "ln",
+    "log", "log10", "log2", "mod", "pi", "pow", "power", "radians", "sin", "sqrt", "tan",
+    "trunc",
+];
+
+/// The static built-in function list for a backend, if it has one. Backends
+/// with a queryable catalog (Postgres) return an empty slice.
+pub fn builtin_functions(kind: DatabaseKind) -> &'static [&'static str] {
+    match kind {
+        DatabaseKind::MySQL => MYSQL_BUILTIN_FUNCTIONS,
+        DatabaseKind::SQLite => SQLITE_BUILTIN_FUNCTIONS,
+        _ => &[],
+    }
+}
+
+/// Split a SQL script into individual statements on top-level semicolons.
+/// Semicolons inside quotes, comments, Postgres dollar-quoted bodies and
+/// `CREATE TRIGGER ... BEGIN ... END` blocks do not end a statement.
+/// Comment-only fragments are dropped; returned statements are trimmed and
+/// carry no trailing semicolon. Backslash escapes in string literals are
+/// only honoured for MySQL; elsewhere a backslash is an ordinary character.
+pub fn split_sql_statements(kind: DatabaseKind, script: &str) -> Vec<String> {
+    let backslash_escapes = kind == DatabaseKind::MySQL;
+    let chars: Vec<char> = script.chars().collect();
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut has_content = false;
+    // Words seen at the top level of the current statement, upper-cased.
+    let mut words: Vec<String> = Vec::new();
+    let mut block_depth = 0usize;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+
+        // Line comment
+        if c == '-' && next == Some('-') {
+            while i < chars.len() && chars[i] != '\n' {
+                current.push(chars[i]);
+                i += 1;
+            }
+            continue;
+        }
+        // Block comment
+        if c == '/' && next == Some('*') {
+            current.push_str("/*");
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                current.push(chars[i]);
+                i += 1;
+            }
+            if i < chars.len() {
+                current.push_str("*/");
+                i += 2;
+            }
+            continue;
+        }
+        // Quoted strings and identifiers; a doubled quote is an escape.
+        if c == '\'' || c == '"' || c == '`' {
+            has_content = true;
+            current.push(c);
+            i += 1;
+            while i < chars.len() {
+                current.push(chars[i]);
+                if backslash_escapes && chars[i] == '\\' && c == '\'' && i + 1 < chars.len() {
+                    current.push(chars[i + 1]);
+                    i += 2;
+                    continue;
+                }
+                if chars[i] == c {
+                    if chars.get(i + 1) == Some(&c) {
+                        current.push(c);
+                        i += 2;
+                        continue;
+                    }
+                    i += 1;
+                    break;
+                }
+                i += 1;
+            }
+            continue;
+        }
+        // Dollar-quoted body: $$ ... $$ or $tag$ ... $tag$
+        if c == '$' {
+            let mut j = i + 1;
+            while j < chars.len() && (chars[j].is_alphanumeric() || chars[j] == '_') {
+                j += 1;
+            }
+            if j < chars.len() && chars[j] == '$' && !chars[i + 1..j].first().is_some_and(|d| d.is_ascii_digit()) {
+                let tag: String = chars[i..=j].iter().collect();
+                let tag_chars: Vec<char> = tag.chars().collect();
+                has_content = true;
+                current.push_str(&tag);
+                i = j + 1;
+                while i < chars.len() {
+                    if chars[i..].starts_with(&tag_chars) {
+                        current.push_str(&tag);
+                        i += tag_chars.len();
+                        break;
+                    }
+                    current.push(chars[i]);
+                    i += 1;
+                }
+                continue;
+            }
+        }
+        if c.is_alphanumeric() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            let upper = word.to_uppercase();
+            let in_trigger = words.first().is_some_and(|w| w == "CREATE") && words.iter().any(|w| w == "TRIGGER");
+            if in_trigger {
+                match upper.as_str() {
+                    "BEGIN" | "CASE" => block_depth += 1,
+                    "END" => block_depth = block_depth.saturating_sub(1),
+                    _ => {}
+                }
+            }
+            words.push(upper);
+            has_content = true;
+            current.push_str(&word);
+            continue;
+        }
+        if c == ';' && block_depth == 0 {
+            if has_content {
+                statements.push(current.trim().to_string());
+            }
+            current.clear();
+            has_content = false;
+            words.clear();
+            i += 1;
+            continue;
+        }
+        if !c.is_whitespace() {
+            has_content = true;
+        }
+        current.push(c);
+        i += 1;
+    }
+
+    if has_content {
+        statements.push(current.trim().to_string());
+    }
+    statements
+}
+
+/// Whether `statement` only opens or closes a transaction (`BEGIN`,
+/// `START TRANSACTION`, `COMMIT`, `END`), as the wrapper of a `.dump` does.
+pub fn is_transaction_control(statement: &str) -> bool {
+    let words: Vec<String> = statement
+        .lines()
+        .filter(|line| !line.trim_start().starts_with("--"))
+        .flat_map(str::split_whitespace)
+        .map(str::to_uppercase)
+        .collect();
+    let Some((first, rest)) = words.split_first() else {
+        return false;
+    };
+    let only = |allowed: &[&str]| rest.iter().all(|w| allowed.contains(&w.as_str()));
+    match first.as_str() {
+        "BEGIN" => only(&["TRANSACTION", "WORK", "DEFERRED", "IMMEDIATE", "EXCLUSIVE"]),
+        "START" => rest.first().is_some_and(|w| w == "TRANSACTION"),
+        "COMMIT" | "END" => only(&["TRANSACTION", "WORK"]),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reserved_word_lists_are_sorted() {
+        for kind in [DatabaseKind::PostgreSQL, DatabaseKind::MySQL, DatabaseKind::SQLite] {
+            let words = reserved_words(kind);
+            assert!(words.windows(2).all(|w| w[0] < w[1]), "{kind:?}");
+        }
+    }
+
+    #[test]
+    fn quote_ident_postgres() {
+        let kind = DatabaseKind::PostgreSQL;
+        assert_eq!(quote_ident(kind, "users", false), "users");
+        assert_eq!(quote_ident(kind, "user", false), "\"user\"");
+        assert_eq!(quote_ident(kind, "CreatedAt", false), "\"CreatedAt\"");
+        assert_eq!(quote_ident(kind, "first name", false), "\"first name\"");
+        assert_eq!(quote_ident(kind, "a\"b", false), "\"a\"\"b\"");
+        assert_eq!(quote_ident(kind, "users", true), "\"users\"");
+    }
+
+    #[test]
+    fn quote_ident_mysql() {
+        let kind = DatabaseKind::MySQL;
+        assert_eq!(quote_ident(kind, "orders", false), "orders");
+        assert_eq!(quote_ident(kind, "key", false), "`key`");
+        assert_eq!(quote_ident(kind, "Order", false), "`Order`");
+        assert_eq!(quote_ident(kind, "a`b", false), "`a``b`");
+        assert_eq!(quote_ident(kind, "orders", true), "`orders`");
+    }
+
+    #[test]
+    fn quote_ident_sqlite() {
+        let kind = DatabaseKind::SQLite;
+        assert_eq!(quote_ident(kind, "notes", false), "notes");
+        assert_eq!(quote_ident(kind, "group", false), "\"group\"");
+        assert_eq!(quote_ident(kind, "2fa", false), "\"2fa\"");
+        assert_eq!(quote_ident(kind, "notes", true), "\"notes\"");
+    }
+
+    #[test]
+    fn auto_limit_appends_to_plain_selects() {
+        let kind = DatabaseKind::PostgreSQL;
+        assert_eq!(
+            with_auto_limit(kind, "SELECT * FROM users", 100).as_deref(),
+            Some("SELECT * FROM users LIMIT 100")
+        );
+        assert_eq!(
+            with_auto_limit(kind, "SELECT id FROM users;", 10).as_deref(),
+            Some("SELECT id FROM users LIMIT 10")
+        );
+    }
+
+    #[test]
+    fn auto_limit_keeps_an_existing_limit() {
+        let kind = DatabaseKind::PostgreSQL;
+        assert_eq!(with_auto_limit(kind, "SELECT * FROM users LIMIT 5", 100), None);
+        assert_eq!(
+            with_auto_limit(kind, "SELECT * FROM users FETCH FIRST 5 ROWS ONLY", 100),
+            None
+        );
+        assert_eq!(with_auto_limit(kind, "DELETE FROM users", 100), None);
+    }
+
+    #[test]
+    fn auto_limit_applies_to_the_whole_union() {
+        let limited = with_auto_limit(
+            DatabaseKind::MySQL,
+            "SELECT id FROM a UNION SELECT id FROM b",
+            50,
+        );
+        assert_eq!(
+            limited.as_deref(),
+            Some("SELECT id FROM a UNION SELECT id FROM b LIMIT 50")
+        );
+        assert_eq!(
+            with_auto_limit(DatabaseKind::MySQL, "(SELECT id FROM a LIMIT 1) UNION SELECT 2", 50)
+                .map(|sql| sql.ends_with("LIMIT 50")),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn read_only_query_accepts_plain_reads() {
+        let kind = DatabaseKind::PostgreSQL;
+        assert!(is_read_only_query(kind, "SELECT * FROM users WHERE id = 1"));
+        assert!(is_read_only_query(kind, "WITH t AS (SELECT 1 AS x) SELECT x FROM t;"));
+        assert!(is_read_only_query(kind, "SELECT 1 UNION SELECT 2"));
+    }
+
+    #[test]
+    fn read_only_query_rejects_writes() {
+        let kind = DatabaseKind::PostgreSQL;
+        assert!(!is_read_only_query(kind, "DELETE FROM users"));
+        assert!(!is_read_only_query(kind, "SELECT 1; DELETE FROM users"));
+        assert!(!is_read_only_query(
+            kind,
+            "WITH moved AS (UPDATE users SET active = false RETURNING id) SELECT * FROM moved"
+        ));
+        assert!(!is_read_only_query(kind, "SELECT * INTO backup FROM users"));
+        assert!(!is_read_only_query(kind, "SELECT * FROM users FOR UPDATE"));
+    }
+
+    #[test]
+    fn split_keeps_backslashes_outside_mysql() {
+        let script = r"INSERT INTO t VALUES ('C:\'); SELECT 1";
+        assert_eq!(
+            split_sql_statements(DatabaseKind::SQLite, script),
+            vec![r"INSERT INTO t VALUES ('C:\')", "SELECT 1"]
+        );
+        let script = r"INSERT INTO t VALUES ('it\'s; fine'); SELECT 1";
+        assert_eq!(
+            split_sql_statements(DatabaseKind::MySQL, script),
+            vec![r"INSERT INTO t VALUES ('it\'s; fine')", "SELECT 1"]
+        );
+    }
+
+    #[test]
+    fn split_keeps_trigger_bodies_together() {
+        let trigger = "CREATE TRIGGER t AFTER INSERT ON a BEGIN UPDATE b SET n = n + 1; END";
+        let script = format!("{trigger}; SELECT 1;");
+        assert_eq!(
+            split_sql_statements(DatabaseKind::SQLite, &script),
+            vec![trigger, "SELECT 1"]
+        );
+    }
+
+    #[test]
+    fn transaction_control_statements() {
+        assert!(is_transaction_control("BEGIN TRANSACTION"));
+        assert!(is_transaction_control("-- dump\nCOMMIT"));
+        assert!(is_transaction_control("START TRANSACTION READ ONLY"));
+        assert!(is_transaction_control("end"));
+        assert!(!is_transaction_control("BEGIN UPDATE b SET n = 1; END"));
+        assert!(!is_transaction_control("CREATE TABLE t (id INTEGER)"));
+    }
+
+    #[test]
+    fn paginate_refuses_data_modifying_ctes() {
+        let kind = DatabaseKind::PostgreSQL;
+        assert_eq!(
+            paginate(kind, "SELECT id FROM users;", 20, 10).as_deref(),
+            Some("SELECT * FROM (\nSELECT id FROM users\n) _arc_sub LIMIT 10 OFFSET 20")
+        );
+        assert_eq!(
+            paginate(kind, "WITH d AS (UPDATE users SET n = 1 RETURNING id) SELECT * FROM d", 0, 10),
+            None
+        );
+    }
+}
@@ -1,12 +1,17 @@
 #[allow(dead_code)]
 mod adapter;
+mod columnar;
 mod commands;
 mod db;
+mod dialect;
+mod lint;
+mod plan;
+mod secrets;
 
 use adapter::ConnectionManager;
 use commands::*;
 use db::Database;
-use tauri::Manager;
+use tauri::{Emitter, Manager};
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -17,6 +22,13 @@ pub fn run() {
         .plugin(tauri_plugin_process::init())
         .setup(|app| {
             let db = Database::new(app.handle())?;
+            if let Some(backup) = db.recovered_backup() {
+                app.emit(
+                    "db-recovered",
+                    serde_json::json!({ "backupPath": backup.display().to_string() }),
+                )
+                .ok();
+            }
             app.manage(db);
 
             // Create a tokio runtime for async database drivers
@@ -32,24 +44,74 @@ pub fn run() {
             // Create the connection manager
             let conn_manager = ConnectionManager::new(rt_handle);
             app.manage(conn_manager);
+            app.manage(TimezoneNotices::default());
+            app.manage(PendingDeletes::default());
+            app.manage(PgListeners::default());
+            app.manage(ActiveExports::default());
+            app.manage(ChatStreams::default());
 
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             list_connections,
             add_connection,
+            get_project_connection_defaults,
+            set_project_connection_defaults,
             remove_connection,
+            clone_connection,
+            duplicate_connection,
+            update_connection,
+            set_connection_quoting,
+            set_connection_read_only,
+            set_connection_environment,
+            set_connection_concurrency,
+            get_pool_stats,
             create_csv_connection,
             test_connection,
+            test_connection_params,
             connect_database,
             auto_connect_project_connections,
+            wait_for_connection,
             disconnect_database,
             get_cached_schema,
+            invalidate_schema_cache,
             get_schema,
+            list_table_names,
+            list_sql_functions,
+            search_objects,
+            list_extensions,
+            create_extension,
+            drop_extension,
             get_table_metadata,
+            get_table_ddl,
+            get_tables_metadata,
+            get_table_size,
+            distinct_values,
+            browse_table,
+            get_server_info,
+            set_session_role,
+            reset_session_role,
             explain_query,
+            explain_query_plan,
+            lint_query,
             write_file,
             execute_query,
+            execute_query_with_params,
+            execute_query_paginated,
+            execute_saved_query,
+            list_saved_query_params,
+            cancel_query,
+            begin_transaction,
+            commit_transaction,
+            rollback_transaction,
+            attach_database,
+            detach_database,
+            export_query_to_file,
+            cancel_export,
+            export_query_csv,
+            export_query_json,
+            execute_query_arrow,
+            export_query_parquet,
             list_projects,
             create_project,
             update_project,
@@ -58,32 +120,71 @@ pub fn run() {
             list_project_connections,
             list_explorations,
             create_exploration,
+            fork_exploration,
             update_exploration,
             delete_exploration,
+            list_trashed_explorations,
+            restore_exploration,
+            purge_exploration,
             list_saved_queries,
+            list_saved_queries_by_tag,
+            list_saved_query_tags,
             save_query,
+            update_saved_query,
             delete_saved_query,
+            list_query_history,
+            clear_query_history,
             list_saved_charts,
             save_saved_chart,
+            update_saved_chart,
             delete_saved_chart,
+            rename_saved_chart,
+            list_dashboards,
+            create_dashboard,
+            add_chart_to_dashboard,
+            list_dashboard_charts,
+            list_snippets,
+            save_snippet,
+            delete_snippet,
+            expand_snippets,
             list_connection_notes,
             set_connection_note,
+            search_workspace,
             get_database_stats,
             import_csv,
+            preview_csv,
+            init_connection_from_sql,
+            execute_script,
+            generate_mock_data,
+            diff_results,
+            generate_migration_sql,
+            diff_schema,
+            coerce_result_column,
+            delete_rows,
+            subscribe_notifications,
+            unsubscribe_notifications,
             scan_queries,
             list_table_links,
             add_table_link,
             remove_table_link,
+            get_relationship_graph,
             list_messages,
             add_message,
+            list_exploration_queries,
             get_message_token_count,
             add_message_tokens,
             get_message_turn_count,
             increment_message_turn,
+            get_message_counters,
             reset_message_history,
             chat_completion,
+            chat_completion_stream,
+            cancel_chat,
+            get_db_recovery,
             get_setting,
             set_setting,
+            list_settings,
+            delete_setting,
         ])
         .run(tauri::generate_context!())
         .expect("error while running Arc");
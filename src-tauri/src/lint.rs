@@ -0,0 +1,367 @@
+// Arc SQL linter
+//
+// Static, advisory checks over a parsed query: nothing here executes SQL.
+// Findings are cross-referenced with the connection's schema (column lists,
+// row counts) so that e.g. a missing WHERE is only flagged on tables large
+// enough for it to matter.
+
+use std::collections::HashMap;
+use std::ops::ControlFlow;
+
+use serde::{Deserialize, Serialize};
+use sqlparser::ast::{
+    Expr, FromTable, GroupByExpr, JoinConstraint, JoinOperator, ObjectName, Query, Select,
+    SelectItem, SetExpr, Statement, TableFactor, Value, visit_expressions, Visit, Visitor,
+};
+use sqlparser::dialect::{Dialect, GenericDialect, MySqlDialect, PostgreSqlDialect, SQLiteDialect};
+use sqlparser::parser::Parser;
+
+use crate::adapter::DatabaseKind;
+use crate::commands::TableSchema;
+
+/// Tables with at least this many rows are "large" for the unbounded-scan check.
+const LARGE_TABLE_ROWS: u64 = 100_000;
+
+/// Functions that collapse rows when called without OVER.
+const AGGREGATE_FUNCTIONS: &[&str] = &[
+    "count", "sum", "avg", "min", "max", "array_agg", "string_agg", "group_concat", "json_agg",
+    "jsonb_agg", "json_object_agg", "jsonb_object_agg", "json_group_array", "json_group_object",
+    "json_arrayagg", "json_objectagg", "bool_and", "bool_or", "bit_and", "bit_or", "bit_xor",
+    "every", "stddev", "stddev_pop", "stddev_samp", "variance", "var_pop", "var_samp", "total",
+];
+
+/// Whether `expr` calls an aggregate function outside a window.
+fn contains_aggregate(expr: &Expr) -> bool {
+    visit_expressions(expr, |e| match e {
+        Expr::Function(f) if f.over.is_none() => {
+            let name = f.name.0.last().map(|i| i.value.to_lowercase()).unwrap_or_default();
+            if AGGREGATE_FUNCTIONS.contains(&name.as_str()) {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        }
+        _ => ControlFlow::Continue(()),
+    })
+    .is_break()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LintSeverity {
+    Info,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LintDiagnostic {
+    /// Stable identifier for the rule, e.g. `select_star`.
+    pub code: String,
+    pub severity: LintSeverity,
+    pub message: String,
+    /// A rewritten fragment the user could use instead, when one is obvious.
+    pub suggestion: Option<String>,
+}
+
+impl LintDiagnostic {
+    fn new(code: &str, severity: LintSeverity, message: String) -> Self {
+        Self {
+            code: code.to_string(),
+            severity,
+            message,
+            suggestion: None,
+        }
+    }
+}
+
+pub(crate) fn parser_dialect(kind: DatabaseKind) -> Box<dyn Dialect> {
+    match kind {
+        DatabaseKind::PostgreSQL | DatabaseKind::CockroachDB => Box::new(PostgreSqlDialect {}),
+        DatabaseKind::MySQL => Box::new(MySqlDialect {}),
+        DatabaseKind::SQLite => Box::new(SQLiteDialect {}),
+        _ => Box::new(GenericDialect {}),
+    }
+}
+
+/// Lint `sql` against `schema`. SQL that doesn't parse yields a single
+/// `parse_error` diagnostic rather than an error.
+pub fn lint_sql(kind: DatabaseKind, sql: &str, schema: &[TableSchema]) -> Vec<LintDiagnostic> {
+    let statements = match Parser::parse_sql(parser_dialect(kind).as_ref(), sql) {
+        Ok(statements) => statements,
+        Err(e) => {
+            return vec![LintDiagnostic::new(
+                "parse_error",
+                LintSeverity::Error,
+                format!("Could not parse query: {e}"),
+            )]
+        }
+    };
+
+    let mut linter = Linter {
+        tables: schema.iter().map(|t| (t.name.to_lowercase(), t)).collect(),
+        diagnostics: Vec::new(),
+    };
+    for statement in &statements {
+        let _ = statement.visit(&mut linter);
+    }
+    linter.diagnostics
+}
+
+struct Linter<'a> {
+    tables: HashMap<String, &'a TableSchema>,
+    diagnostics: Vec<LintDiagnostic>,
+}
+
+impl Linter<'_> {
+    /// Schema entry for a (possibly schema-qualified) table name.
+    fn table(&self, name: &ObjectName) -> Option<&TableSchema> {
+        let last = name.0.last()?.value.to_lowercase();
+        self.tables.get(&last).copied()
+    }
+
+    fn lint_set_expr(&mut self, body: &SetExpr, bounded: bool) {
+        match body {
+            SetExpr::Select(select) => self.lint_select(select, bounded),
+            SetExpr::SetOperation { left, right, .. } => {
+                self.lint_set_expr(left, bounded);
+                self.lint_set_expr(right, bounded);
+            }
+            // Nested queries are visited on their own.
+            _ => {}
+        }
+    }
+
+    fn lint_select(&mut self, select: &Select, bounded: bool) {
+        let single_table = match select.from.as_slice() {
+            [only] if only.joins.is_empty() => match &only.relation {
+                TableFactor::Table { name, .. } => Some(name),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        for item in &select.projection {
+            let qualifier = match item {
+                SelectItem::Wildcard(_) => None,
+                SelectItem::QualifiedWildcard(name, _) => Some(name.to_string()),
+                _ => continue,
+            };
+            let star = qualifier
+                .as_ref()
+                .map(|q| format!("{q}.*"))
+                .unwrap_or_else(|| "*".to_string());
+            let mut diagnostic = LintDiagnostic::new(
+                "select_star",
+                LintSeverity::Warning,
+                format!(
+                    "SELECT {star} fetches every column, including ones added later; list the columns you need"
+                ),
+            );
+            if let Some(table) = single_table.and_then(|name| self.table(name)) {
+                diagnostic.message = format!(
+                    "SELECT {star} fetches all {} columns of {}; list the columns you need",
+                    table.columns.len(),
+                    table.name
+                );
+                let columns: Vec<String> = table
+                    .columns
+                    .iter()
+                    .map(|c| match &qualifier {
+                        Some(q) => format!("{q}.{}", c.name),
+                        None => c.name.clone(),
+                    })
+                    .collect();
+                diagnostic.suggestion = Some(columns.join(", "));
+            }
+            self.diagnostics.push(diagnostic);
+        }
+
+        if select.from.len() > 1 {
+            self.diagnostics.push(LintDiagnostic::new(
+                "implicit_cross_join",
+                LintSeverity::Warning,
+                "Comma-separated tables in FROM form a cross join filtered by WHERE; use explicit JOIN ... ON"
+                    .to_string(),
+            ));
+        }
+        for join in select.from.iter().flat_map(|t| &t.joins) {
+            let unconstrained = matches!(
+                join.join_operator,
+                JoinOperator::CrossJoin
+                    | JoinOperator::Inner(JoinConstraint::None)
+                    | JoinOperator::LeftOuter(JoinConstraint::None)
+                    | JoinOperator::RightOuter(JoinConstraint::None)
+                    | JoinOperator::FullOuter(JoinConstraint::None)
+            );
+            if unconstrained {
+                self.diagnostics.push(LintDiagnostic::new(
+                    "cross_join",
+                    LintSeverity::Info,
+                    format!(
+                        "Join with {} has no ON condition and produces every row combination",
+                        join.relation
+                    ),
+                ));
+            }
+        }
+
+        // Aggregates read the whole table too, but return little, so only
+        // plain row-returning scans are flagged.
+        let aggregated = !matches!(&select.group_by, GroupByExpr::Expressions(exprs, _) if exprs.is_empty())
+            || select.having.is_some()
+            || select.projection.iter().any(|item| match item {
+                SelectItem::UnnamedExpr(expr) | SelectItem::ExprWithAlias { expr, .. } => {
+                    contains_aggregate(expr)
+                }
+                _ => false,
+            });
+        if select.selection.is_none() && !bounded && !aggregated {
+            if let Some(table) = single_table.and_then(|name| self.table(name)) {
+                if table.row_count >= LARGE_TABLE_ROWS {
+                    self.diagnostics.push(LintDiagnostic::new(
+                        "unbounded_scan",
+                        LintSeverity::Warning,
+                        format!(
+                            "No WHERE or LIMIT on {} (~{} rows); the whole table will be read and returned",
+                            table.name, table.row_count
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+
+    fn lint_unfiltered_write(&mut self, verb: &str, table: Option<&ObjectName>) {
+        let target = table.map(|t| t.to_string()).unwrap_or_else(|| "the table".to_string());
+        self.diagnostics.push(LintDiagnostic::new(
+            "missing_where",
+            LintSeverity::Error,
+            format!("{verb} without WHERE affects every row of {target}"),
+        ));
+    }
+}
+
+impl Visitor for Linter<'_> {
+    type Break = ();
+
+    fn pre_visit_query(&mut self, query: &Query) -> ControlFlow<Self::Break> {
+        let bounded = query.limit.is_some() || query.fetch.is_some();
+        self.lint_set_expr(&query.body, bounded);
+        ControlFlow::Continue(())
+    }
+
+    fn pre_visit_statement(&mut self, statement: &Statement) -> ControlFlow<Self::Break> {
+        match statement {
+            Statement::Update {
+                table,
+                selection: None,
+                ..
+            } => {
+                let name = match &table.relation {
+                    TableFactor::Table { name, .. } => Some(name),
+                    _ => None,
+                };
+                self.lint_unfiltered_write("UPDATE", name);
+            }
+            Statement::Delete(delete) if delete.selection.is_none() && delete.using.is_none() => {
+                let from = match &delete.from {
+                    FromTable::WithFromKeyword(tables) | FromTable::WithoutKeyword(tables) => tables,
+                };
+                let name = from.first().and_then(|t| match &t.relation {
+                    TableFactor::Table { name, .. } => Some(name),
+                    _ => None,
+                });
+                self.lint_unfiltered_write("DELETE", name);
+            }
+            _ => {}
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn pre_visit_expr(&mut self, expr: &Expr) -> ControlFlow<Self::Break> {
+        let (operator, pattern) = match expr {
+            Expr::Like { negated: false, pattern, .. } => ("LIKE", pattern),
+            Expr::ILike { negated: false, pattern, .. } => ("ILIKE", pattern),
+            _ => return ControlFlow::Continue(()),
+        };
+        if let Expr::Value(Value::SingleQuotedString(p)) = pattern.as_ref() {
+            if p.starts_with('%') || p.starts_with('_') {
+                self.diagnostics.push(LintDiagnostic::new(
+                    "leading_wildcard",
+                    LintSeverity::Info,
+                    format!(
+                        "{operator} '{p}' starts with a wildcard, so no index on the column can be used"
+                    ),
+                ));
+            }
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::ColumnInfo;
+
+    fn orders() -> TableSchema {
+        TableSchema {
+            name: "orders".to_string(),
+            schema: None,
+            columns: ["id", "email", "total"]
+                .iter()
+                .map(|name| ColumnInfo {
+                    name: name.to_string(),
+                    data_type: "text".to_string(),
+                    nullable: true,
+                    primary_key: false,
+                })
+                .collect(),
+            row_count: 1_000_000,
+            is_estimate: false,
+        }
+    }
+
+    fn codes(sql: &str) -> Vec<String> {
+        lint_sql(DatabaseKind::PostgreSQL, sql, &[orders()])
+            .into_iter()
+            .map(|d| d.code)
+            .collect()
+    }
+
+    #[test]
+    fn unbounded_scan_skips_aggregates_but_not_scalar_functions() {
+        assert!(codes("SELECT lower(email) FROM orders").contains(&"unbounded_scan".to_string()));
+        assert!(codes("SELECT id FROM orders").contains(&"unbounded_scan".to_string()));
+        assert!(codes("SELECT count(*) FROM orders").is_empty());
+        assert!(codes("SELECT round(sum(total), 2) AS total FROM orders").is_empty());
+        assert!(codes("SELECT email, count(*) FROM orders GROUP BY email").is_empty());
+        assert!(codes("SELECT id FROM orders LIMIT 10").is_empty());
+        assert!(codes("SELECT id FROM orders WHERE id = 1").is_empty());
+        // A windowed aggregate still returns every row.
+        assert!(codes("SELECT id, sum(total) OVER () FROM orders")
+            .contains(&"unbounded_scan".to_string()));
+    }
+
+    #[test]
+    fn select_star_suggests_the_table_columns() {
+        let sql = "SELECT * FROM orders LIMIT 5";
+        let diagnostics = lint_sql(DatabaseKind::PostgreSQL, sql, &[orders()]);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, "select_star");
+        assert_eq!(diagnostics[0].suggestion.as_deref(), Some("id, email, total"));
+    }
+
+    #[test]
+    fn flags_joins_wildcards_and_unfiltered_writes() {
+        assert_eq!(
+            codes("SELECT o.id FROM orders o, orders p WHERE o.id = p.id"),
+            ["implicit_cross_join"]
+        );
+        assert_eq!(codes("SELECT id FROM orders WHERE email LIKE '%@x.com'"), ["leading_wildcard"]);
+        assert_eq!(codes("DELETE FROM orders"), ["missing_where"]);
+        assert_eq!(codes("UPDATE orders SET total = 0"), ["missing_where"]);
+        assert_eq!(codes("SELEC id"), ["parse_error"]);
+    }
+}
@@ -0,0 +1,179 @@
+// Arc query plans
+//
+// Parses the output of Postgres `EXPLAIN (FORMAT JSON ...)` into a tree of
+// plan nodes. Cost and row estimates are always present; timing and buffer
+// counts only when the plan was produced with ANALYZE / BUFFERS, so those
+// fields are optional rather than zero.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::commands::AppError;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanNode {
+    /// e.g. `Seq Scan`, `Hash Join`.
+    pub node_type: String,
+    pub relation_name: Option<String>,
+    pub index_name: Option<String>,
+    pub startup_cost: f64,
+    pub total_cost: f64,
+    pub plan_rows: f64,
+    /// Milliseconds per loop, as Postgres reports it.
+    pub actual_startup_time: Option<f64>,
+    pub actual_total_time: Option<f64>,
+    pub actual_rows: Option<f64>,
+    pub actual_loops: Option<f64>,
+    /// Blocks found in shared buffers.
+    pub shared_hit_blocks: Option<u64>,
+    /// Blocks read from disk (or the OS cache): the node's physical I/O.
+    pub shared_read_blocks: Option<u64>,
+    pub shared_dirtied_blocks: Option<u64>,
+    pub shared_written_blocks: Option<u64>,
+    pub children: Vec<PlanNode>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryPlan {
+    pub root: PlanNode,
+    pub planning_time_ms: Option<f64>,
+    pub execution_time_ms: Option<f64>,
+}
+
+fn str_field(node: &Value, key: &str) -> Option<String> {
+    node.get(key).and_then(Value::as_str).map(str::to_string)
+}
+
+fn f64_field(node: &Value, key: &str) -> Option<f64> {
+    node.get(key).and_then(Value::as_f64)
+}
+
+fn u64_field(node: &Value, key: &str) -> Option<u64> {
+    node.get(key).and_then(Value::as_u64)
+}
+
+fn parse_node(node: &Value) -> Result<PlanNode, AppError> {
+    let node_type = str_field(node, "Node Type")
+        .ok_or_else(|| AppError::QueryError("Plan node has no \"Node Type\"".to_string()))?;
+    let children = match node.get("Plans").and_then(Value::as_array) {
+        Some(plans) => plans.iter().map(parse_node).collect::<Result<_, _>>()?,
+        None => Vec::new(),
+    };
+
+    Ok(PlanNode {
+        node_type,
+        relation_name: str_field(node, "Relation Name"),
+        index_name: str_field(node, "Index Name"),
+        startup_cost: f64_field(node, "Startup Cost").unwrap_or(0.0),
+        total_cost: f64_field(node, "Total Cost").unwrap_or(0.0),
+        plan_rows: f64_field(node, "Plan Rows").unwrap_or(0.0),
+        actual_startup_time: f64_field(node, "Actual Startup Time"),
+        actual_total_time: f64_field(node, "Actual Total Time"),
+        actual_rows: f64_field(node, "Actual Rows"),
+        actual_loops: f64_field(node, "Actual Loops"),
+        shared_hit_blocks: u64_field(node, "Shared Hit Blocks"),
+        shared_read_blocks: u64_field(node, "Shared Read Blocks"),
+        shared_dirtied_blocks: u64_field(node, "Shared Dirtied Blocks"),
+        shared_written_blocks: u64_field(node, "Shared Written Blocks"),
+        children,
+    })
+}
+
+/// Parse the single value returned by `EXPLAIN (FORMAT JSON)`: an array
+/// holding one object with a `Plan` key. The value may also arrive as text
+/// when the driver didn't decode the json column.
+pub fn parse_json_plan(value: &Value) -> Result<QueryPlan, AppError> {
+    let parsed;
+    let value = match value {
+        Value::String(text) => {
+            parsed = serde_json::from_str::<Value>(text)
+                .map_err(|e| AppError::QueryError(format!("Invalid JSON plan: {e}")))?;
+            &parsed
+        }
+        other => other,
+    };
+    let top = match value {
+        Value::Array(items) => items.first(),
+        Value::Object(_) => Some(value),
+        _ => None,
+    }
+    .ok_or_else(|| AppError::QueryError("Empty JSON plan".to_string()))?;
+    let root = top
+        .get("Plan")
+        .ok_or_else(|| AppError::QueryError("JSON plan has no \"Plan\" key".to_string()))?;
+
+    Ok(QueryPlan {
+        root: parse_node(root)?,
+        planning_time_ms: f64_field(top, "Planning Time"),
+        execution_time_ms: f64_field(top, "Execution Time"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sample_plan() -> Value {
+        json!([{
+            "Plan": {
+                "Node Type": "Hash Join",
+                "Startup Cost": 1.09,
+                "Total Cost": 25.5,
+                "Plan Rows": 40,
+                "Actual Startup Time": 0.05,
+                "Actual Total Time": 0.42,
+                "Actual Rows": 38,
+                "Actual Loops": 1,
+                "Shared Hit Blocks": 12,
+                "Shared Read Blocks": 3,
+                "Shared Dirtied Blocks": 0,
+                "Shared Written Blocks": 0,
+                "Plans": [{
+                    "Node Type": "Seq Scan",
+                    "Relation Name": "orders",
+                    "Startup Cost": 0.0,
+                    "Total Cost": 18.2,
+                    "Plan Rows": 820,
+                    "Actual Startup Time": 0.01,
+                    "Actual Total Time": 0.21,
+                    "Actual Rows": 800,
+                    "Actual Loops": 1,
+                    "Shared Hit Blocks": 8,
+                    "Shared Read Blocks": 3
+                }]
+            },
+            "Planning Time": 0.12,
+            "Execution Time": 0.51
+        }])
+    }
+
+    #[test]
+    fn analyzed_plan_keeps_timing_and_buffers_per_node() {
+        let plan = parse_json_plan(&sample_plan()).unwrap();
+        assert_eq!(plan.planning_time_ms, Some(0.12));
+        assert_eq!(plan.execution_time_ms, Some(0.51));
+
+        let root = &plan.root;
+        assert_eq!(root.node_type, "Hash Join");
+        assert_eq!((root.shared_hit_blocks, root.shared_read_blocks), (Some(12), Some(3)));
+        assert_eq!(root.actual_total_time, Some(0.42));
+
+        assert_eq!(root.children.len(), 1);
+        let child = &root.children[0];
+        assert_eq!(child.relation_name.as_deref(), Some("orders"));
+        assert_eq!((child.shared_hit_blocks, child.shared_read_blocks), (Some(8), Some(3)));
+        assert_eq!(child.actual_total_time, Some(0.21));
+        assert_eq!(child.shared_dirtied_blocks, None);
+    }
+
+    #[test]
+    fn plan_given_as_text_parses_the_same() {
+        let from_value = parse_json_plan(&sample_plan()).unwrap();
+        let from_text = parse_json_plan(&Value::String(sample_plan().to_string())).unwrap();
+        assert_eq!(
+            serde_json::to_value(&from_text).unwrap(),
+            serde_json::to_value(&from_value).unwrap()
+        );
+    }
+}
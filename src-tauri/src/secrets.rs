@@ -0,0 +1,183 @@
+// Arc credential encryption
+//
+// Connection passwords are stored in `arc.db` encrypted with AES-256-GCM.
+// The key lives in the OS keyring (Keychain, Credential Manager, Secret
+// Service); where no keyring is available it falls back to `arc.key` beside
+// the database, readable only by the current user. Stored values carry a
+// version prefix so that plaintext written by older versions can be
+// recognized and migrated.
+
+use std::path::Path;
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::Engine;
+
+use crate::commands::AppError;
+
+const ENCRYPTED_PREFIX: &str = "enc:v1:";
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+const KEYRING_SERVICE: &str = "arc";
+const KEYRING_USER: &str = "credential-key";
+
+pub struct SecretBox {
+    cipher: Aes256Gcm,
+}
+
+impl SecretBox {
+    /// Load the key from the OS keyring, or from `key_file` when there is
+    /// no usable keyring, generating and saving one on first run. A key that
+    /// is missing or corrupt is replaced; passwords encrypted under the old
+    /// one can no longer be read and have to be entered again.
+    pub fn open(key_file: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let key = match keyring_key(key_file) {
+            Some(key) => key,
+            None => file_key(key_file)?,
+        };
+        let cipher = Aes256Gcm::new_from_slice(&key).map_err(|e| e.to_string())?;
+        Ok(Self { cipher })
+    }
+
+    /// A box with a throwaway key, for tests.
+    #[cfg(test)]
+    pub fn ephemeral() -> Self {
+        Self {
+            cipher: Aes256Gcm::new(&Aes256Gcm::generate_key(OsRng)),
+        }
+    }
+
+    /// Whether `stored` was written by `encrypt`.
+    pub fn is_encrypted(stored: &str) -> bool {
+        stored.starts_with(ENCRYPTED_PREFIX)
+    }
+
+    /// Encrypt `plaintext` under a fresh nonce. The empty string stays empty
+    /// so "no saved password" reads the same either way.
+    pub fn encrypt(&self, plaintext: &str) -> Result<String, AppError> {
+        if plaintext.is_empty() {
+            return Ok(String::new());
+        }
+        let nonce = Aes256Gcm::generate_nonce(OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext.as_bytes())
+            .map_err(|_| AppError::DatabaseError("Failed to encrypt credentials".to_string()))?;
+        let mut sealed = nonce.to_vec();
+        sealed.extend(ciphertext);
+        Ok(format!(
+            "{ENCRYPTED_PREFIX}{}",
+            base64::engine::general_purpose::STANDARD.encode(sealed)
+        ))
+    }
+
+    /// Decrypt a value written by `encrypt`. Anything without the prefix is
+    /// legacy plaintext and is returned as is.
+    pub fn decrypt(&self, stored: &str) -> Result<String, AppError> {
+        let Some(encoded) = stored.strip_prefix(ENCRYPTED_PREFIX) else {
+            return Ok(stored.to_string());
+        };
+        let unreadable =
+            || AppError::DatabaseError("Stored credentials could not be decrypted".to_string());
+        let sealed = base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .map_err(|_| unreadable())?;
+        if sealed.len() < NONCE_LEN {
+            return Err(unreadable());
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| unreadable())?;
+        String::from_utf8(plaintext).map_err(|_| unreadable())
+    }
+}
+
+fn generate_key() -> Vec<u8> {
+    Aes256Gcm::generate_key(OsRng).to_vec()
+}
+
+/// The key held in the OS keyring, created there on first use. A key file
+/// left by an earlier version is moved into the keyring rather than
+/// replaced. `None` when no keyring can be used.
+fn keyring_key(key_file: &Path) -> Option<Vec<u8>> {
+    let entry = keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER).ok()?;
+    match entry.get_password() {
+        Ok(encoded) => {
+            let key = base64::engine::general_purpose::STANDARD.decode(encoded).ok();
+            if let Some(key) = key.filter(|k| k.len() == KEY_LEN) {
+                return Some(key);
+            }
+        }
+        Err(keyring::Error::NoEntry) => {}
+        Err(_) => return None,
+    }
+
+    let key = read_key_file(key_file).unwrap_or_else(generate_key);
+    entry
+        .set_password(&base64::engine::general_purpose::STANDARD.encode(&key))
+        .ok()?;
+    let _ = std::fs::remove_file(key_file);
+    Some(key)
+}
+
+/// The key in `path`, replacing a missing or corrupt file with a new key.
+fn file_key(path: &Path) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    if let Some(key) = read_key_file(path) {
+        return Ok(key);
+    }
+    match std::fs::remove_file(path) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e.into()),
+    }
+    let key = generate_key();
+    write_key(path, &key)?;
+    Ok(key)
+}
+
+fn read_key_file(path: &Path) -> Option<Vec<u8>> {
+    std::fs::read(path).ok().filter(|key| key.len() == KEY_LEN)
+}
+
+/// Create the key file, readable and writable only by its owner.
+fn write_key(path: &Path, key: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    #[cfg(windows)]
+    if let Err(e) = restrict_to_owner(path) {
+        drop(file);
+        let _ = std::fs::remove_file(path);
+        return Err(e);
+    }
+    file.write_all(key)
+}
+
+/// Replace the inherited ACL on `path` with one granting only the current
+/// user access. Done before the key is written, so it is never readable by
+/// anyone else.
+#[cfg(windows)]
+fn restrict_to_owner(path: &Path) -> std::io::Result<()> {
+    let user = std::env::var("USERNAME")
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::NotFound, e))?;
+    let status = std::process::Command::new("icacls")
+        .arg(path)
+        .args(["/inheritance:r", "/grant:r"])
+        .arg(format!("{user}:F"))
+        .stdout(std::process::Stdio::null())
+        .status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(std::io::Error::other(format!("icacls exited with {status}")))
+    }
+}